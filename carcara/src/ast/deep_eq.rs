@@ -6,10 +6,13 @@
 //!
 //! - `are_alpha_equivalent` compares terms by alpha-equivalence, meaning it implements equality of
 //! terms modulo renaming of bound variables.
+//!
+//! This module also implements `alpha_equivalent`, a cheaper check for alpha-equivalence that
+//! doesn't consider reordering of equalities.
 
 use super::{
-    BindingList, Identifier, Operator, ProofArg, ProofCommand, ProofStep, Rc, Sort, Subproof, Term,
-    Terminal,
+    BindingList, Identifier, Operator, ProofArg, ProofCommand, ProofStep, Rc, Sort, SortedVar,
+    Subproof, Term, Terminal,
 };
 use crate::utils::SymbolTable;
 use std::time::{Duration, Instant};
@@ -25,26 +28,79 @@ pub trait DeepEq {
 /// That is, for this function, `=` terms that are reflections of each other are considered as
 /// equal, meaning terms like `(and p (= a b))` and `(and p (= b a))` are considered equal.
 ///
+/// If `depth_limit` is `Some(n)`, the comparison gives up and returns `false` if it needs to recurse
+/// more than `n` levels deep into the terms.
+///
 /// This function records how long it takes to run, and adds that duration to the `time` argument.
-pub fn deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
+pub fn deep_eq(
+    a: &Rc<Term>,
+    b: &Rc<Term>,
+    depth_limit: Option<usize>,
+    time: &mut Duration,
+) -> bool {
     let start = Instant::now();
-    let result = DeepEq::eq(&mut DeepEqualityChecker::new(true, false), a, b);
+    let mut checker = DeepEqualityChecker::new(true, false).depth_limit(depth_limit);
+    let result = DeepEq::eq(&mut checker, a, b);
     *time += start.elapsed();
     result
 }
 
+/// Describes how two terms were found to be equal modulo reordering of equalities. This is
+/// returned by [`tracing_deep_eq`], and can be used to decide how to justify the equality when
+/// elaborating a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeepEqWitness {
+    /// The terms are identical.
+    Identical,
+
+    /// Both terms are equalities, and one is the other with its sides flipped, like `(= a b)` and
+    /// `(= b a)`.
+    Symmetric,
+
+    /// The terms are equal only after reordering one or more equalities nested inside them.
+    Reordered,
+}
+
+impl DeepEqWitness {
+    /// Returns the witness for two terms that are known to be equal modulo reordering of
+    /// equalities.
+    fn of(a: &Rc<Term>, b: &Rc<Term>) -> Self {
+        if a == b {
+            return Self::Identical;
+        }
+        match (a.as_ref(), b.as_ref()) {
+            (Term::Op(Operator::Equals, a_args), Term::Op(Operator::Equals, b_args))
+                if matches!(
+                    (a_args.as_slice(), b_args.as_slice()),
+                    ([a_1, a_2], [b_1, b_2]) if a_1 == b_2 && a_2 == b_1
+                ) =>
+            {
+                Self::Symmetric
+            }
+            _ => Self::Reordered,
+        }
+    }
+}
+
 /// Similar to `deep_eq`, but also records the maximum depth the deep equality checker reached when
-/// comparing the terms.
+/// comparing the terms. If the terms are equal, this returns a witness describing how they were
+/// matched, and `None` otherwise.
 ///
 /// This function records how long it takes to run, and adds that duration to the `time` argument.
-pub fn tracing_deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> (bool, usize) {
+pub fn tracing_deep_eq(
+    a: &Rc<Term>,
+    b: &Rc<Term>,
+    depth_limit: Option<usize>,
+    time: &mut Duration,
+) -> (Option<DeepEqWitness>, usize) {
     let start = Instant::now();
 
-    let mut checker = DeepEqualityChecker::new(true, false);
+    let mut checker = DeepEqualityChecker::new(true, false).depth_limit(depth_limit);
     let result = DeepEq::eq(&mut checker, a, b);
+    let witness = result.then(|| DeepEqWitness::of(a, b));
 
     *time += start.elapsed();
-    (result, checker.max_depth)
+    (witness, checker.max_depth)
 }
 
 /// Similar to `deep_eq`, but instead compares terms for alpha equivalence.
@@ -55,19 +111,154 @@ pub fn tracing_deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> (bool
 /// Int)) (= 0 y))` as equivalent.
 ///
 /// This function records how long it takes to run, and adds that duration to the `time` argument.
-pub fn are_alpha_equivalent(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
+pub fn are_alpha_equivalent(
+    a: &Rc<Term>,
+    b: &Rc<Term>,
+    depth_limit: Option<usize>,
+    time: &mut Duration,
+) -> bool {
     let start = Instant::now();
 
     // When we are checking for alpha-equivalence, we can't always assume that if `a` and `b` are
     // identical, they are alpha-equivalent, so that optimization is not used in `DeepEq::eq`.
     // However, here at the "root" level this assumption is valid, so we check if the terms are
     // directly equal before doing anything else
-    let result = a == b || DeepEq::eq(&mut DeepEqualityChecker::new(true, true), a, b);
+    let result = a == b || {
+        let mut checker = DeepEqualityChecker::new(true, true).depth_limit(depth_limit);
+        DeepEq::eq(&mut checker, a, b)
+    };
 
     *time += start.elapsed();
     result
 }
 
+/// Computes whether the two given terms are alpha-equivalent, that is, equal modulo renaming of
+/// bound variables. Unlike `are_alpha_equivalent`, this function does not consider reordering of
+/// equalities, so `(forall ((x Int)) (= x 0))` and `(forall ((y Int)) (= y 0))` are equivalent, but
+/// `(forall ((x Int)) (= x 0))` and `(forall ((y Int)) (= 0 y))` are not.
+///
+/// Bound variables are compared by the position of the binder that introduces them, similarly to
+/// de Bruijn indices, so this doesn't need to add any terms to a pool or apply any substitutions.
+pub fn alpha_equivalent(a: &Rc<Term>, b: &Rc<Term>) -> bool {
+    a == b || alpha_equivalent_with(a, b, &mut Vec::new(), &mut Vec::new())
+}
+
+/// Compares two terms for alpha-equivalence, given the stacks of variables bound in each term. Both
+/// stacks always have the same length.
+fn alpha_equivalent_with<'a>(
+    a: &'a Rc<Term>,
+    b: &'a Rc<Term>,
+    a_bound: &mut Vec<&'a str>,
+    b_bound: &mut Vec<&'a str>,
+) -> bool {
+    fn all_equivalent<'a>(
+        pairs: impl IntoIterator<Item = (&'a Rc<Term>, &'a Rc<Term>)>,
+        a_bound: &mut Vec<&'a str>,
+        b_bound: &mut Vec<&'a str>,
+    ) -> bool {
+        pairs
+            .into_iter()
+            .all(|(x, y)| alpha_equivalent_with(x, y, a_bound, b_bound))
+    }
+
+    // Compares the bodies of two binder terms, after adding their bound variables to the stacks.
+    // The binding lists must have the same length
+    fn bodies_equivalent<'a>(
+        (a_vars, a_body): (&'a [SortedVar], &'a Rc<Term>),
+        (b_vars, b_body): (&'a [SortedVar], &'a Rc<Term>),
+        a_bound: &mut Vec<&'a str>,
+        b_bound: &mut Vec<&'a str>,
+    ) -> bool {
+        let n = a_bound.len();
+        a_bound.extend(a_vars.iter().map(|(name, _)| name.as_str()));
+        b_bound.extend(b_vars.iter().map(|(name, _)| name.as_str()));
+        let result = alpha_equivalent_with(a_body, b_body, a_bound, b_bound);
+        a_bound.truncate(n);
+        b_bound.truncate(n);
+        result
+    }
+
+    fn same_sorts(a_vars: &[SortedVar], b_vars: &[SortedVar]) -> bool {
+        a_vars.len() == b_vars.len() && a_vars.iter().zip(b_vars).all(|((_, s), (_, t))| s == t)
+    }
+
+    // If no variables are bound, identical terms are always alpha-equivalent
+    if a_bound.is_empty() && a == b {
+        return true;
+    }
+
+    match (a.as_ref(), b.as_ref()) {
+        (
+            Term::Terminal(Terminal::Var(Identifier::Simple(x), x_sort)),
+            Term::Terminal(Terminal::Var(Identifier::Simple(y), y_sort)),
+        ) => {
+            let x_index = a_bound.iter().rposition(|v| v == x);
+            let y_index = b_bound.iter().rposition(|v| v == y);
+            match (x_index, y_index) {
+                (Some(i), Some(j)) => i == j,
+                (None, None) => x == y && x_sort == y_sort,
+                _ => false,
+            }
+        }
+        (Term::App(f, a_args), Term::App(g, b_args)) => {
+            a_args.len() == b_args.len()
+                && all_equivalent(
+                    std::iter::once((f, g)).chain(a_args.iter().zip(b_args)),
+                    a_bound,
+                    b_bound,
+                )
+        }
+        (Term::Op(f, a_args), Term::Op(g, b_args)) => {
+            f == g
+                && a_args.len() == b_args.len()
+                && all_equivalent(a_args.iter().zip(b_args), a_bound, b_bound)
+        }
+        (Term::Quant(p, a_vars, a_body), Term::Quant(q, b_vars, b_body)) => {
+            p == q
+                && same_sorts(a_vars.as_slice(), b_vars.as_slice())
+                && bodies_equivalent(
+                    (a_vars.as_slice(), a_body),
+                    (b_vars.as_slice(), b_body),
+                    a_bound,
+                    b_bound,
+                )
+        }
+        (Term::Lambda(a_vars, a_body), Term::Lambda(b_vars, b_body)) => {
+            same_sorts(a_vars.as_slice(), b_vars.as_slice())
+                && bodies_equivalent(
+                    (a_vars.as_slice(), a_body),
+                    (b_vars.as_slice(), b_body),
+                    a_bound,
+                    b_bound,
+                )
+        }
+        (Term::Choice(x, a_body), Term::Choice(y, b_body)) => {
+            let (x, y) = (std::slice::from_ref(x), std::slice::from_ref(y));
+            same_sorts(x, y) && bodies_equivalent((x, a_body), (y, b_body), a_bound, b_bound)
+        }
+        (Term::Let(a_vars, a_body), Term::Let(b_vars, b_body)) => {
+            // The values in the bindings are in the scope outside of the `let` term
+            a_vars.len() == b_vars.len()
+                && all_equivalent(
+                    a_vars
+                        .iter()
+                        .zip(b_vars.iter())
+                        .map(|((_, s), (_, t))| (s, t)),
+                    a_bound,
+                    b_bound,
+                )
+                && bodies_equivalent(
+                    (a_vars.as_slice(), a_body),
+                    (b_vars.as_slice(), b_body),
+                    a_bound,
+                    b_bound,
+                )
+        }
+        // Other terms can't contain bound variables, so they must be identical
+        _ => a == b,
+    }
+}
+
 /// A configurable checker for equality modulo reordering of equalities and alpha equivalence.
 pub struct DeepEqualityChecker {
     // In order to check alpha-equivalence, we can't use a simple global cache. For instance, let's
@@ -99,6 +290,10 @@ pub struct DeepEqualityChecker {
 
     current_depth: usize,
     max_depth: usize,
+
+    // If set, comparisons that need to recurse deeper than this are considered to fail. This
+    // bounds the time and stack space used on pathological terms
+    depth_limit: Option<usize>,
 }
 
 impl DeepEqualityChecker {
@@ -118,9 +313,17 @@ impl DeepEqualityChecker {
             },
             current_depth: 0,
             max_depth: 0,
+            depth_limit: None,
         }
     }
 
+    /// Sets the maximum depth the checker may reach when comparing terms. If the limit is exceeded,
+    /// the terms are considered different.
+    pub fn depth_limit(mut self, value: Option<usize>) -> Self {
+        self.depth_limit = value;
+        self
+    }
+
     fn check_binder(
         &mut self,
         a_binds: &BindingList,
@@ -179,6 +382,9 @@ impl DeepEq for Rc<Term> {
             return true;
         }
 
+        if matches!(checker.depth_limit, Some(limit) if checker.current_depth >= limit) {
+            return false;
+        }
         checker.current_depth += 1;
         checker.max_depth = std::cmp::max(checker.max_depth, checker.current_depth);
         let result = DeepEq::eq(checker, a.as_ref(), b.as_ref());
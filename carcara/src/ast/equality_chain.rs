@@ -0,0 +1,113 @@
+//! A helper for checking chains of equalities, like the ones used in the `trans` and
+//! `eq_transitive` rules.
+
+use super::{Rc, Term};
+use ahash::{AHashMap, AHashSet};
+
+/// A chain of equalities, together with the equality that it should prove.
+///
+/// Each link in the chain is an equality `(= t u)`, represented by the pair `(t, u)`. The links may
+/// be in any order, and each of them may be used in either direction. For example, the links
+/// `(= b c)` and `(= b a)` form a chain from `a` to `c`, that proves the equality `(= a c)`.
+#[derive(Debug, Clone)]
+pub struct EqualityChain<'a> {
+    /// The equality that the chain should prove.
+    pub conclusion: (&'a Rc<Term>, &'a Rc<Term>),
+
+    /// The equalities that form the chain.
+    pub links: Vec<(&'a Rc<Term>, &'a Rc<Term>)>,
+}
+
+impl<'a> EqualityChain<'a> {
+    /// Returns the two endpoints of the chain formed by all of the links, if they can be arranged
+    /// into a single open chain, in which each link shares a term with the next one. The endpoints
+    /// are returned in the order in which they first appear in the links.
+    ///
+    /// Returns `None` if there are no links, if the links are not all connected, if they branch, or
+    /// if they form a circular chain, which has no endpoints. Note that a single reflexive link,
+    /// like `(= a a)`, is also a circular chain.
+    pub fn endpoints(&self) -> Option<(&'a Rc<Term>, &'a Rc<Term>)> {
+        // Each link is an edge in a graph whose vertices are terms. The links can be arranged into
+        // a single open chain if and only if this graph has a path that goes through every edge
+        // exactly once, and that starts and ends in different vertices. That is the case when the
+        // graph is connected and has exactly two vertices with odd degree
+        let mut degrees: AHashMap<&Rc<Term>, usize> = AHashMap::new();
+        let mut adjacent: AHashMap<&Rc<Term>, Vec<&Rc<Term>>> = AHashMap::new();
+        let mut order = Vec::new();
+        for &(t, u) in &self.links {
+            for (x, y) in [(t, u), (u, t)] {
+                let degree = degrees.entry(x).or_insert_with(|| {
+                    order.push(x);
+                    0
+                });
+                *degree += 1;
+                adjacent.entry(x).or_default().push(y);
+            }
+        }
+
+        let odd: Vec<_> = order.iter().filter(|t| degrees[*t] % 2 == 1).collect();
+        let &[&first, &second] = odd.as_slice() else {
+            return None;
+        };
+
+        let mut seen = AHashSet::from([first]);
+        let mut stack = vec![first];
+        while let Some(t) = stack.pop() {
+            for &u in &adjacent[t] {
+                if seen.insert(u) {
+                    stack.push(u);
+                }
+            }
+        }
+        (seen.len() == order.len()).then_some((first, second))
+    }
+
+    /// Checks that the conclusion can be derived by transitivity from the links. Otherwise, returns
+    /// the two terms between which the chain could not be completed. Not all links need to be
+    /// used, and a reflexive conclusion like `(= a a)` is always valid.
+    pub fn check(&self) -> Result<(), (&'a Rc<Term>, &'a Rc<Term>)> {
+        fn find_chain<'a>(
+            conclusion: (&'a Rc<Term>, &'a Rc<Term>),
+            links: &mut [(&'a Rc<Term>, &'a Rc<Term>)],
+        ) -> Result<(), (&'a Rc<Term>, &'a Rc<Term>)> {
+            // When the conclusion is of the form (= a a), it is trivially valid
+            if conclusion.0 == conclusion.1 {
+                return Ok(());
+            }
+
+            // Find in the links, if it exists, an equality such that one of its terms is equal to
+            // the first term in the conclusion. Possibly reorder this equality so the matching
+            // term is the first one
+            let (index, eq) = links
+                .iter()
+                .enumerate()
+                .find_map(|(i, &(t, u))| {
+                    if t == conclusion.0 {
+                        Some((i, (t, u)))
+                    } else if u == conclusion.0 {
+                        Some((i, (u, t)))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(conclusion)?;
+
+            // We remove the found equality by swapping it with the first link. The remaining
+            // links will then be all elements after the first
+            links.swap(0, index);
+
+            // The new conclusion will be the terms in the conclusion and the found equality that
+            // didn't match. For example, if the conclusion was (= a d) and we found in the links
+            // (= a b), the new conclusion will be (= b d)
+            find_chain((eq.1, conclusion.1), &mut links[1..])
+        }
+
+        find_chain(self.conclusion, &mut self.links.clone())
+    }
+
+    /// Returns `true` if the conclusion can be derived by transitivity from the links. See
+    /// [`EqualityChain::check`].
+    pub fn is_valid_transitive_chain(&self) -> bool {
+        self.check().is_ok()
+    }
+}
@@ -44,6 +44,12 @@ impl<'a> ProofIter<'a> {
         Self { stack: vec![(0, commands)] }
     }
 
+    /// Constructs a new `ProofIter` that starts at the command with the given index in the root
+    /// proof. Premises that reference earlier commands can still be accessed with `get_premise`.
+    pub(crate) fn starting_at(commands: &'a [ProofCommand], index: usize) -> Self {
+        Self { stack: vec![(index, commands)] }
+    }
+
     /// Returns the current nesting depth of the iterator, or more precisely, the nesting depth of
     /// the last command that was returned. This depth starts at zero, for commands in the root
     /// proof.
@@ -71,11 +77,26 @@ impl<'a> ProofIter<'a> {
         }
     }
 
+    /// Returns `true` if a premise index of the form (depth, index in subproof) refers to a
+    /// command that comes before the last command that was returned, either in the same subproof
+    /// or in one of the subproofs that contain it.
+    pub fn is_valid_premise(&self, (depth, index): (usize, usize)) -> bool {
+        self.stack.get(depth).map_or(false, |&(i, _)| index + 1 < i)
+    }
+
     /// Returns the command referenced by a premise index of the form (depth, index in subproof).
-    /// This method may panic if the premise index does not refer to a valid command.
-    pub fn get_premise(&self, (depth, index): (usize, usize)) -> &ProofCommand {
+    /// This method may panic if the premise index does not refer to a valid command. Use
+    /// [`ProofIter::is_valid_premise`] to check the index first.
+    pub fn get_premise(&self, (depth, index): (usize, usize)) -> &'a ProofCommand {
         &self.stack[depth].1[index]
     }
+
+    /// Similar to [`ProofIter::get_premise`], but returns `None` if the premise index is out of
+    /// bounds. Unlike [`ProofIter::is_valid_premise`], this accepts indices that refer to commands
+    /// that were not yet returned.
+    pub fn try_get_premise(&self, (depth, index): (usize, usize)) -> Option<&'a ProofCommand> {
+        self.stack.get(depth)?.1.get(index)
+    }
 }
 
 impl<'a> Iterator for ProofIter<'a> {
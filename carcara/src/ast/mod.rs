@@ -5,33 +5,44 @@
 #[macro_use]
 mod macros;
 mod deep_eq;
+mod equality_chain;
 mod iter;
 mod pool;
 pub(crate) mod printer;
 mod rc;
+#[cfg(feature = "serde")]
+mod serialization;
 mod substitution;
 #[cfg(test)]
 mod tests;
 
-pub use deep_eq::{are_alpha_equivalent, deep_eq, tracing_deep_eq};
+pub use deep_eq::{
+    alpha_equivalent, are_alpha_equivalent, deep_eq, tracing_deep_eq, DeepEqWitness,
+};
+pub use equality_chain::EqualityChain;
 pub use iter::ProofIter;
-pub use pool::TermPool;
+pub use pool::{PoolSnapshot, SnapshotError, TermPool, TermPoolStats};
 pub use printer::print_proof;
 pub use rc::Rc;
+#[cfg(feature = "serde")]
+pub use serialization::with_deserialization_context;
 pub use substitution::{Substitution, SubstitutionError};
 
 pub(crate) use deep_eq::{DeepEq, DeepEqualityChecker};
 
 use crate::checker::error::CheckerError;
-use ahash::AHashSet;
+use crate::parser::Position;
+use crate::{CarcaraResult, Error};
+use ahash::{AHashMap, AHashSet};
 use rug::Integer;
 use rug::Rational;
 use std::{hash::Hash, ops::Deref};
 
 /// The prelude of an SMT-LIB problem instance.
 ///
-/// This stores the sort declarations, function declarations and the problem's logic string.
-#[derive(Debug, Clone, Default)]
+/// This stores the sort declarations, function declarations and definitions, the assertions and
+/// the problem's logic string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ProblemPrelude {
     /// The sort declarations, each represented by its name and arity.
     pub(crate) sort_declarations: Vec<(String, usize)>,
@@ -39,12 +50,19 @@ pub struct ProblemPrelude {
     /// The function declarations, each represented by its name and body.
     pub(crate) function_declarations: Vec<(String, Rc<Term>)>,
 
+    /// The function definitions, each represented by its name, parameters, return sort and body.
+    pub(crate) function_definitions: Vec<(String, BindingList, Rc<Term>, Rc<Term>)>,
+
+    /// The terms introduced in `assert` commands, in the order they appear in the problem.
+    pub(crate) assertions: Vec<Rc<Term>>,
+
     /// The problem's logic string, if it exists.
     pub(crate) logic: Option<String>,
 }
 
 /// A proof in the Alethe format.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Proof {
     /// The proof's premises.
     ///
@@ -60,10 +78,236 @@ impl Proof {
     pub fn iter(&self) -> ProofIter {
         ProofIter::new(&self.commands)
     }
+
+    /// Returns the maximum nesting depth of subproofs in the proof. This is zero if the proof has
+    /// no subproofs, and one if it has subproofs, but none of them contain nested subproofs.
+    pub fn max_subproof_depth(&self) -> usize {
+        let mut iter = self.iter();
+        let mut result = 0;
+        while iter.next().is_some() {
+            result = result.max(iter.depth());
+        }
+        result
+    }
+
+    /// Returns the total number of subproofs in the proof, including nested subproofs.
+    pub fn subproof_count(&self) -> usize {
+        self.iter().filter(|c| c.is_subproof()).count()
+    }
+
+    /// Returns the steps needed to derive the empty clause, ordered so that each step comes after
+    /// the steps it depends on. A step depends on its premises and discharged assumptions, and, if
+    /// it ends a subproof, on the previous command in the subproof. A premise that refers to a
+    /// subproof is a dependency on its end step. The `assume` commands, and the steps that the
+    /// empty clause doesn't depend on, are not included.
+    ///
+    /// The steps are found with a depth-first search starting at the first step in the root proof
+    /// that concludes the empty clause, and are returned in the order in which the search finishes
+    /// visiting them. If there is no such step, this returns `Error::DoesNotReachEmptyClause`. If a
+    /// step depends on itself, this returns `Error::CyclicProof` with the id of a step in the cycle.
+    /// If a premise or discharged assumption doesn't refer to any command, this returns a checker
+    /// error with `CheckerError::InvalidPremiseIndex`.
+    pub fn topological_sort(&self) -> CarcaraResult<Vec<&ProofStep>> {
+        fn end_step(command: &ProofCommand) -> Option<&ProofStep> {
+            match command {
+                ProofCommand::Assume { .. } => None,
+                ProofCommand::Step(step) => Some(step),
+                ProofCommand::Subproof(s) => s.commands.last().and_then(end_step),
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut dependencies = Vec::new();
+        let mut iter = self.iter();
+        while let Some(command) = iter.next() {
+            let ProofCommand::Step(step) = command else {
+                continue;
+            };
+            let mut step_dependencies = Vec::new();
+            for &p in step.premises.iter().chain(&step.discharge) {
+                let premise = iter.try_get_premise(p).ok_or_else(|| Error::Checker {
+                    inner: CheckerError::InvalidPremiseIndex(p),
+                    rule: step.rule.clone(),
+                    step: step.id.clone(),
+                    position: step.source_position.clone(),
+                })?;
+                step_dependencies.extend(end_step(premise));
+            }
+            if iter.is_end_step() {
+                let len = iter.current_subproof().unwrap().len();
+                if len >= 2 {
+                    step_dependencies.extend(end_step(iter.get_premise((iter.depth(), len - 2))));
+                }
+            }
+            steps.push(step);
+            dependencies.push(step_dependencies);
+        }
+
+        // Since step ids may be repeated in proofs that were built programmatically, steps are
+        // identified by their addresses
+        let indices: AHashMap<*const ProofStep, usize> = steps
+            .iter()
+            .enumerate()
+            .map(|(i, &step)| (step as *const _, i))
+            .collect();
+        let root = self
+            .commands
+            .iter()
+            .find_map(|c| match c {
+                ProofCommand::Step(s) if s.clause.is_empty() => Some(indices[&(s as *const _)]),
+                _ => None,
+            })
+            .ok_or(Error::DoesNotReachEmptyClause)?;
+
+        // A step is "visited" once the search reaches it, and "finished" once all of its
+        // dependencies were added to the result. Reaching a step that was visited but not finished
+        // means that it depends on itself
+        let mut visited = vec![false; steps.len()];
+        let mut finished = vec![false; steps.len()];
+        let mut result = Vec::new();
+        let mut stack = vec![(root, 0)];
+        visited[root] = true;
+        while let Some(&(current, next)) = stack.last() {
+            match dependencies[current].get(next) {
+                Some(&dependency) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    let i = indices[&(dependency as *const _)];
+                    if !visited[i] {
+                        visited[i] = true;
+                        stack.push((i, 0));
+                    } else if !finished[i] {
+                        return Err(Error::CyclicProof(steps[i].id.clone()));
+                    }
+                }
+                None => {
+                    finished[current] = true;
+                    result.push(steps[current]);
+                    stack.pop();
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns a copy of the proof in which `prefix` is prepended to the id of every command,
+    /// including the ones inside subproofs. This can be used to avoid id collisions when combining
+    /// commands from different proofs. Since premises are referenced by their indices, they don't
+    /// need to be changed.
+    pub fn clone_with_fresh_ids(&self, prefix: &str) -> Proof {
+        fn rename(commands: &[ProofCommand], prefix: &str) -> Vec<ProofCommand> {
+            commands
+                .iter()
+                .map(|command| match command {
+                    ProofCommand::Assume { id, term } => ProofCommand::Assume {
+                        id: format!("{}{}", prefix, id),
+                        term: term.clone(),
+                    },
+                    ProofCommand::Step(step) => ProofCommand::Step(ProofStep {
+                        id: format!("{}{}", prefix, step.id),
+                        ..step.clone()
+                    }),
+                    ProofCommand::Subproof(subproof) => ProofCommand::Subproof(Subproof {
+                        commands: rename(&subproof.commands, prefix),
+                        assignment_args: subproof.assignment_args.clone(),
+                        variable_args: subproof.variable_args.clone(),
+                    }),
+                })
+                .collect()
+        }
+
+        Proof {
+            premises: self.premises.clone(),
+            commands: rename(&self.commands, prefix),
+        }
+    }
+}
+
+/// Estimates the number of bytes allocated on the heap by a proof. This includes the commands and
+/// the vectors and strings they contain, as well as every term reachable from the proof. Terms are
+/// counted once, even if they are referenced many times. The term pool, as well as any terms in it
+/// that are not reachable from the proof, are not included.
+///
+/// The estimate is based on the capacity of each vector and string, and does not include any
+/// overhead from the allocator itself.
+pub fn proof_size_bytes(proof: &Proof) -> usize {
+    use std::mem::size_of;
+
+    fn vec_size<T>(v: &Vec<T>) -> usize {
+        v.capacity() * size_of::<T>()
+    }
+
+    fn commands_size<'a>(commands: &'a Vec<ProofCommand>, terms: &mut Vec<&'a Rc<Term>>) -> usize {
+        let mut result = vec_size(commands);
+        for command in commands {
+            result += match command {
+                ProofCommand::Assume { id, term } => {
+                    terms.push(term);
+                    id.capacity()
+                }
+                ProofCommand::Step(step) => {
+                    terms.extend(&step.clause);
+                    let args: usize = step
+                        .args
+                        .iter()
+                        .map(|arg| match arg {
+                            ProofArg::Term(t) => {
+                                terms.push(t);
+                                0
+                            }
+                            ProofArg::Assign(name, t) => {
+                                terms.push(t);
+                                name.capacity()
+                            }
+                        })
+                        .sum();
+                    step.id.capacity()
+                        + step.rule.capacity()
+                        + vec_size(&step.clause)
+                        + vec_size(&step.premises)
+                        + vec_size(&step.args)
+                        + vec_size(&step.discharge)
+                        + args
+                }
+                ProofCommand::Subproof(subproof) => {
+                    let mut names = 0;
+                    for (name, t) in subproof
+                        .assignment_args
+                        .iter()
+                        .chain(&subproof.variable_args)
+                    {
+                        terms.push(t);
+                        names += name.capacity();
+                    }
+                    commands_size(&subproof.commands, terms)
+                        + vec_size(&subproof.assignment_args)
+                        + vec_size(&subproof.variable_args)
+                        + names
+                }
+            };
+        }
+        result
+    }
+
+    // Each bucket in the premises set stores the term and one control byte
+    let mut result = proof.premises.capacity() * (size_of::<Rc<Term>>() + 1);
+    let mut to_visit: Vec<&Rc<Term>> = proof.premises.iter().collect();
+    result += commands_size(&proof.commands, &mut to_visit);
+
+    // Each term is stored in its own `Rc` allocation, together with the strong and weak counts
+    let mut seen = AHashSet::new();
+    while let Some(term) = to_visit.pop() {
+        if !seen.insert(term.as_ref() as *const Term) {
+            continue;
+        }
+        result += 2 * size_of::<usize>() + size_of::<Term>() + pool::heap_size(term);
+        pool::for_each_child(term, |child| to_visit.push(child));
+    }
+    result
 }
 
 /// A proof command.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProofCommand {
     /// An `assume` command.
     Assume { id: String, term: Rc<Term> },
@@ -117,7 +361,8 @@ impl ProofCommand {
 }
 
 /// A `step` command.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProofStep {
     /// The step id.
     pub id: String,
@@ -141,8 +386,27 @@ pub struct ProofStep {
     /// The local premises that this step discharges, given via the `:discharge` attribute, and
     /// indexed similarly to premises.
     pub discharge: Vec<(usize, usize)>,
+
+    /// The position in the proof file where the step starts. This is `None` for steps that were
+    /// not parsed from a proof file, like the ones introduced during elaboration. It is not taken
+    /// into account when comparing steps for equality.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_position: Option<Position>,
+}
+
+impl PartialEq for ProofStep {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.clause == other.clause
+            && self.rule == other.rule
+            && self.premises == other.premises
+            && self.args == other.args
+            && self.discharge == other.discharge
+    }
 }
 
+impl Eq for ProofStep {}
+
 /// A subproof.
 ///
 /// Subproofs are started by `anchor` commands, and contain a series of steps, possibly including
@@ -162,6 +426,7 @@ pub struct Subproof {
 
 /// An argument for a `step` command.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProofArg {
     /// An argument that is just a term.
     Term(Rc<Term>),
@@ -191,6 +456,7 @@ impl ProofArg {
 }
 
 /// The operator of an operation term.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Operator {
     // Logic
@@ -267,6 +533,13 @@ pub enum Operator {
 
     /// The `store` operator.
     Store,
+
+    // Strings
+    /// The `str.++` operator.
+    StrConcat,
+
+    /// The `str.len` operator.
+    StrLen,
 }
 
 impl_str_conversion_traits!(Operator {
@@ -296,6 +569,9 @@ impl_str_conversion_traits!(Operator {
 
     Select: "select",
     Store: "store",
+
+    StrConcat: "str.++",
+    StrLen: "str.len",
 });
 
 /// A variable and an associated sort.
@@ -335,6 +611,7 @@ pub enum Sort {
 }
 
 /// A quantifier, either `forall` or `exists`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Quantifier {
     /// The `forall` quantifier.
@@ -607,6 +884,149 @@ impl Term {
             false => self.is_bool_false(),
         }
     }
+
+    /// Returns `true` if the term is trivially valid. That is the case for the constant `true`,
+    /// equalities whose arguments are all the same term, negations of trivial contradictions,
+    /// conjunctions of trivial tautologies, disjunctions that contain a trivial tautology or a term
+    /// and its negation, and implications with a trivially contradictory premise or a trivially
+    /// valid conclusion. Since this is only a syntactic check, most tautologies are not detected.
+    pub fn is_boolean_tautology(&self) -> bool {
+        match self {
+            _ if self.is_bool_true() => true,
+            Term::Op(Operator::Not, args) => args[0].is_boolean_contradiction(),
+            Term::Op(Operator::And, args) => args.iter().all(|t| t.is_boolean_tautology()),
+            Term::Op(Operator::Or, args) => {
+                args.iter().any(|t| t.is_boolean_tautology()) || has_complementary_terms(args)
+            }
+            Term::Op(Operator::Implies, args) => {
+                let (conclusion, premises) = args.split_last().unwrap();
+                conclusion.is_boolean_tautology()
+                    || premises.iter().any(|t| t.is_boolean_contradiction())
+            }
+            Term::Op(Operator::Equals, args) => args.windows(2).all(|w| w[0] == w[1]),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the term is trivially unsatisfiable. This is the dual of
+    /// [`Term::is_boolean_tautology`], and is the case for the constant `false`, negations of
+    /// trivial tautologies, conjunctions that contain a trivial contradiction or a term and its
+    /// negation, disjunctions of trivial contradictions, and `distinct` terms with a repeated
+    /// argument.
+    pub fn is_boolean_contradiction(&self) -> bool {
+        match self {
+            _ if self.is_bool_false() => true,
+            Term::Op(Operator::Not, args) => args[0].is_boolean_tautology(),
+            Term::Op(Operator::And, args) => {
+                args.iter().any(|t| t.is_boolean_contradiction()) || has_complementary_terms(args)
+            }
+            Term::Op(Operator::Or, args) => args.iter().all(|t| t.is_boolean_contradiction()),
+            Term::Op(Operator::Distinct, args) => {
+                let mut seen = AHashSet::new();
+                !args.iter().all(|t| seen.insert(t))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the immediate subterms of this term. The sorts of variables and binders are not
+    /// considered subterms, but the values in `let` bindings are.
+    fn children(&self) -> Vec<&Rc<Term>> {
+        match self {
+            Term::Terminal(_) | Term::Sort(_) => Vec::new(),
+            Term::App(f, args) => std::iter::once(f).chain(args).collect(),
+            Term::Op(_, args) => args.iter().collect(),
+            Term::Quant(_, _, body) | Term::Choice(_, body) | Term::Lambda(_, body) => vec![body],
+            Term::Let(bindings, body) => bindings
+                .iter()
+                .map(|(_, value)| value)
+                .chain(std::iter::once(body))
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if `needle` is a subterm of this term, or the term itself. Note that this
+    /// does not take binders into account, so bound occurrences of a variable are also found. To
+    /// find only the free variables in a term, use [`TermPool::free_vars`].
+    pub fn contains(&self, needle: &Term) -> bool {
+        let mut visited: AHashSet<*const Term> = AHashSet::new();
+        let mut stack = vec![self];
+        while let Some(term) = stack.pop() {
+            if term == needle {
+                return true;
+            }
+            if visited.insert(term as *const _) {
+                stack.extend(term.children().into_iter().map(Rc::as_ref));
+            }
+        }
+        false
+    }
+
+    /// Returns all occurrences of `needle` in this term, in pre-order. A subterm that appears in
+    /// several places of the term is visited once for each occurrence. Like [`Term::contains`],
+    /// this does not take binders into account.
+    pub fn find_all(&self, needle: &Term) -> Vec<&Term> {
+        let mut result = Vec::new();
+        let mut stack = vec![self];
+        while let Some(term) = stack.pop() {
+            if term == needle {
+                result.push(term);
+            } else {
+                stack.extend(term.children().into_iter().rev().map(Rc::as_ref));
+            }
+        }
+        result
+    }
+
+    /// Returns the depth of the term, that is, the length of the longest path from the term to one
+    /// of its leaves. Terminals and sorts have depth zero. Like [`Term::children`], this doesn't
+    /// consider the sorts of variables and binders as subterms.
+    pub fn depth(&self) -> usize {
+        self.fold_subterms(|children| children.iter().max().map_or(0, |d| d + 1))
+    }
+
+    /// Returns the number of nodes in the term, counting a subterm that appears in several places
+    /// once for each occurrence. Since terms may share subterms, this can be much larger than the
+    /// number of distinct subterms, so the result saturates at `usize::MAX`.
+    pub fn size(&self) -> usize {
+        self.fold_subterms(|children| {
+            children
+                .iter()
+                .fold(1, |acc: usize, &size| acc.saturating_add(size))
+        })
+    }
+
+    /// Computes a value for the term bottom-up, calling `combine` with the values computed for the
+    /// immediate subterms of each subterm. This uses an explicit stack instead of recursion, so it
+    /// doesn't overflow on very deep terms, and the value for each distinct subterm is computed
+    /// only once.
+    fn fold_subterms(&self, combine: impl Fn(&[usize]) -> usize) -> usize {
+        let mut cache: AHashMap<*const Term, usize> = AHashMap::new();
+        let mut stack = vec![(self, false)];
+        while let Some((term, children_visited)) = stack.pop() {
+            if children_visited {
+                let values: Vec<_> = term
+                    .children()
+                    .into_iter()
+                    .map(|child| cache[&(child.as_ref() as *const _)])
+                    .collect();
+                cache.insert(term as *const _, combine(&values));
+            } else if !cache.contains_key(&(term as *const _)) {
+                stack.push((term, true));
+                stack.extend(term.children().into_iter().map(|c| (c.as_ref(), false)));
+            }
+        }
+        cache[&(self as *const _)]
+    }
+}
+
+/// Returns `true` if some term in `terms` appears both as is and negated.
+fn has_complementary_terms(terms: &[Rc<Term>]) -> bool {
+    let terms: AHashSet<_> = terms.iter().collect();
+    terms.iter().any(|t| {
+        t.remove_negation()
+            .map_or(false, |inner| terms.contains(inner))
+    })
 }
 
 impl Rc<Term> {
@@ -697,6 +1117,7 @@ pub enum Terminal {
 }
 
 /// An identifier.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Identifier {
     /// A simple identifier, consisting of a symbol.
@@ -707,6 +1128,7 @@ pub enum Identifier {
 }
 
 /// An index for an indexed identifier. This can be either a numeral or a symbol.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum IdentifierIndex {
     Numeral(u64),
@@ -1,7 +1,12 @@
 //! This module implements `TermPool`, a structure that stores terms and implements hash consing.
 
-use super::{Identifier, Rc, Sort, Term, Terminal};
+use super::{
+    BindingList, Identifier, IdentifierIndex, Operator, ProblemPrelude, Quantifier, Rc, Sort, Term,
+    Terminal,
+};
 use ahash::{AHashMap, AHashSet};
+use std::{fmt, mem::size_of};
+use thiserror::Error;
 
 /// A structure to store and manage all allocated terms.
 ///
@@ -12,6 +17,7 @@ use ahash::{AHashMap, AHashSet};
 ///
 /// This struct also provides other utility methods, like computing the sort of a term (see
 /// [`TermPool::sort`]) or its free variables (see [`TermPool::free_vars`]).
+#[derive(Clone)]
 pub struct TermPool {
     /// A map of the terms in the pool.
     pub(crate) terms: AHashMap<Term, Rc<Term>>,
@@ -19,6 +25,113 @@ pub struct TermPool {
     sorts_cache: AHashMap<Rc<Term>, Sort>,
     bool_true: Rc<Term>,
     bool_false: Rc<Term>,
+
+    /// The number of calls to `TermPool::add`, and how many of those found the term already in the
+    /// pool. These are used to compute the hit rate in `TermPool::stats`.
+    num_add_calls: u64,
+    num_add_hits: u64,
+}
+
+/// A copy of all the terms stored in a `TermPool`, that doesn't share any allocations with the
+/// pool. The terms are stored in a list, and each term references the terms it contains by their
+/// indices in this list. Every term comes after the terms it references. See
+/// [`TermPool::to_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolSnapshot {
+    pub(crate) terms: Vec<SnapshotTerm>,
+}
+
+impl PoolSnapshot {
+    /// Returns the number of terms in the snapshot.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Returns `true` if the snapshot contains no terms.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// A term in a `PoolSnapshot`. This mirrors `Term`, except that each term directly referenced by
+/// it is replaced by its index in the snapshot. Numerical constants are stored as strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapshotTerm {
+    Integer(String),
+    Real(String),
+    String(String),
+    Var(Identifier, usize),
+    App(usize, Vec<usize>),
+    Op(Operator, Vec<usize>),
+    Sort(SnapshotSort),
+    Quant(Quantifier, Vec<(String, usize)>, usize),
+    Choice((String, usize), usize),
+    Let(Vec<(String, usize)>, usize),
+    Lambda(Vec<(String, usize)>, usize),
+}
+
+/// A sort in a `PoolSnapshot`. See [`SnapshotTerm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapshotSort {
+    Function(Vec<usize>),
+    Atom(String, Vec<usize>),
+    Bool,
+    Int,
+    Real,
+    String,
+    Array(usize, usize),
+}
+
+/// The error type for errors when constructing a `TermPool` from an invalid `PoolSnapshot`. The
+/// first field of each variant is the index in the snapshot of the term that caused the error.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SnapshotError {
+    /// A term references a term that doesn't come before it in the snapshot.
+    #[error("term {0} references term {1}, which doesn't come before it")]
+    InvalidIndex(usize, usize),
+
+    /// A term references a term that should be a sort, but isn't.
+    #[error("term {0} expects term {1} to be a sort")]
+    ExpectedSort(usize, usize),
+
+    /// A numerical constant couldn't be parsed.
+    #[error("term {0} is not a valid numerical constant")]
+    InvalidNumeral(usize),
+
+    /// The sort of a term couldn't be computed, for example because it applies a term that is not
+    /// a function.
+    #[error("term {0} is not well-sorted")]
+    IllSorted(usize),
+}
+
+/// Statistics about the terms stored in a `TermPool`. See [`TermPool::stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TermPoolStats {
+    /// The number of unique terms stored in the pool.
+    pub unique_terms: usize,
+
+    /// An estimate of the number of bytes allocated to store the terms in the pool. This does not
+    /// include the memory used by the pool's caches.
+    pub total_alloc_bytes: usize,
+
+    /// The ratio of calls to `TermPool::add` that found the term already in the pool. If `add` was
+    /// never called, this is zero.
+    pub hit_rate: f64,
+}
+
+impl fmt::Display for TermPoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} unique terms, ~{:.02} KiB allocated, {:.02}% hit rate",
+            self.unique_terms,
+            self.total_alloc_bytes as f64 / 1024.0,
+            self.hit_rate * 100.0,
+        )
+    }
 }
 
 impl Default for TermPool {
@@ -31,7 +144,113 @@ impl TermPool {
     /// Constructs a new `TermPool`. This new pool will already contain the boolean constants `true`
     /// and `false`, as well as the `Bool` sort.
     pub fn new() -> Self {
+        Self::with_terms(AHashMap::new())
+    }
+
+    /// Constructs a new `TermPool` that already contains all the terms in `prelude`, as well as their
+    /// subterms. The terms are not copied: the new pool reuses the same allocations, so the terms in
+    /// the prelude can be used with the new pool as if they had been added to it.
+    ///
+    /// This is useful when checking many proofs for the same problem, since each proof can use its
+    /// own pool without the problem having to be parsed again.
+    pub fn from_prelude(prelude: &ProblemPrelude) -> Self {
+        let mut to_visit: Vec<&Rc<Term>> = prelude
+            .function_declarations
+            .iter()
+            .map(|(_, sort)| sort)
+            .chain(prelude.assertions.iter())
+            .collect();
+        for (_, params, return_sort, body) in &prelude.function_definitions {
+            to_visit.extend(params.iter().map(|(_, sort)| sort));
+            to_visit.extend([return_sort, body]);
+        }
+
         let mut terms = AHashMap::new();
+        while let Some(term) = to_visit.pop() {
+            if terms.contains_key(term.as_ref()) {
+                continue;
+            }
+            terms.insert(term.as_ref().clone(), term.clone());
+            for_each_child(term, |child| to_visit.push(child));
+        }
+
+        let mut pool = Self::with_terms(terms);
+        let all_terms: Vec<_> = pool.terms.values().cloned().collect();
+        for term in &all_terms {
+            pool.compute_sort(term);
+        }
+        pool
+    }
+
+    /// Returns a snapshot of all the terms in the pool, that can be used to construct a new pool
+    /// with the same terms using [`TermPool::from_snapshot`]. Since the snapshot doesn't reference
+    /// the terms in the pool, it can outlive it, and, with the `serde` feature, be serialized.
+    pub fn to_snapshot(&self) -> PoolSnapshot {
+        let mut terms = Vec::with_capacity(self.terms.len());
+        let mut indices: AHashMap<&Rc<Term>, usize> = AHashMap::with_capacity(self.terms.len());
+
+        // The terms are visited in post-order, so that each term is added to the snapshot after all
+        // the terms it references
+        for root in self.terms.values() {
+            let mut stack = vec![(root, false)];
+            while let Some((term, children_visited)) = stack.pop() {
+                if children_visited {
+                    indices.insert(term, terms.len());
+                    terms.push(to_snapshot_term(term, |child| indices[child]));
+                } else if !indices.contains_key(term) {
+                    stack.push((term, true));
+                    for_each_child(term, |child| {
+                        if !indices.contains_key(child) {
+                            stack.push((child, false));
+                        }
+                    });
+                }
+            }
+        }
+        PoolSnapshot { terms }
+    }
+
+    /// Constructs a new `TermPool` containing the terms in `snapshot`, usually one returned by
+    /// [`TermPool::to_snapshot`]. The terms are added to new allocations, so terms from the
+    /// original pool must not be used with the new pool.
+    ///
+    /// Since a snapshot may be deserialized from untrusted input, it is validated while the terms
+    /// are added. This returns an error if a term references a term that doesn't come before it,
+    /// if a term that should be a sort isn't one, if a numerical constant is invalid, or if the
+    /// sort of a term can't be computed.
+    pub fn from_snapshot(snapshot: &PoolSnapshot) -> Result<Self, SnapshotError> {
+        let mut pool = Self::new();
+        let mut new_allocations: Vec<Rc<Term>> = Vec::with_capacity(snapshot.len());
+        for (position, term) in snapshot.terms.iter().enumerate() {
+            let new_term = from_snapshot_term(term, position, &new_allocations)?;
+            if !pool.is_sortable(&new_term) {
+                return Err(SnapshotError::IllSorted(position));
+            }
+            let new_term = Self::add_term_to_map(&mut pool.terms, new_term);
+            pool.compute_sort(&new_term);
+            new_allocations.push(new_term);
+        }
+        Ok(pool)
+    }
+
+    /// Returns `true` if `TermPool::compute_sort` can compute the sort of `term`, assuming the
+    /// sorts of the terms it references were already computed. This is only needed for terms that
+    /// didn't come from the parser, which ensures that all terms are well-sorted.
+    fn is_sortable(&self, term: &Term) -> bool {
+        match term {
+            Term::Op(Operator::Ite, args) => args.len() > 1,
+            Term::Op(Operator::Select, args) => {
+                matches!(args.first().map(|a| self.sort(a)), Some(Sort::Array(_, _)))
+            }
+            Term::Op(Operator::Store, args) => !args.is_empty(),
+            Term::App(f, _) => matches!(self.sort(f), Sort::Function(sorts) if !sorts.is_empty()),
+            _ => true,
+        }
+    }
+
+    /// Constructs a new `TermPool` from an existing map of terms, adding the boolean constants and
+    /// the `Bool` sort to it if they are not already there.
+    fn with_terms(mut terms: AHashMap<Term, Rc<Term>>) -> Self {
         let mut sorts_cache = AHashMap::new();
         let bool_sort = Self::add_term_to_map(&mut terms, Term::Sort(Sort::Bool));
 
@@ -55,6 +274,8 @@ impl TermPool {
             sorts_cache,
             bool_true,
             bool_false,
+            num_add_calls: 0,
+            num_add_hits: 0,
         }
     }
 
@@ -94,11 +315,24 @@ impl TermPool {
     /// just returns an `Rc` pointing to the existing allocation. This method also computes the
     /// term's sort, and adds it to the sort cache.
     pub fn add(&mut self, term: Term) -> Rc<Term> {
+        let previous_len = self.terms.len();
         let term = Self::add_term_to_map(&mut self.terms, term);
+        self.num_add_calls += 1;
+        self.num_add_hits += (self.terms.len() == previous_len) as u64;
         self.compute_sort(&term);
         term
     }
 
+    /// Returns the existing allocation of a term, if it is already in the pool. Unlike
+    /// [`TermPool::add`], this never adds the term to the pool.
+    ///
+    /// Since terms are compared by reference, a term that is not in the pool can't be equal to any
+    /// term that is. This method can then be used to check whether a term is among a set of terms
+    /// from the pool, without adding it to the pool when it is not.
+    pub fn find(&self, term: &Term) -> Option<Rc<Term>> {
+        self.terms.get(term).cloned()
+    }
+
     /// Takes a vector of terms and calls [`TermPool::add`] on each.
     pub fn add_all(&mut self, terms: Vec<Term>) -> Vec<Rc<Term>> {
         terms.into_iter().map(|t| self.add(t)).collect()
@@ -155,6 +389,8 @@ impl TermPool {
                     _ => unreachable!(),
                 },
                 Operator::Store => self.compute_sort(&args[0]).clone(),
+                Operator::StrConcat => Sort::String,
+                Operator::StrLen => Sort::Int,
             },
             Term::App(f, _) => {
                 match self.compute_sort(f) {
@@ -249,4 +485,290 @@ impl TermPool {
         self.free_vars_cache.insert(term.clone(), set);
         self.free_vars_cache.get(term).unwrap()
     }
+
+    /// Returns statistics about the terms stored in the pool, and how often terms added to it were
+    /// already present.
+    pub fn stats(&self) -> TermPoolStats {
+        // Each term is stored twice: once as the key in the terms map, and once in the allocation
+        // pointed to by the value
+        let total_alloc_bytes = self
+            .terms
+            .keys()
+            .map(|term| {
+                let rc_allocation = 2 * size_of::<usize>() + size_of::<Term>();
+                size_of::<Term>() + size_of::<Rc<Term>>() + rc_allocation + 2 * heap_size(term)
+            })
+            .sum();
+        let hit_rate = if self.num_add_calls == 0 {
+            0.0
+        } else {
+            self.num_add_hits as f64 / self.num_add_calls as f64
+        };
+        TermPoolStats {
+            unique_terms: self.terms.len(),
+            total_alloc_bytes,
+            hit_rate,
+        }
+    }
+
+    /// Returns the number of terms currently stored in the pool.
+    pub fn live_term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Removes from the pool all terms that are not referenced outside of it, and returns the
+    /// number of terms removed. This also clears the free variables cache.
+    ///
+    /// Normally, terms are never removed from the pool, so memory usage grows monotonically. This
+    /// method can be called periodically by long-running processes that reuse the same pool, to
+    /// free the terms that are no longer needed.
+    pub fn gc(&mut self) -> usize {
+        // Since the free variables cache is just a cache, we can simply clear it, removing all the
+        // references it holds
+        self.free_vars_cache.clear();
+
+        // To find which terms are referenced outside the pool, we first count how many references
+        // to each term the pool itself holds. These are: the `Rc` stored in the terms map; the
+        // children of each term, which are referenced both by the key in the terms map and by the
+        // allocation pointed to by the value; and the keys and values in the sorts cache. The
+        // `bool_true` and `bool_false` fields are not counted, so that these terms are always
+        // considered to be referenced
+        let mut internal_counts: AHashMap<*const Term, usize> = AHashMap::new();
+        let mut increment = |term: &Rc<Term>, n: usize| {
+            *internal_counts
+                .entry(term.as_ref() as *const _)
+                .or_default() += n;
+        };
+        for (key, value) in &self.terms {
+            increment(value, 1);
+            for_each_child(key, |child| increment(child, 2));
+        }
+        for (term, sort) in &self.sorts_cache {
+            increment(term, 1);
+            for_each_sort_child(sort, |child| increment(child, 1));
+        }
+
+        // Any term that has more references than the pool holds is referenced from outside the
+        // pool, so it must be kept, as well as all the terms reachable from it
+        let mut to_visit: Vec<&Rc<Term>> = self
+            .terms
+            .values()
+            .filter(|t| Rc::strong_count(t) > internal_counts[&(t.as_ref() as *const _)])
+            .collect();
+        let mut reachable: AHashSet<*const Term> = AHashSet::new();
+        while let Some(term) = to_visit.pop() {
+            if !reachable.insert(term.as_ref() as *const _) {
+                continue;
+            }
+            for_each_child(term, |child| to_visit.push(child));
+            if let Some(sort) = self.sorts_cache.get(term) {
+                for_each_sort_child(sort, |child| to_visit.push(child));
+            }
+        }
+
+        // All other terms are only referenced by each other, so we can safely remove them
+        let old_len = self.terms.len();
+        self.terms
+            .retain(|_, term| reachable.contains(&(term.as_ref() as *const _)));
+        self.sorts_cache
+            .retain(|term, _| reachable.contains(&(term.as_ref() as *const _)));
+        old_len - self.terms.len()
+    }
+}
+
+/// Estimates the number of bytes allocated on the heap by a term, not including the terms it
+/// references.
+pub(super) fn heap_size(term: &Term) -> usize {
+    fn vec_size<T>(v: &Vec<T>) -> usize {
+        v.capacity() * size_of::<T>()
+    }
+
+    fn identifier_size(identifier: &Identifier) -> usize {
+        match identifier {
+            Identifier::Simple(s) => s.capacity(),
+            Identifier::Indexed(s, indices) => {
+                let symbols: usize = indices
+                    .iter()
+                    .map(|i| match i {
+                        IdentifierIndex::Numeral(_) => 0,
+                        IdentifierIndex::Symbol(s) => s.capacity(),
+                    })
+                    .sum();
+                s.capacity() + vec_size(indices) + symbols
+            }
+        }
+    }
+
+    fn sort_size(sort: &Sort) -> usize {
+        match sort {
+            Sort::Function(args) => vec_size(args),
+            Sort::Atom(name, args) => name.capacity() + vec_size(args),
+            Sort::Bool | Sort::Int | Sort::Real | Sort::String | Sort::Array(_, _) => 0,
+        }
+    }
+
+    match term {
+        Term::Terminal(Terminal::Integer(i)) => (i.significant_bits() as usize + 7) / 8,
+        Term::Terminal(Terminal::Real(r)) => {
+            let bits = r.numer().significant_bits() + r.denom().significant_bits();
+            (bits as usize + 7) / 8
+        }
+        Term::Terminal(Terminal::String(s)) => s.capacity(),
+        Term::Terminal(Terminal::Var(identifier, _)) => identifier_size(identifier),
+        Term::App(_, args) | Term::Op(_, args) => vec_size(args),
+        Term::Sort(sort) => sort_size(sort),
+        Term::Quant(_, bindings, _) | Term::Let(bindings, _) | Term::Lambda(bindings, _) => {
+            let names: usize = bindings.iter().map(|(name, _)| name.capacity()).sum();
+            vec_size(&bindings.0) + names
+        }
+        Term::Choice((name, _), _) => name.capacity(),
+    }
+}
+
+/// Calls `f` on each term directly referenced by `term`.
+pub(super) fn for_each_child<'a>(term: &'a Term, mut f: impl FnMut(&'a Rc<Term>)) {
+    match term {
+        Term::Terminal(Terminal::Var(_, sort)) => f(sort),
+        Term::Terminal(_) => (),
+        Term::App(func, args) => {
+            f(func);
+            args.iter().for_each(f);
+        }
+        Term::Op(_, args) => args.iter().for_each(f),
+        Term::Sort(sort) => for_each_sort_child(sort, f),
+        Term::Quant(_, bindings, inner)
+        | Term::Let(bindings, inner)
+        | Term::Lambda(bindings, inner) => {
+            for (_, value) in bindings {
+                f(value);
+            }
+            f(inner);
+        }
+        Term::Choice((_, sort), inner) => {
+            f(sort);
+            f(inner);
+        }
+    }
+}
+
+/// Converts `term` to a `SnapshotTerm`, where `index` gives the index of each term directly
+/// referenced by it.
+fn to_snapshot_term(term: &Term, mut index: impl FnMut(&Rc<Term>) -> usize) -> SnapshotTerm {
+    let mut map_bindings = |bindings: &BindingList| -> Vec<(String, usize)> {
+        bindings
+            .iter()
+            .map(|(x, v)| (x.clone(), index(v)))
+            .collect()
+    };
+    match term {
+        Term::Terminal(Terminal::Integer(i)) => SnapshotTerm::Integer(i.to_string()),
+        Term::Terminal(Terminal::Real(r)) => SnapshotTerm::Real(r.to_string()),
+        Term::Terminal(Terminal::String(s)) => SnapshotTerm::String(s.clone()),
+        Term::Terminal(Terminal::Var(iden, sort)) => SnapshotTerm::Var(iden.clone(), index(sort)),
+        Term::App(func, args) => SnapshotTerm::App(index(func), args.iter().map(index).collect()),
+        Term::Op(op, args) => SnapshotTerm::Op(*op, args.iter().map(index).collect()),
+        Term::Sort(sort) => SnapshotTerm::Sort(match sort {
+            Sort::Function(args) => SnapshotSort::Function(args.iter().map(index).collect()),
+            Sort::Atom(name, args) => {
+                SnapshotSort::Atom(name.clone(), args.iter().map(index).collect())
+            }
+            Sort::Bool => SnapshotSort::Bool,
+            Sort::Int => SnapshotSort::Int,
+            Sort::Real => SnapshotSort::Real,
+            Sort::String => SnapshotSort::String,
+            Sort::Array(x, y) => SnapshotSort::Array(index(x), index(y)),
+        }),
+        Term::Quant(q, bindings, inner) => {
+            let bindings = map_bindings(bindings);
+            SnapshotTerm::Quant(*q, bindings, index(inner))
+        }
+        Term::Choice((name, sort), inner) => {
+            SnapshotTerm::Choice((name.clone(), index(sort)), index(inner))
+        }
+        Term::Let(bindings, inner) => {
+            let bindings = map_bindings(bindings);
+            SnapshotTerm::Let(bindings, index(inner))
+        }
+        Term::Lambda(bindings, inner) => {
+            let bindings = map_bindings(bindings);
+            SnapshotTerm::Lambda(bindings, index(inner))
+        }
+    }
+}
+
+/// Converts the term at `position` in a snapshot back to a `Term`, where `terms` holds the
+/// terms that come before it, already converted. Returns an error if `term` references a term
+/// that is not in `terms`, or that has the wrong kind.
+fn from_snapshot_term(
+    term: &SnapshotTerm,
+    position: usize,
+    terms: &[Rc<Term>],
+) -> Result<Term, SnapshotError> {
+    let get = |i: usize| {
+        terms
+            .get(i)
+            .cloned()
+            .ok_or(SnapshotError::InvalidIndex(position, i))
+    };
+    let get_sort = |i: usize| match get(i)? {
+        t if t.is_sort() => Ok(t),
+        _ => Err(SnapshotError::ExpectedSort(position, i)),
+    };
+    let map_all = |args: &[usize]| -> Result<Vec<_>, _> { args.iter().map(|&i| get(i)).collect() };
+    let map_sorts =
+        |args: &[usize]| -> Result<Vec<_>, _> { args.iter().map(|&i| get_sort(i)).collect() };
+    let map_bindings = |bindings: &[(String, usize)], get: &dyn Fn(usize) -> Result<_, _>| {
+        bindings
+            .iter()
+            .map(|(x, i)| Ok((x.clone(), get(*i)?)))
+            .collect::<Result<_, _>>()
+            .map(BindingList)
+    };
+
+    Ok(match term {
+        SnapshotTerm::Integer(i) => Term::Terminal(Terminal::Integer(
+            i.parse()
+                .map_err(|_| SnapshotError::InvalidNumeral(position))?,
+        )),
+        SnapshotTerm::Real(r) => Term::Terminal(Terminal::Real(
+            r.parse()
+                .map_err(|_| SnapshotError::InvalidNumeral(position))?,
+        )),
+        SnapshotTerm::String(s) => Term::Terminal(Terminal::String(s.clone())),
+        SnapshotTerm::Var(iden, sort) => {
+            Term::Terminal(Terminal::Var(iden.clone(), get_sort(*sort)?))
+        }
+        SnapshotTerm::App(func, args) => Term::App(get(*func)?, map_all(args)?),
+        SnapshotTerm::Op(op, args) => Term::Op(*op, map_all(args)?),
+        SnapshotTerm::Sort(sort) => Term::Sort(match sort {
+            SnapshotSort::Function(args) => Sort::Function(map_sorts(args)?),
+            SnapshotSort::Atom(name, args) => Sort::Atom(name.clone(), map_sorts(args)?),
+            SnapshotSort::Bool => Sort::Bool,
+            SnapshotSort::Int => Sort::Int,
+            SnapshotSort::Real => Sort::Real,
+            SnapshotSort::String => Sort::String,
+            SnapshotSort::Array(x, y) => Sort::Array(get_sort(*x)?, get_sort(*y)?),
+        }),
+        SnapshotTerm::Quant(q, bindings, inner) => {
+            Term::Quant(*q, map_bindings(bindings, &get_sort)?, get(*inner)?)
+        }
+        SnapshotTerm::Choice((name, sort), inner) => {
+            Term::Choice((name.clone(), get_sort(*sort)?), get(*inner)?)
+        }
+        SnapshotTerm::Let(bindings, inner) => {
+            Term::Let(map_bindings(bindings, &get)?, get(*inner)?)
+        }
+        SnapshotTerm::Lambda(bindings, inner) => {
+            Term::Lambda(map_bindings(bindings, &get_sort)?, get(*inner)?)
+        }
+    })
+}
+
+/// Calls `f` on each term directly referenced by `sort`.
+fn for_each_sort_child<'a>(sort: &'a Sort, f: impl FnMut(&'a Rc<Term>)) {
+    match sort {
+        Sort::Function(args) | Sort::Atom(_, args) => args.iter().for_each(f),
+        Sort::Array(x, y) => [x, y].into_iter().for_each(f),
+        Sort::Bool | Sort::Int | Sort::Real | Sort::String => (),
+    }
 }
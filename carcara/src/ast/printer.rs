@@ -23,6 +23,22 @@ pub fn print_proof(commands: &[ProofCommand], use_sharing: bool) -> io::Result<(
     printer.write_proof(commands)
 }
 
+/// Writes the commands of a proof to `dest`, without term sharing, starting at the command with
+/// index `start` in the root proof. The earlier commands are not written, and are only used to find
+/// the ids of premises.
+pub fn write_proof_starting_at(
+    dest: &mut dyn io::Write,
+    commands: &[ProofCommand],
+    start: usize,
+) -> io::Result<()> {
+    let mut printer = AlethePrinter {
+        inner: dest,
+        term_indices: None,
+        term_sharing_variable_prefix: "@p_",
+    };
+    printer.write_commands(ProofIter::starting_at(commands, start))
+}
+
 /// Given the conclusion clause of a `lia_generic` step, this method will write to `dest` the
 /// corresponding SMT problem instance.
 pub fn write_lia_smt_instance(
@@ -113,8 +129,22 @@ struct AlethePrinter<'a> {
 
 impl<'a> PrintProof for AlethePrinter<'a> {
     fn write_proof(&mut self, commands: &[ProofCommand]) -> io::Result<()> {
-        let mut iter = ProofIter::new(commands);
+        self.write_commands(ProofIter::new(commands))
+    }
+}
+
+impl<'a> AlethePrinter<'a> {
+    fn write_commands(&mut self, mut iter: ProofIter) -> io::Result<()> {
         while let Some(command) = iter.next() {
+            // When the iterator yields an `anchor` command, it has already entered the subproof, so
+            // we indent it, as well as the step that closes the subproof, one level less
+            let indentation = match command {
+                ProofCommand::Subproof(_) => iter.depth() - 1,
+                _ if iter.is_end_step() => iter.depth() - 1,
+                _ => iter.depth(),
+            };
+            write!(self.inner, "{}", "  ".repeat(indentation))?;
+
             match command {
                 ProofCommand::Assume { id, term } => {
                     write!(self.inner, "(assume {} ", id)?;
@@ -154,9 +184,7 @@ impl<'a> PrintProof for AlethePrinter<'a> {
 
         Ok(())
     }
-}
 
-impl<'a> AlethePrinter<'a> {
     fn write_s_expr<H, T>(&mut self, head: &H, tail: &[T]) -> io::Result<()>
     where
         H: PrintWithSharing + ?Sized,
@@ -313,17 +341,24 @@ fn escape_string(string: &str) -> Cow<str> {
     }
 }
 
-impl fmt::Display for Term {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Term {
+    /// Returns the term as an SMT-LIB s-expression, without making use of sharing. This is the
+    /// representation used when displaying terms, for example in error messages.
+    pub fn to_smt2_string(&self) -> String {
         let mut buf = Vec::new();
         let mut printer = AlethePrinter {
             inner: &mut buf,
-            term_indices: Some(AHashMap::new()),
-            term_sharing_variable_prefix: "@p_",
+            term_indices: None,
+            term_sharing_variable_prefix: "",
         };
         printer.write_raw_term(self).unwrap();
-        let result = std::str::from_utf8(&buf).unwrap();
-        write!(f, "{}", result)
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_smt2_string())
     }
 }
 
@@ -438,11 +473,11 @@ impl fmt::Display for ProblemPrelude {
         writeln!(f, "(set-logic {})", self.logic.as_deref().unwrap_or("ALL"))?;
 
         for (name, arity) in &self.sort_declarations {
-            writeln!(f, "(declare-sort {} {})", name, arity)?;
+            writeln!(f, "(declare-sort {} {})", quote_symbol(name), arity)?;
         }
 
         for (name, sort) in &self.function_declarations {
-            write!(f, "(declare-fun {} ", name)?;
+            write!(f, "(declare-fun {} ", quote_symbol(name))?;
             if let Sort::Function(sorts) = sort.as_sort().unwrap() {
                 write_s_expr(f, &sorts[0], &sorts[1..sorts.len() - 1])?;
                 writeln!(f, " {})", sorts.last().unwrap())?;
@@ -453,3 +488,47 @@ impl fmt::Display for ProblemPrelude {
         Ok(())
     }
 }
+
+impl Proof {
+    /// Writes the proof to `out` in the Alethe format. Commands inside subproofs are indented
+    /// according to their nesting depth.
+    pub fn to_alethe(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        let mut printer = AlethePrinter {
+            inner: out,
+            term_indices: None,
+            term_sharing_variable_prefix: "",
+        };
+        printer.write_proof(&self.commands)
+    }
+}
+
+impl ProblemPrelude {
+    /// Writes the prelude to `out` in the SMT-LIB format. This emits the `set-logic`,
+    /// `declare-sort`, `declare-fun`, `define-fun` and `assert` commands from the original problem.
+    pub fn to_smt2(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        write!(out, "{}", self)?;
+
+        let mut printer = AlethePrinter {
+            inner: out,
+            term_indices: None,
+            term_sharing_variable_prefix: "",
+        };
+        for (name, params, return_sort, body) in &self.function_definitions {
+            write!(
+                printer.inner,
+                "(define-fun {} {} {} ",
+                quote_symbol(name),
+                params,
+                return_sort
+            )?;
+            printer.write_raw_term(body)?;
+            writeln!(printer.inner, ")")?;
+        }
+        for term in &self.assertions {
+            write!(printer.inner, "(assert ")?;
+            printer.write_raw_term(term)?;
+            writeln!(printer.inner, ")")?;
+        }
+        Ok(())
+    }
+}
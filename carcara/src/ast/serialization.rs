@@ -0,0 +1,243 @@
+//! Support for serializing and deserializing proofs using `serde`.
+//!
+//! Terms are serialized as SMT-LIB strings. Since terms must be added to a `TermPool`, and may
+//! reference sorts and functions declared in the original problem, deserializing them is only
+//! possible inside a call to [`with_deserialization_context`].
+
+use super::*;
+use crate::{parser, CarcaraResult};
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{cell::RefCell, fmt};
+
+/// The state needed to deserialize terms.
+struct DeserializationContext {
+    pool: TermPool,
+
+    /// The prelude used when parsing terms. While deserializing a subproof, its arguments are
+    /// temporarily added to the function declarations in this prelude.
+    prelude: ProblemPrelude,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<DeserializationContext>> = const { RefCell::new(None) };
+}
+
+/// Gives the term pool back to its owner when a call to `with_deserialization_context` ends, even
+/// if it ends by panicking.
+struct ContextGuard<'a> {
+    pool: &'a mut TermPool,
+    previous: Option<DeserializationContext>,
+}
+
+impl Drop for ContextGuard<'_> {
+    fn drop(&mut self) {
+        let context = CONTEXT.with(|c| c.replace(self.previous.take()));
+        if let Some(context) = context {
+            *self.pool = context.pool;
+        }
+    }
+}
+
+/// Calls `f` in a context where terms can be deserialized. Any term deserialized inside `f` is
+/// parsed using the sort and function declarations from `prelude`, and added to `pool`.
+///
+/// # Examples
+///
+/// ```
+/// # use carcara::{ast::*, parser::parse_instance};
+/// let problem = "(declare-fun p () Bool) (assert p) (assert (not p))".as_bytes();
+/// let proof = "(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule resolution :premises (h1 h2))";
+/// let (prelude, proof, mut pool) =
+///     parse_instance(problem, proof.as_bytes(), true, false, false).unwrap();
+///
+/// let json = serde_json::to_string(&proof).unwrap();
+/// let deserialized: Proof = with_deserialization_context(&mut pool, &prelude, || {
+///     serde_json::from_str(&json).unwrap()
+/// });
+/// assert_eq!(proof.commands, deserialized.commands);
+/// ```
+pub fn with_deserialization_context<T, F>(pool: &mut TermPool, prelude: &ProblemPrelude, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let context = DeserializationContext {
+        pool: std::mem::take(pool),
+        prelude: prelude.clone(),
+    };
+    let previous = CONTEXT.with(|c| c.replace(Some(context)));
+    let _guard = ContextGuard { pool, previous };
+    f()
+}
+
+/// Calls `f` with the current deserialization context. Returns an error if there is no context
+/// available.
+fn with_context<T, F, E>(f: F) -> Result<T, E>
+where
+    F: FnOnce(&mut DeserializationContext) -> CarcaraResult<T>,
+    E: de::Error,
+{
+    CONTEXT.with(|c| match c.borrow_mut().as_mut() {
+        Some(context) => f(context).map_err(E::custom),
+        None => Err(E::custom(
+            "terms can only be deserialized inside `with_deserialization_context`",
+        )),
+    })
+}
+
+impl Serialize for Rc<Term> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_smt2_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rc<Term> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        with_context(|c| parser::parse_term(&input, &mut c.pool, &c.prelude))
+    }
+}
+
+/// The prelude is serialized as the SMT-LIB problem produced by [`ProblemPrelude::to_smt2`], since
+/// the sorts of declared functions can't be represented as regular sort strings.
+impl Serialize for ProblemPrelude {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut buf = Vec::new();
+        self.to_smt2(&mut buf).map_err(ser::Error::custom)?;
+        serializer.serialize_str(std::str::from_utf8(&buf).map_err(ser::Error::custom)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProblemPrelude {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        with_context(|c| {
+            let mut parser =
+                parser::Parser::new(&mut c.pool, input.as_bytes(), true, false, false)?;
+            Ok(parser.parse_problem()?.0)
+        })
+    }
+}
+
+const SUBPROOF_FIELDS: &[&str] = &["variable_args", "assignment_args", "commands"];
+
+/// Subproofs are serialized with their arguments before their commands, mirroring the `anchor`
+/// command. This way, the arguments are already known when the commands are deserialized.
+impl Serialize for Subproof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let variable_args: Vec<_> = self
+            .variable_args
+            .iter()
+            .map(|(name, sort)| (name, sort.as_sort().unwrap().to_string()))
+            .collect();
+
+        let mut state = serializer.serialize_struct("Subproof", 3)?;
+        state.serialize_field("variable_args", &variable_args)?;
+        state.serialize_field("assignment_args", &self.assignment_args)?;
+        state.serialize_field("commands", &self.commands)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Subproof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Subproof", SUBPROOF_FIELDS, SubproofVisitor)
+    }
+}
+
+struct SubproofVisitor;
+
+impl SubproofVisitor {
+    /// Adds the variable arguments of a subproof to the context, and returns them. The sorts of the
+    /// variables are parsed using the context prelude.
+    fn declare_variable_args<E: de::Error>(
+        args: Vec<(String, String)>,
+    ) -> Result<Vec<SortedVar>, E> {
+        with_context(|c| {
+            let mut result = Vec::with_capacity(args.len());
+            for (name, sort) in args {
+                let sort = parser::parse_sort(&sort, &mut c.pool, &c.prelude)?;
+                c.prelude
+                    .function_declarations
+                    .push((name.clone(), sort.clone()));
+                result.push((name, sort));
+            }
+            Ok(result)
+        })
+    }
+
+    /// Adds the variables introduced by the assignment arguments of a subproof to the context.
+    fn declare_assignment_args<E: de::Error>(args: &[(String, Rc<Term>)]) -> Result<(), E> {
+        with_context(|c| {
+            for (name, value) in args {
+                let sort = c.pool.add(Term::Sort(c.pool.sort(value).clone()));
+                c.prelude.function_declarations.push((name.clone(), sort));
+            }
+            Ok(())
+        })
+    }
+
+    /// Removes the arguments of a subproof from the context, after its commands were deserialized.
+    fn pop_args<E: de::Error>(num_args: usize) -> Result<(), E> {
+        with_context(|c| {
+            let declarations = &mut c.prelude.function_declarations;
+            declarations.truncate(declarations.len() - num_args);
+            Ok(())
+        })
+    }
+}
+
+fn expect_key<'de, A: MapAccess<'de>>(map: &mut A, expected: &'static str) -> Result<(), A::Error> {
+    match map.next_key::<String>()? {
+        Some(key) if key == expected => Ok(()),
+        Some(key) => Err(de::Error::unknown_field(&key, SUBPROOF_FIELDS)),
+        None => Err(de::Error::missing_field(expected)),
+    }
+}
+
+impl<'de> Visitor<'de> for SubproofVisitor {
+    type Value = Subproof;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a subproof")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Subproof, A::Error> {
+        let missing = |i| de::Error::invalid_length(i, &self);
+
+        let variable_args = seq.next_element()?.ok_or_else(|| missing(0))?;
+        let variable_args = Self::declare_variable_args(variable_args)?;
+        let assignment_args: Vec<_> = seq.next_element()?.ok_or_else(|| missing(1))?;
+        Self::declare_assignment_args(&assignment_args)?;
+        let commands = seq.next_element()?.ok_or_else(|| missing(2))?;
+        Self::pop_args(variable_args.len() + assignment_args.len())?;
+
+        Ok(Subproof {
+            commands,
+            assignment_args,
+            variable_args,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Subproof, A::Error> {
+        // Since the commands may only be deserialized after the arguments are declared, we require
+        // the fields to be in the same order as they were serialized
+        expect_key(&mut map, "variable_args")?;
+        let variable_args = Self::declare_variable_args(map.next_value()?)?;
+        expect_key(&mut map, "assignment_args")?;
+        let assignment_args: Vec<_> = map.next_value()?;
+        Self::declare_assignment_args(&assignment_args)?;
+        expect_key(&mut map, "commands")?;
+        let commands = map.next_value()?;
+        Self::pop_args(variable_args.len() + assignment_args.len())?;
+
+        Ok(Subproof {
+            commands,
+            assignment_args,
+            variable_args,
+        })
+    }
+}
@@ -5,7 +5,7 @@ use ahash::{AHashMap, AHashSet};
 use thiserror::Error;
 
 /// The error type for errors when constructing or applying substitutions.
-#[derive(Debug, PartialEq, Eq, Error)]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum SubstitutionError {
     /// A term in the left-hand side of the substitution was not a variable.
     #[error("term in the left-hand side of substitution is not a variable: '{0}'")]
@@ -14,6 +14,10 @@ pub enum SubstitutionError {
     /// One of the mappings in the substitution was mapping a term to a term of a different sort.
     #[error("trying to substitute term '{0}' with a term of a different sort: '{1}'")]
     DifferentSorts(Rc<Term>, Rc<Term>),
+
+    /// A variable was assigned more than once in the same context.
+    #[error("variable '{0}' is assigned more than once")]
+    RepeatedVariable(String),
 }
 
 type SubstitutionResult<T> = Result<T, SubstitutionError>;
@@ -87,6 +91,23 @@ impl Substitution {
         self.map.is_empty()
     }
 
+    /// Returns the inverse of this substitution, if it is a bijection between variables. That is,
+    /// this returns `Some` if and only if every variable is mapped to another variable, and no two
+    /// variables are mapped to the same one.
+    pub fn invert(&self) -> Option<Substitution> {
+        let mut map = AHashMap::with_capacity(self.map.len());
+        for (k, v) in &self.map {
+            if !v.is_var() || map.insert(v.clone(), k.clone()).is_some() {
+                return None;
+            }
+        }
+        Some(Self {
+            map,
+            should_be_renamed: None,
+            cache: AHashMap::new(),
+        })
+    }
+
     /// Extends the substitution by adding a new mapping from `x` to `t`. This returns an error if
     /// the sorts of the given terms are not the same, or if `x` is not a variable term.
     pub(crate) fn insert(
@@ -350,7 +371,8 @@ impl Substitution {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::*;
+    use crate::{ast::Sort, parser::*};
+    use proptest::prelude::*;
 
     fn run_test(definitions: &str, original: &str, x: &str, t: &str, result: &str) {
         let mut pool = TermPool::new();
@@ -426,4 +448,73 @@ mod tests {
             // TODO: Add tests for `choice`, `let`, and `lambda` terms
         }
     }
+
+    #[test]
+    fn test_invert() {
+        let mut pool = TermPool::new();
+        let mut parser = Parser::new(
+            &mut pool,
+            "(declare-fun x () Int) (declare-fun y () Int) (declare-fun z () Int)".as_bytes(),
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        parser.parse_problem().unwrap();
+        let [x, y, z, t] = ["x", "y", "z", "(+ x 1)"].map(|s| {
+            parser.reset(s.as_bytes()).unwrap();
+            parser.parse_term().unwrap()
+        });
+
+        let swap: AHashMap<_, _> = [(x.clone(), y.clone()), (y.clone(), x.clone())].into();
+        let inverse = Substitution::new(&mut pool, swap.clone())
+            .unwrap()
+            .invert()
+            .unwrap();
+        assert_eq!(inverse.map, swap);
+
+        let not_injective: AHashMap<_, _> = [(x.clone(), z.clone()), (y, z.clone())].into();
+        let s = Substitution::new(&mut pool, not_injective).unwrap();
+        assert!(s.invert().is_none());
+
+        let not_variable: AHashMap<_, _> = [(x, t), (z.clone(), z)].into();
+        let s = Substitution::new(&mut pool, not_variable).unwrap();
+        assert!(s.invert().is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn test_invert_is_involution(
+            permutation in proptest::collection::vec(0..8usize, 1..8)
+                .prop_map(|v| (0..v.len()).collect::<Vec<_>>())
+                .prop_shuffle(),
+            targets in proptest::collection::vec(0..8usize, 1..8),
+        ) {
+            let mut pool = TermPool::new();
+            let int = pool.add(Term::Sort(Sort::Int));
+            let vars: Vec<_> = (0..8)
+                .map(|i| pool.add(Term::var(format!("x{}", i), int.clone())))
+                .collect();
+
+            // A permutation of variables is always bijective
+            let map: AHashMap<_, _> = permutation
+                .iter()
+                .enumerate()
+                .map(|(i, &j)| (vars[i].clone(), vars[j].clone()))
+                .collect();
+            let s = Substitution::new(&mut pool, map).unwrap();
+            let inverse = s.invert().unwrap();
+            prop_assert_eq!(&inverse.invert().unwrap().map, &s.map);
+
+            // An arbitrary mapping is only invertible if it is injective
+            let map: AHashMap<_, _> = targets
+                .iter()
+                .enumerate()
+                .map(|(i, &j)| (vars[i].clone(), vars[j].clone()))
+                .collect();
+            let is_injective = targets.iter().collect::<AHashSet<_>>().len() == targets.len();
+            let s = Substitution::new(&mut pool, map).unwrap();
+            prop_assert_eq!(s.invert().is_some(), is_injective);
+        }
+    }
 }
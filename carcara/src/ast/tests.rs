@@ -1,4 +1,8 @@
-use crate::{ast::TermPool, parser::tests::parse_terms};
+use crate::{
+    ast::{alpha_equivalent, pool::SnapshotTerm, Operator, ProofCommand, Rc, Sort, Term, TermPool},
+    checker::error::CheckerError,
+    parser::tests::parse_terms,
+};
 use ahash::AHashSet;
 
 #[test]
@@ -50,10 +54,12 @@ fn test_deep_eq() {
             let mut time = std::time::Duration::ZERO;
             match test_type {
                 TestType::ModReordering => {
-                    assert!(super::deep_eq::deep_eq(&a, &b, &mut time));
+                    assert!(super::deep_eq::deep_eq(&a, &b, None, &mut time));
                 }
                 TestType::AlphaEquiv => {
-                    assert!(super::deep_eq::are_alpha_equivalent(&a, &b, &mut time));
+                    assert!(super::deep_eq::are_alpha_equivalent(
+                        &a, &b, None, &mut time
+                    ));
                 }
             }
         }
@@ -104,3 +110,853 @@ fn test_deep_eq() {
         TestType::AlphaEquiv,
     );
 }
+
+#[test]
+fn test_tracing_deep_eq_witness() {
+    use crate::ast::{tracing_deep_eq, DeepEqWitness};
+
+    let definitions = "
+        (declare-sort T 0)
+        (declare-fun a () T)
+        (declare-fun b () T)
+        (declare-fun p () Bool)
+    ";
+    let cases = [
+        ("(= a b)", "(= a b)", Some(DeepEqWitness::Identical)),
+        ("(= a b)", "(= b a)", Some(DeepEqWitness::Symmetric)),
+        (
+            "(= p (= a b))",
+            "(= (= a b) p)",
+            Some(DeepEqWitness::Symmetric),
+        ),
+        (
+            "(= p (= a b))",
+            "(= (= b a) p)",
+            Some(DeepEqWitness::Reordered),
+        ),
+        (
+            "(and p (= a b))",
+            "(and p (= b a))",
+            Some(DeepEqWitness::Reordered),
+        ),
+        ("(= a b)", "(= a a)", None),
+    ];
+    let mut pool = TermPool::new();
+    for (a, b, expected) in cases {
+        let [a, b] = parse_terms(&mut pool, definitions, [a, b]);
+        let mut time = std::time::Duration::ZERO;
+        assert_eq!(tracing_deep_eq(&a, &b, None, &mut time).0, expected);
+    }
+}
+
+#[test]
+fn test_pool_gc() {
+    let mut pool = TermPool::new();
+    let initial_count = pool.live_term_count();
+    assert_eq!(pool.gc(), 0);
+
+    let definitions = "
+        (declare-fun f (Int) Int)
+        (declare-fun x () Int)
+    ";
+    let [kept, removed] = ["(= (f x) 0)", "(forall ((y Int)) (> (f (+ x y)) 1))"]
+        .map(|s| parse_terms(&mut pool, definitions, [s])[0].clone());
+    let _ = pool.free_vars(&removed);
+    let count_with_both = pool.live_term_count();
+    drop(removed);
+
+    // The terms in `(forall ((y Int)) (> (f (+ x y)) 1))` that don't appear in `(= (f x) 0)` are:
+    // the quantifier term itself, `y`, `(+ x y)`, `(f (+ x y))`, `1` and `(> (f (+ x y)) 1)`
+    assert_eq!(pool.gc(), 6);
+    assert_eq!(pool.live_term_count(), count_with_both - 6);
+    assert_eq!(pool.gc(), 0);
+
+    // Terms that are still referenced are not removed, and hash consing still works for them
+    let [kept_again] = parse_terms(&mut pool, definitions, ["(= (f x) 0)"]);
+    assert_eq!(kept, kept_again);
+    drop(kept_again);
+
+    drop(kept);
+    pool.gc();
+    assert_eq!(pool.live_term_count(), initial_count);
+    assert!(pool.bool_true() != pool.bool_false());
+}
+
+#[test]
+fn test_pool_from_prelude() {
+    let problem = "
+        (declare-fun f (Int) Int)
+        (declare-fun x () Int)
+        (define-fun p ((y Int)) Bool (> y 0))
+        (assert (= (f x) 0))
+        (assert (or true (p x)))
+    ";
+    let (prelude, _, _) =
+        crate::parser::parse_instance(problem.as_bytes(), "".as_bytes(), false, false, false)
+            .unwrap();
+    let mut pool = TermPool::from_prelude(&prelude);
+
+    // Adding a term that is already in the prelude gives back the same allocation
+    let [first, second] = [0, 1].map(|i| prelude.assertions[i].clone());
+    let (Term::Op(_, eq_args), Term::Op(_, or_args)) = (first.as_ref(), second.as_ref()) else {
+        panic!();
+    };
+    let rebuilt = pool.add(Term::Op(Operator::Equals, eq_args.clone()));
+    assert_eq!(rebuilt, first);
+    assert_eq!(or_args[0], pool.bool_true());
+    assert_eq!(pool.sort(&eq_args[0]), &Sort::Int);
+    assert_eq!(pool.sort(&second), &Sort::Bool);
+
+    // Terms that only appear in function definitions are also in the pool
+    let [y_gt_zero] = parse_terms(&mut pool, "(declare-fun y () Int)", ["(> y 0)"]);
+    assert_eq!(y_gt_zero, prelude.function_definitions[0].3);
+}
+
+#[test]
+fn test_pool_snapshot() {
+    const TERM: &str =
+        "(forall ((y Int)) (and (select a (f y x)) (let ((z y)) (> z 0)) (= ((lambda ((w Int)) w) 1) y)))";
+    let definitions = "
+        (declare-sort T 0)
+        (declare-fun f (Int T) Int)
+        (declare-fun x () T)
+        (declare-fun a () (Array Int Bool))
+    ";
+    let mut pool = TermPool::new();
+    let [root] = parse_terms(&mut pool, definitions, [TERM]);
+
+    // The snapshot doesn't hold references to the terms in the pool
+    let count = Rc::strong_count(&root);
+    let snapshot = pool.to_snapshot();
+    assert_eq!(Rc::strong_count(&root), count);
+    assert_eq!(snapshot.len(), pool.live_term_count());
+
+    // Every term comes after the terms it references
+    let (position, body) = snapshot
+        .terms
+        .iter()
+        .enumerate()
+        .find_map(|(i, term)| match term {
+            SnapshotTerm::Quant(_, _, body) => Some((i, *body)),
+            _ => None,
+        })
+        .unwrap();
+    assert!(body < position);
+
+    let mut restored = TermPool::from_snapshot(&snapshot).unwrap();
+    assert_eq!(restored.live_term_count(), pool.live_term_count());
+    assert_eq!(restored.to_snapshot().len(), snapshot.len());
+
+    // Adding a term that was in the snapshot gives back the existing allocation
+    let [new_root] = parse_terms(&mut restored, definitions, [TERM]);
+    assert_eq!(restored.live_term_count(), pool.live_term_count());
+    assert_ne!(new_root, root);
+    assert_eq!(restored.sort(&new_root), &Sort::Bool);
+}
+
+#[test]
+fn test_invalid_pool_snapshot() {
+    use crate::ast::{
+        pool::{PoolSnapshot, SnapshotSort},
+        Identifier, SnapshotError,
+    };
+    use SnapshotTerm::*;
+
+    let x = || Identifier::Simple("x".to_owned());
+    let cases = [
+        (
+            vec![Op(Operator::Not, vec![0])],
+            SnapshotError::InvalidIndex(0, 0),
+        ),
+        (
+            vec![Integer("1".to_owned()), Op(Operator::Not, vec![2])],
+            SnapshotError::InvalidIndex(1, 2),
+        ),
+        (
+            vec![Integer("one".to_owned())],
+            SnapshotError::InvalidNumeral(0),
+        ),
+        (
+            vec![Real("1/0".to_owned())],
+            SnapshotError::InvalidNumeral(0),
+        ),
+        (
+            vec![Integer("1".to_owned()), Var(x(), 0)],
+            SnapshotError::ExpectedSort(1, 0),
+        ),
+        (
+            vec![Integer("1".to_owned()), Sort(SnapshotSort::Array(0, 0))],
+            SnapshotError::ExpectedSort(1, 0),
+        ),
+        (
+            vec![Sort(SnapshotSort::Int), Var(x(), 0), App(1, vec![])],
+            SnapshotError::IllSorted(2),
+        ),
+        (
+            vec![Integer("1".to_owned()), Op(Operator::Select, vec![0, 0])],
+            SnapshotError::IllSorted(1),
+        ),
+        (
+            vec![Integer("1".to_owned()), Op(Operator::Ite, vec![0])],
+            SnapshotError::IllSorted(1),
+        ),
+    ];
+    for (terms, expected) in cases {
+        let result = TermPool::from_snapshot(&PoolSnapshot { terms });
+        assert_eq!(result.err(), Some(expected));
+    }
+}
+
+#[test]
+fn test_pool_find() {
+    let mut pool = TermPool::new();
+    let [term] = parse_terms(&mut pool, "(declare-fun x () Int)", ["(+ x 1)"]);
+    assert_eq!(pool.find(&term), Some(term.clone()));
+    assert_eq!(
+        pool.find(&Term::Sort(Sort::Bool)),
+        Some(pool.add(Term::Sort(Sort::Bool)))
+    );
+
+    let int = pool.add(Term::Sort(Sort::Int));
+    let count = pool.live_term_count();
+    let stats = pool.stats();
+    assert_eq!(pool.find(&Term::var("y", int.clone())), None);
+    assert_eq!(
+        pool.find(&Term::Op(Operator::Add, vec![term.clone(), term])),
+        None
+    );
+    assert_eq!(pool.live_term_count(), count);
+    assert_eq!(pool.stats(), stats);
+
+    let y = pool.add(Term::var("y", int.clone()));
+    assert_eq!(pool.find(&Term::var("y", int)), Some(y));
+}
+
+#[test]
+fn test_pool_stats() {
+    let mut pool = TermPool::new();
+    let initial = pool.stats();
+    assert_eq!(initial.unique_terms, 3);
+    assert_eq!(initial.hit_rate, 0.0);
+
+    let int_sort = pool.add(Term::Sort(Sort::Int));
+    let [a, b] = ["a", "b"].map(|s| pool.add(Term::var(s, int_sort.clone())));
+    let _ = pool.add(Term::var("a", int_sort));
+    let _ = pool.add(Term::Op(Operator::Add, vec![a.clone(), b.clone()]));
+    let _ = pool.add(Term::Op(Operator::Add, vec![a, b]));
+
+    // Of the six calls to `add`, two found the term already in the pool
+    let stats = pool.stats();
+    assert_eq!(stats.unique_terms, 7);
+    assert_eq!(stats.hit_rate, 2.0 / 6.0);
+    assert!(stats.total_alloc_bytes > initial.total_alloc_bytes);
+    assert!(stats.to_string().starts_with("7 unique terms"));
+}
+
+#[test]
+fn test_to_smt2_string() {
+    let definitions = "
+        (declare-sort T 0)
+        (declare-fun f (Int Int) Int)
+        (declare-fun a () T)
+        (declare-fun x () Int)
+        (declare-fun |weird symbol| () Bool)
+    ";
+    let cases = [
+        ("(f (+ x 1) (+ x 1))", "(f (+ x 1) (+ x 1))"),
+        ("(- 2.5)", "(- 2.5)"),
+        ("\"foo \"\"bar\"\"\"", "\"foo \"\"bar\"\"\""),
+        (
+            "(and |weird symbol| (= a a))",
+            "(and |weird symbol| (= a a))",
+        ),
+        (
+            "(forall ((y Int) (z T)) (and (= z a) (> (f x y) 0)))",
+            "(forall ((y Int) (z T)) (and (= z a) (> (f x y) 0)))",
+        ),
+        (
+            "(let ((y (+ x 1))) (= (f y y) y))",
+            "(let ((y (+ x 1))) (= (f y y) y))",
+        ),
+        ("(choice ((y Int)) (= y x))", "(choice ((y Int)) (= y x))"),
+        (
+            "((lambda ((y Int)) (+ y 1)) x)",
+            "((lambda ((y Int)) (+ y 1)) x)",
+        ),
+    ];
+    let mut pool = TermPool::new();
+    for (input, expected) in cases {
+        let [term] = parse_terms(&mut pool, definitions, [input]);
+        assert_eq!(expected, term.to_smt2_string());
+        assert_eq!(expected, term.to_string());
+    }
+}
+
+#[test]
+fn test_prelude_to_smt2() {
+    use crate::parser::Parser;
+
+    let problem = "
+        (set-logic QF_UFLIA)
+        (declare-sort U 0)
+        (declare-fun f (U Int) Int)
+        (declare-const |a b| U)
+        (define-fun g ((x Int) (y Int)) Int (f |a b| (+ x y)))
+        (define-fun c () Int (g 1 2))
+        (assert (> (g c 0) 0))
+        (assert (forall ((z Int)) (>= (f |a b| z) c)))
+    ";
+    let mut pool = TermPool::new();
+    let parse = |pool: &mut TermPool, input: &[u8]| {
+        Parser::new(pool, input, true, false, false)
+            .and_then(|mut p| p.parse_problem())
+            .unwrap()
+    };
+    let (prelude, premises) = parse(&mut pool, problem.as_bytes());
+
+    let mut output = Vec::new();
+    prelude.to_smt2(&mut output).unwrap();
+    let (reparsed, reparsed_premises) = parse(&mut pool, &output);
+    assert_eq!(prelude, reparsed);
+    assert_eq!(premises, reparsed_premises);
+}
+
+#[test]
+fn test_proof_to_alethe() {
+    use crate::{
+        checker::{Config, ProofChecker},
+        parser::parse_instance,
+    };
+
+    let problem = "
+        (declare-fun p (Int) Bool)
+        (assert (forall ((x Int)) (p x)))
+        (assert (not (forall ((y Int)) (p y))))
+    ";
+    let proof = "
+        (assume h1 (forall ((x Int)) (p x)))
+        (assume h2 (not (forall ((y Int)) (p y))))
+        (anchor :step t3 :args ((y Int) (:= x y)))
+        (step t3.t1 (cl (= x y)) :rule refl)
+        (step t3.t2 (cl (= (p x) (p y))) :rule cong :premises (t3.t1))
+        (step t3 (cl (= (forall ((x Int)) (p x)) (forall ((y Int)) (p y)))) :rule bind)
+        (step t4 (cl (not (forall ((x Int)) (p x))) (forall ((y Int)) (p y)))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2))
+    ";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let mut output = Vec::new();
+    proof.to_alethe(&mut output).unwrap();
+    assert!(std::str::from_utf8(&output)
+        .unwrap()
+        .contains("\n  (step t3.t2 (cl (= (p x) (p y))) :rule cong :premises (t3.t1))\n(step t3 "));
+
+    let (_, elaborated) = ProofChecker::new(&mut pool, Config::new(), prelude)
+        .check_and_elaborate(proof)
+        .unwrap();
+    let mut output = Vec::new();
+    elaborated.to_alethe(&mut output).unwrap();
+
+    let (prelude, reparsed, mut pool) =
+        parse_instance(problem.as_bytes(), output.as_slice(), true, false, false).unwrap();
+    assert_eq!(elaborated.commands.len(), reparsed.commands.len());
+    ProofChecker::new_strict(&mut pool, prelude)
+        .check(&reparsed)
+        .unwrap();
+}
+
+#[test]
+fn test_contains_and_find_all() {
+    let definitions = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun f (Int) Int)
+    ";
+    let mut pool = TermPool::new();
+    let [term, a, b, fa, x, zero] = parse_terms(
+        &mut pool,
+        definitions,
+        [
+            "(and (= (f a) b) (forall ((x Int)) (= (f a) x)) (let ((y 0)) (> y b)))",
+            "a",
+            "b",
+            "(f a)",
+            "(forall ((x Int)) (= x x))",
+            "0",
+        ],
+    );
+
+    assert!(term.contains(&term));
+    assert!(term.contains(&a));
+    assert!(term.contains(&fa));
+    assert!(term.contains(&zero));
+    assert!(!term.contains(&x));
+    assert!(!a.contains(&b));
+
+    assert_eq!(term.find_all(&fa).len(), 2);
+    assert_eq!(term.find_all(&a).len(), 2);
+    assert_eq!(term.find_all(&b).len(), 2);
+    assert_eq!(term.find_all(&term), [term.as_ref()]);
+    assert!(term.find_all(&x).is_empty());
+}
+
+#[test]
+fn test_alpha_equivalent() {
+    let definitions = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun f (Int Int) Int)
+    ";
+    let cases = [
+        (
+            "(forall ((x Int)) (= x 0))",
+            "(forall ((y Int)) (= y 0))",
+            true,
+        ),
+        (
+            "(forall ((x Int)) (= x a))",
+            "(forall ((a Int)) (= a a))",
+            false,
+        ),
+        (
+            "(forall ((x Int)) (= x 0))",
+            "(forall ((y Int)) (= 0 y))",
+            false,
+        ),
+        (
+            "(forall ((x Int)) (= x 0))",
+            "(exists ((y Int)) (= y 0))",
+            false,
+        ),
+        (
+            "(forall ((x Int)) (= x 0))",
+            "(forall ((y Real)) (= y 0.0))",
+            false,
+        ),
+        (
+            "(forall ((x Int) (y Int)) (= (f x y) a))",
+            "(forall ((y Int) (x Int)) (= (f y x) a))",
+            true,
+        ),
+        (
+            "(forall ((x Int) (y Int)) (= (f x y) a))",
+            "(forall ((y Int) (x Int)) (= (f x y) a))",
+            false,
+        ),
+        (
+            "(forall ((x Int)) (forall ((x Int)) (= x 0)))",
+            "(forall ((x Int)) (forall ((y Int)) (= y 0)))",
+            true,
+        ),
+        (
+            "(forall ((x Int)) (forall ((x Int)) (= x 0)))",
+            "(forall ((y Int)) (forall ((x Int)) (= y 0)))",
+            false,
+        ),
+        ("(= a b)", "(= a b)", true),
+        ("(= a b)", "(= b a)", false),
+        ("(let ((x a)) (= x b))", "(let ((y a)) (= y b))", true),
+        ("(let ((x a)) (= x b))", "(let ((y b)) (= y b))", false),
+        (
+            "(lambda ((x Int)) (f x a))",
+            "(lambda ((y Int)) (f y a))",
+            true,
+        ),
+        (
+            "(choice ((x Int)) (= x a))",
+            "(choice ((y Int)) (= y a))",
+            true,
+        ),
+    ];
+    for (a, b, expected) in cases {
+        let mut pool = TermPool::new();
+        let [a, b] = parse_terms(&mut pool, definitions, [a, b]);
+        assert_eq!(alpha_equivalent(&a, &b), expected, "{} and {}", a, b);
+    }
+}
+
+#[test]
+fn test_term_depth_and_size() {
+    let definitions = "
+        (declare-fun p () Bool)
+        (declare-fun a () Int)
+        (declare-fun f (Int Int) Int)
+    ";
+    let cases = [
+        ("a", 0, 1),
+        ("1", 0, 1),
+        ("(+ a 1)", 1, 3),
+        ("(f a (f a 1))", 2, 7),
+        ("(not (and p (= a 1)))", 3, 6),
+        ("(forall ((x Int)) (= x a))", 2, 4),
+        ("(choice ((x Int)) (= x a))", 2, 4),
+        ("(let ((x (+ a 1))) (= x a))", 2, 7),
+        ("(= ((lambda ((x Int)) x) 1) a)", 3, 6),
+    ];
+    for (term, depth, size) in cases {
+        let mut pool = TermPool::new();
+        let [term] = parse_terms(&mut pool, definitions, [term]);
+        assert_eq!(term.depth(), depth, "{}", term);
+        assert_eq!(term.size(), size, "{}", term);
+    }
+
+    let mut pool = TermPool::new();
+    let int = pool.add(Term::Sort(Sort::Int));
+    assert_eq!((int.depth(), int.size()), (0, 1));
+
+    // Deep terms don't overflow the stack, and shared subterms are counted once for each
+    // occurrence
+    let [mut deep, mut shared] = parse_terms(&mut pool, definitions, ["a", "a"]);
+    for _ in 0..100_000 {
+        deep = pool.add(Term::Op(Operator::Add, vec![deep, shared.clone()]));
+    }
+    assert_eq!(deep.depth(), 100_000);
+    assert_eq!(deep.size(), 200_001);
+    for _ in 0..100 {
+        shared = pool.add(Term::Op(Operator::Add, vec![shared.clone(), shared]));
+    }
+    assert_eq!(shared.depth(), 100);
+    assert_eq!(shared.size(), usize::MAX);
+
+    // Dropping a term is recursive, so dropping the deep term would overflow the stack
+    std::mem::forget((pool, deep));
+}
+
+#[test]
+fn test_is_boolean_tautology_and_contradiction() {
+    let definitions = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+    ";
+    let mut pool = TermPool::new();
+    let tautologies = parse_terms(
+        &mut pool,
+        definitions,
+        [
+            "true",
+            "(not false)",
+            "(and true true)",
+            "(and true (not false) (= a a))",
+            "(or false true)",
+            "(or p q (not p))",
+            "(= (+ a b) (+ a b) (+ a b))",
+            "(=> false p)",
+            "(=> p q true)",
+            "(not (and p (not p)))",
+        ],
+    );
+    let contradictions = parse_terms(
+        &mut pool,
+        definitions,
+        [
+            "false",
+            "(not true)",
+            "(or false false)",
+            "(or false (not (= a a)))",
+            "(and p false q)",
+            "(and p q (not q))",
+            "(distinct a b a)",
+            "(not (or p (not p)))",
+        ],
+    );
+    let neither = parse_terms(
+        &mut pool,
+        definitions,
+        [
+            "p",
+            "(and p true)",
+            "(or p q false)",
+            "(= a b)",
+            "(=> p q)",
+            "(distinct a b)",
+            "(not p)",
+            "(or p (not q))",
+        ],
+    );
+
+    for t in &tautologies {
+        assert!(t.is_boolean_tautology(), "{}", t);
+        assert!(!t.is_boolean_contradiction(), "{}", t);
+    }
+    for t in &contradictions {
+        assert!(t.is_boolean_contradiction(), "{}", t);
+        assert!(!t.is_boolean_tautology(), "{}", t);
+    }
+    for t in &neither {
+        assert!(!t.is_boolean_tautology(), "{}", t);
+        assert!(!t.is_boolean_contradiction(), "{}", t);
+    }
+}
+
+#[test]
+fn test_subproof_depth_and_count() {
+    fn run_test(proof: &str, expected_depth: usize, expected_count: usize) {
+        let problem = "(declare-fun p () Bool)";
+        let (_, proof, _) =
+            crate::parser::parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false)
+                .unwrap();
+        assert_eq!(proof.max_subproof_depth(), expected_depth);
+        assert_eq!(proof.subproof_count(), expected_count);
+    }
+
+    run_test(
+        "(assume h1 p) (step t2 (cl p) :rule hole :premises (h1))",
+        0,
+        0,
+    );
+    run_test(
+        "(anchor :step t1)
+        (assume t1.a0 p)
+        (step t1 (cl (not p) p) :rule subproof :discharge (t1.a0))
+        (anchor :step t2)
+        (assume t2.a0 p)
+        (step t2 (cl (not p) p) :rule subproof :discharge (t2.a0))",
+        1,
+        2,
+    );
+    run_test(
+        "(anchor :step t1)
+        (anchor :step t1.t1)
+        (anchor :step t1.t1.t1)
+        (assume t1.t1.t1.a0 p)
+        (step t1.t1.t1 (cl (not p) p) :rule subproof :discharge (t1.t1.t1.a0))
+        (step t1.t1 (cl (not p) p) :rule hole :premises (t1.t1.t1))
+        (anchor :step t1.t2)
+        (assume t1.t2.a0 p)
+        (step t1.t2 (cl (not p) p) :rule subproof :discharge (t1.t2.a0))
+        (step t1 (cl (not p) p) :rule hole :premises (t1.t1))
+        (step t2 (cl p) :rule hole)",
+        3,
+        4,
+    );
+}
+
+#[test]
+fn test_clone_with_fresh_ids() {
+    use crate::{
+        ast::{Proof, ProofCommand},
+        checker::{Config, ProofChecker},
+        parser::parse_instance,
+    };
+
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (assert p)
+        (assert (not p))
+    ";
+    let proofs = [
+        "(assume h1 p)
+        (assume h2 (not p))
+        (step t3 (cl) :rule resolution :premises (h1 h2))",
+        "(assume h1 p)
+        (assume h2 (not p))
+        (anchor :step t3)
+        (assume t3.a0 q)
+        (step t3.t1 (cl q) :rule resolution :premises (t3.a0))
+        (step t3 (cl (not q) q) :rule subproof :discharge (t3.a0))
+        (step t4 (cl) :rule resolution :premises (h1 h2))",
+    ];
+
+    let mut all_ids = AHashSet::new();
+    for (proof, prefix) in proofs.into_iter().zip(["a.", "b."]) {
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let cloned: Proof = proof.clone_with_fresh_ids(prefix);
+        assert!(ProofChecker::new(&mut pool, Config::new(), prelude)
+            .check(&cloned)
+            .is_ok());
+
+        let (mut original, mut renamed) = (proof.iter(), cloned.iter());
+        while let (Some(a), Some(b)) = (original.next(), renamed.next()) {
+            assert_eq!(b.id(), format!("{}{}", prefix, a.id()));
+            assert_eq!(a.clause(), b.clause());
+            if let (ProofCommand::Step(a), ProofCommand::Step(b)) = (a, b) {
+                for (&p, &q) in a.premises.iter().zip(&b.premises) {
+                    let expected = format!("{}{}", prefix, original.get_premise(p).id());
+                    assert_eq!(renamed.get_premise(q).id(), expected);
+                }
+            }
+            if !b.is_subproof() {
+                assert!(all_ids.insert(b.id().to_owned()));
+            }
+        }
+        assert!(original.next().is_none() && renamed.next().is_none());
+    }
+}
+
+#[test]
+fn test_equality_chain() {
+    use crate::ast::EqualityChain;
+
+    fn from_clause(clause: &[Rc<Term>]) -> Option<EqualityChain<'_>> {
+        let (last, rest) = clause.split_last()?;
+        Some(EqualityChain {
+            conclusion: match_term!((= t u) = last)?,
+            links: rest
+                .iter()
+                .map(|term| match_term!((not (= t u)) = term))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    fn from_equalities<'a>(
+        conclusion: &'a Rc<Term>,
+        links: &[&'a Rc<Term>],
+    ) -> Option<EqualityChain<'a>> {
+        Some(EqualityChain {
+            conclusion: match_term!((= t u) = conclusion)?,
+            links: links
+                .iter()
+                .map(|term| match_term!((= t u) = term))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    let mut pool = TermPool::new();
+    let definitions = "
+        (declare-sort T 0)
+        (declare-fun a () T)
+        (declare-fun b () T)
+        (declare-fun c () T)
+        (declare-fun d () T)
+    ";
+    let [a, b, c, d] = parse_terms(&mut pool, definitions, ["a", "b", "c", "d"]);
+    let endpoints = |clause: &[_]| {
+        from_clause(clause)
+            .unwrap()
+            .endpoints()
+            .map(|(x, y)| (x.clone(), y.clone()))
+    };
+
+    // Single-element chains
+    let single = parse_terms(&mut pool, definitions, ["(not (= a b))", "(= b a)"]);
+    assert!(from_clause(&single).unwrap().is_valid_transitive_chain());
+    assert_eq!(endpoints(&single), Some((a.clone(), b.clone())));
+
+    let reflexive = parse_terms(&mut pool, definitions, ["(not (= a a))", "(= a a)"]);
+    assert!(from_clause(&reflexive).unwrap().is_valid_transitive_chain());
+    assert_eq!(endpoints(&reflexive), None);
+
+    // Two-element chains
+    let two = parse_terms(
+        &mut pool,
+        definitions,
+        ["(not (= b c))", "(not (= b a))", "(= a c)"],
+    );
+    assert!(from_clause(&two).unwrap().is_valid_transitive_chain());
+    assert_eq!(endpoints(&two), Some((c.clone(), a.clone())));
+
+    let broken = parse_terms(
+        &mut pool,
+        definitions,
+        ["(not (= a b))", "(not (= c d))", "(= a d)"],
+    );
+    let chain = from_clause(&broken).unwrap();
+    assert!(!chain.is_valid_transitive_chain());
+    assert_eq!(chain.endpoints(), None);
+
+    // Circular chains have no endpoints, but can still prove an equality between any two of their
+    // terms
+    let circular = parse_terms(
+        &mut pool,
+        definitions,
+        ["(not (= a b))", "(not (= b c))", "(not (= c a))", "(= a c)"],
+    );
+    let chain = from_clause(&circular).unwrap();
+    assert!(chain.is_valid_transitive_chain());
+    assert_eq!(chain.endpoints(), None);
+
+    let circular = parse_terms(
+        &mut pool,
+        definitions,
+        ["(not (= a b))", "(not (= b c))", "(not (= c a))", "(= a d)"],
+    );
+    assert!(!from_clause(&circular).unwrap().is_valid_transitive_chain());
+
+    // A chain that branches has no endpoints either
+    let branching = parse_terms(
+        &mut pool,
+        definitions,
+        ["(not (= a b))", "(not (= b c))", "(not (= b d))", "(= a c)"],
+    );
+    let chain = from_clause(&branching).unwrap();
+    assert!(chain.is_valid_transitive_chain());
+    assert_eq!(chain.endpoints(), None);
+
+    // Chains can also be built from positive equalities, like the premises of a `trans` step
+    let [conclusion, first, second] =
+        parse_terms(&mut pool, definitions, ["(= d a)", "(= c d)", "(= a c)"]);
+    let chain = from_equalities(&conclusion, &[&first, &second]).unwrap();
+    assert!(chain.is_valid_transitive_chain());
+    assert_eq!(chain.endpoints(), Some((&d, &a)));
+    assert!(from_equalities(&conclusion, &[&a]).is_none());
+    assert!(from_clause(&[]).is_none());
+}
+
+#[test]
+fn test_topological_sort() {
+    let proof = "
+        (assume h1 (forall ((x Int)) (> x 0)))
+        (assume h2 (not (forall ((y Int)) (> y 0))))
+        (step t0 (cl (= 1 1)) :rule refl)
+        (anchor :step t3 :args ((y Int) (:= x y)))
+        (step t3.t1 (cl (= x y)) :rule refl)
+        (step t3.t2 (cl (= (> x 0) (> y 0))) :rule cong :premises (t3.t1))
+        (step t3 (cl (= (forall ((x Int)) (> x 0)) (forall ((y Int)) (> y 0)))) :rule bind)
+        (step t4 (cl (not (forall ((x Int)) (> x 0))) (forall ((y Int)) (> y 0)))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2))
+    ";
+    let (_, mut proof, _) =
+        crate::parser::parse_instance("".as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+
+    // `t0` is not used to derive the empty clause, and `t3` depends on `t3.t2` because it ends the
+    // subproof
+    let ids: Vec<_> = proof
+        .topological_sort()
+        .unwrap()
+        .iter()
+        .map(|s| s.id.as_str())
+        .collect();
+    assert_eq!(ids, ["t3.t1", "t3.t2", "t3", "t4", "t5"]);
+
+    // Making `t3.t1` depend on `t4` creates a cycle
+    let ProofCommand::Subproof(subproof) = &mut proof.commands[3] else {
+        panic!();
+    };
+    let ProofCommand::Step(step) = &mut subproof.commands[0] else {
+        panic!();
+    };
+    step.premises = vec![(0, 4)];
+    assert!(matches!(
+        proof.topological_sort(),
+        Err(crate::Error::CyclicProof(id)) if id == "t4"
+    ));
+
+    proof.commands.pop();
+    assert!(matches!(
+        proof.topological_sort(),
+        Err(crate::Error::DoesNotReachEmptyClause)
+    ));
+
+    // Premises and discharged assumptions that don't refer to any command are reported as errors
+    for (premises, discharge) in [(vec![(0, 7)], vec![]), (vec![], vec![(2, 0)])] {
+        let expected = premises.iter().chain(&discharge).copied().next().unwrap();
+        let ProofCommand::Subproof(subproof) = &mut proof.commands[3] else {
+            panic!();
+        };
+        let ProofCommand::Step(step) = &mut subproof.commands[0] else {
+            panic!();
+        };
+        (step.premises, step.discharge) = (premises, discharge);
+        assert!(matches!(
+            proof.topological_sort(),
+            Err(crate::Error::Checker { inner: CheckerError::InvalidPremiseIndex(p), step, .. })
+                if p == expected && step == "t3.t1"
+        ));
+    }
+}
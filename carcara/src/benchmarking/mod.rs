@@ -5,7 +5,11 @@ mod tests;
 pub use metrics::*;
 
 use ahash::AHashMap;
-use std::{fmt, io, time::Duration};
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 fn combine_map<K, V, M>(mut a: AHashMap<String, M>, b: AHashMap<String, M>) -> AHashMap<String, M>
 where
@@ -44,7 +48,170 @@ impl fmt::Display for StepId {
 
 type RunId = (String, usize);
 
-#[derive(Debug, Default)]
+/// Escapes a string so it can be used as a JSON string literal.
+fn escape_json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Returns the measurements of each run, sorted by file and run index.
+fn sorted_runs(runs: &AHashMap<RunId, RunMeasurement>) -> Vec<(&RunId, &RunMeasurement)> {
+    let mut runs: Vec<_> = runs.iter().collect();
+    runs.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    runs
+}
+
+/// Returns the time spent in `deep_eq` by each rule in a run, sorted by rule name.
+fn sorted_deep_eq_by_rule(m: &RunMeasurement) -> Vec<(&str, Duration)> {
+    let mut by_rule: Vec<_> = m
+        .deep_eq_by_rule
+        .iter()
+        .map(|(rule, t)| (rule.as_str(), *t))
+        .collect();
+    by_rule.sort_unstable();
+    by_rule
+}
+
+/// Returns the number of uses of each rule in a run, sorted by rule name.
+fn sorted_rule_histogram(m: &RunMeasurement) -> Vec<(&str, usize)> {
+    let mut histogram: Vec<_> = m
+        .rule_histogram
+        .iter()
+        .map(|(rule, n)| (rule.as_str(), *n))
+        .collect();
+    histogram.sort_unstable();
+    histogram
+}
+
+/// Writes the measurements of each run to `dest` in the CSV format. All times are in nanoseconds.
+/// The time spent in `deep_eq` by each rule is written in a single column, with entries of the
+/// form `rule=time` separated by semicolons. The number of uses of each rule is written in the same
+/// way.
+fn write_runs_csv(
+    runs: &AHashMap<RunId, RunMeasurement>,
+    dest: &mut dyn io::Write,
+) -> io::Result<()> {
+    writeln!(
+        dest,
+        "proof_file,run_id,parsing,checking,elaboration,total_accounted_for,\
+        total,deep_eq,deep_eq_ratio,assume,assume_ratio,peak_memory_bytes,deep_eq_by_rule,\
+        step_count,rule_histogram,max_subproof_depth,subproof_count"
+    )?;
+
+    for (id, m) in sorted_runs(runs) {
+        let total_accounted_for = m.parsing + m.checking;
+        let deep_eq_ratio = m.deep_eq.as_secs_f64() / m.checking.as_secs_f64();
+        let assume_ratio = m.assume.as_secs_f64() / m.checking.as_secs_f64();
+        let peak_memory = m.peak_memory_bytes.map(|b| b.to_string());
+        let deep_eq_by_rule: Vec<_> = sorted_deep_eq_by_rule(m)
+            .into_iter()
+            .map(|(rule, t)| format!("{}={}", rule, t.as_nanos()))
+            .collect();
+        let rule_histogram: Vec<_> = sorted_rule_histogram(m)
+            .into_iter()
+            .map(|(rule, n)| format!("{}={}", rule, n))
+            .collect();
+        writeln!(
+            dest,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            id.0,
+            id.1,
+            m.parsing.as_nanos(),
+            m.checking.as_nanos(),
+            m.elaboration.as_nanos(),
+            total_accounted_for.as_nanos(),
+            m.total.as_nanos(),
+            m.deep_eq.as_nanos(),
+            deep_eq_ratio,
+            m.assume.as_nanos(),
+            assume_ratio,
+            peak_memory.as_deref().unwrap_or(""),
+            deep_eq_by_rule.join(";"),
+            m.step_count,
+            rule_histogram.join(";"),
+            m.max_subproof_depth,
+            m.subproof_count,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes the measurements of each run to `dest` as a JSON array, with one object per run. The
+/// objects have the same fields as the columns in `write_runs_csv`, except that `deep_eq_by_rule`
+/// and `rule_histogram` are objects mapping each rule name to the time spent in `deep_eq` by that
+/// rule, and to its number of uses, respectively.
+fn write_runs_json(
+    runs: &AHashMap<RunId, RunMeasurement>,
+    dest: &mut dyn io::Write,
+) -> io::Result<()> {
+    write!(dest, "[")?;
+    for (i, (id, m)) in sorted_runs(runs).into_iter().enumerate() {
+        if i > 0 {
+            write!(dest, ",")?;
+        }
+        let total_accounted_for = m.parsing + m.checking;
+        let ratio = |t: Duration| {
+            let ratio = t.as_secs_f64() / m.checking.as_secs_f64();
+            // JSON has no representation for NaN or infinity
+            if ratio.is_finite() {
+                ratio.to_string()
+            } else {
+                "null".to_owned()
+            }
+        };
+        let deep_eq_by_rule: Vec<_> = sorted_deep_eq_by_rule(m)
+            .into_iter()
+            .map(|(rule, t)| format!("{}:{}", escape_json_string(rule), t.as_nanos()))
+            .collect();
+        let rule_histogram: Vec<_> = sorted_rule_histogram(m)
+            .into_iter()
+            .map(|(rule, n)| format!("{}:{}", escape_json_string(rule), n))
+            .collect();
+        write!(
+            dest,
+            "\n  {{\"proof_file\":{},\"run_id\":{},\"parsing\":{},\"checking\":{},\
+            \"elaboration\":{},\"total_accounted_for\":{},\"total\":{},\"deep_eq\":{},\
+            \"deep_eq_ratio\":{},\"assume\":{},\"assume_ratio\":{},\"peak_memory_bytes\":{},\
+            \"deep_eq_by_rule\":{{{}}},\"step_count\":{},\"rule_histogram\":{{{}}},\
+            \"max_subproof_depth\":{},\"subproof_count\":{}}}",
+            escape_json_string(&id.0),
+            id.1,
+            m.parsing.as_nanos(),
+            m.checking.as_nanos(),
+            m.elaboration.as_nanos(),
+            total_accounted_for.as_nanos(),
+            m.total.as_nanos(),
+            m.deep_eq.as_nanos(),
+            ratio(m.deep_eq),
+            m.assume.as_nanos(),
+            ratio(m.assume),
+            m.peak_memory_bytes
+                .map_or_else(|| "null".to_owned(), |b| b.to_string()),
+            deep_eq_by_rule.join(","),
+            m.step_count,
+            rule_histogram.join(","),
+            m.max_subproof_depth,
+            m.subproof_count,
+        )?;
+    }
+    writeln!(dest, "\n]")
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct RunMeasurement {
     pub parsing: Duration,
     pub checking: Duration,
@@ -53,6 +220,72 @@ pub struct RunMeasurement {
     pub deep_eq: Duration,
     pub assume: Duration,
     pub assume_core: Duration,
+
+    /// The peak memory usage of the process, in bytes, or `None` if it could not be measured. See
+    /// `peak_memory_usage`.
+    pub peak_memory_bytes: Option<u64>,
+
+    /// The time spent in `deep_eq` by the steps of each rule. The sum of these times is `deep_eq`.
+    pub deep_eq_by_rule: AHashMap<String, Duration>,
+
+    /// The number of `step` commands checked.
+    pub step_count: usize,
+
+    /// The number of `step` commands checked that use each rule. The sum of these counts is
+    /// `step_count`.
+    pub rule_histogram: AHashMap<String, usize>,
+
+    /// The maximum nesting depth of subproofs in the proof. See [`Proof::max_subproof_depth`].
+    ///
+    /// [`Proof::max_subproof_depth`]: crate::ast::Proof::max_subproof_depth
+    pub max_subproof_depth: usize,
+
+    /// The total number of subproofs in the proof, including nested subproofs.
+    pub subproof_count: usize,
+}
+
+/// Returns the peak memory usage of the current process, in bytes. On Linux, this is the `VmPeak`
+/// value from `/proc/self/status`, and on macOS this is the maximum resident set size reported by
+/// `getrusage`. On other platforms, or if the value can't be read, this returns `None`.
+///
+/// Note that this measures the whole process, so if multiple proofs are checked concurrently, the
+/// value reflects all of them.
+#[cfg(target_os = "linux")]
+pub fn peak_memory_usage() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmPeak:"))?;
+
+    // The line has the form "VmPeak:    12345 kB"
+    let kilobytes: u64 = line
+        .trim_start_matches("VmPeak:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(target_os = "macos")]
+pub fn peak_memory_usage() -> Option<u64> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+
+    // SAFETY: `getrusage` only writes to the struct pointed to by `usage`, which is valid for
+    // writes. If the call succeeds, the struct is fully initialized.
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        usage.assume_init()
+    };
+
+    // On macOS, `ru_maxrss` is measured in bytes
+    Some(usage.ru_maxrss as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn peak_memory_usage() -> Option<u64> {
+    None
 }
 
 // Higher kinded types would be very useful here. Ideally, I would like `BenchmarkResults` to be
@@ -80,6 +313,9 @@ pub struct BenchmarkResults<ByRun, ByStep, ByRunF64, ByDeepEq> {
 
     pub is_holey: bool,
     pub had_error: bool,
+
+    /// The individual measurements of each run, used when exporting the results.
+    pub runs: AHashMap<RunId, RunMeasurement>,
 }
 
 pub type OnlineBenchmarkResults = BenchmarkResults<
@@ -151,6 +387,30 @@ where
     pub fn step_time_by_rule(&self) -> &AHashMap<String, ByStep> {
         &self.step_time_by_rule
     }
+
+    /// The run with the highest peak memory usage, and its peak memory usage in bytes. Returns
+    /// `None` if memory usage could not be measured for any run.
+    pub fn peak_memory(&self) -> Option<(&RunId, u64)> {
+        self.runs
+            .iter()
+            .filter_map(|(id, m)| Some((id, m.peak_memory_bytes?)))
+            .max_by_key(|(_, bytes)| *bytes)
+    }
+
+    /// Writes the measurements of each run to `out` in the CSV format, with one row per run. The
+    /// columns contain the time spent parsing, checking, elaborating, in `deep_eq`, and in `assume`
+    /// steps, the total time, the peak memory usage in bytes, the time spent in `deep_eq` by each
+    /// rule, the number of steps, and the number of uses of each rule. All times are in
+    /// nanoseconds.
+    pub fn to_csv(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        write_runs_csv(&self.runs, out)
+    }
+
+    /// Writes the measurements of each run to `out` as a JSON array, with one object per run. The
+    /// objects have the same fields as the columns of the output of `to_csv`.
+    pub fn to_json(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        write_runs_json(&self.runs, out)
+    }
 }
 
 #[derive(Default)]
@@ -179,46 +439,22 @@ impl CsvBenchmarkResults {
         runs_dest: &mut dyn io::Write,
         by_rule_dest: &mut dyn io::Write,
     ) -> io::Result<()> {
-        Self::write_runs_csv(self.runs, runs_dest)?;
-        Self::write_by_rule_csv(self.step_time_by_rule, by_rule_dest)
-    }
-
-    fn write_runs_csv(
-        data: AHashMap<RunId, RunMeasurement>,
-        dest: &mut dyn io::Write,
-    ) -> io::Result<()> {
-        writeln!(
-            dest,
-            "proof_file,run_id,parsing,checking,elaboration,total_accounted_for,\
-            total,deep_eq,deep_eq_ratio,assume,assume_ratio"
-        )?;
+        write_runs_csv(&self.runs, runs_dest)?;
 
-        for (id, m) in data {
-            let total_accounted_for = m.parsing + m.checking;
-            let deep_eq_ratio = m.deep_eq.as_secs_f64() / m.checking.as_secs_f64();
-            let assume_ratio = m.assume.as_secs_f64() / m.checking.as_secs_f64();
-            writeln!(
-                dest,
-                "{},{},{},{},{},{},{},{},{},{},{}",
-                id.0,
-                id.1,
-                m.parsing.as_nanos(),
-                m.checking.as_nanos(),
-                m.elaboration.as_nanos(),
-                total_accounted_for.as_nanos(),
-                m.total.as_nanos(),
-                m.deep_eq.as_nanos(),
-                deep_eq_ratio,
-                m.assume.as_nanos(),
-                assume_ratio,
-            )?;
+        let mut deep_eq_by_rule: AHashMap<&str, Duration> = AHashMap::new();
+        for m in self.runs.values() {
+            for (rule, t) in &m.deep_eq_by_rule {
+                *deep_eq_by_rule.entry(rule).or_default() += *t;
+            }
         }
-
-        Ok(())
+        Self::write_by_rule_csv(self.step_time_by_rule, &deep_eq_by_rule, by_rule_dest)
     }
 
+    /// Writes the time spent checking the steps of each rule to `dest` in the CSV format. The
+    /// `deep_eq` column holds the total time spent in `deep_eq` by each rule, across all runs.
     fn write_by_rule_csv(
         data: AHashMap<String, OfflineMetrics<StepId>>,
+        deep_eq_by_rule: &AHashMap<&str, Duration>,
         dest: &mut dyn io::Write,
     ) -> io::Result<()> {
         let mut data: Vec<_> = data.into_iter().collect();
@@ -226,14 +462,16 @@ impl CsvBenchmarkResults {
 
         writeln!(
             dest,
-            "rule,count,total,mean,lower_whisker,first_quartile,median,third_quartile,upper_whisker"
+            "rule,count,total,mean,lower_whisker,first_quartile,median,third_quartile,upper_whisker,\
+            deep_eq"
         )?;
         for (rule, mut m) in data {
             let [lower_whisker, first_quartile, median, third_quartile, upper_whisker] =
                 m.quartiles().map(|(_, t)| t.as_nanos());
+            let deep_eq = deep_eq_by_rule.get(rule.as_str()).copied();
             writeln!(
                 dest,
-                "{},{},{},{},{},{},{},{},{}",
+                "{},{},{},{},{},{},{},{},{},{}",
                 rule,
                 m.count(),
                 m.total().as_nanos(),
@@ -243,6 +481,7 @@ impl CsvBenchmarkResults {
                 median,
                 third_quartile,
                 upper_whisker,
+                deep_eq.unwrap_or_default().as_nanos(),
             )?;
         }
         Ok(())
@@ -260,6 +499,16 @@ pub trait CollectResults {
     fn combine(a: Self, b: Self) -> Self
     where
         Self: Sized;
+
+    /// Combines many results into one. By default, this simply folds them with
+    /// [`CollectResults::combine`], but implementations for which combining is more expensive the
+    /// larger the results are may override it to combine them in a balanced way.
+    fn combine_all(results: Vec<Self>) -> Self
+    where
+        Self: Sized + Default,
+    {
+        results.into_iter().fold(Self::default(), Self::combine)
+    }
 }
 
 impl<ByRun, ByStep, ByRunF64, ByDeepEq> CollectResults
@@ -308,7 +557,14 @@ where
             deep_eq,
             assume,
             assume_core,
+            peak_memory_bytes: _,
+            deep_eq_by_rule: _,
+            step_count: _,
+            rule_histogram: _,
+            max_subproof_depth: _,
+            subproof_count: _,
         } = measurement;
+        self.runs.insert(id.clone(), measurement);
 
         self.parsing.add_sample(id, parsing);
         self.checking.add_sample(id, checking);
@@ -348,7 +604,27 @@ where
             num_easy_assumes: a.num_easy_assumes + b.num_easy_assumes,
             is_holey: a.is_holey || b.is_holey,
             had_error: a.had_error || b.had_error,
+
+            // This assumes that the same run never appears in both `a` and `b`
+            runs: a.runs.into_iter().chain(b.runs).collect(),
+        }
+    }
+
+    fn combine_all(mut results: Vec<Self>) -> Self {
+        // We combine the results in pairs, halving their number in each round, so each result is
+        // only part of `log(n)` combinations, instead of up to `n` when folding
+        while results.len() > 1 {
+            let mut next = Vec::with_capacity((results.len() + 1) / 2);
+            let mut iter = results.into_iter();
+            while let Some(a) = iter.next() {
+                next.push(match iter.next() {
+                    Some(b) => Self::combine(a, b),
+                    None => a,
+                });
+            }
+            results = next;
         }
+        results.pop().unwrap_or_default()
     }
 
     fn register_holey(&mut self) {
@@ -400,3 +676,47 @@ impl CollectResults for CsvBenchmarkResults {
         a
     }
 }
+
+/// Results shared between many threads. Each thread may hold a clone of the `Arc`, and add
+/// measurements to it while the others are still running, instead of collecting its own results
+/// and combining them with the others' only when it is done. This allows the results to be
+/// inspected while a benchmark is running, at the cost of locking the mutex for each measurement.
+impl<T: CollectResults + Default> CollectResults for Arc<Mutex<T>> {
+    fn add_step_measurement(&mut self, file: &str, step_id: &str, rule: &str, time: Duration) {
+        let mut inner = self.lock().unwrap();
+        inner.add_step_measurement(file, step_id, rule, time);
+    }
+
+    fn add_assume_measurement(&mut self, file: &str, id: &str, is_easy: bool, time: Duration) {
+        let mut inner = self.lock().unwrap();
+        inner.add_assume_measurement(file, id, is_easy, time);
+    }
+
+    fn add_deep_eq_depth(&mut self, depth: usize) {
+        self.lock().unwrap().add_deep_eq_depth(depth);
+    }
+
+    fn add_run_measurement(&mut self, id: &RunId, measurement: RunMeasurement) {
+        self.lock().unwrap().add_run_measurement(id, measurement);
+    }
+
+    fn register_holey(&mut self) {
+        self.lock().unwrap().register_holey();
+    }
+
+    fn register_error(&mut self, error: &crate::Error) {
+        self.lock().unwrap().register_error(error);
+    }
+
+    fn combine(a: Self, b: Self) -> Self {
+        // We take the results out of `b` before locking `a`, so this doesn't deadlock if `a` and
+        // `b` are the same
+        let b_inner = std::mem::take(&mut *b.lock().unwrap());
+        {
+            let mut a_inner = a.lock().unwrap();
+            let taken = std::mem::take(&mut *a_inner);
+            *a_inner = T::combine(taken, b_inner);
+        }
+        a
+    }
+}
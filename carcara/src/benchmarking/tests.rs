@@ -1,4 +1,7 @@
-use super::{Duration, Metrics, MetricsUnit, OfflineMetrics, OnlineMetrics};
+use super::{
+    peak_memory_usage, CollectResults, Duration, Metrics, MetricsUnit, OfflineMetrics,
+    OnlineBenchmarkResults, OnlineMetrics, RunMeasurement,
+};
 use rand::{prelude::ThreadRng, Rng};
 use std::fmt;
 
@@ -135,3 +138,176 @@ fn test_metrics_combine() {
     // `Metrics::add` with that entry, which makes the numerical error small again
     run_tests(10_000, 1, 1.0e-6);
 }
+
+#[test]
+fn test_results_export() {
+    let mut results = OnlineBenchmarkResults::new();
+    for (file, i, checking) in [("b.proof", 0, 40), ("a.proof", 1, 20), ("a.proof", 0, 10)] {
+        let measurement = RunMeasurement {
+            parsing: Duration::from_nanos(5),
+            checking: Duration::from_nanos(checking),
+            total: Duration::from_nanos(checking + 5),
+            deep_eq: Duration::from_nanos(checking / 2),
+            peak_memory_bytes: (i == 1).then_some(1024),
+            deep_eq_by_rule: [
+                ("refl".to_owned(), Duration::from_nanos(checking / 4)),
+                ("cong".to_owned(), Duration::from_nanos(checking / 4)),
+            ]
+            .into_iter()
+            .collect(),
+            step_count: 3,
+            rule_histogram: [("refl".to_owned(), 1), ("cong".to_owned(), 2)]
+                .into_iter()
+                .collect(),
+            max_subproof_depth: i,
+            subproof_count: 2 * i,
+            ..Default::default()
+        };
+        results.add_run_measurement(&(file.to_owned(), i), measurement);
+    }
+
+    let mut csv = Vec::new();
+    results.to_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<_> = csv.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert!(rows[0].starts_with("proof_file,run_id,parsing,checking,elaboration"));
+    assert_eq!(
+        rows[1],
+        "a.proof,0,5,10,0,15,15,5,0.5,0,0,,cong=2;refl=2,3,cong=2;refl=1,0,0"
+    );
+    assert_eq!(
+        rows[2],
+        "a.proof,1,5,20,0,25,25,10,0.5,0,0,1024,cong=5;refl=5,3,cong=2;refl=1,1,2"
+    );
+    assert_eq!(
+        rows[3],
+        "b.proof,0,5,40,0,45,45,20,0.5,0,0,,cong=10;refl=10,3,cong=2;refl=1,0,0"
+    );
+
+    let mut json = Vec::new();
+    results.to_json(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 3);
+    assert_eq!(json[0]["proof_file"], "a.proof");
+    assert_eq!(json[1]["run_id"], 1);
+    assert_eq!(json[2]["checking"], 40);
+    assert_eq!(json[2]["deep_eq_ratio"], 0.5);
+    assert_eq!(json[1]["peak_memory_bytes"], 1024);
+    assert!(json[2]["peak_memory_bytes"].is_null());
+    assert_eq!(json[2]["deep_eq_by_rule"]["refl"], 10);
+    assert_eq!(json[2]["deep_eq_by_rule"]["cong"], 10);
+    assert_eq!(json[0]["step_count"], 3);
+    assert_eq!(json[0]["rule_histogram"]["cong"], 2);
+    assert_eq!(json[1]["max_subproof_depth"], 1);
+    assert_eq!(json[1]["subproof_count"], 2);
+
+    assert_eq!(
+        results.peak_memory(),
+        Some((&("a.proof".to_owned(), 1), 1024))
+    );
+}
+
+#[test]
+fn test_results_combine_all() {
+    fn results(index: usize) -> OnlineBenchmarkResults {
+        let mut results = OnlineBenchmarkResults::new();
+        for run in 0..=index {
+            let measurement = RunMeasurement {
+                parsing: Duration::from_nanos(index as u64),
+                checking: Duration::from_nanos(10 * (index + run) as u64 + 1),
+                ..Default::default()
+            };
+            let file = format!("{}.proof", index);
+            results.add_step_measurement(&file, "t1", "refl", measurement.checking);
+            results.add_run_measurement(&(file, run), measurement);
+        }
+        results.add_assume_measurement("a.proof", "h1", index % 2 == 0, Duration::from_nanos(3));
+        if index == 3 {
+            results.register_holey();
+        }
+        results
+    }
+
+    fn summary(results: &OnlineBenchmarkResults) -> (String, usize, Duration, bool) {
+        let mut csv = Vec::new();
+        results.to_csv(&mut csv).unwrap();
+        (
+            String::from_utf8(csv).unwrap(),
+            results.step_time().count(),
+            results.checking().total(),
+            results.is_holey,
+        )
+    }
+
+    let n = 7;
+    let expected = summary(&CollectResults::combine_all((0..n).map(results).collect()));
+    assert_eq!(expected.0.lines().count(), 1 + n * (n + 1) / 2);
+    assert!(expected.3);
+
+    // The result doesn't depend on the order of the results, or on how they are grouped
+    let reversed = CollectResults::combine_all((0..n).rev().map(results).collect());
+    assert_eq!(summary(&reversed), expected);
+    let grouped: OnlineBenchmarkResults = CollectResults::combine_all(vec![
+        CollectResults::combine_all((0..3).map(results).collect()),
+        CollectResults::combine_all((3..n).map(results).collect()),
+    ]);
+    assert_eq!(summary(&grouped), expected);
+    let folded = (0..n).map(results).reduce(CollectResults::combine).unwrap();
+    assert_eq!(summary(&folded), expected);
+
+    let empty: OnlineBenchmarkResults = CollectResults::combine_all(Vec::new());
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_shared_results() {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    let num_threads = 8;
+    let shared = Arc::new(Mutex::new(OnlineBenchmarkResults::new()));
+    thread::scope(|s| {
+        for i in 0..num_threads {
+            let mut results = shared.clone();
+            s.spawn(move || {
+                for j in 0..100 {
+                    let id = format!("t{}", j);
+                    let time = Duration::from_nanos(j);
+                    results.add_step_measurement("a.proof", &id, "refl", time);
+                }
+                results.add_run_measurement(&("a.proof".to_owned(), i), RunMeasurement::default());
+            });
+        }
+    });
+    assert_eq!(
+        shared.lock().unwrap().step_time().count(),
+        100 * num_threads
+    );
+    assert_eq!(shared.lock().unwrap().parsing().count(), num_threads);
+
+    let mut other = Arc::new(Mutex::new(OnlineBenchmarkResults::new()));
+    other.register_holey();
+    let combined = CollectResults::combine(shared.clone(), other);
+    assert!(Arc::ptr_eq(&combined, &shared));
+    assert!(combined.lock().unwrap().is_holey);
+
+    // Combining the results with themselves doesn't deadlock
+    let combined = CollectResults::combine(combined.clone(), combined);
+    assert_eq!(
+        combined.lock().unwrap().step_time().count(),
+        100 * num_threads
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_peak_memory_usage() {
+    let before = peak_memory_usage().unwrap();
+    assert!(before > 0);
+    let after = peak_memory_usage().unwrap();
+    assert!(after >= before);
+}
@@ -7,6 +7,31 @@ pub struct Context {
     pub cumulative_substitution: Option<Substitution>,
 }
 
+impl Context {
+    /// Checks that the arguments of an anchor are well-formed. That is, no variable is assigned
+    /// more than once, and if an assigned variable is also declared in `variable_args`, the value
+    /// assigned to it has the declared sort.
+    pub fn validate(
+        pool: &mut TermPool,
+        assignment_args: &[(String, Rc<Term>)],
+        variable_args: &[SortedVar],
+    ) -> Result<(), SubstitutionError> {
+        let mut seen = AHashSet::with_capacity(assignment_args.len());
+        for (var, value) in assignment_args {
+            if !seen.insert(var.as_str()) {
+                return Err(SubstitutionError::RepeatedVariable(var.clone()));
+            }
+            if let Some((_, sort)) = variable_args.iter().find(|(name, _)| name == var) {
+                if sort.as_sort() != Some(pool.sort(value)) {
+                    let var_term = pool.add((var.clone(), sort.clone()).into());
+                    return Err(SubstitutionError::DifferentSorts(var_term, value.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct ContextStack {
     stack: Vec<Context>,
@@ -40,6 +65,8 @@ impl ContextStack {
         assignment_args: &[(String, Rc<Term>)],
         variable_args: &[SortedVar],
     ) -> Result<(), SubstitutionError> {
+        Context::validate(pool, assignment_args, variable_args)?;
+
         // Since some rules (like `refl`) need to apply substitutions until a fixed point, we
         // precompute these substitutions into a separate hash map. This assumes that the assignment
         // arguments are in the correct order.
@@ -132,6 +159,16 @@ impl ContextStack {
                 .apply(pool, term)
         }
     }
+
+    /// Applies the current context to every term in `clause`. This is equivalent to calling
+    /// `apply` on each term, but the substitution is only computed and looked up once.
+    pub fn apply_to_clause(&mut self, pool: &mut TermPool, clause: &[Rc<Term>]) -> Vec<Rc<Term>> {
+        if self.is_empty() {
+            return clause.to_vec();
+        }
+        let substitution = self.get_substitution(pool, self.len() - 1);
+        clause.iter().map(|t| substitution.apply(pool, t)).collect()
+    }
 }
 
 fn build_simultaneous_substitution(
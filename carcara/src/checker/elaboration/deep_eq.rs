@@ -124,6 +124,7 @@ impl<'a> DeepEqElaborator<'a> {
                         premises: Vec::new(),
                         args: Vec::new(),
                         discharge: Vec::new(),
+                        source_position: None,
                     },
                 )
             }
@@ -168,6 +169,7 @@ impl<'a> DeepEqElaborator<'a> {
                         premises,
                         args: Vec::new(),
                         discharge: Vec::new(),
+                        source_position: None,
                     },
                 )
             }
@@ -230,6 +232,7 @@ impl<'a> DeepEqElaborator<'a> {
             premises,
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         };
         self.inner.add_new_step(step)
     }
@@ -282,6 +285,7 @@ impl<'a> DeepEqElaborator<'a> {
                 premises: Vec::new(),
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             };
             return self.inner.add_new_step(step);
         }
@@ -304,6 +308,7 @@ impl<'a> DeepEqElaborator<'a> {
             premises: Vec::new(),
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         });
 
         let id = self.inner.get_new_id(self.root_id);
@@ -314,6 +319,7 @@ impl<'a> DeepEqElaborator<'a> {
             premises: vec![cong_step, equiv_step],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         })
     }
 
@@ -358,6 +364,7 @@ impl<'a> DeepEqElaborator<'a> {
                     premises: vec![inner_eq],
                     args: Vec::new(),
                     discharge: Vec::new(),
+                    source_position: None,
                 }),
                 true,
             );
@@ -1,5 +1,5 @@
 use crate::ast::*;
-use std::{iter, vec};
+use std::{borrow::Cow, iter, ops, vec};
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct ProofDiff {
@@ -15,17 +15,29 @@ pub enum CommandDiff {
 }
 
 pub fn apply_diff(root: ProofDiff, proof: Vec<ProofCommand>) -> Vec<ProofCommand> {
-    struct Frame {
+    apply_diff_starting_at(root.commands, &root.new_indices, proof, 0)
+}
+
+/// Applies the diff of the root proof to some of its commands, where `proof` contains the commands
+/// starting at index `start`. The `new_indices` must still contain the new indices of all the
+/// earlier commands, since they may be referenced as premises.
+pub fn apply_diff_starting_at(
+    diff: Vec<(usize, CommandDiff)>,
+    new_indices: &[(usize, usize)],
+    proof: Vec<ProofCommand>,
+    start: usize,
+) -> Vec<ProofCommand> {
+    struct Frame<'a> {
         result: Subproof,
-        commands: iter::Enumerate<vec::IntoIter<ProofCommand>>,
+        commands: iter::Zip<ops::RangeFrom<usize>, vec::IntoIter<ProofCommand>>,
         diff_iter: vec::IntoIter<(usize, CommandDiff)>,
-        new_indices: Vec<(usize, usize)>,
+        new_indices: Cow<'a, [(usize, usize)]>,
     }
     let mut stack = vec![Frame {
         result: Subproof::default(),
-        commands: proof.into_iter().enumerate(),
-        diff_iter: root.commands.into_iter(),
-        new_indices: root.new_indices,
+        commands: (start..).zip(proof),
+        diff_iter: diff.into_iter(),
+        new_indices: Cow::Borrowed(new_indices),
     }];
 
     loop {
@@ -51,9 +63,9 @@ pub fn apply_diff(root: ProofDiff, proof: Vec<ProofCommand>) -> Vec<ProofCommand
                         let commands = std::mem::take(&mut subproof.commands);
                         let new_frame = Frame {
                             result: subproof,
-                            commands: commands.into_iter().enumerate(),
+                            commands: (0..).zip(commands),
                             diff_iter: diff.commands.into_iter(),
-                            new_indices: diff.new_indices,
+                            new_indices: Cow::Owned(diff.new_indices),
                         };
                         stack.push(new_frame);
                     }
@@ -1,13 +1,45 @@
 mod accumulator;
 mod deep_eq;
-mod diff;
-mod pruning;
+pub mod diff;
+pub mod pruning;
 
 use crate::{ast::*, utils::SymbolTable};
 use accumulator::Accumulator;
+use ahash::AHashMap;
 use deep_eq::DeepEqElaborator;
-use diff::{apply_diff, CommandDiff, ProofDiff};
+use diff::{apply_diff, apply_diff_starting_at, CommandDiff, ProofDiff};
 use pruning::prune_proof;
+use std::time::{Duration, Instant};
+
+/// Statistics about the elaboration of a proof, returned by
+/// [`ProofChecker::check_and_elaborate_with_stats`](super::ProofChecker::check_and_elaborate_with_stats).
+#[derive(Debug, Clone, Default)]
+pub struct ElaborationStats {
+    /// The number of commands in the original proof, including the ones inside subproofs.
+    pub steps_before: usize,
+
+    /// The number of commands in the elaborated proof, including the ones inside subproofs.
+    pub steps_after: usize,
+
+    /// The number of steps of each rule that were changed by elaboration. Steps whose elaboration
+    /// rule left them unchanged are not counted. Elaborated `assume` commands are counted under
+    /// the "assume" rule.
+    pub rules_elaborated: AHashMap<String, usize>,
+
+    /// The time spent elaborating steps, and building the elaborated proof.
+    pub elaboration_time: Duration,
+}
+
+/// Returns the number of commands in a proof, including the ones inside subproofs.
+fn count_commands(commands: &[ProofCommand]) -> usize {
+    commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Subproof(s) => count_commands(&s.commands),
+            _ => 1,
+        })
+        .sum()
+}
 
 #[derive(Debug, Default)]
 struct Frame {
@@ -35,6 +67,10 @@ pub struct Elaborator {
     stack: Vec<Frame>,
     seen_clauses: SymbolTable<Vec<Rc<Term>>, usize>,
     accumulator: Accumulator,
+    stats: ElaborationStats,
+
+    /// The number of commands that were replaced by elaboration so far.
+    num_changed: usize,
 }
 
 impl Default for Elaborator {
@@ -49,7 +85,32 @@ impl Elaborator {
             stack: vec![Frame::default()],
             accumulator: Accumulator::new(),
             seen_clauses: SymbolTable::new(),
+            stats: ElaborationStats::default(),
+            num_changed: 0,
+        }
+    }
+
+    /// Returns the statistics collected so far. The number of commands before and after
+    /// elaboration are only known after [`Elaborator::end`] is called.
+    pub fn statistics(&self) -> &ElaborationStats {
+        &self.stats
+    }
+
+    /// Elaborates a command that uses the given rule, by calling `elaborate`, and records it in the
+    /// statistics.
+    pub fn elaborate_with<T>(&mut self, rule: &str, elaborate: impl FnOnce(&mut Self) -> T) -> T {
+        let time = Instant::now();
+        let num_changed = self.num_changed;
+        let result = elaborate(self);
+        self.stats.elaboration_time += time.elapsed();
+        if self.num_changed > num_changed {
+            *self
+                .stats
+                .rules_elaborated
+                .entry(rule.to_owned())
+                .or_default() += 1;
         }
+        result
     }
 
     fn top_frame(&self) -> &Frame {
@@ -123,6 +184,7 @@ impl Elaborator {
         // TODO: discard elaborated steps that introduce already seen conclusions (and can be
         // deleted)
 
+        self.num_changed += 1;
         let clause = step.clause.clone();
         let elaboration = {
             let mut added = std::mem::take(&mut self.accumulator).end();
@@ -174,11 +236,13 @@ impl Elaborator {
         let frame = self.top_frame_mut();
         let (old_index, new_index) = frame.push_new_index(depth);
 
-        if let Some((depth, &index)) = self.seen_clauses.get_with_depth(clause) {
+        if let Some((seen_depth, &index)) = self.seen_clauses.get_with_depth(clause) {
+            // Assumptions inside subproofs are discharged by the step that closes the subproof, so
+            // they can't be removed even if their clause was already seen
             let must_keep = self.must_keep(old_index) || is_assume && depth > 0;
             if !must_keep {
                 let frame = self.top_frame_mut();
-                frame.new_indices[old_index] = (depth, index);
+                frame.new_indices[old_index] = (seen_depth, index);
                 frame.diff.push((old_index, CommandDiff::Delete));
                 frame.current_offset -= 1;
             }
@@ -213,6 +277,7 @@ impl Elaborator {
             premises: vec![original_premise],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         };
         self.add_new_step(step)
     }
@@ -232,6 +297,7 @@ impl Elaborator {
             premises: Vec::new(),
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         };
         self.add_new_step(step)
     }
@@ -253,6 +319,7 @@ impl Elaborator {
         premise: Rc<Term>,
         term: Rc<Term>,
         id: &str,
+        witness: DeepEqWitness,
     ) -> (usize, usize) {
         let new_assume = self.add_new_command(
             ProofCommand::Assume {
@@ -261,6 +328,22 @@ impl Elaborator {
             },
             false,
         );
+
+        // If the term is just the premise with its sides flipped, a single `symm` step is enough
+        // to derive it
+        if witness == DeepEqWitness::Symmetric {
+            let new_id = self.get_new_id(id);
+            return self.push_elaborated_step(ProofStep {
+                id: new_id,
+                clause: vec![term],
+                rule: "symm".to_owned(),
+                premises: vec![new_assume],
+                args: Vec::new(),
+                discharge: Vec::new(),
+                source_position: None,
+            });
+        }
+
         let equality_step = self.elaborate_deep_eq(pool, id, premise.clone(), term.clone(), false);
         let equiv1_step = {
             let new_id = self.get_new_id(id);
@@ -272,6 +355,7 @@ impl Elaborator {
                 premises: vec![equality_step],
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             })
         };
 
@@ -283,6 +367,7 @@ impl Elaborator {
             premises: vec![new_assume, equiv1_step],
             args: vec![ProofArg::Term(premise), ProofArg::Term(pool.bool_true())],
             discharge: Vec::new(),
+            source_position: None,
         })
     }
 
@@ -324,9 +409,28 @@ impl Elaborator {
             self.depth() == 0,
             "trying to end proof building before closing subproof"
         );
+        let time = Instant::now();
+        self.stats.steps_before = count_commands(&original);
         let Frame { diff, new_indices, .. } = self.stack.pop().unwrap();
         let diff = ProofDiff { commands: diff, new_indices };
         let elaborated = apply_diff(diff, original);
-        apply_diff(prune_proof(&elaborated), elaborated)
+        let result = apply_diff(prune_proof(&elaborated), elaborated);
+        self.stats.steps_after = count_commands(&result);
+        self.stats.elaboration_time += time.elapsed();
+        result
+    }
+
+    /// Ends the elaboration of a single command in the root proof, and returns the commands that
+    /// replace it in the elaborated proof. The command must be the last one that was checked, and
+    /// `index` must be its index in the original proof. This is used to elaborate a proof one
+    /// command at a time, so, unlike [`Elaborator::end`], the result is not pruned.
+    pub fn end_command(&mut self, command: ProofCommand, index: usize) -> Vec<ProofCommand> {
+        assert!(
+            self.depth() == 0,
+            "trying to end a command before closing subproof"
+        );
+        let frame = self.top_frame_mut();
+        let diff = std::mem::take(&mut frame.diff);
+        apply_diff_starting_at(diff, &frame.new_indices, vec![command], index)
     }
 }
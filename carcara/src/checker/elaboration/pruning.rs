@@ -20,7 +20,12 @@ pub fn prune_proof(proof: &[ProofCommand]) -> ProofDiff {
         .iter()
         .position(|c| c.clause().is_empty())
         .expect("proof does not reach empty clause");
+    prune_proof_at(proof, end_step)
+}
 
+/// Computes the diff that removes all commands that the command at index `end_step` in the root
+/// proof doesn't depend on, including the commands after it.
+pub fn prune_proof_at(proof: &[ProofCommand], end_step: usize) -> ProofDiff {
     let root = Frame {
         commands: proof,
         subproof_diffs: vec![None; proof.len()],
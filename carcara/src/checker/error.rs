@@ -7,7 +7,7 @@ use rug::Rational;
 use std::{fmt, io};
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum CheckerError {
     #[error("unspecified error")]
     Unspecified,
@@ -71,6 +71,9 @@ pub enum CheckerError {
     #[error("term '{0}' is not a valid n-ary operation")]
     NotValidNaryTerm(Rc<Term>),
 
+    #[error("congruence closure of the arguments doesn't justify '(= {0} {1})'")]
+    CongruenceClosureFailed(Rc<Term>, Rc<Term>),
+
     // General errors
     #[error("expected {0} premises, got {1}")]
     WrongNumberOfPremises(Range, usize),
@@ -123,6 +126,16 @@ pub enum CheckerError {
     #[error("this rule can only be used in the last step of a subproof")]
     MustBeLastStepInSubproof,
 
+    #[error("premise index {0:?} doesn't refer to a previous command")]
+    InvalidPremiseIndex((usize, usize)),
+
+    #[error(
+        "expected sort '{}', got '{}'",
+        expected.to_smt2_string(),
+        got.to_smt2_string()
+    )]
+    SortMismatch { expected: Rc<Term>, got: Rc<Term> },
+
     // Equality errors
     #[error(transparent)]
     TermEquality(#[from] EqualityError<Rc<Term>>),
@@ -133,12 +146,84 @@ pub enum CheckerError {
     #[error(transparent)]
     BindingListEquality(#[from] EqualityError<BindingList>),
 
-    #[error("unknown rule")]
-    UnknownRule,
+    #[error("unknown rule: `{0}`")]
+    UnknownRule(String),
+
+    /// An error annotated with a message describing what was being checked when it happened. See
+    /// [`CheckerError::with_context`].
+    #[error("{message}: {inner}")]
+    WithContext {
+        message: String,
+        inner: Box<CheckerError>,
+    },
+}
+
+impl CheckerError {
+    /// Wraps this error with a message describing what was being checked when it happened. When
+    /// displayed, the message is printed before the original error, separated by a colon.
+    pub fn with_context(self, message: impl fmt::Display) -> CheckerError {
+        CheckerError::WithContext {
+            message: message.to_string(),
+            inner: Box::new(self),
+        }
+    }
+
+    /// Returns a stable identifier for the kind of this error, that can be used by tools to
+    /// categorize errors without relying on their messages. Errors from a specific group of rules
+    /// (for instance, all `ResolutionError`s) share the same code. Errors wrapped with
+    /// [`CheckerError::with_context`] have the code of the original error.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            CheckerError::Unspecified => "E_UNSPECIFIED",
+            CheckerError::Substitution(_) => "E_SUBSTITUTION",
+            CheckerError::Assume(_) => "E_ASSUME",
+            CheckerError::Resolution(_) => "E_RESOLUTION",
+            CheckerError::Cong(_) => "E_CONGRUENCE",
+            CheckerError::Quant(_) => "E_QUANTIFIER",
+            CheckerError::LinearArithmetic(_) => "E_LINEAR_ARITHMETIC",
+            CheckerError::LiaGeneric(_) => "E_LIA_GENERIC",
+            CheckerError::Subproof(_) => "E_SUBPROOF",
+            CheckerError::ReflexivityFailed(_, _) => "E_REFLEXIVITY_FAILED",
+            CheckerError::SimplificationFailed { .. } => "E_SIMPLIFICATION_FAILED",
+            CheckerError::CycleInSimplification(_) => "E_CYCLE_IN_SIMPLIFICATION",
+            CheckerError::SumProdSimplifyInvalidConclusion(_) => "E_INVALID_SIMPLIFICATION_RESULT",
+            CheckerError::TermIsNotConnective(_) => "E_TERM_IS_NOT_CONNECTIVE",
+            CheckerError::IsNotValidIteIntro(_) => "E_INVALID_ITE_INTRO",
+            CheckerError::BrokenTransitivityChain(_, _) => "E_BROKEN_TRANSITIVITY_CHAIN",
+            CheckerError::ContractionMissingTerm(_) => "E_CONTRACTION_MISSING_TERM",
+            CheckerError::ContractionExtraTerm(_) => "E_CONTRACTION_EXTRA_TERM",
+            CheckerError::NotValidNaryTerm(_) => "E_INVALID_NARY_TERM",
+            CheckerError::CongruenceClosureFailed(_, _) => "E_CONGRUENCE_CLOSURE_FAILED",
+            CheckerError::WrongNumberOfPremises(_, _) => "E_WRONG_NUMBER_OF_PREMISES",
+            CheckerError::WrongLengthOfClause(_, _) => "E_WRONG_LENGTH_OF_CLAUSE",
+            CheckerError::WrongNumberOfArgs(_, _) => "E_WRONG_NUMBER_OF_ARGS",
+            CheckerError::WrongNumberOfTermsInOp(_, _, _) => "E_WRONG_NUMBER_OF_TERMS_IN_OP",
+            CheckerError::TermDoesntApperInOp(_, _) => "E_TERM_DOESNT_APPEAR_IN_OP",
+            CheckerError::WrongLengthOfPremiseClause(_, _, _) => "E_WRONG_LENGTH_OF_PREMISE_CLAUSE",
+            CheckerError::TermOfWrongForm(_, _) => "E_TERM_OF_WRONG_FORM",
+            CheckerError::ExpectedBoolConstant(_, _) => "E_EXPECTED_BOOL_CONSTANT",
+            CheckerError::ExpectedAnyBoolConstant(_) => "E_EXPECTED_ANY_BOOL_CONSTANT",
+            CheckerError::ExpectedNumber(_, _) => "E_EXPECTED_NUMBER",
+            CheckerError::ExpectedAnyNumber(_) => "E_EXPECTED_ANY_NUMBER",
+            CheckerError::ExpectedOperationTerm(_) => "E_EXPECTED_OPERATION_TERM",
+            CheckerError::ExpectedQuantifierTerm(_) => "E_EXPECTED_QUANTIFIER_TERM",
+            CheckerError::ExpectedLetTerm(_) => "E_EXPECTED_LET_TERM",
+            CheckerError::ExpectedTermStyleArg(_, _) => "E_EXPECTED_TERM_STYLE_ARG",
+            CheckerError::ExpectedAssignStyleArg(_) => "E_EXPECTED_ASSIGN_STYLE_ARG",
+            CheckerError::MustBeLastStepInSubproof => "E_MUST_BE_LAST_STEP_IN_SUBPROOF",
+            CheckerError::InvalidPremiseIndex(_) => "E_INVALID_PREMISE_INDEX",
+            CheckerError::SortMismatch { .. } => "E_SORT_MISMATCH",
+            CheckerError::TermEquality(_)
+            | CheckerError::QuantifierEquality(_)
+            | CheckerError::BindingListEquality(_) => "E_EQUALITY",
+            CheckerError::UnknownRule(_) => "E_UNKNOWN_RULE",
+            CheckerError::WithContext { inner, .. } => inner.error_code(),
+        }
+    }
 }
 
 /// Errors in which we expected two things to be equal but they weren't.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum EqualityError<T: TypeName> {
     #[error("expected {}s to be equal: '{0}' and '{1}'", T::NAME)]
     ExpectedEqual(T, T),
@@ -147,7 +232,7 @@ pub enum EqualityError<T: TypeName> {
     ExpectedToBe { expected: T, got: T },
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ResolutionError {
     #[error("couldn't find tautology in clause")]
     TautologyFailed,
@@ -163,9 +248,12 @@ pub enum ResolutionError {
 
     #[error("pivot was not found in clause: '{0}'")]
     PivotNotFound(Rc<Term>),
+
+    #[error("premises don't contain complementary literals")]
+    NoComplementaryLiterals,
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum CongruenceError {
     #[error("too many premises")]
     TooManyPremises,
@@ -193,10 +281,13 @@ pub enum CongruenceError {
 
     #[error("term is not an application or operation: '{0}'")]
     NotApplicationOrOperation(Rc<Term>),
+
+    #[error("argument '{0}' is not one of the hypotheses in the conclusion")]
+    ArgumentIsNotHypothesis(Rc<Term>),
 }
 
 /// Errors relevant to the rules dealing with quantifiers.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum QuantifierError {
     #[error("argument doesn't match any binding: '{0}'")]
     NoBindingMatchesArg(String),
@@ -222,7 +313,7 @@ pub enum QuantifierError {
 }
 
 /// Errors relevant to the linear arithmetic rules.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum LinearArithmeticError {
     #[error("term '{0}' doesn't match any tautology case")]
     NotValidTautologyCase(Rc<Term>),
@@ -244,8 +335,21 @@ pub enum LinearArithmeticError {
 
     #[error("expected term '{0}' to be less than or equal to term '{1}'")]
     ExpectedLessEq(Rc<Term>, Rc<Term>),
+
+    #[error("multiplier '{0}' of inequality is not a non-negative constant")]
+    InvalidNlMultiplier(Rc<Term>),
+
+    #[error("sum of hypotheses multiplied by their multipliers is not a constant")]
+    NlSumIsNotConstant,
+
+    #[error("final sum is not contradictory: '{1} {0} 0'")]
+    NlSumIsNotContradiction(Operator, Rational),
+
+    #[error("term '{0}' is not of sort 'Int' or 'Real'")]
+    NotArithmeticTerm(Rc<Term>),
 }
 
+// Since `io::Error` doesn't implement `Clone`, we can't derive it. See the implementation below
 #[derive(Debug, Error)]
 pub enum LiaGenericError {
     #[error("failed to spawn cvc5 process")]
@@ -276,8 +380,24 @@ pub enum LiaGenericError {
     InnerProofError(Box<crate::Error>),
 }
 
+impl Clone for LiaGenericError {
+    fn clone(&self) -> Self {
+        use crate::utils::clone_io_error;
+        match self {
+            Self::FailedSpawnCvc5(e) => Self::FailedSpawnCvc5(clone_io_error(e)),
+            Self::FailedWriteToCvc5Stdin(e) => Self::FailedWriteToCvc5Stdin(clone_io_error(e)),
+            Self::FailedWaitForCvc5(e) => Self::FailedWaitForCvc5(clone_io_error(e)),
+            Self::Cvc5GaveInvalidOutput => Self::Cvc5GaveInvalidOutput,
+            Self::Cvc5OutputNotUnsat => Self::Cvc5OutputNotUnsat,
+            Self::Cvc5Timeout => Self::Cvc5Timeout,
+            Self::Cvc5NonZeroExitCode(code) => Self::Cvc5NonZeroExitCode(*code),
+            Self::InnerProofError(e) => Self::InnerProofError(e.clone()),
+        }
+    }
+}
+
 /// Errors relevant to all rules that end subproofs (not just the `subproof` rule).
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum SubproofError {
     #[error("discharge must be 'assume' command: '{0}'")]
     DischargeMustBeAssume(String),
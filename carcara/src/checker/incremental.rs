@@ -0,0 +1,97 @@
+use super::*;
+
+/// A proof checker that receives the proof one command at a time, instead of all at once.
+///
+/// This is useful when the proof is being produced incrementally, for example by a solver, since
+/// each command is checked as soon as it is received, without the need to buffer the entire proof.
+/// The commands that were checked are accumulated, so the whole proof can be retrieved or
+/// elaborated afterwards.
+///
+/// # Examples
+///
+/// ```
+/// # use carcara::{ast::*, checker::*, parser::*};
+/// let problem = "(declare-fun p () Bool) (assert p) (assert (not p))";
+/// let proof = "(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule resolution :premises (h1 h2))";
+/// let (prelude, proof, mut pool) =
+///     parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+///
+/// let mut checker =
+///     IncrementalProofChecker::new(&mut pool, Config::new(), prelude, proof.premises);
+/// for command in &proof.commands {
+///     assert!(!checker.is_complete());
+///     checker.push_step(command).unwrap();
+/// }
+/// assert!(checker.is_complete());
+/// ```
+pub struct IncrementalProofChecker<'c> {
+    checker: ProofChecker<'c>,
+    proof: Proof,
+}
+
+impl<'c> IncrementalProofChecker<'c> {
+    /// Constructs a new `IncrementalProofChecker`. The `premises` are the terms introduced by the
+    /// `assert` commands in the original problem.
+    pub fn new(
+        pool: &'c mut TermPool,
+        config: Config<'c>,
+        prelude: ProblemPrelude,
+        premises: AHashSet<Rc<Term>>,
+    ) -> Self {
+        Self {
+            checker: ProofChecker::new(pool, config, prelude),
+            proof: Proof { premises, commands: Vec::new() },
+        }
+    }
+
+    /// Checks a command, and adds it to the end of the proof. Subproofs must be given as a single
+    /// `ProofCommand::Subproof`, containing all of their commands. Premises are indexed the same
+    /// way as in a regular proof, and may reference any command that was previously added.
+    ///
+    /// If the command is invalid, an error is returned and the command is not added to the proof.
+    pub fn push_step(&mut self, command: &ProofCommand) -> CarcaraResult<()> {
+        let index = self.proof.commands.len();
+        let context_depth = self.checker.context.len();
+        self.proof.commands.push(command.clone());
+
+        let iter = ProofIter::starting_at(&self.proof.commands, index);
+        let result = self
+            .checker
+            .check_commands(iter, &self.proof.premises, None);
+
+        if result.is_err() {
+            // If the error happened inside a subproof, its context may still be in the stack
+            while self.checker.context.len() > context_depth {
+                self.checker.context.pop();
+            }
+            self.proof.commands.pop();
+        }
+        result
+    }
+
+    /// Returns `true` if the proof received so far reaches the empty clause.
+    pub fn is_complete(&self) -> bool {
+        self.checker.reached_empty_clause
+    }
+
+    /// Returns `true` if any of the commands received so far is a hole.
+    pub fn is_holey(&self) -> bool {
+        self.checker.is_holey
+    }
+
+    /// Returns the proof received so far.
+    pub fn proof(&self) -> &Proof {
+        &self.proof
+    }
+
+    /// Consumes the checker, returning the proof received so far.
+    pub fn into_proof(self) -> Proof {
+        self.proof
+    }
+
+    /// Consumes the checker and elaborates the proof received so far. This checks the proof again,
+    /// and returns an error if it does not reach the empty clause.
+    pub fn elaborate(mut self) -> CarcaraResult<(bool, Proof)> {
+        self.checker.check_and_elaborate(self.proof)
+    }
+}
@@ -1,6 +1,10 @@
 use super::*;
-use crate::{checker::error::LiaGenericError, parser};
+use crate::{
+    checker::error::LiaGenericError,
+    parser::{self, Lexer, Token},
+};
 use ahash::AHashMap;
+use rug::Rational;
 use std::{
     io::{BufRead, Write},
     process::{Command, Stdio},
@@ -32,8 +36,8 @@ pub fn lia_generic(
     root_id: &str,
 ) -> bool {
     let problem = get_problem_string(conclusion, prelude);
-    let commands = match get_cvc5_proof(pool, problem) {
-        Ok(c) => c,
+    let result = match get_cvc5_result(pool, conclusion, &problem) {
+        Ok(r) => r,
         Err(e) => {
             log::warn!("failed to check `lia_generic` step using cvc5: {}", e);
             if let Some(elaborator) = elaborator {
@@ -44,15 +48,168 @@ pub fn lia_generic(
     };
 
     if let Some(elaborator) = elaborator {
-        insert_cvc5_proof(pool, elaborator, commands, conclusion, root_id);
+        match result {
+            Cvc5Result::Proof(commands) => {
+                insert_cvc5_proof(pool, elaborator, commands, conclusion, root_id);
+            }
+            Cvc5Result::Certificate(args) => {
+                insert_certificate(elaborator, args, conclusion, root_id);
+            }
+        }
     }
     false
 }
 
-fn get_cvc5_proof(
+/// What was used to check a `lia_generic` step: either the proof given by cvc5, or the Farkas
+/// certificate extracted from it, already converted into `la_generic` arguments.
+enum Cvc5Result {
+    Proof(Vec<ProofCommand>),
+    Certificate(Vec<ProofArg>),
+}
+
+fn get_cvc5_result(
     pool: &mut TermPool,
-    problem: String,
-) -> Result<Vec<ProofCommand>, LiaGenericError> {
+    conclusion: &[Rc<Term>],
+    problem: &str,
+) -> Result<Cvc5Result, LiaGenericError> {
+    let output = get_cvc5_output(problem)?;
+
+    // If cvc5 proved the step with a single Farkas certificate, we can check it internally, and
+    // embed it in the elaborated proof as the arguments of a `la_generic` step. Otherwise, we fall
+    // back to checking the proof given by cvc5
+    if let Some(coefficients) = extract_certificate(&output) {
+        let args = certificate_args(pool, &coefficients);
+        if check_certificate(pool, conclusion, &args) {
+            return Ok(Cvc5Result::Certificate(args));
+        }
+    }
+    parse_and_check_cvc5_proof(pool, problem.as_bytes(), output.as_bytes())
+        .map(Cvc5Result::Proof)
+        .map_err(|e| LiaGenericError::InnerProofError(Box::new(e)))
+}
+
+/// Extracts the Farkas certificate from the proof printed by cvc5 for a `lia_generic` step. This
+/// is only possible if the proof contains exactly one `la_generic` step, in which case the
+/// coefficients given as its arguments are returned.
+pub fn extract_certificate(cvc5_output: &str) -> Option<Vec<Rational>> {
+    fn next(lexer: &mut Lexer<&[u8]>) -> Option<Token> {
+        match lexer.next_token() {
+            Ok((Token::Eof, _)) | Err(_) => None,
+            Ok((token, _)) => Some(token),
+        }
+    }
+
+    // Parses a coefficient, which can be a numeral or decimal, possibly negated with `-` or
+    // divided with `/`
+    fn coefficient(lexer: &mut Lexer<&[u8]>, token: Token) -> Option<Rational> {
+        let result = match token {
+            Token::Numeral(n) => n.into(),
+            Token::Decimal(r) => r,
+            Token::OpenParen => {
+                let Token::Symbol(op) = next(lexer)? else {
+                    return None;
+                };
+                let first = next(lexer).and_then(|t| coefficient(lexer, t))?;
+                let result = match (op.as_str(), next(lexer)?) {
+                    ("-", Token::CloseParen) => return Some(-first),
+                    ("/", t) => {
+                        let second = coefficient(lexer, t)?;
+                        if second == 0 {
+                            return None;
+                        }
+                        first / second
+                    }
+                    _ => return None,
+                };
+                (next(lexer)? == Token::CloseParen).then_some(result)?
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    let mut lexer = Lexer::new(cvc5_output.as_bytes()).ok()?;
+    let mut certificate = None;
+    let mut previous = None;
+    while let Some(token) = next(&mut lexer) {
+        let is_la_generic = matches!(
+            (&previous, &token),
+            (Some(Token::Keyword(k)), Token::Symbol(s)) if k == "rule" && s == "la_generic"
+        );
+        previous = Some(token);
+        if !is_la_generic {
+            continue;
+        }
+        if certificate.is_some() {
+            return None;
+        }
+
+        // The arguments come after the rule, possibly after the premises
+        let mut depth = 0;
+        let coefficients = loop {
+            match next(&mut lexer)? {
+                Token::Keyword(k) if depth == 0 && k == "args" => break Some(()),
+                Token::OpenParen => depth += 1,
+                Token::CloseParen if depth == 0 => break None,
+                Token::CloseParen => depth -= 1,
+                _ => (),
+            }
+        };
+        if coefficients.is_none() || next(&mut lexer)? != Token::OpenParen {
+            return None;
+        }
+        let mut coefficients = Vec::new();
+        loop {
+            match next(&mut lexer)? {
+                Token::CloseParen => break,
+                t => coefficients.push(coefficient(&mut lexer, t)?),
+            }
+        }
+        certificate = Some(coefficients);
+        previous = None;
+    }
+    certificate
+}
+
+/// Converts the coefficients of a Farkas certificate into arguments for a `la_generic` step.
+pub(super) fn certificate_args(pool: &mut TermPool, coefficients: &[Rational]) -> Vec<ProofArg> {
+    coefficients
+        .iter()
+        .map(|c| {
+            // Non-integer coefficients are written as the division of two real constants, since
+            // `/` can only be applied to reals
+            let (numer, denom) = c.clone().abs().into_numer_denom();
+            let mut term = if denom == 1 {
+                pool.add(Term::integer(numer))
+            } else {
+                let args = vec![pool.add(Term::real(numer)), pool.add(Term::real(denom))];
+                pool.add(Term::Op(Operator::RealDiv, args))
+            };
+            if *c < 0 {
+                term = pool.add(Term::Op(Operator::Sub, vec![term]));
+            }
+            ProofArg::Term(term)
+        })
+        .collect()
+}
+
+fn check_certificate(pool: &mut TermPool, conclusion: &[Rc<Term>], args: &[ProofArg]) -> bool {
+    let mut time = Duration::ZERO;
+    let result = rules::linear_arithmetic::la_generic(RuleArgs {
+        conclusion,
+        premises: &[],
+        args,
+        pool,
+        context: &mut ContextStack::new(),
+        previous_command: None,
+        discharge: &[],
+        deep_eq_depth_limit: None,
+        deep_eq_time: &mut time,
+    });
+    result.is_ok()
+}
+
+fn get_cvc5_output(problem: &str) -> Result<String, LiaGenericError> {
     let mut cvc5 = Command::new("cvc5")
         .args([
             "--tlimit=10000",
@@ -97,8 +254,7 @@ fn get_cvc5_proof(
         return Err(LiaGenericError::Cvc5OutputNotUnsat);
     }
 
-    parse_and_check_cvc5_proof(pool, problem.as_bytes(), proof)
-        .map_err(|e| LiaGenericError::InnerProofError(Box::new(e)))
+    String::from_utf8(proof.to_vec()).map_err(|_| LiaGenericError::Cvc5GaveInvalidOutput)
 }
 
 fn parse_and_check_cvc5_proof(
@@ -179,6 +335,24 @@ fn insert_missing_assumes(
     (all, num_added)
 }
 
+fn insert_certificate(
+    elaborator: &mut Elaborator,
+    args: Vec<ProofArg>,
+    conclusion: &[Rc<Term>],
+    root_id: &str,
+) {
+    let id = elaborator.get_new_id(root_id);
+    elaborator.push_elaborated_step(ProofStep {
+        id,
+        clause: conclusion.to_vec(),
+        rule: "la_generic".to_owned(),
+        premises: Vec::new(),
+        args,
+        discharge: Vec::new(),
+        source_position: None,
+    });
+}
+
 fn insert_cvc5_proof(
     pool: &mut TermPool,
     elaborator: &mut Elaborator,
@@ -224,6 +398,7 @@ fn insert_cvc5_proof(
             premises: Vec::new(),
             args: Vec::new(),
             discharge,
+            source_position: None,
         },
         root_id,
     );
@@ -247,6 +422,7 @@ fn insert_cvc5_proof(
                 premises: Vec::new(),
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             })
         })
         .collect();
@@ -258,6 +434,7 @@ fn insert_cvc5_proof(
         premises: Vec::new(),
         args: Vec::new(),
         discharge: Vec::new(),
+        source_position: None,
     });
 
     let mut premises = vec![subproof];
@@ -272,5 +449,6 @@ fn insert_cvc5_proof(
         premises,
         args: Vec::new(),
         discharge: Vec::new(),
+        source_position: None,
     });
 }
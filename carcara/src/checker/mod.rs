@@ -1,24 +1,43 @@
 mod context;
-mod elaboration;
+pub(crate) mod elaboration;
 pub mod error;
+mod incremental;
 mod lia_generic;
 mod rules;
+mod streaming;
+#[cfg(test)]
+mod tests;
 
-use crate::{ast::*, benchmarking::CollectResults, CarcaraResult, Error};
-use ahash::AHashSet;
+use crate::{ast::*, benchmarking::CollectResults, utils::LruCache, CarcaraResult, Error};
+use ahash::{AHashMap, AHashSet};
 use context::*;
+pub use elaboration::ElaborationStats;
 use elaboration::Elaborator;
 use error::CheckerError;
+pub use incremental::IncrementalProofChecker;
 use rules::{ElaborationRule, Premise, Rule, RuleArgs, RuleResult};
+pub use rules::{RuleCategory, RuleMetadata};
 use std::{
     fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
+pub use streaming::StreamingElaborator;
 
 pub struct CheckerStatistics<'s> {
     pub file_name: &'s str,
     pub elaboration_time: &'s mut Duration,
     pub deep_eq_time: &'s mut Duration,
+
+    // The time spent in `deep_eq` by the steps of each rule. The time spent comparing `assume`
+    // commands with the problem premises is attributed to the "assume" rule.
+    pub deep_eq_time_by_rule: &'s mut AHashMap<String, Duration>,
+
+    // The number of `step` commands checked that use each rule
+    pub rule_histogram: &'s mut AHashMap<String, usize>,
     pub assume_time: &'s mut Duration,
 
     // This is the time to compare the `assume` term with the `assert` that matches it. That is,
@@ -35,12 +54,37 @@ impl fmt::Debug for CheckerStatistics<'_> {
             .field("file_name", &self.file_name)
             .field("elaboration_time", &self.elaboration_time)
             .field("deep_eq_time", &self.deep_eq_time)
+            .field("deep_eq_time_by_rule", &self.deep_eq_time_by_rule)
+            .field("rule_histogram", &self.rule_histogram)
             .field("assume_time", &self.assume_time)
             .field("assume_core_time", &self.assume_core_time)
             .finish()
     }
 }
 
+impl CheckerStatistics<'_> {
+    fn add_deep_eq_time(&mut self, rule: &str, time: Duration) {
+        if time.is_zero() {
+            return;
+        }
+        match self.deep_eq_time_by_rule.get_mut(rule) {
+            Some(total) => *total += time,
+            None => {
+                self.deep_eq_time_by_rule.insert(rule.to_owned(), time);
+            }
+        }
+    }
+
+    fn count_rule(&mut self, rule: &str) {
+        match self.rule_histogram.get_mut(rule) {
+            Some(count) => *count += 1,
+            None => {
+                self.rule_histogram.insert(rule.to_owned(), 1);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Config<'c> {
     strict: bool,
@@ -48,6 +92,10 @@ pub struct Config<'c> {
     is_running_test: bool,
     statistics: Option<CheckerStatistics<'c>>,
     lia_via_cvc5: bool,
+    max_steps: Option<usize>,
+    deep_eq_depth_limit: Option<usize>,
+    deep_eq_cache_size: Option<usize>,
+    abort_flag: Option<Arc<AtomicBool>>,
 }
 
 impl<'c> Config<'c> {
@@ -70,40 +118,310 @@ impl<'c> Config<'c> {
         self
     }
 
+    /// If set, the checker stops after checking this many `assume` and `step` commands. In that
+    /// case, the checker returns `Ok` even if the proof does not reach the empty clause. This makes
+    /// it possible to check only the beginning of an incomplete proof. This option is ignored when
+    /// elaborating.
+    pub fn max_steps(mut self, value: Option<usize>) -> Self {
+        self.max_steps = value;
+        self
+    }
+
+    /// If set, comparisons of terms using `deep_eq` (and alpha-equivalence) give up once they
+    /// recurse this many levels deep, and the terms are considered different. This bounds the time
+    /// and stack space spent on pathological terms, at the cost of rejecting some valid steps.
+    pub fn deep_eq_depth_limit(mut self, value: Option<usize>) -> Self {
+        self.deep_eq_depth_limit = value;
+        self
+    }
+
+    /// If set, the premise matched by each term introduced by an `assume` command, modulo
+    /// reordering of equalities, is cached, keeping at most this many entries. When the same term
+    /// is assumed again, it doesn't need to be compared with all premises.
+    pub fn deep_eq_cache_size(mut self, value: Option<usize>) -> Self {
+        self.deep_eq_cache_size = value;
+        self
+    }
+
+    /// If set, the checker stops with an `Error::Timeout` as soon as this flag is set. The flag is
+    /// only checked between commands, so a single slow step may still delay the abort.
+    pub fn abort_flag(mut self, value: Option<Arc<AtomicBool>>) -> Self {
+        self.abort_flag = value;
+        self
+    }
+
     pub fn statistics(mut self, value: CheckerStatistics<'c>) -> Self {
         self.statistics = Some(value);
         self
     }
 }
 
+/// Defines `RULE_NAMES`, `ProofChecker::get_rule_function` and `ProofChecker::has_strict_version`
+/// from a single table, so they can't go out of sync. Each entry maps one or more rule names to
+/// the function that implements the rule and the rule's category, and optionally to a different
+/// function that is used in strict mode.
+macro_rules! rule_table {
+    (@is_given) => { false };
+    (@is_given $strict:expr) => { true };
+    ($(
+        $($name:literal)|+ => ($rule:expr, $category:ident $(, strict: $strict:expr)? $(,)?)
+    ),* $(,)?) => {
+        /// The names of all rules known by the checker, in the same order as they appear in
+        /// [`ProofChecker::get_rule`].
+        const RULE_NAMES: &[&str] = &[$($($name),+),*];
+
+        impl ProofChecker<'_> {
+            fn get_rule_function(rule_name: &str, strict: bool) -> Option<(Rule, RuleCategory)> {
+                use rules::*;
+                use RuleCategory::*;
+
+                Some(match rule_name {
+                    $($($name)|+ => {
+                        let rule: Rule = $rule;
+                        $(let rule: Rule = if strict { $strict } else { rule };)?
+                        (rule, $category)
+                    })*
+                    _ => return None,
+                })
+            }
+
+            /// Returns `true` if `get_rule_function` returns a different function for the rule
+            /// with the given name when `strict` is `true`.
+            fn has_strict_version(rule_name: &str) -> bool {
+                match rule_name {
+                    $($($name)|+ => rule_table!(@is_given $($strict)?),)*
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
+rule_table! {
+    "true" => (tautology::r#true, Propositional),
+    "false" => (tautology::r#false, Propositional),
+    "not_not" => (tautology::not_not, Propositional),
+    "and_pos" => (tautology::and_pos, Propositional),
+    "and_neg" => (tautology::and_neg, Propositional),
+    "or_pos" => (tautology::or_pos, Propositional),
+    "or_neg" => (tautology::or_neg, Propositional),
+    "xor_pos1" => (tautology::xor_pos1, Propositional),
+    "xor_pos2" => (tautology::xor_pos2, Propositional),
+    "xor_neg1" => (tautology::xor_neg1, Propositional),
+    "xor_neg2" => (tautology::xor_neg2, Propositional),
+    "implies_pos" => (tautology::implies_pos, Propositional),
+    "implies_neg1" => (tautology::implies_neg1, Propositional),
+    "implies_neg2" => (tautology::implies_neg2, Propositional),
+    "equiv_pos1" => (tautology::equiv_pos1, Propositional),
+    "equiv_pos2" => (tautology::equiv_pos2, Propositional),
+    "equiv_neg1" => (tautology::equiv_neg1, Propositional),
+    "equiv_neg2" => (tautology::equiv_neg2, Propositional),
+    "ite_pos1" => (tautology::ite_pos1, Propositional),
+    "ite_pos2" => (tautology::ite_pos2, Propositional),
+    "ite_neg1" => (tautology::ite_neg1, Propositional),
+    "ite_neg2" => (tautology::ite_neg2, Propositional),
+    "eq_reflexive" => (reflexivity::eq_reflexive, Congruence),
+    "eq_transitive" => (transitivity::eq_transitive, Congruence),
+    "eq_congruent" => (congruence::eq_congruent, Congruence),
+    "eq_congruent_pred" => (congruence::eq_congruent_pred, Congruence),
+    "distinct_elim" => (clausification::distinct_elim, Propositional),
+    "la_rw_eq" => (linear_arithmetic::la_rw_eq, Arithmetic),
+    // `lia_generic` steps that provide Farkas coefficients are checked in the same way as
+    // `la_generic` steps. Steps without them are handled separately by `check_step`
+    "la_generic" | "lia_generic" => (linear_arithmetic::la_generic, Arithmetic),
+    "nl_generic" => (linear_arithmetic::nl_generic, Arithmetic),
+    "la_disequality" => (linear_arithmetic::la_disequality, Arithmetic),
+    "la_totality" => (linear_arithmetic::la_totality, Arithmetic),
+    "la_tautology" => (linear_arithmetic::la_tautology, Arithmetic),
+    "forall_inst" => (quantifier::forall_inst, Quantifier),
+    "exists_pos" => (quantifier::exists_pos, Quantifier),
+    "exists_neg" => (quantifier::exists_neg, Quantifier),
+    "forall_neg" => (quantifier::forall_neg, Quantifier),
+    "qnt_join" => (quantifier::qnt_join, Quantifier),
+    "qnt_rm_unused" => (quantifier::qnt_rm_unused, Quantifier),
+    "resolution" | "th_resolution" => (
+        resolution::resolution,
+        Propositional,
+        strict: resolution::resolution_with_args
+    ),
+    "refl" => (reflexivity::refl, Congruence, strict: reflexivity::strict_refl),
+    "trans" => (transitivity::trans, Congruence),
+    "cong" => (congruence::cong, Congruence),
+    "cc_lemma" => (congruence::cc_lemma, Congruence),
+    "ho_cong" => (congruence::ho_cong, Congruence),
+    "and" => (clausification::and, Propositional),
+    "tautology" => (resolution::tautology, Propositional),
+    "not_or" => (clausification::not_or, Propositional),
+    "or" => (clausification::or, Propositional),
+    "not_and" => (clausification::not_and, Propositional),
+    "xor1" => (clausification::xor1, Propositional),
+    "xor2" => (clausification::xor2, Propositional),
+    "not_xor1" => (clausification::not_xor1, Propositional),
+    "not_xor2" => (clausification::not_xor2, Propositional),
+    "implies" => (clausification::implies, Propositional),
+    "not_implies1" => (clausification::not_implies1, Propositional),
+    "not_implies2" => (clausification::not_implies2, Propositional),
+    "equiv1" => (tautology::equiv1, Propositional),
+    "equiv2" => (tautology::equiv2, Propositional),
+    "not_equiv1" => (tautology::not_equiv1, Propositional),
+    "not_equiv2" => (tautology::not_equiv2, Propositional),
+    "ite1" => (tautology::ite1, Propositional),
+    "ite2" => (tautology::ite2, Propositional),
+    "not_ite1" => (tautology::not_ite1, Propositional),
+    "not_ite2" => (tautology::not_ite2, Propositional),
+    "ite_intro" => (tautology::ite_intro, Propositional),
+    "contraction" => (resolution::contraction, Propositional),
+    "connective_def" => (tautology::connective_def, Propositional),
+    "ite_simplify" => (simplification::ite_simplify, Propositional),
+    "eq_simplify" => (simplification::eq_simplify, Propositional),
+    "and_simplify" => (simplification::and_simplify, Propositional),
+    "or_simplify" => (simplification::or_simplify, Propositional),
+    "not_simplify" => (simplification::not_simplify, Propositional),
+    "implies_simplify" => (simplification::implies_simplify, Propositional),
+    "equiv_simplify" => (simplification::equiv_simplify, Propositional),
+    "bool_simplify" => (simplification::bool_simplify, Propositional),
+    "qnt_simplify" => (simplification::qnt_simplify, Quantifier),
+    "div_simplify" => (simplification::div_simplify, Arithmetic),
+    "prod_simplify" => (simplification::prod_simplify, Arithmetic),
+    // Despite being separate rules in the specification, proofs generated by veriT don't
+    // differentiate between `unary_minus_simplify` and `minus_simplify`. To account for
+    // that, `simplification::minus_simplify` implements both rules in the same function.
+    "unary_minus_simplify" | "minus_simplify" => (simplification::minus_simplify, Arithmetic),
+    "sum_simplify" => (simplification::sum_simplify, Arithmetic),
+    "comp_simplify" => (simplification::comp_simplify, Arithmetic),
+    "nary_elim" => (clausification::nary_elim, Propositional),
+    "ac_simp" => (simplification::ac_simp, Propositional),
+    "bfun_elim" => (clausification::bfun_elim, Propositional),
+    "bind" => (subproof::bind, Subproof),
+    "qnt_cnf" => (quantifier::qnt_cnf, Quantifier),
+    "subproof" => (subproof::subproof, Subproof),
+    "let" => (subproof::r#let, Subproof),
+    "onepoint" => (subproof::onepoint, Subproof),
+    "sko_ex" => (subproof::sko_ex, Subproof),
+    "sko_forall" => (subproof::sko_forall, Subproof),
+    "reordering" => (extras::reordering, Extra),
+    "symm" => (extras::symm, Extra),
+    "not_symm" => (extras::not_symm, Extra),
+    "eq_symmetric" => (extras::eq_symmetric, Extra),
+    "or_intro" => (extras::or_intro, Extra),
+    "cut" => (extras::cut, Extra),
+    "th_lemma" => (extras::th_lemma, Extra),
+    "sym_bp" => (extras::sym_bp, Extra),
+    "bind_let" => (extras::bind_let, Extra),
+    "la_mult_pos" => (extras::la_mult_pos, Extra),
+    "la_mult_neg" => (extras::la_mult_neg, Extra),
+    "str_concat_flatten" => (strings::str_concat_flatten, Extra),
+    "str_len_pos" => (strings::str_len_pos, Extra),
+
+    // Special rules that always check as valid, and are used to indicate holes in the
+    // proof.
+    "hole" => (|_| Ok(()), Hole),
+
+    // The Alethe specification does not yet describe how this more strict version of the
+    // resolution rule will be called. Until that is decided and added to the specification,
+    // we define a new specialized rule that calls it
+    "strict_resolution" => (resolution::strict_resolution, Propositional),
+}
+
+/// Returns the metadata of all rules known by the checker, in the same order as they appear in
+/// [`ProofChecker::get_rule`].
+pub fn list_all_rules() -> Vec<RuleMetadata> {
+    RULE_NAMES
+        .iter()
+        .map(|name| ProofChecker::get_rule(name, false).unwrap().1)
+        .collect()
+}
+
+/// Maps terms introduced by `assume` commands to the premise they matched modulo reordering of
+/// equalities, together with the witness of how they were matched.
+type DeepEqCache = LruCache<Rc<Term>, (Rc<Term>, DeepEqWitness)>;
+
 pub struct ProofChecker<'c> {
     pool: &'c mut TermPool,
     config: Config<'c>,
     prelude: ProblemPrelude,
     context: ContextStack,
     elaborator: Option<Elaborator>,
+
+    /// See [`Config::deep_eq_cache_size`].
+    deep_eq_cache: Option<DeepEqCache>,
     reached_empty_clause: bool,
     is_holey: bool,
 }
 
 impl<'c> ProofChecker<'c> {
     pub fn new(pool: &'c mut TermPool, config: Config<'c>, prelude: ProblemPrelude) -> Self {
+        let deep_eq_cache = config.deep_eq_cache_size.map(LruCache::new);
         ProofChecker {
             pool,
             config,
             prelude,
             context: ContextStack::new(),
             elaborator: None,
+            deep_eq_cache,
             reached_empty_clause: false,
             is_holey: false,
         }
     }
 
+    /// Constructs a new checker in strict mode, with an otherwise default configuration. This is
+    /// equivalent to using `Config::new().strict(true)`.
+    pub fn new_strict(pool: &'c mut TermPool, prelude: ProblemPrelude) -> Self {
+        Self::new(pool, Config::new().strict(true), prelude)
+    }
+
+    /// Constructs a new checker that skips steps with unknown rules, with an otherwise default
+    /// configuration. This is equivalent to using `Config::new().skip_unknown_rules(true)`.
+    pub fn new_permissive(pool: &'c mut TermPool, prelude: ProblemPrelude) -> Self {
+        Self::new(pool, Config::new().skip_unknown_rules(true), prelude)
+    }
+
     pub fn check(&mut self, proof: &Proof) -> CarcaraResult<bool> {
+        let max_steps = match self.elaborator {
+            Some(_) => None,
+            None => self.config.max_steps,
+        };
+        self.check_commands(proof.iter(), &proof.premises, max_steps)?;
+
+        if self.config.is_running_test || self.reached_empty_clause || max_steps.is_some() {
+            Ok(self.is_holey)
+        } else {
+            Err(Error::DoesNotReachEmptyClause)
+        }
+    }
+
+    /// Checks a proof that is only a fragment of a larger proof. Unlike [`ProofChecker::check`],
+    /// this doesn't require the proof to reach the empty clause. Returns `true` if the fragment is
+    /// holey.
+    pub fn check_fragment(&mut self, proof: &Proof) -> CarcaraResult<bool> {
+        self.check_commands(proof.iter(), &proof.premises, self.config.max_steps)?;
+        Ok(self.is_holey)
+    }
+
+    /// Checks all commands yielded by `iter`, stopping after `max_steps` `assume` and `step`
+    /// commands, if it is given.
+    fn check_commands(
+        &mut self,
+        mut iter: ProofIter,
+        premises: &AHashSet<Rc<Term>>,
+        max_steps: Option<usize>,
+    ) -> CarcaraResult<()> {
         // Similarly to the parser, to avoid stack overflows in proofs with many nested subproofs,
         // we check the subproofs iteratively, instead of recursively
-        let mut iter = proof.iter();
+        let mut num_checked_steps = 0;
         while let Some(command) = iter.next() {
+            if let Some(flag) = &self.config.abort_flag {
+                if flag.load(Ordering::Relaxed) {
+                    return Err(Error::Timeout);
+                }
+            }
+            if !command.is_subproof() {
+                if max_steps == Some(num_checked_steps) {
+                    return Ok(());
+                }
+                num_checked_steps += 1;
+            }
+
             match command {
                 ProofCommand::Step(step) => {
                     let is_end_of_subproof = iter.is_end_step();
@@ -119,11 +437,15 @@ impl<'c> ProofChecker<'c> {
                     } else {
                         None
                     };
+                    if let Some(s) = &mut self.config.statistics {
+                        s.count_rule(&step.rule);
+                    }
                     self.check_step(step, previous_command, &iter)
                         .map_err(|e| Error::Checker {
                             inner: e,
                             rule: step.rule.clone(),
                             step: step.id.clone(),
+                            position: step.source_position.clone(),
                         })?;
 
                     // If this is the last command of a subproof, we have to pop the subproof
@@ -150,6 +472,7 @@ impl<'c> ProofChecker<'c> {
                             inner: e.into(),
                             rule: "anchor".into(),
                             step: step_id.to_owned(),
+                            position: None,
                         })?;
 
                     if let Some(elaborator) = &mut self.elaborator {
@@ -170,24 +493,31 @@ impl<'c> ProofChecker<'c> {
                     }
                 }
                 ProofCommand::Assume { id, term } => {
-                    if !self.check_assume(id, term, &proof.premises, &iter) {
+                    if !self.check_assume(id, term, premises, &iter) {
                         return Err(Error::Checker {
                             inner: CheckerError::Assume(term.clone()),
                             rule: "assume".into(),
                             step: id.clone(),
+                            position: None,
                         });
                     }
                 }
             }
         }
-        if self.config.is_running_test || self.reached_empty_clause {
-            Ok(self.is_holey)
-        } else {
-            Err(Error::DoesNotReachEmptyClause)
-        }
+        Ok(())
     }
 
-    pub fn check_and_elaborate(&mut self, mut proof: Proof) -> CarcaraResult<(bool, Proof)> {
+    pub fn check_and_elaborate(&mut self, proof: Proof) -> CarcaraResult<(bool, Proof)> {
+        self.check_and_elaborate_with_stats(proof)
+            .map(|(is_holey, proof, _)| (is_holey, proof))
+    }
+
+    /// Checks and elaborates a proof, like [`ProofChecker::check_and_elaborate`], and also returns
+    /// statistics about the elaboration.
+    pub fn check_and_elaborate_with_stats(
+        &mut self,
+        mut proof: Proof,
+    ) -> CarcaraResult<(bool, Proof, ElaborationStats)> {
         self.elaborator = Some(Elaborator::new());
         let result = self.check(&proof);
 
@@ -201,7 +531,7 @@ impl<'c> ProofChecker<'c> {
         if let Some(stats) = &mut self.config.statistics {
             *stats.elaboration_time += elaboration_time.elapsed();
         }
-        Ok((self.is_holey, proof))
+        Ok((self.is_holey, proof, elaborator.statistics().clone()))
     }
 
     fn check_assume(
@@ -242,30 +572,50 @@ impl<'c> ProofChecker<'c> {
             return false;
         }
 
-        let mut found = None;
         let mut deep_eq_time = Duration::ZERO;
         let mut core_time = Duration::ZERO;
 
+        // If this term was already assumed before, the premise it matched is in the cache, and we
+        // don't need to compare it with the premises again
+        let mut found = self
+            .deep_eq_cache
+            .as_mut()
+            .and_then(|c| c.get(term).cloned());
         for p in premises {
+            if found.is_some() {
+                break;
+            }
             let mut this_deep_eq_time = Duration::ZERO;
-            let (result, depth) = tracing_deep_eq(term, p, &mut this_deep_eq_time);
+            let (witness, depth) = tracing_deep_eq(
+                term,
+                p,
+                self.config.deep_eq_depth_limit,
+                &mut this_deep_eq_time,
+            );
             deep_eq_time += this_deep_eq_time;
             if let Some(s) = &mut self.config.statistics {
                 s.results.add_deep_eq_depth(depth);
             }
-            if result {
+            if let Some(witness) = witness {
+                if let Some(cache) = &mut self.deep_eq_cache {
+                    cache.insert(term.clone(), (p.clone(), witness));
+                }
                 core_time = this_deep_eq_time;
-                found = Some(p.clone());
+                found = Some((p.clone(), witness));
                 break;
             }
         }
 
-        let Some(p) = found else { return false };
+        let Some((p, witness)) = found else {
+            return false;
+        };
 
         if let Some(elaborator) = &mut self.elaborator {
             let elaboration_time = Instant::now();
 
-            elaborator.elaborate_assume(self.pool, p, term.clone(), id);
+            elaborator.elaborate_with("assume", |elaborator| {
+                elaborator.elaborate_assume(self.pool, p, term.clone(), id, witness)
+            });
 
             if let Some(s) = &mut self.config.statistics {
                 *s.elaboration_time += elaboration_time.elapsed();
@@ -277,6 +627,7 @@ impl<'c> ProofChecker<'c> {
             *s.assume_time += time;
             *s.assume_core_time += core_time;
             *s.deep_eq_time += deep_eq_time;
+            s.add_deep_eq_time("assume", deep_eq_time);
             s.results
                 .add_assume_measurement(s.file_name, id, false, time);
         }
@@ -294,15 +645,31 @@ impl<'c> ProofChecker<'c> {
         let mut deep_eq_time = Duration::ZERO;
 
         let mut elaborated = false;
-        if step.rule == "lia_generic" {
+
+        // The parser ensures that all premise indices are valid, but proof fragments that were
+        // built programmatically may reference commands that are not in the fragment
+        let mut indices = step.premises.iter().chain(&step.discharge);
+        if let Some(&p) = indices.find(|&&p| !iter.is_valid_premise(p)) {
+            return Err(CheckerError::InvalidPremiseIndex(p));
+        }
+
+        // A `lia_generic` step that provides Farkas coefficients as arguments can be checked
+        // internally, in the same way as a `la_generic` step. Otherwise, we need cvc5 to check it
+        if step.rule == "lia_generic" && step.args.is_empty() {
             if self.config.lia_via_cvc5 {
-                let is_hole = lia_generic::lia_generic(
-                    self.pool,
-                    &step.clause,
-                    &self.prelude,
-                    self.elaborator.as_mut(),
-                    &step.id,
-                );
+                let (pool, prelude) = (&mut *self.pool, &self.prelude);
+                let is_hole = match &mut self.elaborator {
+                    Some(elaborator) => elaborator.elaborate_with(&step.rule, |elaborator| {
+                        lia_generic::lia_generic(
+                            pool,
+                            &step.clause,
+                            prelude,
+                            Some(elaborator),
+                            &step.id,
+                        )
+                    }),
+                    None => lia_generic::lia_generic(pool, &step.clause, prelude, None, &step.id),
+                };
                 self.is_holey = self.is_holey || is_hole;
                 elaborated = self.elaborator.is_some();
             } else {
@@ -313,8 +680,8 @@ impl<'c> ProofChecker<'c> {
                 }
             }
         } else {
-            let rule = match Self::get_rule(&step.rule, self.config.strict) {
-                Some(r) => r,
+            let rule = match Self::get_rule_function(&step.rule, self.config.strict) {
+                Some((r, _)) => r,
                 None if self.config.skip_unknown_rules => {
                     self.is_holey = true;
                     if let Some(elaborator) = &mut self.elaborator {
@@ -322,10 +689,21 @@ impl<'c> ProofChecker<'c> {
                     }
                     return Ok(());
                 }
-                None => return Err(CheckerError::UnknownRule),
+                None => return Err(CheckerError::UnknownRule(step.rule.clone())),
             };
 
-            if step.rule == "hole" {
+            // `nl_generic` steps without a certificate can't be checked, so they are treated as
+            // holes. The same goes for `th_lemma` steps of theories we don't know how to check, and
+            // for `sym_bp` steps, which are only checked to be well-formed
+            let unchecked_th_lemma = step.rule == "th_lemma"
+                && rules::extras::th_lemma_theory(&step.args).map_or(false, |t| {
+                    !rules::extras::TH_LEMMA_CHECKED_THEORIES.contains(&t)
+                });
+            if step.rule == "hole"
+                || step.rule == "sym_bp"
+                || (step.rule == "nl_generic" && step.args.is_empty())
+                || unchecked_th_lemma
+            {
                 self.is_holey = true;
             }
 
@@ -351,12 +729,15 @@ impl<'c> ProofChecker<'c> {
                 context: &mut self.context,
                 previous_command,
                 discharge: &discharge,
+                deep_eq_depth_limit: self.config.deep_eq_depth_limit,
                 deep_eq_time: &mut deep_eq_time,
             };
 
             if let Some(elaborator) = &mut self.elaborator {
-                if let Some(elaboration_rule) = Self::get_elaboration_rule(&step.rule) {
-                    elaboration_rule(rule_args, step.id.clone(), elaborator)?;
+                if let Some((elaboration_rule, _)) = Self::get_elaboration_rule(&step.rule) {
+                    elaborator.elaborate_with(&step.rule, |elaborator| {
+                        elaboration_rule(rule_args, step.id.clone(), elaborator)
+                    })?;
                     elaborated = true;
                 } else {
                     rule(rule_args)?;
@@ -372,6 +753,7 @@ impl<'c> ProofChecker<'c> {
             s.results
                 .add_step_measurement(s.file_name, &step.id, &step.rule, time);
             *s.deep_eq_time += deep_eq_time;
+            s.add_deep_eq_time(&step.rule, deep_eq_time);
             if elaborated {
                 *s.elaboration_time += time;
             }
@@ -379,132 +761,81 @@ impl<'c> ProofChecker<'c> {
         Ok(())
     }
 
-    pub fn get_rule(rule_name: &str, strict: bool) -> Option<Rule> {
-        use rules::*;
-
-        Some(match rule_name {
-            "true" => tautology::r#true,
-            "false" => tautology::r#false,
-            "not_not" => tautology::not_not,
-            "and_pos" => tautology::and_pos,
-            "and_neg" => tautology::and_neg,
-            "or_pos" => tautology::or_pos,
-            "or_neg" => tautology::or_neg,
-            "xor_pos1" => tautology::xor_pos1,
-            "xor_pos2" => tautology::xor_pos2,
-            "xor_neg1" => tautology::xor_neg1,
-            "xor_neg2" => tautology::xor_neg2,
-            "implies_pos" => tautology::implies_pos,
-            "implies_neg1" => tautology::implies_neg1,
-            "implies_neg2" => tautology::implies_neg2,
-            "equiv_pos1" => tautology::equiv_pos1,
-            "equiv_pos2" => tautology::equiv_pos2,
-            "equiv_neg1" => tautology::equiv_neg1,
-            "equiv_neg2" => tautology::equiv_neg2,
-            "ite_pos1" => tautology::ite_pos1,
-            "ite_pos2" => tautology::ite_pos2,
-            "ite_neg1" => tautology::ite_neg1,
-            "ite_neg2" => tautology::ite_neg2,
-            "eq_reflexive" => reflexivity::eq_reflexive,
-            "eq_transitive" => transitivity::eq_transitive,
-            "eq_congruent" => congruence::eq_congruent,
-            "eq_congruent_pred" => congruence::eq_congruent_pred,
-            "distinct_elim" => clausification::distinct_elim,
-            "la_rw_eq" => linear_arithmetic::la_rw_eq,
-            "la_generic" => linear_arithmetic::la_generic,
-            "la_disequality" => linear_arithmetic::la_disequality,
-            "la_totality" => linear_arithmetic::la_totality,
-            "la_tautology" => linear_arithmetic::la_tautology,
-            "forall_inst" => quantifier::forall_inst,
-            "qnt_join" => quantifier::qnt_join,
-            "qnt_rm_unused" => quantifier::qnt_rm_unused,
-            "resolution" | "th_resolution" if strict => resolution::resolution_with_args,
-            "resolution" | "th_resolution" => resolution::resolution,
-            "refl" if strict => reflexivity::strict_refl,
-            "refl" => reflexivity::refl,
-            "trans" => transitivity::trans,
-            "cong" => congruence::cong,
-            "ho_cong" => congruence::ho_cong,
-            "and" => clausification::and,
-            "tautology" => resolution::tautology,
-            "not_or" => clausification::not_or,
-            "or" => clausification::or,
-            "not_and" => clausification::not_and,
-            "xor1" => clausification::xor1,
-            "xor2" => clausification::xor2,
-            "not_xor1" => clausification::not_xor1,
-            "not_xor2" => clausification::not_xor2,
-            "implies" => clausification::implies,
-            "not_implies1" => clausification::not_implies1,
-            "not_implies2" => clausification::not_implies2,
-            "equiv1" => tautology::equiv1,
-            "equiv2" => tautology::equiv2,
-            "not_equiv1" => tautology::not_equiv1,
-            "not_equiv2" => tautology::not_equiv2,
-            "ite1" => tautology::ite1,
-            "ite2" => tautology::ite2,
-            "not_ite1" => tautology::not_ite1,
-            "not_ite2" => tautology::not_ite2,
-            "ite_intro" => tautology::ite_intro,
-            "contraction" => resolution::contraction,
-            "connective_def" => tautology::connective_def,
-            "ite_simplify" => simplification::ite_simplify,
-            "eq_simplify" => simplification::eq_simplify,
-            "and_simplify" => simplification::and_simplify,
-            "or_simplify" => simplification::or_simplify,
-            "not_simplify" => simplification::not_simplify,
-            "implies_simplify" => simplification::implies_simplify,
-            "equiv_simplify" => simplification::equiv_simplify,
-            "bool_simplify" => simplification::bool_simplify,
-            "qnt_simplify" => simplification::qnt_simplify,
-            "div_simplify" => simplification::div_simplify,
-            "prod_simplify" => simplification::prod_simplify,
-            // Despite being separate rules in the specification, proofs generated by veriT don't
-            // differentiate between `unary_minus_simplify` and `minus_simplify`. To account for
-            // that, `simplification::minus_simplify` implements both rules in the same function.
-            "unary_minus_simplify" | "minus_simplify" => simplification::minus_simplify,
-            "sum_simplify" => simplification::sum_simplify,
-            "comp_simplify" => simplification::comp_simplify,
-            "nary_elim" => clausification::nary_elim,
-            "ac_simp" => simplification::ac_simp,
-            "bfun_elim" => clausification::bfun_elim,
-            "bind" => subproof::bind,
-            "qnt_cnf" => quantifier::qnt_cnf,
-            "subproof" => subproof::subproof,
-            "let" => subproof::r#let,
-            "onepoint" => subproof::onepoint,
-            "sko_ex" => subproof::sko_ex,
-            "sko_forall" => subproof::sko_forall,
-            "reordering" => extras::reordering,
-            "symm" => extras::symm,
-            "not_symm" => extras::not_symm,
-            "eq_symmetric" => extras::eq_symmetric,
-            "or_intro" => extras::or_intro,
-            "bind_let" => extras::bind_let,
-            "la_mult_pos" => extras::la_mult_pos,
-            "la_mult_neg" => extras::la_mult_neg,
-
-            // Special rules that always check as valid, and are used to indicate holes in the
-            // proof.
-            "hole" => |_| Ok(()),
-
-            // The Alethe specification does not yet describe how this more strict version of the
-            // resolution rule will be called. Until that is decided and added to the specification,
-            // we define a new specialized rule that calls it
-            "strict_resolution" => resolution::strict_resolution,
+    /// Checks a single step in isolation, by calling the rule with the given name directly. This is
+    /// useful to test rules without having to parse a whole proof. The step is checked as if it
+    /// were outside of any subproof, and each premise is given only by its clause. The premises are
+    /// named `p0`, `p1`, and so on, in error messages.
+    ///
+    /// Since there is no subproof context, rules that need one (like `bind` or `subproof`) can't be
+    /// checked this way. The same goes for `lia_generic` steps without Farkas coefficients, which
+    /// can only be checked by calling cvc5. If there is no rule with the given name, this returns
+    /// `CheckerError::UnknownRule`.
+    pub fn check_step_isolated(
+        rule_name: &str,
+        conclusion: &[Rc<Term>],
+        premises: &[&[Rc<Term>]],
+        args: &[ProofArg],
+        pool: &mut TermPool,
+    ) -> Result<(), CheckerError> {
+        let (rule, _) = Self::get_rule_function(rule_name, false)
+            .ok_or_else(|| CheckerError::UnknownRule(rule_name.to_owned()))?;
+        let ids: Vec<_> = (0..premises.len()).map(|i| format!("p{}", i)).collect();
+        let premises: Vec<_> = premises
+            .iter()
+            .zip(&ids)
+            .enumerate()
+            .map(|(i, (clause, id))| Premise { id, clause, index: (0, i) })
+            .collect();
+        let mut deep_eq_time = Duration::ZERO;
+        let rule_args = RuleArgs {
+            conclusion,
+            premises: &premises,
+            args,
+            pool,
+            context: &mut ContextStack::new(),
+            previous_command: None,
+            discharge: &[],
+            deep_eq_depth_limit: None,
+            deep_eq_time: &mut deep_eq_time,
+        };
+        rule(rule_args)
+    }
 
-            _ => return None,
-        })
+    /// Returns the function that implements the rule with the given name, together with
+    /// information about the rule, like its category. If `strict` is `true`, the strict version of
+    /// the rule is returned, for the rules that have one. Returns `None` if there is no rule with
+    /// the given name.
+    pub fn get_rule(rule_name: &str, strict: bool) -> Option<(Rule, RuleMetadata)> {
+        let (rule, category) = Self::get_rule_function(rule_name, strict)?;
+        let name = RULE_NAMES.iter().find(|&&name| name == rule_name)?;
+        let metadata = RuleMetadata {
+            name,
+            category,
+            is_eliminable: Self::get_elaboration_rule(rule_name).map_or(false, |(_, e)| e),
+            has_strict_version: Self::has_strict_version(rule_name),
+        };
+        Some((rule, metadata))
     }
 
-    fn get_elaboration_rule(rule_name: &str) -> Option<ElaborationRule> {
+    /// Returns the function that elaborates the rule with the given name, if there is one. The
+    /// returned boolean is `true` if the elaborated steps don't use the rule anymore, but only more
+    /// primitive rules (see [`RuleMetadata::is_eliminable`]). Other elaboration rules only add
+    /// missing information to the step, like the pivots of a `resolution` step.
+    fn get_elaboration_rule(rule_name: &str) -> Option<(ElaborationRule, bool)> {
         use rules::*;
 
         Some(match rule_name {
-            "eq_transitive" => transitivity::elaborate_eq_transitive,
-            "resolution" | "th_resolution" => resolution::elaborate_resolution,
-            "refl" => reflexivity::elaborate_refl,
-            "trans" => transitivity::elaborate_trans,
+            "eq_transitive" => (transitivity::elaborate_eq_transitive, false),
+            "resolution" | "th_resolution" => (resolution::elaborate_resolution, false),
+            "refl" => (reflexivity::elaborate_refl, false),
+            "trans" => (transitivity::elaborate_trans, false),
+            "la_disequality" => (linear_arithmetic::elaborate_la_disequality, true),
+            "la_tautology" => (linear_arithmetic::elaborate_la_tautology, true),
+            "or_intro" => (extras::elaborate_or_intro, true),
+            "distinct_elim" => (clausification::elaborate_distinct_elim, false),
+            "connective_def" => (tautology::elaborate_connective_def, true),
+            "sko_ex" => (subproof::elaborate_sko_ex, true),
+            "sko_forall" => (subproof::elaborate_sko_forall, true),
             _ => return None,
         })
     }
@@ -1,12 +1,16 @@
 use super::{
     assert_clause_len, assert_deep_eq_is_expected, assert_eq, assert_is_expected,
-    assert_num_premises, assert_operation_len, get_premise_term, CheckerError, EqualityError,
-    RuleArgs, RuleResult,
+    assert_num_premises, assert_operation_len, get_premise_term, CheckerError, Elaborator,
+    EqualityError, RuleArgs, RuleResult,
 };
 use crate::ast::*;
 use ahash::AHashMap;
 
 pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    check_distinct_elim(conclusion, pool)
+}
+
+fn check_distinct_elim(conclusion: &[Rc<Term>], pool: &mut TermPool) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (distinct_args, second_term) = match_term_err!((= (distinct ...) second) = &conclusion[0])?;
@@ -60,6 +64,64 @@ pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
     }
 }
 
+pub fn elaborate_distinct_elim(
+    RuleArgs { conclusion, pool, .. }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    check_distinct_elim(conclusion, pool)?;
+
+    // The `distinct_elim` rule allows each equality in the conclusion to be in either order. If any
+    // of them is flipped, we elaborate the step into a `distinct_elim` step where all equalities
+    // follow the order of the arguments, and a proof that the two terms are equal modulo reordering
+    // of equalities
+    let (distinct_term, second_term) = match_term!((= d s) = &conclusion[0]).unwrap();
+    let distinct_args = match_term!((distinct ...) = distinct_term).unwrap();
+    let n = distinct_args.len();
+    let expected = if n == 2 {
+        let (a, b) = (distinct_args[0].clone(), distinct_args[1].clone());
+        build_term!(pool, (not (= {a} {b})))
+    } else if *pool.sort(&distinct_args[0]) == Sort::Bool {
+        second_term.clone()
+    } else {
+        let mut and_args = Vec::with_capacity(n * (n - 1) / 2);
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let (a, b) = (distinct_args[i].clone(), distinct_args[j].clone());
+                and_args.push(build_term!(pool, (not (= {a} {b}))));
+            }
+        }
+        pool.add(Term::Op(Operator::And, and_args))
+    };
+    if expected == *second_term {
+        elaborator.unchanged(conclusion);
+        return Ok(());
+    }
+
+    let id = elaborator.get_new_id(&command_id);
+    let distinct_elim_step = elaborator.add_new_step(ProofStep {
+        id,
+        clause: vec![build_term!(pool, (= {distinct_term.clone()} {expected.clone()}))],
+        rule: "distinct_elim".to_owned(),
+        premises: Vec::new(),
+        args: Vec::new(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    let equality_step =
+        elaborator.elaborate_deep_eq(pool, &command_id, expected, second_term.clone(), false);
+    elaborator.push_elaborated_step(ProofStep {
+        id: command_id,
+        clause: conclusion.to_vec(),
+        rule: "trans".to_owned(),
+        premises: vec![distinct_elim_step, equality_step],
+        args: Vec::new(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    Ok(())
+}
+
 pub fn and(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 1)?;
@@ -385,6 +447,7 @@ pub fn bfun_elim(
         conclusion,
         premises,
         pool,
+        deep_eq_depth_limit,
         deep_eq_time,
         ..
     }: RuleArgs,
@@ -395,7 +458,7 @@ pub fn bfun_elim(
     let psi = get_premise_term(&premises[0])?;
 
     let expected = apply_bfun_elim(pool, psi, &mut AHashMap::new())?;
-    assert_deep_eq_is_expected(&conclusion[0], expected, deep_eq_time)
+    assert_deep_eq_is_expected(&conclusion[0], expected, deep_eq_depth_limit, deep_eq_time)
 }
 
 #[cfg(test)]
@@ -1,7 +1,9 @@
 use super::{
-    assert_clause_len, assert_num_premises, get_premise_term, CheckerError, RuleArgs, RuleResult,
+    assert_clause_len, assert_num_args, assert_num_premises, get_premise_term, CheckerError,
+    RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::CongruenceError};
+use ahash::{AHashMap, AHashSet};
 
 pub fn eq_congruent(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 2..)?;
@@ -212,6 +214,139 @@ pub fn ho_cong(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     check_cong(&premises, f_args, g_args)
 }
 
+/// A simple congruence closure over a fixed set of terms. Each term is assigned an index, and the
+/// equivalence classes are kept in a union-find structure over these indices.
+struct CongruenceClosure<'a> {
+    ids: AHashMap<&'a Rc<Term>, usize>,
+    terms: Vec<&'a Rc<Term>>,
+    parents: Vec<usize>,
+}
+
+impl<'a> CongruenceClosure<'a> {
+    fn new() -> Self {
+        Self {
+            ids: AHashMap::new(),
+            terms: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Adds a term and all of its subterms to the closure, and returns its index.
+    fn add_term(&mut self, term: &'a Rc<Term>) -> usize {
+        if let Some(&id) = self.ids.get(term) {
+            return id;
+        }
+        match term.as_ref() {
+            Term::App(f, args) => {
+                self.add_term(f);
+                for a in args {
+                    self.add_term(a);
+                }
+            }
+            Term::Op(_, args) => {
+                for a in args {
+                    self.add_term(a);
+                }
+            }
+            _ => (),
+        }
+        let id = self.terms.len();
+        self.ids.insert(term, id);
+        self.terms.push(term);
+        self.parents.push(id);
+        id
+    }
+
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            self.parents[id] = self.parents[self.parents[id]];
+            id = self.parents[id];
+        }
+        id
+    }
+
+    fn merge(&mut self, a: &'a Rc<Term>, b: &'a Rc<Term>) {
+        let (a, b) = (self.add_term(a), self.add_term(b));
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+
+    fn are_equal(&mut self, a: &'a Rc<Term>, b: &'a Rc<Term>) -> bool {
+        let (a, b) = (self.add_term(a), self.add_term(b));
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns `true` if the terms with indices `i` and `j` are applications of equivalent
+    /// functions (or of the same operator) to equivalent arguments.
+    fn are_congruent(&mut self, i: usize, j: usize) -> bool {
+        let (heads, a_args, b_args) = match (self.terms[i].as_ref(), self.terms[j].as_ref()) {
+            (Term::App(f, a_args), Term::App(g, b_args)) => (Some((f, g)), a_args, b_args),
+            (Term::Op(f, a_args), Term::Op(g, b_args)) if f == g => (None, a_args, b_args),
+            _ => return false,
+        };
+        if a_args.len() != b_args.len() {
+            return false;
+        }
+        let pairs: Vec<_> = heads
+            .into_iter()
+            .chain(a_args.iter().zip(b_args))
+            .map(|(a, b)| (self.ids[a], self.ids[b]))
+            .collect();
+        pairs.into_iter().all(|(a, b)| self.find(a) == self.find(b))
+    }
+
+    /// Merges congruent terms until a fixed point is reached.
+    fn close(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..self.terms.len() {
+                for j in i + 1..self.terms.len() {
+                    if self.find(i) != self.find(j) && self.are_congruent(i, j) {
+                        let (a, b) = (self.find(i), self.find(j));
+                        self.parents[a] = b;
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The `cc_lemma` rule. The conclusion is a clause of the form `(cl (not e_1) ... (not e_n) (= t
+/// u))`, and the arguments are the equalities, among the `e_i`, that justify `(= t u)` by
+/// reflexivity, symmetry, transitivity and congruence. The arguments may be given in any order.
+pub fn cc_lemma(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1..)?;
+    assert_num_args(args, 1..)?;
+
+    let (t, u) = match_term_err!((= t u) = conclusion.last().unwrap())?;
+    let hypotheses: AHashSet<_> = conclusion[..conclusion.len() - 1]
+        .iter()
+        .map(Rc::remove_negation_err)
+        .collect::<Result<_, _>>()?;
+
+    let mut closure = CongruenceClosure::new();
+    for arg in args {
+        let arg = arg.as_term()?;
+        rassert!(
+            hypotheses.contains(arg),
+            CongruenceError::ArgumentIsNotHypothesis(arg.clone())
+        );
+        let (a, b) = match_term_err!((= a b) = arg)?;
+        closure.merge(a, b);
+    }
+    closure.add_term(t);
+    closure.add_term(u);
+    closure.close();
+
+    rassert!(
+        closure.are_equal(t, u),
+        CheckerError::CongruenceClosureFailed(t.clone(), u.clone())
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -462,4 +597,49 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cc_lemma() {
+        test_cases! {
+            definitions = "
+                (declare-sort T 0)
+                (declare-fun a () T)
+                (declare-fun b () T)
+                (declare-fun c () T)
+                (declare-fun d () T)
+                (declare-fun e () T)
+                (declare-fun f (T) T)
+                (declare-fun g (T T) T)
+                (declare-fun p (T) Bool)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (not (= a b)) (not (= b c)) (not (= c d)) (not (= d e))
+                    (= (f a) (f e))) :rule cc_lemma
+                    :args ((= a b) (= b c) (= c d) (= d e)))": true,
+
+                "(step t1 (cl (not (= a b)) (not (= c b)) (= (g a c) (g b b)))
+                    :rule cc_lemma :args ((= c b) (= a b)))": true,
+
+                "(step t1 (cl (not (= a (f a))) (= a (f (f a))))
+                    :rule cc_lemma :args ((= a (f a))))": true,
+
+                "(step t1 (cl (not (= a b)) (= (p a) (p b))) :rule cc_lemma :args ((= a b)))": true,
+            }
+            "Unused hypotheses are allowed" {
+                "(step t1 (cl (not (= a b)) (not (= d e)) (= (f a) (f b)))
+                    :rule cc_lemma :args ((= a b)))": true,
+            }
+            "Argument is not a hypothesis" {
+                "(step t1 (cl (not (= a b)) (= (f a) (f c)))
+                    :rule cc_lemma :args ((= a b) (= b c)))": false,
+            }
+            "Closure does not justify conclusion" {
+                "(step t1 (cl (not (= a b)) (not (= c d)) (= (f a) (f d)))
+                    :rule cc_lemma :args ((= a b) (= c d)))": false,
+
+                "(step t1 (cl (not (= (f a) (f b))) (= a b))
+                    :rule cc_lemma :args ((= (f a) (f b))))": false,
+            }
+        }
+    }
 }
@@ -1,11 +1,15 @@
 //! This module contains rules that are not yet in the specification for the Alethe format.
 
 use super::{
-    assert_clause_len, assert_eq, assert_num_premises, get_premise_term, CheckerError,
-    EqualityError, RuleArgs, RuleResult,
+    assert_clause_len, assert_eq, assert_num_args, assert_num_premises, get_premise_term,
+    linear_arithmetic, CheckerError, Elaborator, EqualityError, RuleArgs, RuleResult,
 };
-use crate::{ast::*, checker::rules::assert_operation_len};
-use ahash::AHashSet;
+use crate::{
+    ast::*,
+    checker::{error::ResolutionError, rules::assert_operation_len},
+    utils::DedupIterator,
+};
+use ahash::{AHashMap, AHashSet};
 
 pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
@@ -13,14 +17,19 @@ pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     let premise = premises[0].clause;
     assert_clause_len(conclusion, premise.len())?;
 
-    let premise_set: AHashSet<_> = premise.iter().collect();
-    let conclusion_set: AHashSet<_> = conclusion.iter().collect();
-    if let Some(&t) = premise_set.difference(&conclusion_set).next() {
-        Err(CheckerError::ContractionMissingTerm(t.clone()))
-    } else if let Some(&t) = conclusion_set.difference(&premise_set).next() {
-        Err(CheckerError::ContractionExtraTerm(t.clone()))
-    } else {
-        Ok(())
+    // The conclusion must be a permutation of the premise, so each literal must appear the same
+    // number of times in both clauses
+    let mut counts: AHashMap<&Rc<Term>, isize> = AHashMap::new();
+    for t in premise {
+        *counts.entry(t).or_default() += 1;
+    }
+    for t in conclusion {
+        *counts.entry(t).or_default() -= 1;
+    }
+    match counts.into_iter().find(|&(_, count)| count != 0) {
+        Some((t, count)) if count > 0 => Err(CheckerError::ContractionMissingTerm(t.clone())),
+        Some((t, _)) => Err(CheckerError::ContractionExtraTerm(t.clone())),
+        None => Ok(()),
     }
 }
 
@@ -56,7 +65,10 @@ pub fn eq_symmetric(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
 
 pub fn or_intro(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
-    let premise = premises[0].clause;
+    check_or_intro(conclusion, premises[0].clause)
+}
+
+fn check_or_intro(conclusion: &[Rc<Term>], premise: &[Rc<Term>]) -> RuleResult {
     assert_clause_len(conclusion, premise.len()..)?;
     for (t, u) in premise.iter().zip(conclusion) {
         assert_eq(t, u)?;
@@ -64,6 +76,123 @@ pub fn or_intro(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
+pub fn elaborate_or_intro(
+    RuleArgs { conclusion, premises, pool, .. }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    assert_num_premises(premises, 1)?;
+    let premise = premises[0];
+    check_or_intro(conclusion, premise.clause)?;
+
+    // If the premise is empty, the disjunction can't be derived from it by resolution, so the step
+    // is kept as is. Also, a disjunction must have at least two arguments
+    if premise.clause.is_empty() || conclusion.len() < 2 {
+        elaborator.unchanged(conclusion);
+        return Ok(());
+    }
+
+    // An `or_intro` step concluding `(cl a_1 ... a_n b_1 ... b_m)` from the premise
+    // `(cl a_1 ... a_n)` is elaborated into:
+    //
+    // ```
+    // (step t.t1 (cl (or a_1 ... b_m) (not a_1)) :rule or_neg)
+    // ...
+    // (step t.tn (cl (or a_1 ... b_m) (not a_n)) :rule or_neg)
+    // (step t.tn+1 (cl (not (or a_1 ... b_m)) a_1 ... b_m) :rule or_pos)
+    // (step t (cl a_1 ... b_m) :rule resolution :premises (p t.t1 ... t.tn+1)
+    //     :args (a_1 true ... a_n true (or a_1 ... b_m) true))
+    // ```
+    let mut add_step = |rule: &str, clause| {
+        let id = elaborator.get_new_id(&command_id);
+        elaborator.add_new_step(ProofStep {
+            id,
+            clause,
+            rule: rule.to_owned(),
+            premises: Vec::new(),
+            args: Vec::new(),
+            discharge: Vec::new(),
+            source_position: None,
+        })
+    };
+    let or_term = pool.add(Term::Op(Operator::Or, conclusion.to_vec()));
+    let mut new_premises = Vec::new();
+    let mut args = Vec::new();
+    for literal in premise.clause.iter().dedup() {
+        let negated = build_term!(pool, (not {literal.clone()}));
+        new_premises.push(add_step("or_neg", vec![or_term.clone(), negated]));
+        args.extend([literal.clone(), pool.bool_true()]);
+    }
+    let mut or_pos_clause = vec![build_term!(pool, (not {or_term.clone()}))];
+    or_pos_clause.extend(conclusion.iter().cloned());
+    new_premises.push(add_step("or_pos", or_pos_clause));
+    args.extend([or_term, pool.bool_true()]);
+
+    new_premises.insert(0, elaborator.map_index(premise.index));
+    elaborator.push_elaborated_step(ProofStep {
+        id: command_id,
+        clause: conclusion.to_vec(),
+        rule: "resolution".to_owned(),
+        premises: new_premises,
+        args: args.into_iter().map(ProofArg::Term).collect(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    Ok(())
+}
+
+/// The `cut` rule is a case split on a formula `P`. It takes two premises, one containing `P` and
+/// the other containing `(not P)`, and concludes the remaining literals of both premises. Usually,
+/// the premises are simply `(cl P)` and `(cl (not P))`, and the conclusion is the empty clause.
+pub fn cut(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+    /// Returns the literals that should remain after cutting on `pivot`, which appears in `pos`,
+    /// while its negation appears in `neg`.
+    fn remaining<'a>(
+        pivot: &Rc<Term>,
+        pos: &'a [Rc<Term>],
+        neg: &'a [Rc<Term>],
+    ) -> AHashSet<&'a Rc<Term>> {
+        let pos = pos.iter().filter(|&t| t != pivot);
+        let neg = neg.iter().filter(|t| t.remove_negation() != Some(pivot));
+        pos.chain(neg).collect()
+    }
+
+    assert_num_premises(premises, 2)?;
+
+    let (first, second) = (premises[0].clause, premises[1].clause);
+    let conclusion: AHashSet<_> = conclusion.iter().collect();
+
+    let candidates = first
+        .iter()
+        .filter(|&p| second.iter().any(|t| t.remove_negation() == Some(p)))
+        .map(|p| remaining(p, first, second))
+        .chain(
+            second
+                .iter()
+                .filter(|&p| first.iter().any(|t| t.remove_negation() == Some(p)))
+                .map(|p| remaining(p, second, first)),
+        );
+
+    // If there is more than one pair of complementary literals, any of them may have been used as
+    // the pivot
+    let mut error = ResolutionError::NoComplementaryLiterals;
+    for expected in candidates {
+        if let Some(&t) = expected.difference(&conclusion).next() {
+            error = ResolutionError::MissingTermInConclusion(t.clone());
+        } else if let Some(&t) = conclusion.difference(&expected).next() {
+            error = ResolutionError::ExtraTermInConclusion(t.clone());
+        } else {
+            return Ok(());
+        }
+    }
+    Err(error.into())
+}
+
+/// Steps that use this rule are not elaborated. The only rule that can remove `let` binders is
+/// `let`, which needs its own subproof, and a strict `refl` step can't justify the equality between
+/// the expanded bodies, since they are only equal modulo the equalities proved in the `bind_let`
+/// subproof. So a `bind_let` step is kept as is in elaborated proofs. The steps in its subproof are
+/// still elaborated.
 pub fn bind_let(
     RuleArgs {
         conclusion,
@@ -183,6 +312,76 @@ fn la_mult_generic(conclusion: &[Rc<Term>], is_pos: bool) -> RuleResult {
     assert_eq(r, r_2)
 }
 
+/// The theories whose `th_lemma` steps can be checked. Steps tagged with any other theory are
+/// accepted, but are considered holes.
+pub const TH_LEMMA_CHECKED_THEORIES: &[&str] = &["lra", "lia"];
+
+/// Returns the theory tag of a `th_lemma` step, which must be given as a string constant in its
+/// first argument.
+pub fn th_lemma_theory(args: &[ProofArg]) -> Result<&str, CheckerError> {
+    assert_num_args(args, 1..)?;
+    let tag = args[0].as_term()?;
+    match tag.as_ref() {
+        Term::Terminal(Terminal::String(s)) => Ok(s),
+        _ => Err(CheckerError::TermOfWrongForm("\"<theory>\"", tag.clone())),
+    }
+}
+
+/// The `th_lemma` rule. Its first argument is a tag indicating the theory of the lemma, and the
+/// remaining arguments are a theory-specific certificate. For the `lra` and `lia` theories, the
+/// certificate is a list of Farkas coefficients, and the step is checked like a `la_generic` step.
+/// Lemmas from any other theory are not checked.
+pub fn th_lemma(rule_args: RuleArgs) -> RuleResult {
+    let theory = th_lemma_theory(rule_args.args)?;
+    if TH_LEMMA_CHECKED_THEORIES.contains(&theory) {
+        let args = &rule_args.args[1..];
+        linear_arithmetic::la_generic(RuleArgs { args, ..rule_args })
+    } else {
+        Ok(())
+    }
+}
+
+/// The `sym_bp` rule, used for symmetry breaking predicates. The conclusion must be of the form
+/// `(cl (p x y))`, where the arguments are the two constants `x` and `y`, which must have the same
+/// sort `S`, and the binary predicate `p`, which must have sort `(-> S S Bool)`.
+///
+/// Note that this rule only checks that the step is well-formed. Justifying a symmetry breaking
+/// predicate requires reasoning about the whole problem, so these steps are always considered
+/// holes in the proof.
+pub fn sym_bp(RuleArgs { conclusion, args, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+    assert_num_args(args, 3)?;
+
+    let (x, y, p) = (args[0].as_term()?, args[1].as_term()?, args[2].as_term()?);
+    for var in [x, y] {
+        if var.as_var().is_none() {
+            return Err(CheckerError::TermOfWrongForm("<constant>", var.clone()));
+        }
+    }
+
+    let x_sort = pool.add(Term::Sort(pool.sort(x).clone()));
+    let y_sort = pool.add(Term::Sort(pool.sort(y).clone()));
+    rassert!(
+        x_sort == y_sort,
+        CheckerError::SortMismatch {
+            expected: x_sort.clone(),
+            got: y_sort,
+        }
+    );
+
+    let bool_sort = pool.add(Term::Sort(Sort::Bool));
+    let expected = Sort::Function(vec![x_sort.clone(), x_sort, bool_sort]);
+    let expected = pool.add(Term::Sort(expected));
+    let got = pool.add(Term::Sort(pool.sort(p).clone()));
+    rassert!(
+        expected == got,
+        CheckerError::SortMismatch { expected, got }
+    );
+
+    let expected = pool.add(Term::App(p.clone(), vec![x.clone(), y.clone()]));
+    assert_eq(&expected, &conclusion[0])
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -204,9 +403,78 @@ mod tests {
                 "(step t1 (cl) :rule hole)
                 (step t2 (cl) :rule reordering :premises (t1))": true,
             }
+            "Single literal clauses" {
+                "(step t1 (cl p) :rule hole)
+                (step t2 (cl p) :rule reordering :premises (t1))": true,
+
+                "(step t1 (cl p) :rule hole)
+                (step t2 (cl q) :rule reordering :premises (t1))": false,
+            }
+            "Identity permutation" {
+                "(step t1 (cl p q r s) :rule hole)
+                (step t2 (cl p q r s) :rule reordering :premises (t1))": true,
+            }
+            "Repeated literals" {
+                "(step t1 (cl p p p) :rule hole)
+                (step t2 (cl p p p) :rule reordering :premises (t1))": true,
+
+                "(step t1 (cl p p q) :rule hole)
+                (step t2 (cl p q q) :rule reordering :premises (t1))": false,
+
+                "(step t1 (cl (not p) p) :rule hole)
+                (step t2 (cl p (not p)) :rule reordering :premises (t1))": true,
+            }
+            "Clause lengths differ" {
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl q p r) :rule reordering :premises (t1))": false,
+
+                "(step t1 (cl p q r) :rule hole)
+                (step t2 (cl q p) :rule reordering :premises (t1))": false,
+
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl q p p) :rule reordering :premises (t1))": false,
+
+                "(step t1 (cl) :rule hole)
+                (step t2 (cl p) :rule reordering :premises (t1))": false,
+            }
+            "Literal is replaced" {
+                "(step t1 (cl p q r) :rule hole)
+                (step t2 (cl r s p) :rule reordering :premises (t1))": false,
+            }
+            "Wrong number of premises" {
+                "(step t1 (cl p q) :rule reordering)": false,
+            }
         }
     }
 
+    #[test]
+    fn reordering_large_clause() {
+        let n = 60;
+        let definitions: String = (0..n)
+            .map(|i| format!("(declare-fun p{} () Bool)\n", i))
+            .collect();
+        let literals: Vec<_> = (0..n).map(|i| format!("p{}", i)).collect();
+        let reversed: Vec<_> = literals.iter().rev().cloned().collect();
+        let make_proof = |conclusion: &[String]| {
+            format!(
+                "(step t1 (cl {}) :rule hole)\n(step t2 (cl {}) :rule reordering :premises (t1))",
+                literals.join(" "),
+                conclusion.join(" "),
+            )
+        };
+
+        let valid = make_proof(&reversed);
+        let mut with_extra = reversed.clone();
+        with_extra[0] = "p1".to_owned();
+        let invalid = make_proof(&with_extra);
+
+        crate::checker::rules::run_tests(
+            "Large clauses",
+            &definitions,
+            &[(&valid, true), (&invalid, false)],
+        );
+    }
+
     #[test]
     fn symm() {
         test_cases! {
@@ -289,6 +557,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cut() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () Bool)
+                (declare-fun b () Bool)
+                (declare-fun c () Bool)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl a) :rule hole)
+                (step t2 (cl (not a)) :rule hole)
+                (step t3 (cl) :rule cut :premises (t1 t2))": true,
+
+                "(step t1 (cl (not a)) :rule hole)
+                (step t2 (cl a) :rule hole)
+                (step t3 (cl) :rule cut :premises (t1 t2))": true,
+            }
+            "Remaining literals are concluded" {
+                "(step t1 (cl a b) :rule hole)
+                (step t2 (cl c (not a)) :rule hole)
+                (step t3 (cl c b) :rule cut :premises (t1 t2))": true,
+
+                "(step t1 (cl a b) :rule hole)
+                (step t2 (cl (not a) (not b)) :rule hole)
+                (step t3 (cl b (not b)) :rule cut :premises (t1 t2))": true,
+            }
+            "Missing or extra literal in conclusion" {
+                "(step t1 (cl a b) :rule hole)
+                (step t2 (cl c (not a)) :rule hole)
+                (step t3 (cl b) :rule cut :premises (t1 t2))": false,
+
+                "(step t1 (cl a) :rule hole)
+                (step t2 (cl (not a)) :rule hole)
+                (step t3 (cl c) :rule cut :premises (t1 t2))": false,
+            }
+            "No complementary literals" {
+                "(step t1 (cl a) :rule hole)
+                (step t2 (cl b) :rule hole)
+                (step t3 (cl) :rule cut :premises (t1 t2))": false,
+            }
+            "Wrong number of premises" {
+                "(step t1 (cl a) :rule hole)
+                (step t2 (cl) :rule cut :premises (t1))": false,
+            }
+        }
+    }
+
     #[test]
     fn bind_let() {
         test_cases! {
@@ -366,4 +681,65 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn th_lemma() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () Real)
+                (declare-fun b () Real)
+                (declare-fun m () Int)
+                (declare-fun n () Int)
+            ",
+            "Linear arithmetic lemmas" {
+                "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule th_lemma :args (\"lra\" 1.0 1.0))": true,
+                "(step t1 (cl (not (<= (- a b) 1.0)) (not (<= (- b a) (- 2.0))))
+                    :rule th_lemma :args (\"lra\" 1.0 1.0))": true,
+                "(step t1 (cl (not (> m 0)) (not (< m 1))) :rule th_lemma :args (\"lia\" 1 1))": true,
+            }
+            "Invalid certificates" {
+                "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule th_lemma :args (\"lra\" 1.0 0.0))": false,
+                "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule th_lemma :args (\"lra\" 1.0))": false,
+            }
+            "Other theories are not checked" {
+                "(step t1 (cl (= m n)) :rule th_lemma :args (\"bitvec\" 1 2 3))": true,
+            }
+            "Missing or invalid theory tag" {
+                "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule th_lemma)": false,
+                "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule th_lemma :args (1.0 1.0))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn sym_bp() {
+        test_cases! {
+            definitions = "
+                (declare-sort U 0)
+                (declare-fun a () U)
+                (declare-fun b () U)
+                (declare-fun n () Int)
+                (declare-fun lt (U U) Bool)
+                (declare-fun lt-int (Int Int) Bool)
+                (declare-fun f (U U) U)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (lt a b)) :rule sym_bp :args (a b lt))": true,
+            }
+            "Conclusion doesn't match arguments" {
+                "(step t1 (cl (lt b a)) :rule sym_bp :args (a b lt))": false,
+                "(step t1 (cl (lt a b) (lt b a)) :rule sym_bp :args (a b lt))": false,
+            }
+            "Arguments have different sorts" {
+                "(step t1 (cl (lt-int n n)) :rule sym_bp :args (a n lt-int))": false,
+            }
+            "Predicate is not an ordering over the sort of the arguments" {
+                "(step t1 (cl (lt a b)) :rule sym_bp :args (a b lt-int))": false,
+                "(step t1 (cl (= (f a b) a)) :rule sym_bp :args (a b f))": false,
+            }
+            "Arguments are not constants" {
+                "(step t1 (cl (lt (f a b) b)) :rule sym_bp :args ((f a b) b lt))": false,
+            }
+        }
+    }
 }
@@ -1,7 +1,8 @@
-use super::{assert_clause_len, assert_eq, assert_num_args, RuleArgs, RuleResult};
+use super::{assert_clause_len, assert_eq, assert_num_args, Elaborator, RuleArgs, RuleResult};
 use crate::{
     ast::*,
     checker::error::{CheckerError, LinearArithmeticError},
+    utils::DedupIterator,
 };
 use ahash::AHashMap;
 use rug::{ops::NegAssign, Integer, Rational};
@@ -26,6 +27,14 @@ pub fn la_rw_eq(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
 /// - The negation of an application of one of these operators
 /// - The negation of an application of the `=` operator
 fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearComb), CheckerError> {
+    let (op, a, b) = negate_disequality_terms(term)?;
+    Ok((op, LinearComb::from_term(a), LinearComb::from_term(b)))
+}
+
+/// Similar to `negate_disequality`, but returns the two sides of the negated disequality as terms.
+fn negate_disequality_terms(
+    term: &Rc<Term>,
+) -> Result<(Operator, &Rc<Term>, &Rc<Term>), CheckerError> {
     use Operator::*;
 
     fn negate_operator(op: Operator) -> Option<Operator> {
@@ -53,7 +62,7 @@ fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearCo
         inner(term).ok_or_else(|| LinearArithmeticError::InvalidDisequalityOp(term.clone()))?;
 
     match args {
-        [a, b] => Ok((op, LinearComb::from_term(a), LinearComb::from_term(b))),
+        [a, b] => Ok((op, a, b)),
         _ => Err(LinearArithmeticError::TooManyArgsInDisequality(term.clone()).into()),
     }
 }
@@ -61,7 +70,7 @@ fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearCo
 /// A linear combination, represented by a hash map from non-constant terms to their coefficients,
 /// plus a constant term. This is also used to represent a disequality, in which case the left side
 /// is the non-constant terms and their coefficients, and the right side is the constant term.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LinearComb(pub(crate) AHashMap<Rc<Term>, Rational>, pub(crate) Rational);
 
 impl LinearComb {
@@ -259,7 +268,20 @@ fn strengthen(op: Operator, disequality: &mut LinearComb, a: &Rational) -> Opera
     }
 }
 
-pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+/// Checks that a term is of sort `Int` or `Real`. Since linear arithmetic reasoning is done over the
+/// reals, terms of any other sort are reported as expected to be of sort `Real`.
+fn assert_arithmetic_sort(pool: &mut TermPool, term: &Rc<Term>) -> RuleResult {
+    let sort = pool.sort(term).clone();
+    if matches!(sort, Sort::Int | Sort::Real) {
+        return Ok(());
+    }
+    Err(CheckerError::SortMismatch {
+        expected: pool.add(Term::Sort(Sort::Real)),
+        got: pool.add(Term::Sort(sort)),
+    })
+}
+
+pub fn la_generic(RuleArgs { conclusion, args, pool, .. }: RuleArgs) -> RuleResult {
     assert_num_args(args, conclusion.len())?;
 
     let args: Vec<_> = args
@@ -276,7 +298,10 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
         .zip(args)
         .map(|(phi, a)| -> Result<_, CheckerError> {
             // Steps 1 and 2: Negate the disequality
-            let (mut op, s1, s2) = negate_disequality(phi)?;
+            let (mut op, s1, s2) = negate_disequality_terms(phi)?;
+            assert_arithmetic_sort(pool, s1)?;
+            assert_arithmetic_sort(pool, s2)?;
+            let (s1, s2) = (LinearComb::from_term(s1), LinearComb::from_term(s2));
 
             // Step 3: Move all non constant terms to the left side, and the d terms to the right.
             // We move everything to the left side by subtracting s2 from s1
@@ -342,7 +367,200 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn la_disequality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+/// A polynomial with rational coefficients, represented by a hash map from monomials to their
+/// coefficients. Each monomial is represented by the list of non-constant atoms that are multiplied
+/// in it, sorted by their address in memory. The constant term is represented by the empty
+/// monomial.
+#[derive(Debug)]
+struct Polynomial(AHashMap<Vec<Rc<Term>>, Rational>);
+
+impl Polynomial {
+    fn new() -> Self {
+        Self(AHashMap::new())
+    }
+
+    fn constant(value: Rational) -> Self {
+        let mut result = Self::new();
+        result.insert(Vec::new(), value);
+        result
+    }
+
+    /// Builds a polynomial from a term. Takes a term with nested additions, subtractions,
+    /// multiplications and divisions by constants, and expands it into a sum of monomials. Any
+    /// other term is considered an atom.
+    fn from_term(term: &Rc<Term>) -> Self {
+        if let Some(r) = term.as_fraction() {
+            return Self::constant(r);
+        }
+        match term.as_ref() {
+            Term::Op(Operator::Add, args) => args
+                .iter()
+                .fold(Self::new(), |acc, a| acc.add(Self::from_term(a))),
+            Term::Op(Operator::Sub, args) if args.len() == 1 => {
+                Self::from_term(&args[0]).scale(&Rational::from(-1))
+            }
+            Term::Op(Operator::Sub, args) => {
+                let first = Self::from_term(&args[0]);
+                args[1..].iter().fold(first, |acc, a| {
+                    acc.add(Self::from_term(a).scale(&Rational::from(-1)))
+                })
+            }
+            Term::Op(Operator::Mult, args) => args
+                .iter()
+                .fold(Self::constant(Rational::from(1)), |acc, a| {
+                    acc.mul(&Self::from_term(a))
+                }),
+            Term::Op(Operator::RealDiv, args) if args.len() == 2 => {
+                match args[1].as_fraction().filter(|d| *d != 0) {
+                    Some(d) => Self::from_term(&args[0]).scale(&d.recip()),
+                    None => Self::atom(term.clone()),
+                }
+            }
+            _ => Self::atom(term.clone()),
+        }
+    }
+
+    fn atom(term: Rc<Term>) -> Self {
+        let mut result = Self::new();
+        result.insert(vec![term], Rational::from(1));
+        result
+    }
+
+    fn insert(&mut self, key: Vec<Rc<Term>>, value: Rational) {
+        use std::collections::hash_map::Entry;
+
+        match self.0.entry(key) {
+            Entry::Occupied(mut e) => {
+                *e.get_mut() += value;
+                if *e.get() == 0 {
+                    e.remove();
+                }
+            }
+            Entry::Vacant(e) => {
+                if value != 0 {
+                    e.insert(value);
+                }
+            }
+        }
+    }
+
+    fn add(mut self, other: Self) -> Self {
+        for (monomial, coeff) in other.0 {
+            self.insert(monomial, coeff);
+        }
+        self
+    }
+
+    fn scale(mut self, scalar: &Rational) -> Self {
+        if *scalar == 0 {
+            return Self::new();
+        }
+        for coeff in self.0.values_mut() {
+            *coeff *= scalar;
+        }
+        self
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        for (a, a_coeff) in &self.0 {
+            for (b, b_coeff) in &other.0 {
+                let mut monomial: Vec<_> = a.iter().chain(b).cloned().collect();
+                monomial.sort_unstable_by_key(|t| t.as_ref() as *const Term);
+                result.insert(monomial, Rational::from(a_coeff * b_coeff));
+            }
+        }
+        result
+    }
+
+    /// If the polynomial is a constant, returns its value.
+    fn as_constant(&self) -> Option<Rational> {
+        match self.0.len() {
+            0 => Some(Rational::new()),
+            1 => self.0.get(&Vec::new()).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// Checks a `nl_generic` step. The arguments are a certificate of the unsatisfiability of the
+/// negation of the conclusion, similar to the Farkas coefficients of a `la_generic` step, but the
+/// disequalities may contain non-linear terms. Each literal in the conclusion is negated, and the
+/// resulting hypothesis is written in the form `p op 0`, where `op` is `>`, `>=` or `=`. The
+/// argument corresponding to each literal is a multiplier for that hypothesis. Multipliers of
+/// equalities can be any polynomial, while multipliers of inequalities must be non-negative
+/// constants. The rule holds if the sum of the hypotheses multiplied by their multipliers is a
+/// contradiction between constants, like `0 > 0` or `-1 >= 0`. This is a restricted form of a
+/// Positivstellensatz refutation.
+///
+/// If there are no arguments, the step can't be checked, and it is considered a hole.
+pub fn nl_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+    use Operator::*;
+
+    if args.is_empty() {
+        return Ok(());
+    }
+    assert_num_args(args, conclusion.len())?;
+
+    let mut sum = Polynomial::new();
+    let mut sum_op = Equals;
+    for (phi, multiplier) in conclusion.iter().zip(args) {
+        let multiplier = multiplier.as_term()?;
+        let (op, s1, s2) = negate_disequality_terms(phi)?;
+
+        // Move everything to the left side, and flip the disequality if needed, so it is of the
+        // form `p op 0`, with `op` being `>`, `>=` or `=`
+        let mut p =
+            Polynomial::from_term(s1).add(Polynomial::from_term(s2).scale(&Rational::from(-1)));
+        let op = match op {
+            LessThan | LessEq => {
+                p = p.scale(&Rational::from(-1));
+                if op == LessThan {
+                    GreaterThan
+                } else {
+                    GreaterEq
+                }
+            }
+            _ => op,
+        };
+
+        let p = if op == Equals {
+            p.mul(&Polynomial::from_term(multiplier))
+        } else {
+            let c = multiplier
+                .as_fraction()
+                .filter(|c| *c >= 0)
+                .ok_or_else(|| LinearArithmeticError::InvalidNlMultiplier(multiplier.clone()))?;
+            if c == 0 {
+                continue;
+            }
+            sum_op = match (sum_op, op) {
+                (GreaterThan, _) | (_, GreaterThan) => GreaterThan,
+                _ => GreaterEq,
+            };
+            p.scale(&c)
+        };
+        sum = sum.add(p);
+    }
+
+    let constant = sum
+        .as_constant()
+        .ok_or(LinearArithmeticError::NlSumIsNotConstant)?;
+    let is_contradiction = match sum_op {
+        Equals => constant != 0,
+        GreaterEq => constant < 0,
+        _ => constant <= 0,
+    };
+    rassert!(
+        is_contradiction,
+        LinearArithmeticError::NlSumIsNotContradiction(sum_op, constant)
+    );
+    Ok(())
+}
+
+/// Checks that the conclusion of a `la_disequality` step is of the form `(or (= t1 t2) (not (<= t1
+/// t2)) (not (<= t2 t1)))`, and returns the terms `t1` and `t2`.
+fn match_la_disequality(conclusion: &[Rc<Term>]) -> Result<(&Rc<Term>, &Rc<Term>), CheckerError> {
     assert_clause_len(conclusion, 1)?;
 
     let ((t1_1, t2_1), (t1_2, t2_2), (t2_3, t1_3)) = match_term_err!(
@@ -351,17 +569,123 @@ pub fn la_disequality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_eq(t1_1, t1_2)?;
     assert_eq(t1_2, t1_3)?;
     assert_eq(t2_1, t2_2)?;
-    assert_eq(t2_2, t2_3)
+    assert_eq(t2_2, t2_3)?;
+    Ok((t1_1, t2_1))
+}
+
+pub fn la_disequality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+    match_la_disequality(conclusion).map(|_| ())
+}
+
+pub fn elaborate_la_disequality(
+    RuleArgs { conclusion, pool, .. }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    let (t1, t2) = match_la_disequality(conclusion)?;
+
+    // A `la_disequality` step concluding `(or (= t1 t2) (not (<= t1 t2)) (not (<= t2 t1)))` is
+    // elaborated into:
+    //
+    // ```
+    // (step t.t1 (cl (= (= t1 t2) (and (<= t1 t2) (<= t2 t1)))) :rule la_rw_eq)
+    // (step t.t2 (cl (= t1 t2) (not (and (<= t1 t2) (<= t2 t1)))) :rule equiv2 :premises (t.t1))
+    // (step t.t3 (cl (and (<= t1 t2) (<= t2 t1)) (not (<= t1 t2)) (not (<= t2 t1))) :rule and_neg)
+    // (step t.t4 (cl (= t1 t2) (not (<= t1 t2)) (not (<= t2 t1))) :rule resolution
+    //     :premises (t.t2 t.t3) :args ((and (<= t1 t2) (<= t2 t1)) false))
+    // (step t.t5 (cl (or ...) (not (= t1 t2))) :rule or_neg)
+    // (step t.t6 (cl (or ...) (not (not (<= t1 t2)))) :rule or_neg)
+    // (step t.t7 (cl (or ...) (not (not (<= t2 t1)))) :rule or_neg)
+    // (step t (cl (or ...)) :rule resolution :premises (t.t4 t.t5 t.t6 t.t7)
+    //     :args ((= t1 t2) true (not (<= t1 t2)) true (not (<= t2 t1)) true))
+    // ```
+    //
+    // If `t1` and `t2` are the same term, the last two literals are equal, so they only appear once
+    // in the clause concluded by `t.t4`, and only one of them is resolved in the last step
+    let mut add_step = |rule: &str, clause, premises, args: Vec<Rc<Term>>| {
+        let id = elaborator.get_new_id(&command_id);
+        elaborator.add_new_step(ProofStep {
+            id,
+            clause,
+            rule: rule.to_owned(),
+            premises,
+            args: args.into_iter().map(ProofArg::Term).collect(),
+            discharge: Vec::new(),
+            source_position: None,
+        })
+    };
+    let or_term = conclusion[0].clone();
+    let literals = match_term!((or ...) = &or_term).unwrap();
+    let unique_literals: Vec<_> = literals.iter().cloned().dedup().collect();
+    let eq = literals[0].clone();
+    let le = build_term!(pool, (<= {t1.clone()} {t2.clone()}));
+    let ge = build_term!(pool, (<= {t2.clone()} {t1.clone()}));
+    let and_term = build_term!(pool, (and {le.clone()} {ge.clone()}));
+
+    let rw_eq_step = add_step(
+        "la_rw_eq",
+        vec![build_term!(pool, (= {eq.clone()} {and_term.clone()}))],
+        Vec::new(),
+        Vec::new(),
+    );
+    let equiv_step = add_step(
+        "equiv2",
+        vec![eq.clone(), build_term!(pool, (not {and_term.clone()}))],
+        vec![rw_eq_step],
+        Vec::new(),
+    );
+    let and_neg_step = add_step(
+        "and_neg",
+        vec![and_term.clone(), literals[1].clone(), literals[2].clone()],
+        Vec::new(),
+        Vec::new(),
+    );
+    let clause_step = add_step(
+        "resolution",
+        unique_literals.clone(),
+        vec![equiv_step, and_neg_step],
+        vec![and_term, pool.bool_false()],
+    );
+
+    let mut premises = vec![clause_step];
+    let mut args = Vec::new();
+    for literal in unique_literals {
+        let negated = build_term!(pool, (not {literal.clone()}));
+        premises.push(add_step(
+            "or_neg",
+            vec![or_term.clone(), negated],
+            Vec::new(),
+            Vec::new(),
+        ));
+        args.extend([literal, pool.bool_true()]);
+    }
+    elaborator.push_elaborated_step(ProofStep {
+        id: command_id,
+        clause: conclusion.to_vec(),
+        rule: "resolution".to_owned(),
+        premises,
+        args: args.into_iter().map(ProofArg::Term).collect(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    Ok(())
 }
 
-pub fn la_totality(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn la_totality(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let ((t1_1, t2_1), (t2_2, t1_2)) =
         match_term_err!((or (<= t1 t2) (<= t2 t1)) = &conclusion[0])?;
 
     assert_eq(t1_1, t1_2)?;
-    assert_eq(t2_1, t2_2)
+    assert_eq(t2_1, t2_2)?;
+    for t in [t1_1, t2_1] {
+        rassert!(
+            matches!(pool.sort(t), Sort::Int | Sort::Real),
+            LinearArithmeticError::NotArithmeticTerm(t.clone())
+        );
+    }
+    Ok(())
 }
 
 fn assert_less_than(a: &Rc<Term>, b: &Rc<Term>) -> RuleResult {
@@ -381,6 +705,10 @@ fn assert_less_eq(a: &Rc<Term>, b: &Rc<Term>) -> RuleResult {
 }
 
 pub fn la_tautology(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+    check_la_tautology(conclusion)
+}
+
+fn check_la_tautology(conclusion: &[Rc<Term>]) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     if let Some((first, second)) = match_term!((or phi_1 phi_2) = conclusion[0]) {
@@ -453,6 +781,83 @@ pub fn la_tautology(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     }
 }
 
+pub fn elaborate_la_tautology(
+    RuleArgs { conclusion, pool, .. }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    check_la_tautology(conclusion)?;
+
+    // In all cases accepted by `la_tautology`, negating the literals and adding them together
+    // already results in a contradiction, so they can be justified by a `la_generic` step in which
+    // all coefficients are one. If the conclusion is a single disequality, the step is replaced by
+    // a `la_generic` step with the same conclusion. Otherwise, it is of the form `(or phi_1 phi_2)`,
+    // and is elaborated into:
+    //
+    // ```
+    // (step t.t1 (cl phi_1 phi_2) :rule la_generic :args (1 1))
+    // (step t.t2 (cl (or phi_1 phi_2) (not phi_1)) :rule or_neg)
+    // (step t.t3 (cl (or phi_1 phi_2) (not phi_2)) :rule or_neg)
+    // (step t (cl (or phi_1 phi_2)) :rule resolution :premises (t.t1 t.t2 t.t3)
+    //     :args (phi_1 true phi_2 true))
+    // ```
+    let one = pool.add(Term::integer(1));
+    let or_term = conclusion[0].clone();
+    let Some((first, second)) = match_term!((or phi_1 phi_2) = &or_term) else {
+        elaborator.push_elaborated_step(ProofStep {
+            id: command_id,
+            clause: conclusion.to_vec(),
+            rule: "la_generic".to_owned(),
+            premises: Vec::new(),
+            args: vec![ProofArg::Term(one)],
+            discharge: Vec::new(),
+            source_position: None,
+        });
+        return Ok(());
+    };
+
+    let mut add_step = |rule: &str, clause, premises, args: Vec<Rc<Term>>| {
+        let id = elaborator.get_new_id(&command_id);
+        elaborator.add_new_step(ProofStep {
+            id,
+            clause,
+            rule: rule.to_owned(),
+            premises,
+            args: args.into_iter().map(ProofArg::Term).collect(),
+            discharge: Vec::new(),
+            source_position: None,
+        })
+    };
+    let literals = [first.clone(), second.clone()];
+    let mut premises = vec![add_step(
+        "la_generic",
+        literals.to_vec(),
+        Vec::new(),
+        vec![one.clone(), one],
+    )];
+    let mut args = Vec::new();
+    for literal in literals {
+        let negated = build_term!(pool, (not {literal.clone()}));
+        premises.push(add_step(
+            "or_neg",
+            vec![or_term.clone(), negated],
+            Vec::new(),
+            Vec::new(),
+        ));
+        args.extend([literal, pool.bool_true()]);
+    }
+    elaborator.push_elaborated_step(ProofStep {
+        id: command_id,
+        clause: conclusion.to_vec(),
+        rule: "resolution".to_owned(),
+        premises,
+        args: args.into_iter().map(ProofArg::Term).collect(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -484,6 +889,8 @@ mod tests {
                 (declare-fun c () Real)
                 (declare-fun m () Int)
                 (declare-fun n () Int)
+                (declare-fun p () Bool)
+                (declare-fun q () Bool)
             ",
             "Simple working examples" {
                 "(step t1 (cl (> a 0.0) (<= a 0.0)) :rule la_generic :args (1.0 1.0))": true,
@@ -511,6 +918,10 @@ mod tests {
                 "(step t1 (cl (ite (= a b) false true)) :rule la_generic :args (1.0))": false,
                 "(step t1 (cl (= a 0.0) (< a 0.0)) :rule la_generic :args (1.0 1.0))": false,
             }
+            "Terms are not arithmetic" {
+                "(step t1 (cl (not (= p q))) :rule la_generic :args (1.0))": false,
+                "(step t1 (cl (not (= p q)) (not (= q p))) :rule la_generic :args (1.0 1.0))": false,
+            }
             "Negation of disequalities is satisfiable" {
                 "(step t1 (cl (< 0.0 0.0)) :rule la_generic :args (1.0))": false,
 
@@ -528,6 +939,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nl_generic() {
+        test_cases! {
+            definitions = "
+                (declare-fun x () Real)
+                (declare-fun y () Real)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (not (= (* x y) 1.0)) (not (= x 0.0)))
+                    :rule nl_generic :args ((- 1.0) y))": true,
+                "(step t1 (cl (not (> (* x y) 0.0)) (not (< (* y x) 0.0)))
+                    :rule nl_generic :args (1.0 1.0))": true,
+                "(step t1 (cl (<= (* x (+ y 1.0)) (+ (* x y) x)))
+                    :rule nl_generic :args (1.0))": true,
+                "(step t1 (cl (not (= (* x x) (- 1.0))) (not (= x 0.0)))
+                    :rule nl_generic :args (1.0 (- x)))": true,
+            }
+            "Missing certificate" {
+                "(step t1 (cl (not (= (* x y) 1.0))) :rule nl_generic)": true,
+            }
+            "Wrong number of arguments" {
+                "(step t1 (cl (not (= (* x y) 1.0)) (not (= x 0.0)))
+                    :rule nl_generic :args ((- 1.0)))": false,
+            }
+            "Invalid multiplier for inequality" {
+                "(step t1 (cl (not (> (* x y) 0.0)) (not (< (* x y) 0.0)))
+                    :rule nl_generic :args (1.0 (- 1.0)))": false,
+                "(step t1 (cl (not (> (* x y) 0.0)) (not (< (* x y) 0.0)))
+                    :rule nl_generic :args (x 1.0))": false,
+            }
+            "Sum is not a contradiction" {
+                "(step t1 (cl (not (= (* x y) 1.0)) (not (= x 0.0)))
+                    :rule nl_generic :args (1.0 y))": false,
+                "(step t1 (cl (not (>= (* x y) 0.0)) (not (<= (* y x) 0.0)))
+                    :rule nl_generic :args (1.0 1.0))": false,
+            }
+        }
+    }
+
     #[test]
     fn la_disequality() {
         test_cases! {
@@ -569,6 +1019,19 @@ mod tests {
                 "(step t1 (cl (or (<= a b) (<= a b))) :rule la_totality)": false,
                 "(step t1 (cl (<= x y) (<= x y)) :rule la_totality)": false,
                 "(step t1 (cl (<= 0 1) (<= 0.0 1.0)) :rule la_totality)": false,
+                "(step t1 (cl (or (<= a b) (<= b a) (<= a b))) :rule la_totality)": false,
+                "(step t1 (cl (or (<= a b) (< b a))) :rule la_totality)": false,
+            }
+            "Inequalities use different terms" {
+                "(step t1 (cl (or (<= a b) (<= a a))) :rule la_totality)": false,
+                "(step t1 (cl (or (<= a b) (<= b (+ a 0)))) :rule la_totality)": false,
+                "(step t1 (cl (or (<= x y) (<= b a))) :rule la_totality)": false,
+            }
+            "Compound terms and numerals" {
+                "(step t1 (cl (or (<= (+ a 1) (* 2 b)) (<= (* 2 b) (+ a 1)))) :rule la_totality)": true,
+                "(step t1 (cl (or (<= (- x) 1.5) (<= 1.5 (- x)))) :rule la_totality)": true,
+                "(step t1 (cl (or (<= 0 1) (<= 1 0))) :rule la_totality)": true,
+                "(step t1 (cl (or (<= 0.0 1.0) (<= 1.0 0.0))) :rule la_totality)": true,
             }
         }
     }
@@ -14,6 +14,72 @@ pub type Rule = fn(RuleArgs) -> RuleResult;
 
 pub type ElaborationRule = fn(RuleArgs, String, &mut Elaborator) -> Result<(), CheckerError>;
 
+/// The category of a rule, roughly following the sections of the Alethe specification.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RuleCategory {
+    /// Tautologies, clausification, resolution, and the simplification of boolean terms.
+    Propositional,
+
+    /// Linear and non-linear arithmetic, including the simplification of arithmetic terms.
+    Arithmetic,
+
+    /// Quantifier instantiation, skolemization outside of subproofs, and quantifier manipulation.
+    Quantifier,
+
+    /// Reflexivity, transitivity, and congruence of equality.
+    Congruence,
+
+    /// Rules that end a subproof, like `bind` or `subproof`.
+    Subproof,
+
+    /// Rules that are not in the Alethe specification, but are used by some solvers.
+    Extra,
+
+    /// Rules that are not checked, and always hold.
+    Hole,
+}
+
+impl std::fmt::Display for RuleCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            RuleCategory::Propositional => "propositional",
+            RuleCategory::Arithmetic => "arithmetic",
+            RuleCategory::Quantifier => "quantifier",
+            RuleCategory::Congruence => "congruence",
+            RuleCategory::Subproof => "subproof",
+            RuleCategory::Extra => "extra",
+            RuleCategory::Hole => "hole",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Information about a rule, as returned by [`ProofChecker::get_rule`](super::ProofChecker::get_rule).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleMetadata {
+    pub name: &'static str,
+    pub category: RuleCategory,
+
+    /// Whether steps that use this rule are replaced by steps that use more primitive rules during
+    /// elaboration. Rules whose elaboration only adds information to the step, like `resolution`
+    /// or `refl`, are not eliminable. Steps may still keep an eliminable rule in degenerate cases
+    /// that the elaboration doesn't handle.
+    pub is_eliminable: bool,
+
+    /// Whether this rule is checked differently in strict mode. See [`Config::strict`].
+    ///
+    /// [`Config::strict`]: super::Config::strict
+    pub has_strict_version: bool,
+}
+
+impl RuleMetadata {
+    /// Returns `true` if this rule is not checked, and always holds. Steps that use such rules are
+    /// holes in the proof.
+    pub fn is_hole(&self) -> bool {
+        self.category == RuleCategory::Hole
+    }
+}
+
 pub struct RuleArgs<'a> {
     pub(super) conclusion: &'a [Rc<Term>],
     pub(super) premises: &'a [Premise<'a>],
@@ -27,6 +93,9 @@ pub struct RuleArgs<'a> {
     pub(super) previous_command: Option<Premise<'a>>,
     pub(super) discharge: &'a [&'a ProofCommand],
 
+    // The maximum depth `deep_eq` and similar functions may reach when comparing terms. See
+    // `Config::deep_eq_depth_limit`.
+    pub(super) deep_eq_depth_limit: Option<usize>,
     pub(super) deep_eq_time: &'a mut Duration,
 }
 
@@ -128,8 +197,13 @@ where
     Ok(())
 }
 
-fn assert_deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> Result<(), CheckerError> {
-    if !deep_eq(a, b, time) {
+fn assert_deep_eq(
+    a: &Rc<Term>,
+    b: &Rc<Term>,
+    depth_limit: Option<usize>,
+    time: &mut Duration,
+) -> Result<(), CheckerError> {
+    if !deep_eq(a, b, depth_limit, time) {
         return Err(EqualityError::ExpectedEqual(a.clone(), b.clone()).into());
     }
     Ok(())
@@ -138,9 +212,10 @@ fn assert_deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> Result<(),
 fn assert_deep_eq_is_expected(
     got: &Rc<Term>,
     expected: Rc<Term>,
+    depth_limit: Option<usize>,
     time: &mut Duration,
 ) -> RuleResult {
-    if !deep_eq(got, &expected, time) {
+    if !deep_eq(got, &expected, depth_limit, time) {
         return Err(EqualityError::ExpectedToBe { expected, got: got.clone() }.into());
     }
     Ok(())
@@ -179,6 +254,10 @@ fn run_tests(test_name: &str, definitions: &str, cases: &[(&str, bool)]) {
                 is_running_test: true,
                 statistics: None,
                 lia_via_cvc5: false,
+                max_steps: None,
+                deep_eq_depth_limit: None,
+                deep_eq_cache_size: None,
+                abort_flag: None,
             },
             prelude,
         );
@@ -216,6 +295,7 @@ pub(super) mod quantifier;
 pub(super) mod reflexivity;
 pub(super) mod resolution;
 pub(super) mod simplification;
+pub(super) mod strings;
 pub(super) mod subproof;
 pub(super) mod tautology;
 pub(super) mod transitivity;
@@ -1,6 +1,6 @@
 use super::{
     assert_clause_len, assert_deep_eq_is_expected, assert_eq, assert_is_expected, assert_num_args,
-    CheckerError, RuleArgs, RuleResult,
+    assert_num_premises, get_premise_term, CheckerError, EqualityError, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::QuantifierError, utils::DedupIterator};
 use ahash::{AHashMap, AHashSet};
@@ -10,6 +10,7 @@ pub fn forall_inst(
         conclusion,
         args,
         pool,
+        deep_eq_depth_limit,
         deep_eq_time,
         ..
     }: RuleArgs,
@@ -19,6 +20,22 @@ pub fn forall_inst(
     let ((bindings, original), substituted) =
         match_term_err!((or (not (forall ... original)) result) = &conclusion[0])?;
 
+    let mut substitution = instantiation_substitution(pool, bindings, args)?;
+
+    // Equalities may be reordered in the final term, so we need to use deep equality modulo
+    // reordering
+    let expected = substitution.apply(pool, original);
+    assert_deep_eq_is_expected(substituted, expected, deep_eq_depth_limit, deep_eq_time)
+}
+
+/// Builds the substitution that instantiates the variables in `bindings` with the values given in
+/// the arguments of a step. The arguments must be of the form `(:= x t)`, and there must be
+/// exactly one argument for each binding.
+fn instantiation_substitution(
+    pool: &mut TermPool,
+    bindings: &BindingList,
+    args: &[ProofArg],
+) -> Result<Substitution, CheckerError> {
     assert_num_args(args, bindings.len())?;
 
     // Since the bindings and arguments may not be in the same order, we collect the bindings into
@@ -38,18 +55,135 @@ pub fn forall_inst(
             Ok((pool.add(ident_term), arg_value.clone()))
         })
         .collect::<Result<_, CheckerError>>()?;
-    let mut substitution = Substitution::new(pool, substitution)?;
+    let substitution = Substitution::new(pool, substitution)?;
 
     // All bindings were accounted for in the arguments
     rassert!(
         bindings.is_empty(),
         QuantifierError::NoArgGivenForBinding(bindings.iter().next().unwrap().0.clone())
     );
+    Ok(substitution)
+}
 
-    // Equalities may be reordered in the final term, so we need to use deep equality modulo
-    // reordering
+/// Computes the Skolem terms for the variables in `bindings`, in a quantifier of type `quantifier`
+/// with body `phi`. These are the same Skolem terms used by the `sko_ex` and `sko_forall` rules:
+/// each variable `x_i` is replaced by the choice term
+/// `(choice ((x_i T_i)) (exists ((x_i+1 T_i+1) ...) phi))`, after the previous variables are
+/// replaced by their Skolem terms. If the quantifier is universal, the body of the choice term is
+/// negated instead. Returns the Skolem terms and the result of substituting them in `phi`.
+pub(super) fn skolemize(
+    pool: &mut TermPool,
+    quantifier: Quantifier,
+    bindings: &BindingList,
+    phi: &Rc<Term>,
+) -> Result<(Vec<Rc<Term>>, Rc<Term>), CheckerError> {
+    let mut skolem_terms = Vec::with_capacity(bindings.len());
+    let mut current = phi.clone();
+    for (i, x) in bindings.iter().enumerate() {
+        let mut inner = current.clone();
+
+        // If this is the last binding, all bindings were skolemized, so we don't need to wrap the
+        // term in a quantifier
+        if i < bindings.len() - 1 {
+            let rest = BindingList(bindings.0[i + 1..].to_vec());
+            inner = pool.add(Term::Quant(quantifier, rest, inner));
+        }
+        if quantifier == Quantifier::Forall {
+            inner = build_term!(pool, (not { inner }));
+        }
+        let skolem_term = pool.add(Term::Choice(x.clone(), inner));
+        let x_term = pool.add(Term::from(x.clone()));
+        let mut substitution = Substitution::single(pool, x_term, skolem_term.clone())?;
+        current = substitution.apply(pool, &current);
+        skolem_terms.push(skolem_term);
+    }
+    Ok((skolem_terms, current))
+}
+
+/// The `exists_pos` rule eliminates an existential quantifier by replacing its variables with
+/// Skolem terms. Its conclusion is of the form:
+/// ```text
+/// (cl (not (exists ((x_1 T_1) ... (x_n T_n)) P)) P[x_1 := s_1, ..., x_n := s_n])
+/// ```
+/// where each `s_i` is the Skolem term for `x_i`, as computed by `skolemize`.
+pub fn exists_pos(
+    RuleArgs {
+        conclusion,
+        pool,
+        deep_eq_depth_limit,
+        deep_eq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_clause_len(conclusion, 2)?;
+
+    let (bindings, phi) = match_term_err!((not (exists ... phi)) = &conclusion[0])?;
+    let (_, expected) = skolemize(pool, Quantifier::Exists, bindings, phi)?;
+    assert_deep_eq_is_expected(&conclusion[1], expected, deep_eq_depth_limit, deep_eq_time)
+}
+
+/// The `forall_neg` rule derives a counterexample from a refuted universal quantifier. Its premise
+/// is of the form `(not (forall ((x_1 T_1) ... (x_n T_n)) P))`, and its conclusion is
+/// `(cl (not P[x_1 := t_1, ..., x_n := t_n]))`, where each `t_i` is given as an argument of the
+/// form `(:= x_i t_i)`. Each `t_i` must be the Skolem term for `x_i`, as computed by `skolemize`,
+/// since that is the only witness guaranteed to falsify `P`.
+pub fn forall_neg(
+    RuleArgs {
+        conclusion,
+        premises,
+        args,
+        pool,
+        deep_eq_depth_limit,
+        deep_eq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_num_premises(premises, 1)?;
+    assert_clause_len(conclusion, 1)?;
+
+    let premise = get_premise_term(&premises[0])?;
+    let (bindings, phi) = match_term_err!((not (forall ... phi)) = premise)?;
+    let substituted = match_term_err!((not s) = &conclusion[0])?;
+
+    // This checks that there is exactly one argument for each binding, with the correct sort
+    instantiation_substitution(pool, bindings, args)?;
+
+    let (skolem_terms, expected) = skolemize(pool, Quantifier::Forall, bindings, phi)?;
+    for arg in args {
+        let (name, value) = arg.as_assign()?;
+        let i = bindings.iter().position(|(x, _)| x == name).unwrap();
+        if !are_alpha_equivalent(value, &skolem_terms[i], deep_eq_depth_limit, deep_eq_time) {
+            return Err(
+                EqualityError::ExpectedEqual(value.clone(), skolem_terms[i].clone()).into(),
+            );
+        }
+    }
+    assert_deep_eq_is_expected(substituted, expected, deep_eq_depth_limit, deep_eq_time)
+}
+
+/// The `exists_neg` rule is the dual of `forall_inst`. Its conclusion is of the form:
+/// ```text
+/// (cl (exists ((x_1 T_1) ... (x_n T_n)) P) (not P[x_1 := t_1, ..., x_n := t_n]))
+/// ```
+/// where each `t_i` is given as an argument of the form `(:= x_i t_i)`.
+pub fn exists_neg(
+    RuleArgs {
+        conclusion,
+        args,
+        pool,
+        deep_eq_depth_limit,
+        deep_eq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
+    assert_clause_len(conclusion, 2)?;
+
+    let (bindings, original) = match_term_err!((exists ... original) = &conclusion[0])?;
+    let substituted = match_term_err!((not s) = &conclusion[1])?;
+
+    let mut substitution = instantiation_substitution(pool, bindings, args)?;
     let expected = substitution.apply(pool, original);
-    assert_deep_eq_is_expected(substituted, expected, deep_eq_time)
+    assert_deep_eq_is_expected(substituted, expected, deep_eq_depth_limit, deep_eq_time)
 }
 
 pub fn qnt_join(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
@@ -135,14 +269,30 @@ fn negation_normal_form(
             .map(|a| negation_normal_form(pool, a, polarity, cache))
             .collect();
         pool.add(Term::Op(op, args))
-    } else if let Some((p, q)) = match_term!((=> p q) = term) {
-        let a = negation_normal_form(pool, p, !polarity, cache);
-        let b = negation_normal_form(pool, q, polarity, cache);
+    } else if let Term::Op(Operator::Implies, args) = term.as_ref() {
+        // Implication is right-associative, so `(=> p_1 ... p_n q)` is equivalent to
+        // `(or (not p_1) ... (not p_n) q)`
+        let (q, ps) = args.split_last().unwrap();
+        let mut args: Vec<_> = ps
+            .iter()
+            .map(|p| negation_normal_form(pool, p, !polarity, cache))
+            .collect();
+        args.push(negation_normal_form(pool, q, polarity, cache));
 
-        match polarity {
-            true => build_term!(pool, (or {a} {b})),
-            false => build_term!(pool, (and {a} {b})),
-        }
+        let op = if polarity {
+            Operator::Or
+        } else {
+            Operator::And
+        };
+        pool.add(Term::Op(op, args))
+    } else if let Some((p, q)) = match_term!((xor p q) = term) {
+        // `(xor p q)` is equivalent to `(and (or p q) (or (not p) (not q)))`, and its negation is
+        // equivalent to `(and (or p (not q)) (or (not p) q))`
+        let a = negation_normal_form(pool, p, true, cache);
+        let b = negation_normal_form(pool, q, polarity, cache);
+        let c = negation_normal_form(pool, p, false, cache);
+        let d = negation_normal_form(pool, q, !polarity, cache);
+        build_term!(pool, (and (or {a} {b}) (or {c} {d})))
     } else if let Some((p, q, r)) = match_term!((ite p q r) = term) {
         let a = negation_normal_form(pool, p, !polarity, cache);
         let b = negation_normal_form(pool, q, polarity, cache);
@@ -311,9 +461,12 @@ pub fn qnt_cnf(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     // right-hand side quantifier if they don't appear in `phi_prime` as free variables.  If there
     // is a binding in the left side that is a free variable in the selected clause, but doesn't
     // appear in the right-hand side bindings, we must return an error
-    let found = new_bindings
-        .into_iter()
-        .find(|var| !r_bindings.contains(var) && free_vars.contains(&pool.add(var.clone().into())));
+    let found = new_bindings.into_iter().find(|var| {
+        !r_bindings.contains(var)
+            && pool
+                .find(&var.clone().into())
+                .map_or(false, |var| free_vars.contains(&var))
+    });
     if let Some((var, _)) = found {
         return Err(QuantifierError::CnfBindingIsMissing(var).into());
     }
@@ -372,6 +525,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn exists_pos() {
+        test_cases! {
+            definitions = "
+                (declare-fun p (Real) Bool)
+                (declare-fun q (Real Real) Bool)
+                (declare-fun a () Real)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (not (exists ((x Real)) (p x)))
+                    (p (choice ((x Real)) (p x)))) :rule exists_pos)": true,
+
+                "(step t1 (cl (not (exists ((x Real) (y Real)) (q x y)))
+                    (q (choice ((x Real)) (exists ((y Real)) (q x y)))
+                       (choice ((y Real)) (q (choice ((x Real)) (exists ((y Real)) (q x y))) y))))
+                    :rule exists_pos)": true,
+            }
+            "Term is not the Skolem term" {
+                "(step t1 (cl (not (exists ((x Real)) (p x))) (p a)) :rule exists_pos)": false,
+                "(step t1 (cl (not (exists ((x Real)) (p x)))
+                    (p (choice ((x Real)) (not (p x))))) :rule exists_pos)": false,
+            }
+            "Quantifier is not existential" {
+                "(step t1 (cl (not (forall ((x Real)) (p x)))
+                    (p (choice ((x Real)) (p x)))) :rule exists_pos)": false,
+            }
+            "Wrong number of terms in clause" {
+                "(step t1 (cl (not (exists ((x Real)) (p x)))) :rule exists_pos)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn exists_neg() {
+        test_cases! {
+            definitions = "
+                (declare-fun p (Real) Bool)
+                (declare-fun q (Real Real) Bool)
+                (declare-fun a () Real)
+                (declare-fun b () Real)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (exists ((x Real)) (p x)) (not (p a)))
+                    :rule exists_neg :args ((:= x a)))": true,
+
+                "(step t1 (cl (exists ((x Real) (y Real)) (q x y)) (not (q a b)))
+                    :rule exists_neg :args ((:= y b) (:= x a)))": true,
+            }
+            "Substitution was not applied correctly" {
+                "(step t1 (cl (exists ((x Real)) (p x)) (not (p b)))
+                    :rule exists_neg :args ((:= x a)))": false,
+                "(step t1 (cl (exists ((x Real)) (p x)) (p a))
+                    :rule exists_neg :args ((:= x a)))": false,
+            }
+            "Wrong number of arguments" {
+                "(step t1 (cl (exists ((x Real) (y Real)) (q x y)) (not (q a b)))
+                    :rule exists_neg :args ((:= x a)))": false,
+            }
+            "Argument has the wrong sort" {
+                "(step t1 (cl (exists ((x Real)) (p x)) (not (p a)))
+                    :rule exists_neg :args ((:= x true)))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn forall_neg() {
+        test_cases! {
+            definitions = "
+                (declare-fun p (Real) Bool)
+                (declare-fun q (Real Real) Bool)
+                (declare-fun a () Real)
+            ",
+            "Simple working examples" {
+                "(assume h1 (not (forall ((x Real)) (p x))))
+                (step t2 (cl (not (p (choice ((x Real)) (not (p x))))))
+                    :rule forall_neg :premises (h1)
+                    :args ((:= x (choice ((x Real)) (not (p x))))))": true,
+
+                "(assume h1 (not (forall ((x Real) (y Real)) (q x y))))
+                (step t2 (cl (not (q
+                    (choice ((x Real)) (not (forall ((y Real)) (q x y))))
+                    (choice ((y Real)) (not (q
+                        (choice ((x Real)) (not (forall ((y Real)) (q x y))))
+                        y))))))
+                    :rule forall_neg :premises (h1)
+                    :args (
+                        (:= y (choice ((y Real)) (not (q
+                            (choice ((x Real)) (not (forall ((y Real)) (q x y))))
+                            y))))
+                        (:= x (choice ((x Real)) (not (forall ((y Real)) (q x y)))))
+                    ))": true,
+            }
+            "Witness is not the Skolem term" {
+                "(assume h1 (not (forall ((x Real)) (p x))))
+                (step t2 (cl (not (p a))) :rule forall_neg :premises (h1) :args ((:= x a)))": false,
+            }
+            "Witness has the wrong sort" {
+                "(assume h1 (not (forall ((x Real)) (p x))))
+                (step t2 (cl (not (p (choice ((x Real)) (not (p x))))))
+                    :rule forall_neg :premises (h1)
+                    :args ((:= x (choice ((x Int)) (not (p 0.0))))))": false,
+            }
+            "Conclusion is not negated" {
+                "(assume h1 (not (forall ((x Real)) (p x))))
+                (step t2 (cl (p (choice ((x Real)) (not (p x)))))
+                    :rule forall_neg :premises (h1)
+                    :args ((:= x (choice ((x Real)) (not (p x))))))": false,
+            }
+            "Premise is not a negated universal quantifier" {
+                "(assume h1 (forall ((x Real)) (p x)))
+                (step t2 (cl (not (p (choice ((x Real)) (not (p x))))))
+                    :rule forall_neg :premises (h1)
+                    :args ((:= x (choice ((x Real)) (not (p x))))))": false,
+            }
+        }
+    }
+
     #[test]
     fn qnt_join() {
         test_cases! {
@@ -593,6 +864,71 @@ mod tests {
                     (forall ((p Bool) (q Bool)) (or p (not q) (not true)))
                 )) :rule qnt_cnf)": true,
             }
+            "Conjunctions are split into one clause per conjunct" {
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (and p q)))
+                    (forall ((p Bool)) p)
+                )) :rule qnt_cnf)": true,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (and p q)))
+                    (forall ((q Bool)) q)
+                )) :rule qnt_cnf)": true,
+            }
+            "Negations flip connectives and quantifiers" {
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (not (or p q))))
+                    (forall ((q Bool)) (not q))
+                )) :rule qnt_cnf)": true,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool)) (not (exists ((q Bool)) (or p q)))))
+                    (forall ((p Bool)) (not p))
+                )) :rule qnt_cnf)": true,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool)) (not (forall ((q Bool)) (or p q)))))
+                    (forall ((p Bool)) (exists ((q Bool)) (and (not p) (not q))))
+                )) :rule qnt_cnf)": true,
+            }
+            "Implications and exclusive disjunctions" {
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool) (r Bool)) (=> p q r)))
+                    (forall ((p Bool) (q Bool) (r Bool)) (or (not p) (not q) r))
+                )) :rule qnt_cnf)": true,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (xor p q)))
+                    (forall ((p Bool) (q Bool)) (or (not p) (not q)))
+                )) :rule qnt_cnf)": true,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (not (xor p q))))
+                    (forall ((p Bool) (q Bool)) (or p (not q)))
+                )) :rule qnt_cnf)": true,
+            }
+            "Clause is not in the CNF of the original term" {
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (or p q)))
+                    (forall ((p Bool)) p)
+                )) :rule qnt_cnf)": false,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (not (and p q))))
+                    (forall ((p Bool)) (not p))
+                )) :rule qnt_cnf)": false,
+
+                "(step t1 (cl (or
+                    (not (forall ((p Bool) (q Bool)) (xor p q)))
+                    (forall ((p Bool) (q Bool)) (or p (not q)))
+                )) :rule qnt_cnf)": false,
+            }
+            "Quantifier is not universal" {
+                "(step t1 (cl (or
+                    (not (exists ((p Bool) (q Bool)) (and p q)))
+                    (exists ((p Bool)) p)
+                )) :rule qnt_cnf)": false,
+            }
             "Quantifier bindings added due to prenexing" {
                 "(step t1 (cl (or
                     (not (forall ((p Bool)) (forall ((q Bool)) (or p q))))
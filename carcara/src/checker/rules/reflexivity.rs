@@ -12,6 +12,7 @@ pub fn refl(
         conclusion,
         pool,
         context,
+        deep_eq_depth_limit,
         deep_eq_time,
         ..
     }: RuleArgs,
@@ -22,8 +23,11 @@ pub fn refl(
 
     // If the two terms are directly identical, we don't need to do any more work. We make sure to
     // do this check before we try to get the context substitution, because `refl` can be used
-    // outside of any subproof
-    if are_alpha_equivalent(left, right, deep_eq_time) {
+    // outside of any subproof. The cheaper `alpha_equivalent` check is tried first, since it is
+    // enough in most cases. However, it has no depth limit, so it is skipped if there is one
+    if (deep_eq_depth_limit.is_none() && alpha_equivalent(left, right))
+        || are_alpha_equivalent(left, right, deep_eq_depth_limit, deep_eq_time)
+    {
         return Ok(());
     }
 
@@ -36,10 +40,10 @@ pub fn refl(
     // don't compute the new left and right terms until they are needed, to avoid doing unnecessary
     // work
     let new_left = context.apply(pool, left);
-    let result = are_alpha_equivalent(&new_left, right, deep_eq_time) || {
+    let result = are_alpha_equivalent(&new_left, right, deep_eq_depth_limit, deep_eq_time) || {
         let new_right = context.apply(pool, right);
-        are_alpha_equivalent(left, &new_right, deep_eq_time)
-            || are_alpha_equivalent(&new_left, &new_right, deep_eq_time)
+        are_alpha_equivalent(left, &new_right, deep_eq_depth_limit, deep_eq_time)
+            || are_alpha_equivalent(&new_left, &new_right, deep_eq_depth_limit, deep_eq_time)
     };
     rassert!(
         result,
@@ -81,7 +85,7 @@ fn elaborate_equality(
     id: &str,
     deep_eq_time: &mut std::time::Duration,
 ) -> (usize, usize) {
-    let is_alpha_equivalence = !deep_eq(left, right, deep_eq_time);
+    let is_alpha_equivalence = !deep_eq(left, right, None, deep_eq_time);
     elaborator.elaborate_deep_eq(pool, id, left.clone(), right.clone(), is_alpha_equivalence)
 }
 
@@ -90,6 +94,7 @@ pub fn elaborate_refl(
         conclusion,
         pool,
         context,
+        deep_eq_depth_limit,
         deep_eq_time,
         ..
     }: RuleArgs,
@@ -122,7 +127,7 @@ pub fn elaborate_refl(
     // directly. In the second case, we need to first apply the context to the left term, using a
     // `refl` step, and then prove the equivalence of the new left term with the right term. In the
     // third case, we also need to apply the context to the right term, using another `refl` step.
-    if are_alpha_equivalent(left, right, deep_eq_time) {
+    if are_alpha_equivalent(left, right, deep_eq_depth_limit, deep_eq_time) {
         let equality_step =
             elaborate_equality(elaborator, pool, left, right, &command_id, deep_eq_time);
         let id = elaborator.get_new_id(&command_id);
@@ -137,12 +142,13 @@ pub fn elaborate_refl(
             premises: vec![equality_step],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         });
     } else {
         let id = elaborator.get_new_id(&command_id);
         let first_step = elaborator.add_refl_step(pool, left.clone(), new_left.clone(), id);
 
-        if are_alpha_equivalent(&new_left, right, deep_eq_time) {
+        if are_alpha_equivalent(&new_left, right, deep_eq_depth_limit, deep_eq_time) {
             let second_step = elaborate_equality(
                 elaborator,
                 pool,
@@ -159,8 +165,9 @@ pub fn elaborate_refl(
                 premises: vec![first_step, second_step],
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             });
-        } else if are_alpha_equivalent(&new_left, &new_right, deep_eq_time) {
+        } else if are_alpha_equivalent(&new_left, &new_right, deep_eq_depth_limit, deep_eq_time) {
             let second_step = elaborate_equality(
                 elaborator,
                 pool,
@@ -180,6 +187,7 @@ pub fn elaborate_refl(
                 premises: vec![first_step, second_step, third_step],
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             });
         } else {
             return Err(CheckerError::ReflexivityFailed(left.clone(), right.clone()));
@@ -367,8 +367,12 @@ fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
         .map(Rc::remove_all_negations)
         .collect();
 
-    for (premise, (pivot, polarity)) in premises[1..].iter().zip(args) {
-        binary_resolution(pool, &mut current, premise.clause, pivot, polarity)?;
+    for (i, (premise, (pivot, polarity))) in premises[1..].iter().zip(args).enumerate() {
+        binary_resolution(pool, &mut current, premise.clause, pivot, polarity).map_err(|e| {
+            // The position is the index of the pivot in the step arguments
+            CheckerError::from(e)
+                .with_context(format!("while checking pivot at position {}", 2 * i))
+        })?;
     }
 
     Ok(current)
@@ -409,10 +413,20 @@ fn binary_resolution<'a, C: ClauseCollection<'a>>(
 }
 
 pub fn elaborate_resolution(
-    RuleArgs { conclusion, premises, pool, .. }: RuleArgs,
+    rule_args: RuleArgs,
     command_id: String,
     elaborator: &mut Elaborator,
 ) -> RuleResult {
+    // If the pivots are already given as arguments, there is nothing to infer, so the step is kept
+    // as is
+    if !rule_args.args.is_empty() {
+        let conclusion = rule_args.conclusion;
+        resolution_with_args(rule_args)?;
+        elaborator.unchanged(conclusion);
+        return Ok(());
+    }
+    let RuleArgs { conclusion, premises, pool, .. } = rule_args;
+
     // In the cases where the rule is used to get an empty clause from `(not true)`, we add a `true`
     // step to get an actual resolution step
     if conclusion.is_empty() && premises.len() == 1 {
@@ -426,6 +440,7 @@ pub fn elaborate_resolution(
                     premises: Vec::new(),
                     args: Vec::new(),
                     discharge: Vec::new(),
+                    source_position: None,
                 });
                 let premises = vec![elaborator.map_index(premises[0].index), true_step];
                 elaborator.push_elaborated_step(ProofStep {
@@ -437,6 +452,7 @@ pub fn elaborate_resolution(
                         .map(|a| ProofArg::Term(pool.bool_constant(a)))
                         .to_vec(),
                     discharge: Vec::new(),
+                    source_position: None,
                 });
                 return Ok(());
             }
@@ -468,6 +484,7 @@ pub fn elaborate_resolution(
         premises,
         args: pivots,
         discharge: Vec::new(),
+        source_position: None,
     };
 
     if not_not_added {
@@ -511,6 +528,7 @@ pub fn elaborate_resolution(
             premises: Vec::new(),
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         });
         let id = elaborator.get_new_id(&command_id);
         let second_not_not_step = elaborator.add_new_step(ProofStep {
@@ -520,6 +538,7 @@ pub fn elaborate_resolution(
             premises: Vec::new(),
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         });
 
         // Finally, we add a new resolution step, refering to the preivous three, and concluding the
@@ -536,6 +555,7 @@ pub fn elaborate_resolution(
             premises: vec![resolution_step, first_not_not_step, second_not_not_step],
             args,
             discharge: Vec::new(),
+            source_position: None,
         });
     } else {
         elaborator.push_elaborated_step(resolution_step);
@@ -408,8 +408,12 @@ pub fn qnt_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
     let (_, _, inner) = left.unwrap_quant_err()?;
+
+    // Besides the boolean constants, we also accept any term that is trivially valid or
+    // unsatisfiable, like `(= x x)`. Since its value doesn't depend on the bound variables, the
+    // quantifier can be removed
     rassert!(
-        inner.is_bool_false() || inner.is_bool_true(),
+        inner.is_boolean_tautology() || inner.is_boolean_contradiction(),
         CheckerError::ExpectedAnyBoolConstant(inner.clone())
     );
     assert_eq(right, inner)?;
@@ -662,10 +666,58 @@ pub fn comp_simplify(args: RuleArgs) -> RuleResult {
 
             // t_1 > t_2 => ¬(t_1 <= t_2)
             (> t_1 t_2): (t_1, t_2) => build_term!(pool, (not (<= {t_1.clone()} {t_2.clone()}))),
+
+            (distinct ...): args => {
+                let numbers: Option<Vec<_>> = args.iter().map(|a| a.as_signed_number()).collect();
+                if args.iter().dedup().count() < args.len() {
+                    // distinct t_1 ... t_n => false, where two of the t_i are the same term
+                    pool.bool_false()
+                } else if let Some(numbers) = numbers {
+                    // distinct t_1 ... t_n => phi, where all t_i are numerical constants
+                    let all_different = (0..numbers.len())
+                        .all(|i| numbers[i + 1..].iter().all(|n| *n != numbers[i]));
+                    pool.bool_constant(all_different)
+                } else {
+                    return None
+                }
+            },
+
+            // (t_1 < t_2) = false => t_1 >= t_2, and similarly for the other comparisons
+            (= phi false): (phi, _) => {
+                let (op, args) = match phi.as_ref() {
+                    Term::Op(op, args) if args.len() == 2 => (*op, args),
+                    _ => return None,
+                };
+                let negated = match op {
+                    Operator::LessThan => Operator::GreaterEq,
+                    Operator::LessEq => Operator::GreaterThan,
+                    Operator::GreaterThan => Operator::LessEq,
+                    Operator::GreaterEq => Operator::LessThan,
+                    _ => return None,
+                };
+                pool.add(Term::Op(negated, args.clone()))
+            },
         })
     })
 }
 
+/// Pushes to `result` the arguments of a series of nested `xor` terms, after applying `ac_simp` to
+/// each of them. Nested terms are flattened before any arguments are cancelled, so that the result
+/// of `ac_simp` doesn't depend on how the arguments are associated.
+fn flatten_xor(
+    pool: &mut TermPool,
+    cache: &mut AHashMap<Rc<Term>, Rc<Term>>,
+    args: &[Rc<Term>],
+    result: &mut Vec<Rc<Term>>,
+) {
+    for term in args {
+        match term.as_ref() {
+            Term::Op(Operator::Xor, inner) => flatten_xor(pool, cache, inner, result),
+            _ => result.push(apply_ac_simp(pool, cache, term)),
+        }
+    }
+}
+
 fn apply_ac_simp(
     pool: &mut TermPool,
     cache: &mut AHashMap<Rc<Term>, Rc<Term>>,
@@ -693,6 +745,29 @@ fn apply_ac_simp(
                 Term::Op(*op, args)
             }
         }
+        Term::Op(Operator::Xor, args) => {
+            // Since `(xor t t)` is equivalent to `false`, and `(xor t false)` to `t`, we only keep
+            // the arguments that appear an odd number of times, at their first occurrence, and
+            // remove all `false` arguments
+            let mut flattened = Vec::new();
+            flatten_xor(pool, cache, args, &mut flattened);
+            flattened.retain(|t| !t.is_bool_false());
+            let mut counts: AHashMap<&Rc<Term>, usize> = AHashMap::new();
+            for t in &flattened {
+                *counts.entry(t).or_default() += 1;
+            }
+            let mut args: Vec<_> = flattened
+                .iter()
+                .dedup()
+                .filter(|t| counts[t] % 2 == 1)
+                .cloned()
+                .collect();
+            match args.len() {
+                0 => return pool.bool_false(),
+                1 => return args.pop().unwrap(),
+                _ => Term::Op(Operator::Xor, args),
+            }
+        }
         Term::Op(op, args) => {
             let args = args
                 .iter()
@@ -1153,7 +1228,10 @@ mod tests {
     #[test]
     fn qnt_simplify() {
         test_cases! {
-            definitions = "",
+            definitions = "
+                (declare-fun x () Int)
+                (declare-fun p () Bool)
+            ",
             "Simple working examples" {
                 "(step t1 (cl (= (forall ((x Int)) false) false)) :rule qnt_simplify)": true,
                 "(step t1 (cl (= (forall ((x Int) (p Bool)) true) true)) :rule qnt_simplify)": true,
@@ -1161,8 +1239,14 @@ mod tests {
             "Quantifier is not \"forall\"" {
                 "(step t1 (cl (= (exists ((x Int)) false) false)) :rule qnt_simplify)": true,
             }
+            "Inner term is trivially valid or unsatisfiable" {
+                "(step t1 (cl (= (forall ((x Int)) (= x x)) (= x x))) :rule qnt_simplify)": true,
+                "(step t1 (cl (= (exists ((p Bool)) (and p (not p))) (and p (not p))))
+                    :rule qnt_simplify)": true,
+            }
             "Inner term is not boolean constant" {
                 "(step t1 (cl (= (forall ((x Int)) (not false)) true)) :rule qnt_simplify)": false,
+                "(step t1 (cl (= (forall ((x Int)) (> x 0)) (> x 0))) :rule qnt_simplify)": false,
             }
             "Left and right terms don't match" {
                 "(step t1 (cl (= (forall ((x Int)) false) true)) :rule qnt_simplify)": false,
@@ -1378,13 +1462,63 @@ mod tests {
                 "(step t1 (cl (= (> a b) (not (>= b a)))) :rule comp_simplify)": false,
                 "(step t1 (cl (= (> a b) (< b a))) :rule comp_simplify)": false,
             }
+            "Transformation #8" {
+                "(step t1 (cl (= (distinct a a) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (distinct a b a) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (distinct 1 2 1) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (distinct 1 2 3) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (distinct 1.5 (- 1.5)) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (distinct a b) true)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (distinct 1 2) false)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (distinct a 1 a) true)) :rule comp_simplify)": false,
+            }
+            "Transformation #9" {
+                "(step t1 (cl (= (= (< a b) false) (>= a b))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (<= a b) false) (> a b))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (> a b) false) (<= a b))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (>= a b) false) (< a b))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (< a b) true) (>= a b))) :rule comp_simplify)": false,
+                "(step t1 (cl (= (= (< a b) false) (< b a))) :rule comp_simplify)": false,
+                "(step t1 (cl (= (= (= a b) false) (distinct a b))) :rule comp_simplify)": false,
+            }
             "Multiple transformations" {
                 "(step t1 (cl (= (>= a a) true)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (>= 5.0 8.0) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (< a b) false) (<= b a))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (<= a b) false) (not (<= a b)))) :rule comp_simplify)": true,
+                "(step t1 (cl (= (= (< 1 2) false) true)) :rule comp_simplify)": false,
             }
         }
     }
 
+    #[test]
+    fn comp_simplify_int_real_subtyping() {
+        use crate::{
+            checker::{Config, ProofChecker},
+            parser::parse_instance,
+        };
+
+        let definitions = "(declare-fun a () Int) (declare-fun x () Real)";
+        let cases = [
+            ("(= (< a x) (not (<= x a)))", true),
+            ("(= (<= 2 2.5) true)", true),
+            ("(= (< 1 1.0) false)", true),
+            ("(= (= (< a x) false) (>= a x))", true),
+            ("(= (<= 1.0 1) false)", false),
+            ("(= (< a x) (not (<= a x)))", false),
+        ];
+        for (term, expected) in cases {
+            let proof = format!("(step t1 (cl {}) :rule comp_simplify)", term);
+            let (prelude, proof, mut pool) =
+                parse_instance(definitions.as_bytes(), proof.as_bytes(), true, false, true)
+                    .unwrap();
+            let got = ProofChecker::new(&mut pool, Config::new(), prelude)
+                .check_fragment(&proof)
+                .is_ok();
+            assert_eq!(expected, got, "{}", term);
+        }
+    }
+
     #[test]
     fn ac_simp() {
         test_cases! {
@@ -1408,8 +1542,6 @@ mod tests {
                 "(step t1 (cl (= (or (= (and (and p q) r) s) (or p q))
                     (or (= (and (and p q) r) s) p q))) :rule ac_simp)": false,
 
-                "(step t1 (cl (= (xor (xor (xor p q) r) s) (xor p q r s))) :rule ac_simp)": false,
-
                 "(step t1 (cl (= (forall ((p Bool) (q Bool)) (and (and p q) p))
                     (forall ((p Bool) (q Bool)) (and p q)))) :rule ac_simp)": true,
             }
@@ -1418,6 +1550,89 @@ mod tests {
                 "(step t1 (cl (= (and (and p q) (and q r)) (and p q r))) :rule ac_simp)": true,
                 "(step t1 (cl (= (and (and p q) (and q r)) (and p q q r))) :rule ac_simp)": false,
             }
+            "Flattening xor" {
+                "(step t1 (cl (= (xor (xor (xor p q) r) s) (xor p q r s))) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor p (xor q (xor r s))) (xor p q r s))) :rule ac_simp)": true,
+                "(step t1 (cl (= (and (xor p (xor q r)) s) (and (xor p q r) s))) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor (xor p q) r) (xor p (xor q r)))) :rule ac_simp)": false,
+            }
+            "Cancelling repeated xor arguments" {
+                "(step t1 (cl (= (xor p p) false)) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor (xor p q) (xor p r)) (xor q r))) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor (xor p q) (xor q p)) false)) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor p (xor p (xor p q))) (xor p q))) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor (xor p q) q) p)) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor p p q) (xor q))) :rule ac_simp)": false,
+                "(step t1 (cl (= (xor p p q) (xor p q))) :rule ac_simp)": false,
+            }
+            "Removing false from xor" {
+                "(step t1 (cl (= (xor p false) p)) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor (xor p false) (xor false q)) (xor p q))) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor false false) false)) :rule ac_simp)": true,
+                "(step t1 (cl (= (xor p false) (xor p false))) :rule ac_simp)": false,
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn ac_simp_xor_is_confluent(
+            leaves in proptest::collection::vec(0..5usize, 1..12),
+            first_splits in proptest::collection::vec(proptest::num::usize::ANY, 12),
+            second_splits in proptest::collection::vec(proptest::num::usize::ANY, 12),
+        ) {
+            use super::apply_ac_simp;
+            use crate::ast::*;
+            use ahash::AHashMap;
+
+            // Builds a tree of binary `xor` terms whose leaves are `leaves`, in order. The shape of
+            // the tree is determined by `splits`
+            fn build(
+                pool: &mut TermPool,
+                leaves: &[Rc<Term>],
+                splits: &mut impl Iterator<Item = usize>,
+            ) -> Rc<Term> {
+                if leaves.len() == 1 {
+                    return leaves[0].clone();
+                }
+                let k = 1 + splits.next().unwrap_or(0) % (leaves.len() - 1);
+                let left = build(pool, &leaves[..k], splits);
+                let right = build(pool, &leaves[k..], splits);
+                pool.add(Term::Op(Operator::Xor, vec![left, right]))
+            }
+
+            let mut pool = TermPool::new();
+            let bool_sort = pool.add(Term::Sort(Sort::Bool));
+            let mut atoms: Vec<_> = ["p", "q", "r", "s"]
+                .iter()
+                .map(|name| pool.add(Term::var(*name, bool_sort.clone())))
+                .collect();
+            atoms.push(pool.bool_false());
+            let leaves: Vec<_> = leaves.into_iter().map(|i| atoms[i].clone()).collect();
+
+            let first = build(&mut pool, &leaves, &mut first_splits.into_iter());
+            let second = build(&mut pool, &leaves, &mut second_splits.into_iter());
+            let first = apply_ac_simp(&mut pool, &mut AHashMap::new(), &first);
+            let second = apply_ac_simp(&mut pool, &mut AHashMap::new(), &second);
+
+            // Different ways of associating the same arguments result in the same term, which is
+            // already in normal form
+            proptest::prop_assert_eq!(&first, &second);
+            let again = apply_ac_simp(&mut pool, &mut AHashMap::new(), &first);
+            proptest::prop_assert_eq!(&first, &again);
+
+            // The result contains exactly the atoms that appear an odd number of times
+            let expected: Vec<_> = atoms[..4]
+                .iter()
+                .filter(|a| leaves.iter().filter(|l| l == a).count() % 2 == 1)
+                .collect();
+            let got: Vec<_> = match first.as_ref() {
+                Term::Op(Operator::Xor, args) => args.iter().collect(),
+                _ if first.is_bool_false() => Vec::new(),
+                _ => vec![&first],
+            };
+            proptest::prop_assert_eq!(expected.len(), got.len());
+            proptest::prop_assert!(expected.iter().all(|a| got.contains(a)));
         }
     }
 }
@@ -0,0 +1,108 @@
+//! This module contains rules for the theory of strings, used in proofs generated by cvc5.
+
+use super::{assert_clause_len, assert_is_expected, CheckerError, RuleArgs, RuleResult};
+use crate::ast::*;
+use rug::Rational;
+
+/// Returns the arguments of a `str.++` term, recursively replacing each argument that is itself a
+/// `str.++` term by its arguments. Arguments that are not `str.++` terms, including empty strings,
+/// are kept as they are.
+fn flatten_concat(args: &[Rc<Term>]) -> Vec<Rc<Term>> {
+    let mut result = Vec::with_capacity(args.len());
+    let mut stack: Vec<_> = args.iter().rev().collect();
+    while let Some(term) = stack.pop() {
+        match term.as_ref() {
+            Term::Op(Operator::StrConcat, inner) => stack.extend(inner.iter().rev()),
+            _ => result.push(term.clone()),
+        }
+    }
+    result
+}
+
+pub fn str_concat_flatten(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
+    let args = match left.as_ref() {
+        Term::Op(Operator::StrConcat, args) => args,
+        _ => return Err(CheckerError::TermOfWrongForm("(str.++ ...)", left.clone())),
+    };
+    let expected = pool.add(Term::Op(Operator::StrConcat, flatten_concat(args)));
+    assert_is_expected(right, expected)
+}
+
+pub fn str_len_pos(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let (zero, length) = match_term_err!((<= z l) = &conclusion[0])?;
+    if zero.as_number() != Some(Rational::new()) {
+        return Err(CheckerError::ExpectedNumber(Rational::new(), zero.clone()));
+    }
+    match length.as_ref() {
+        Term::Op(Operator::StrLen, _) => Ok(()),
+        _ => Err(CheckerError::TermOfWrongForm(
+            "(str.len ...)",
+            length.clone(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn str_concat_flatten() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () String)
+                (declare-fun b () String)
+                (declare-fun c () String)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (= (str.++ a (str.++ b c)) (str.++ a b c)))
+                    :rule str_concat_flatten)": true,
+                "(step t1 (cl (= (str.++ (str.++ a b) (str.++ (str.++ c a) b))
+                    (str.++ a b c a b))) :rule str_concat_flatten)": true,
+                "(step t1 (cl (= (str.++ a b) (str.++ a b))) :rule str_concat_flatten)": true,
+            }
+            "Empty strings are kept" {
+                "(step t1 (cl (= (str.++ \"\" (str.++ a \"\")) (str.++ \"\" a \"\")))
+                    :rule str_concat_flatten)": true,
+                "(step t1 (cl (= (str.++ \"\" (str.++ a \"\")) (str.++ \"\" a)))
+                    :rule str_concat_flatten)": false,
+                "(step t1 (cl (= (str.++ \"\" (str.++ a \"\")) a)) :rule str_concat_flatten)": false,
+            }
+            "Conclusion is not completely flattened" {
+                "(step t1 (cl (= (str.++ a (str.++ b (str.++ c a))) (str.++ a b (str.++ c a))))
+                    :rule str_concat_flatten)": false,
+            }
+            "Terms are reordered" {
+                "(step t1 (cl (= (str.++ a (str.++ b c)) (str.++ a c b)))
+                    :rule str_concat_flatten)": false,
+            }
+            "Left side is not a concatenation" {
+                "(step t1 (cl (= a a)) :rule str_concat_flatten)": false,
+            }
+        }
+    }
+
+    #[test]
+    fn str_len_pos() {
+        test_cases! {
+            definitions = "
+                (declare-fun a () String)
+                (declare-fun b () String)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl (<= 0 (str.len a))) :rule str_len_pos)": true,
+                "(step t1 (cl (<= 0 (str.len (str.++ a b)))) :rule str_len_pos)": true,
+                "(step t1 (cl (<= 0 (str.len \"\"))) :rule str_len_pos)": true,
+            }
+            "Conclusion is not of the correct form" {
+                "(step t1 (cl (<= 1 (str.len a))) :rule str_len_pos)": false,
+                "(step t1 (cl (< 0 (str.len a))) :rule str_len_pos)": false,
+                "(step t1 (cl (<= 0 (+ 1 (str.len a)))) :rule str_len_pos)": false,
+                "(step t1 (cl (<= 0 (str.len a)) (<= 0 (str.len b))) :rule str_len_pos)": false,
+            }
+        }
+    }
+}
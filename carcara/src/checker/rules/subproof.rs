@@ -1,6 +1,6 @@
 use super::{
     assert_clause_len, assert_eq, assert_is_expected, assert_num_premises, get_premise_term,
-    CheckerError, EqualityError, RuleArgs, RuleResult,
+    CheckerError, Elaborator, EqualityError, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::SubproofError};
 use ahash::{AHashMap, AHashSet};
@@ -45,6 +45,10 @@ pub fn subproof(
     assert_eq(conclusion.last().unwrap(), &phi)
 }
 
+/// Steps that use this rule are not elaborated. Besides `bind`, no rule can derive an equality
+/// between two quantified terms whose bindings or bodies differ, since `cong` only applies to
+/// function applications and operators. So a `bind` step can't be expanded into `cong` and `refl`
+/// steps, and is kept as is in elaborated proofs. The steps in its subproof are still elaborated.
 pub fn bind(
     RuleArgs {
         conclusion,
@@ -230,6 +234,11 @@ fn extract_points(quant: Quantifier, term: &Rc<Term>) -> AHashSet<(Rc<Term>, Rc<
     result
 }
 
+/// Checks a `onepoint` step. The equality in the previous command may be in either order.
+///
+/// Steps that use this rule are not elaborated. No other rule can remove the bindings that are
+/// substituted by their points, so a `onepoint` step can't be expanded into `let` and `refl`
+/// steps, and is kept as is in elaborated proofs.
 pub fn onepoint(
     RuleArgs {
         conclusion,
@@ -293,11 +302,12 @@ pub fn onepoint(
     // substitution to the points in order to replace these variables by their value. We also
     // create a duplicate of every point in the reverse order, since the order of equalities may be
     // flipped
-    let points: AHashSet<_> = points
+    let (xs, ts): (Vec<_>, Vec<_>) = points
         .into_iter()
         .flat_map(|(x, t)| [(x.clone(), t.clone()), (t, x)])
-        .map(|(x, t)| (x, context.apply(pool, &t)))
-        .collect();
+        .unzip();
+    let ts = context.apply_to_clause(pool, &ts);
+    let points: AHashSet<_> = xs.into_iter().zip(ts).collect();
 
     let last_context = context.last_mut().unwrap();
     // For each substitution (:= x t) in the context, the equality (= x t) must appear in phi
@@ -330,6 +340,7 @@ fn generic_skolemization_rule(
         pool,
         context,
         previous_command,
+        deep_eq_depth_limit,
         deep_eq_time,
         ..
     }: RuleArgs,
@@ -382,7 +393,7 @@ fn generic_skolemization_rule(
             }
             pool.add(Term::Choice(x.clone(), inner))
         };
-        if !are_alpha_equivalent(t, &expected, deep_eq_time) {
+        if !are_alpha_equivalent(t, &expected, deep_eq_depth_limit, deep_eq_time) {
             return Err(EqualityError::ExpectedEqual(t.clone(), expected).into());
         }
 
@@ -401,6 +412,220 @@ pub fn sko_forall(args: RuleArgs) -> RuleResult {
     generic_skolemization_rule(Quantifier::Forall, args)
 }
 
+/// Elaborates a `sko_ex` or `sko_forall` step into steps that use only the `exists_pos`,
+/// `exists_neg`, `forall_inst` and `forall_neg` rules, together with propositional rules. If the
+/// quantifier is `Q` and its skolemized body is `S`, this derives the clauses `(cl (not Q) S)` and
+/// `(cl Q (not S))`, and uses them to conclude `(= Q S)` by resolution. If the right-hand side of
+/// the conclusion is not `S`, this is then combined with the previous command by transitivity,
+/// using a `refl` step that applies the context substitution.
+fn elaborate_skolemization(
+    rule_type: Quantifier,
+    RuleArgs {
+        conclusion,
+        premises,
+        args,
+        pool,
+        context,
+        previous_command,
+        discharge,
+        deep_eq_depth_limit,
+        deep_eq_time,
+    }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    generic_skolemization_rule(
+        rule_type,
+        RuleArgs {
+            conclusion,
+            premises,
+            args,
+            pool,
+            context,
+            previous_command,
+            discharge,
+            deep_eq_depth_limit,
+            deep_eq_time,
+        },
+    )?;
+
+    // Since the step was already checked, we know the previous command and the conclusion are of
+    // the expected form
+    let previous_command = previous_command.unwrap();
+    let (quant_term, psi) = match_term!((= l r) = &conclusion[0]).unwrap();
+    let (_, bindings, phi) = quant_term.unwrap_quant().unwrap();
+    let (skolem_terms, skolemized) = super::quantifier::skolemize(pool, rule_type, bindings, phi)?;
+
+    // The `refl` step can only be added if the context substitution uses exactly the Skolem terms,
+    // and if there are no outer contexts that would also be applied to `phi`. Otherwise, the step
+    // is left unchanged
+    let needs_refl = skolemized != *psi;
+    if needs_refl {
+        let substitution: AHashMap<_, _> =
+            context.last().unwrap().mappings.iter().cloned().collect();
+        let uses_skolem_terms = bindings.iter().zip(&skolem_terms).all(|(x, s)| {
+            let x = pool.add(Term::from(x.clone()));
+            substitution.get(&x) == Some(s)
+        });
+        if context.len() >= 2 || !uses_skolem_terms {
+            elaborator.unchanged(conclusion);
+            return Ok(());
+        }
+    }
+
+    // The subproof already contains steps with ids derived from `command_id`, so we derive the new
+    // ids from the id of the previous command instead
+    let root_id = previous_command.id;
+    let new_step = |elaborator: &mut Elaborator, clause, rule: &str, premises, args| ProofStep {
+        id: elaborator.get_new_id(root_id),
+        clause,
+        rule: rule.to_owned(),
+        premises,
+        args,
+        discharge: Vec::new(),
+        source_position: None,
+    };
+
+    let instantiation_args: Vec<_> = bindings
+        .iter()
+        .zip(&skolem_terms)
+        .map(|((x, _), s)| ProofArg::Assign(x.clone(), s.clone()))
+        .collect();
+    let not_quant = build_term!(pool, (not {quant_term.clone()}));
+    let not_skolemized = build_term!(pool, (not {skolemized.clone()}));
+
+    // `forward` is the clause `(cl (not Q) S)`. `backward` is the clause `(cl Q (not S))`, or
+    // `(cl (not (not Q)) (not S))` if the quantifier is universal
+    let (forward, backward, backward_pivot) = match rule_type {
+        Quantifier::Exists => {
+            let clause = vec![not_quant.clone(), skolemized.clone()];
+            let step = new_step(elaborator, clause, "exists_pos", Vec::new(), Vec::new());
+            let forward = elaborator.add_new_step(step);
+
+            let clause = vec![quant_term.clone(), not_skolemized.clone()];
+            let args = instantiation_args;
+            let step = new_step(elaborator, clause, "exists_neg", Vec::new(), args);
+            let backward = elaborator.add_new_step(step);
+            (forward, backward, [quant_term.clone(), pool.bool_false()])
+        }
+        Quantifier::Forall => {
+            let clause = vec![build_term!(pool, (or {not_quant.clone()} {skolemized.clone()}))];
+            let args = instantiation_args.clone();
+            let step = new_step(elaborator, clause, "forall_inst", Vec::new(), args);
+            let instantiation = elaborator.add_new_step(step);
+
+            let clause = vec![not_quant.clone(), skolemized.clone()];
+            let step = new_step(elaborator, clause, "or", vec![instantiation], Vec::new());
+            let forward = elaborator.add_new_step(step);
+
+            // The `forall_neg` rule needs the negated quantifier as a premise, so we introduce it
+            // as an assumption in a new subproof. The commands in this subproof must be kept even
+            // if their conclusions were already seen, since the subproof discharges the
+            // assumption and implicitly references the `forall_neg` step
+            elaborator.open_accumulator_subproof();
+            let id = elaborator.get_new_id(root_id);
+            let term = not_quant.clone();
+            let assumption = elaborator.add_new_command(ProofCommand::Assume { id, term }, true);
+            let clause = vec![not_skolemized.clone()];
+            let args = instantiation_args;
+            let step = new_step(elaborator, clause, "forall_neg", vec![assumption], args);
+            elaborator.add_new_command(ProofCommand::Step(step), true);
+
+            let clause = vec![
+                build_term!(pool, (not {not_quant.clone()})),
+                not_skolemized.clone(),
+            ];
+            let mut end_step = new_step(elaborator, clause, "subproof", Vec::new(), Vec::new());
+            end_step.discharge = vec![assumption];
+            let backward =
+                elaborator.close_accumulator_subproof(Vec::new(), Vec::new(), end_step, root_id);
+            (forward, backward, [not_quant.clone(), pool.bool_true()])
+        }
+    };
+
+    let equality = build_term!(pool, (= {quant_term.clone()} {skolemized.clone()}));
+    let clause = vec![equality.clone(), quant_term.clone(), skolemized.clone()];
+    let step = new_step(elaborator, clause, "equiv_neg2", Vec::new(), Vec::new());
+    let equiv_neg2 = elaborator.add_new_step(step);
+
+    let clause = vec![equality.clone(), not_quant, not_skolemized];
+    let step = new_step(elaborator, clause, "equiv_neg1", Vec::new(), Vec::new());
+    let equiv_neg1 = elaborator.add_new_step(step);
+
+    let clause = vec![equality.clone(), skolemized.clone()];
+    let args = vec![
+        ProofArg::Term(quant_term.clone()),
+        ProofArg::Term(pool.bool_true()),
+    ];
+    let step = new_step(
+        elaborator,
+        clause,
+        "resolution",
+        vec![equiv_neg2, forward],
+        args,
+    );
+    let resolved_forward = elaborator.add_new_step(step);
+
+    let [pivot, polarity] = backward_pivot;
+    let mut equality_step = ProofStep {
+        id: command_id.clone(),
+        clause: vec![equality],
+        rule: "resolution".to_owned(),
+        premises: vec![equiv_neg1, backward, resolved_forward],
+        args: vec![
+            ProofArg::Term(pivot),
+            ProofArg::Term(polarity),
+            ProofArg::Term(skolemized.clone()),
+            ProofArg::Term(pool.bool_false()),
+        ],
+        discharge: Vec::new(),
+        source_position: None,
+    };
+    if !needs_refl {
+        elaborator.push_elaborated_step(equality_step);
+        return Ok(());
+    }
+
+    equality_step.id = elaborator.get_new_id(root_id);
+    let equality_step = elaborator.add_new_step(equality_step);
+
+    let clause = vec![build_term!(pool, (= {skolemized} {phi.clone()}))];
+    let step = new_step(elaborator, clause, "refl", Vec::new(), Vec::new());
+    let refl_step = elaborator.add_new_step(step);
+
+    let premises = vec![
+        equality_step,
+        refl_step,
+        elaborator.map_index(previous_command.index),
+    ];
+    elaborator.push_elaborated_step(ProofStep {
+        id: command_id,
+        clause: conclusion.to_vec(),
+        rule: "trans".to_owned(),
+        premises,
+        args: Vec::new(),
+        discharge: Vec::new(),
+        source_position: None,
+    });
+    Ok(())
+}
+
+pub fn elaborate_sko_ex(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_skolemization(Quantifier::Exists, args, command_id, elaborator)
+}
+
+pub fn elaborate_sko_forall(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_skolemization(Quantifier::Forall, args, command_id, elaborator)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -1,9 +1,12 @@
 use super::{
     assert_clause_len, assert_deep_eq, assert_eq, assert_num_premises, get_premise_term,
-    CheckerError, RuleArgs, RuleResult,
+    CheckerError, Elaborator, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::rules::assert_operation_len};
 
+/// The conclusion must be exactly `(cl true)`. Other trivially valid terms, that are detected by
+/// [`Term::is_boolean_tautology`], are not accepted, since the specification only allows the
+/// constant itself.
 pub fn r#true(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     if !conclusion[0].is_bool_true() {
@@ -15,6 +18,8 @@ pub fn r#true(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
+/// The conclusion must be exactly `(cl (not false))`. Like in the `true` rule, terms that are only
+/// detected by [`Term::is_boolean_contradiction`] are not accepted.
 pub fn r#false(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let t = conclusion[0].remove_negation_err()?;
@@ -258,7 +263,14 @@ pub fn not_ite2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_eq(phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn ite_intro(RuleArgs { conclusion, deep_eq_time, .. }: RuleArgs) -> RuleResult {
+pub fn ite_intro(
+    RuleArgs {
+        conclusion,
+        deep_eq_depth_limit,
+        deep_eq_time,
+        ..
+    }: RuleArgs,
+) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (root_term, right_side) = match_term_err!((= t u) = &conclusion[0])?;
@@ -278,13 +290,13 @@ pub fn ite_intro(RuleArgs { conclusion, deep_eq_time, .. }: RuleArgs) -> RuleRes
     // ```
     // For cases like this, we first check if `t` equals the right side term modulo reordering of
     // equalities. If not, we unwrap the conjunction and continue checking the rule normally.
-    if deep_eq(root_term, right_side, deep_eq_time) {
+    if deep_eq(root_term, right_side, deep_eq_depth_limit, deep_eq_time) {
         return Ok(());
     }
     let us = match_term_err!((and ...) = right_side)?;
 
     // `us` must be a conjunction where the first term is the root term
-    assert_deep_eq(&us[0], root_term, deep_eq_time)?;
+    assert_deep_eq(&us[0], root_term, deep_eq_depth_limit, deep_eq_time)?;
 
     // The remaining terms in `us` should be of the correct form
     for u_i in &us[1..] {
@@ -292,11 +304,11 @@ pub fn ite_intro(RuleArgs { conclusion, deep_eq_time, .. }: RuleArgs) -> RuleRes
 
         let mut is_valid = |r_1, s_1, r_2, s_2| {
             // s_1 == s_2 == (ite cond r_1 r_2)
-            if deep_eq(s_1, s_2, deep_eq_time) {
+            if deep_eq(s_1, s_2, deep_eq_depth_limit, deep_eq_time) {
                 if let Some((a, b, c)) = match_term!((ite a b c) = s_1) {
-                    return deep_eq(a, cond, deep_eq_time)
-                        && deep_eq(b, r_1, deep_eq_time)
-                        && deep_eq(c, r_2, deep_eq_time);
+                    return deep_eq(a, cond, deep_eq_depth_limit, deep_eq_time)
+                        && deep_eq(b, r_1, deep_eq_depth_limit, deep_eq_time)
+                        && deep_eq(c, r_2, deep_eq_depth_limit, deep_eq_time);
                 }
             }
             false
@@ -316,6 +328,10 @@ pub fn ite_intro(RuleArgs { conclusion, deep_eq_time, .. }: RuleArgs) -> RuleRes
 }
 
 pub fn connective_def(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+    check_connective_def(conclusion)
+}
+
+fn check_connective_def(conclusion: &[Rc<Term>]) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (first, second) = match_term_err!((= f s) = &conclusion[0])?;
@@ -350,6 +366,313 @@ pub fn connective_def(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     }
 }
 
+/// A step that was planned while elaborating a step, but not yet added to the elaborated proof.
+struct PlannedStep {
+    rule: &'static str,
+    clause: Vec<Rc<Term>>,
+    premises: Vec<usize>,
+    args: Vec<Rc<Term>>,
+}
+
+/// A clause derived while elaborating a step, together with the index of the planned step that
+/// concludes it.
+type DerivedClause = (usize, Vec<Rc<Term>>);
+
+/// Plans steps that derive clauses using propositional tautologies and resolution, while
+/// elaborating a step. The steps are only added to the elaborated proof by
+/// [`ClauseDeriver::commit`], so nothing is added if the derivation fails midway.
+struct ClauseDeriver<'a> {
+    pool: &'a mut TermPool,
+    steps: Vec<PlannedStep>,
+}
+
+impl<'a> ClauseDeriver<'a> {
+    fn new(pool: &'a mut TermPool) -> Self {
+        Self { pool, steps: Vec::new() }
+    }
+
+    fn not(&mut self, term: &Rc<Term>) -> Rc<Term> {
+        build_term!(self.pool, (not {term.clone()}))
+    }
+
+    fn add_step(
+        &mut self,
+        rule: &'static str,
+        clause: Vec<Rc<Term>>,
+        premises: Vec<usize>,
+        args: Vec<Rc<Term>>,
+    ) -> DerivedClause {
+        self.steps.push(PlannedStep {
+            rule,
+            clause: clause.clone(),
+            premises,
+            args,
+        });
+        (self.steps.len() - 1, clause)
+    }
+
+    /// Adds a step that introduces a tautological clause, like `and_neg` or `equiv_pos1`.
+    fn tautology(&mut self, rule: &'static str, clause: Vec<Rc<Term>>) -> DerivedClause {
+        self.add_step(rule, clause, Vec::new(), Vec::new())
+    }
+
+    /// Adds a `resolution` step that resolves the given clauses in order, using the given pivots.
+    /// Each pivot must appear either in the current clause, with its negation in the next premise,
+    /// or negated in the current clause, and as is in the next premise. Like in the `resolution`
+    /// rule, clauses are compared as sets, modulo double negations. Returns `None` if a pivot is not
+    /// found.
+    fn resolve(
+        &mut self,
+        premises: Vec<DerivedClause>,
+        pivots: &[&Rc<Term>],
+    ) -> Option<DerivedClause> {
+        let mut premises = premises.into_iter();
+        let (first_index, mut current) = premises.next().unwrap();
+        let mut indices = vec![first_index];
+        let mut args = Vec::with_capacity(pivots.len() * 2);
+        for ((index, mut next), &pivot) in premises.zip(pivots) {
+            let (n, atom) = pivot.remove_all_negations();
+            let position = |clause: &[Rc<Term>], n| {
+                clause
+                    .iter()
+                    .position(|t| t.remove_all_negations() == (n, atom))
+            };
+            let polarity = position(&current, n).is_some();
+            let (in_current, in_next) = if polarity { (n, n + 1) } else { (n + 1, n) };
+            current.remove(position(&current, in_current)?);
+            next.remove(position(&next, in_next)?);
+            for t in next {
+                let key = t.remove_all_negations();
+                if !current.iter().any(|u| u.remove_all_negations() == key) {
+                    current.push(t);
+                }
+            }
+            indices.push(index);
+            args.extend([pivot.clone(), self.pool.bool_constant(polarity)]);
+        }
+        Some(self.add_step("resolution", current, indices, args))
+    }
+
+    /// Adds the planned steps to the elaborated proof. The last planned step is pushed as the
+    /// elaboration of the step with id `command_id`, and its clause is replaced by `clause`.
+    fn commit(mut self, elaborator: &mut Elaborator, command_id: String, clause: &[Rc<Term>]) {
+        let to_step = |step: PlannedStep, id, indices: &[(usize, usize)]| ProofStep {
+            id,
+            clause: step.clause,
+            rule: step.rule.to_owned(),
+            premises: step.premises.iter().map(|&i| indices[i]).collect(),
+            args: step.args.into_iter().map(ProofArg::Term).collect(),
+            discharge: Vec::new(),
+            source_position: None,
+        };
+        let mut last = self.steps.pop().unwrap();
+        last.clause = clause.to_vec();
+        let mut indices = Vec::with_capacity(self.steps.len());
+        for step in self.steps {
+            let id = elaborator.get_new_id(&command_id);
+            indices.push(elaborator.add_new_step(to_step(step, id, &indices)));
+        }
+        elaborator.push_elaborated_step(to_step(last, command_id, &indices));
+    }
+}
+
+pub fn elaborate_connective_def(
+    RuleArgs { conclusion, pool, .. }: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    check_connective_def(conclusion)?;
+
+    let (first, second) = match_term!((= f s) = &conclusion[0]).unwrap();
+    let operands: Vec<&Rc<Term>> = if let Some((p, q)) = match_term!((xor p q) = first) {
+        vec![p, q]
+    } else if let Some((p, q)) = match_term!((= p q) = first) {
+        vec![p, q]
+    } else if let Some((c, a, b)) = match_term!((ite c a b) = first) {
+        vec![c, a, b]
+    } else {
+        // There is no simpler rule that can be used to justify the definition of `exists`
+        elaborator.unchanged(conclusion);
+        return Ok(());
+    };
+
+    // The derivation resolves on each operand, so it only works if they are different literals,
+    // that are not the negation of one another. In cases like `(xor a a)` or `(xor a (not a))`, we
+    // keep the step as is
+    let atoms: Vec<_> = operands
+        .iter()
+        .map(|t| t.remove_all_negations().1)
+        .collect();
+    if (1..atoms.len()).any(|i| atoms[..i].contains(&atoms[i])) {
+        elaborator.unchanged(conclusion);
+        return Ok(());
+    }
+
+    // We prove the equivalence `(= f s)` by deriving the clauses `(cl (= f s) (not f))` and
+    // `(cl (= f s) f)`, using only the introduction and elimination tautologies for each
+    // connective, and resolving them together
+    let mut d = ClauseDeriver::new(pool);
+    let derived = match operands.as_slice() {
+        [p, q] if match_term!((xor p q) = first).is_some() => {
+            connective_def_xor(&mut d, (&conclusion[0], first, second), (p, q))
+        }
+        [p, q] => connective_def_equiv(&mut d, (&conclusion[0], first, second), (p, q)),
+        [c, a, b] => connective_def_ite(&mut d, (&conclusion[0], first, second), (c, a, b)),
+        _ => unreachable!(),
+    };
+
+    // As a safeguard, if the derivation fails or doesn't reach the conclusion, the step is also
+    // kept as is
+    match derived {
+        Some((_, clause)) if clause == conclusion => d.commit(elaborator, command_id, conclusion),
+        _ => elaborator.unchanged(conclusion),
+    }
+    Ok(())
+}
+
+/// Derives the clause `(cl e (not f))`, where `e` is `(= f s)`, `s` is `(and i1 i2)`, and `i1` and
+/// `i2` are implications. For each implication `(=> a b)`, a clause `(cl (not f) a' b)` must be
+/// given, where `a'` can be resolved against `a` on the given pivot.
+fn connective_def_and_neg(
+    d: &mut ClauseDeriver,
+    (e, f, s): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+    implications: [(DerivedClause, &Rc<Term>); 2],
+) -> Option<DerivedClause> {
+    let (i1, i2) = match_term!((and i1 i2) = s).unwrap();
+    let [not_f, not_s, not_i1, not_i2] = [f, s, i1, i2].map(|t| d.not(t));
+    let and_neg = d.tautology("and_neg", vec![s.clone(), not_i1, not_i2]);
+    let mut premises = vec![and_neg];
+    for ((clause, pivot), i) in implications.into_iter().zip([i1, i2]) {
+        let (a, b) = match_term!((=> a b) = i).unwrap();
+        let not_b = d.not(b);
+        let implies_neg1 = d.tautology("implies_neg1", vec![i.clone(), a.clone()]);
+        let implies_neg2 = d.tautology("implies_neg2", vec![i.clone(), not_b]);
+        premises.push(d.resolve(vec![clause, implies_neg1, implies_neg2], &[pivot, b])?);
+    }
+    premises.push(d.tautology("equiv_neg1", vec![e.clone(), not_f, not_s]));
+    d.resolve(premises, &[i1, i2, s])
+}
+
+/// Derives the clause `(cl e)`, where `e` is `(= f s)` and `s` is `(and i1 i2)`. The first two
+/// clauses must be resolvable on `pivot`, and their other literals must be only `f`, `(not i1)`,
+/// and `(not i2)`. The last clause, `forward`, must be `(cl e (not f))`.
+fn connective_def_and_pos(
+    d: &mut ClauseDeriver,
+    (e, f, s): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+    clauses: [DerivedClause; 2],
+    pivot: &Rc<Term>,
+    forward: DerivedClause,
+) -> Option<DerivedClause> {
+    let (i1, i2) = match_term!((and i1 i2) = s).unwrap();
+    let not_s = d.not(s);
+    let and_pos1 = d.tautology("and_pos", vec![not_s.clone(), i1.clone()]);
+    let and_pos2 = d.tautology("and_pos", vec![not_s, i2.clone()]);
+    let equiv_neg2 = d.tautology("equiv_neg2", vec![e.clone(), f.clone(), s.clone()]);
+    let [first, second] = clauses;
+    d.resolve(
+        vec![first, second, and_pos1, and_pos2, equiv_neg2, forward],
+        &[pivot, i1, i2, s, f],
+    )
+}
+
+/// Derives the clause `(cl e)`, where `e` is `(= f s)`, `f` is `(xor p q)` and `s` is
+/// `(or (and (not p) q) (and p (not q)))`.
+fn connective_def_xor(
+    d: &mut ClauseDeriver,
+    (e, f, s): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+    (p, q): (&Rc<Term>, &Rc<Term>),
+) -> Option<DerivedClause> {
+    let (a, b) = match_term!((or a b) = s).unwrap();
+    let [not_f, not_s, not_a, not_b, not_p, not_q] = [f, s, a, b, p, q].map(|t| d.not(t));
+    let [not_not_p, not_not_q] = [&not_p, &not_q].map(|t| d.not(t));
+    let [triple_not_p, triple_not_q] = [&not_not_p, &not_not_q].map(|t| d.not(t));
+
+    // (cl e (not f))
+    let and_neg_a = d.tautology("and_neg", vec![a.clone(), not_not_p.clone(), not_q.clone()]);
+    let not_not_p_step = d.tautology("not_not", vec![triple_not_p, p.clone()]);
+    let xor_pos2 = d.tautology(
+        "xor_pos2",
+        vec![not_f.clone(), not_p.clone(), not_q.clone()],
+    );
+    let with_a = d.resolve(vec![and_neg_a, not_not_p_step, xor_pos2], &[&not_not_p, p])?;
+    let and_neg_b = d.tautology("and_neg", vec![b.clone(), not_p.clone(), not_not_q.clone()]);
+    let not_not_q_step = d.tautology("not_not", vec![triple_not_q, q.clone()]);
+    let xor_pos1 = d.tautology("xor_pos1", vec![not_f.clone(), p.clone(), q.clone()]);
+    let with_b = d.resolve(vec![and_neg_b, not_not_q_step, xor_pos1], &[&not_not_q, p])?;
+    let or_neg_a = d.tautology("or_neg", vec![s.clone(), not_a.clone()]);
+    let or_neg_b = d.tautology("or_neg", vec![s.clone(), not_b.clone()]);
+    let equiv_neg1 = d.tautology("equiv_neg1", vec![e.clone(), not_f, not_s.clone()]);
+    let forward = d.resolve(
+        vec![with_b, with_a, or_neg_a, or_neg_b, equiv_neg1],
+        &[q, a, b, s],
+    )?;
+
+    // (cl e)
+    let xor_neg1 = d.tautology("xor_neg1", vec![f.clone(), p.clone(), not_q.clone()]);
+    let and_pos_a1 = d.tautology("and_pos", vec![not_a.clone(), not_p.clone()]);
+    let and_pos_a2 = d.tautology("and_pos", vec![not_a, q.clone()]);
+    let from_a = d.resolve(vec![xor_neg1, and_pos_a1, and_pos_a2], &[p, q])?;
+    let xor_neg2 = d.tautology("xor_neg2", vec![f.clone(), not_p, q.clone()]);
+    let and_pos_b1 = d.tautology("and_pos", vec![not_b.clone(), p.clone()]);
+    let and_pos_b2 = d.tautology("and_pos", vec![not_b, not_q]);
+    let from_b = d.resolve(vec![xor_neg2, and_pos_b1, and_pos_b2], &[p, q])?;
+    let or_pos = d.tautology("or_pos", vec![not_s, a.clone(), b.clone()]);
+    let equiv_neg2 = d.tautology("equiv_neg2", vec![e.clone(), f.clone(), s.clone()]);
+    d.resolve(
+        vec![or_pos, from_a, from_b, equiv_neg2, forward],
+        &[a, b, s, f],
+    )
+}
+
+/// Derives the clause `(cl e)`, where `e` is `(= f s)`, `f` is `(= p q)` and `s` is
+/// `(and (=> p q) (=> q p))`.
+fn connective_def_equiv(
+    d: &mut ClauseDeriver,
+    (e, f, s): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+    (p, q): (&Rc<Term>, &Rc<Term>),
+) -> Option<DerivedClause> {
+    let (i1, i2) = match_term!((and i1 i2) = s).unwrap();
+    let [not_f, not_i1, not_i2, not_p, not_q] = [f, i1, i2, p, q].map(|t| d.not(t));
+
+    let equiv_pos2 = d.tautology("equiv_pos2", vec![not_f.clone(), not_p.clone(), q.clone()]);
+    let equiv_pos1 = d.tautology("equiv_pos1", vec![not_f, p.clone(), not_q.clone()]);
+    let forward = connective_def_and_neg(d, (e, f, s), [(equiv_pos2, p), (equiv_pos1, q)])?;
+
+    let equiv_neg2 = d.tautology("equiv_neg2", vec![f.clone(), p.clone(), q.clone()]);
+    let implies_pos2 = d.tautology("implies_pos", vec![not_i2, not_q.clone(), p.clone()]);
+    let with_p = d.resolve(vec![equiv_neg2, implies_pos2], &[q])?;
+    let equiv_neg1 = d.tautology("equiv_neg1", vec![f.clone(), not_p.clone(), not_q]);
+    let implies_pos1 = d.tautology("implies_pos", vec![not_i1, not_p, q.clone()]);
+    let with_not_p = d.resolve(vec![equiv_neg1, implies_pos1], &[q])?;
+    connective_def_and_pos(d, (e, f, s), [with_p, with_not_p], p, forward)
+}
+
+/// Derives the clause `(cl e)`, where `e` is `(= f s)`, `f` is `(ite c a b)` and `s` is
+/// `(and (=> c a) (=> (not c) b))`.
+fn connective_def_ite(
+    d: &mut ClauseDeriver,
+    (e, f, s): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+    (c, a, b): (&Rc<Term>, &Rc<Term>, &Rc<Term>),
+) -> Option<DerivedClause> {
+    let (i1, i2) = match_term!((and i1 i2) = s).unwrap();
+    let [not_f, not_i1, not_i2, not_c, not_a, not_b] = [f, i1, i2, c, a, b].map(|t| d.not(t));
+    let not_not_c = d.not(&not_c);
+
+    let ite_pos2 = d.tautology("ite_pos2", vec![not_f.clone(), not_c.clone(), a.clone()]);
+    let ite_pos1 = d.tautology("ite_pos1", vec![not_f, c.clone(), b.clone()]);
+    let forward = connective_def_and_neg(d, (e, f, s), [(ite_pos2, c), (ite_pos1, c)])?;
+
+    let implies_pos2 = d.tautology("implies_pos", vec![not_i2, not_not_c.clone(), b.clone()]);
+    let triple_not_c = d.not(&not_not_c);
+    let not_not = d.tautology("not_not", vec![triple_not_c, c.clone()]);
+    let ite_neg1 = d.tautology("ite_neg1", vec![f.clone(), c.clone(), not_b]);
+    let with_c = d.resolve(vec![implies_pos2, not_not, ite_neg1], &[&not_not_c, b])?;
+    let ite_neg2 = d.tautology("ite_neg2", vec![f.clone(), not_c.clone(), not_a]);
+    let implies_pos1 = d.tautology("implies_pos", vec![not_i1, not_c, a.clone()]);
+    let with_not_c = d.resolve(vec![ite_neg2, implies_pos1], &[a])?;
+    connective_def_and_pos(d, (e, f, s), [with_c, with_not_c], c, forward)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -1,52 +1,48 @@
 use super::{assert_clause_len, get_premise_term, CheckerError, Elaborator, RuleArgs, RuleResult};
 use crate::ast::*;
 
-/// Function to find a transitive chain given a conclusion equality and a series of premise
-/// equalities.
-fn find_chain(
-    conclusion: (&Rc<Term>, &Rc<Term>),
-    premises: &mut [(&Rc<Term>, &Rc<Term>)],
-) -> RuleResult {
-    // When the conclusion is of the form (= a a), it is trivially valid
-    if conclusion.0 == conclusion.1 {
-        return Ok(());
-    }
+/// Builds the chain of equalities in a clause of the form
+/// `(cl (not (= t_1 t_2)) ... (not (= t_{n-1} t_n)) (= t_1 t_n))`, like the conclusion of an
+/// `eq_transitive` step. The last literal is the conclusion of the chain, and the negated
+/// equalities are its links. The clause must not be empty.
+fn chain_from_clause(clause: &[Rc<Term>]) -> Result<EqualityChain<'_>, CheckerError> {
+    let (last, rest) = clause.split_last().unwrap();
+    Ok(EqualityChain {
+        conclusion: match_term_err!((= t u) = last)?,
+        links: rest
+            .iter()
+            .map(|term| match_term_err!((not (= t u)) = term))
+            .collect::<Result<_, _>>()?,
+    })
+}
 
-    // Find in the premises, if it exists, an equality such that one of its terms is equal to the
-    // first term in the conclusion. Possibly reorder this equality so the matching term is the
-    // first one
-    let (index, eq) = premises
-        .iter()
-        .enumerate()
-        .find_map(|(i, &(t, u))| {
-            if t == conclusion.0 {
-                Some((i, (t, u)))
-            } else if u == conclusion.0 {
-                Some((i, (u, t)))
-            } else {
-                None
-            }
-        })
-        .ok_or_else(|| {
-            let (a, b) = conclusion;
-            CheckerError::BrokenTransitivityChain(a.clone(), b.clone())
-        })?;
-
-    // We remove the found equality by swapping it with the first element in `premises`.  The new
-    // premises will then be all elements after the first
-    premises.swap(0, index);
-
-    // The new conclusion will be the terms in the conclusion and the found equality that didn't
-    // match. For example, if the conclusion was (= a d) and we found in the premises (= a b), the
-    // new conclusion will be (= b d)
-    find_chain((eq.1, conclusion.1), &mut premises[1..])
+/// Builds the chain formed by the equalities `links`, that should prove the equality `conclusion`,
+/// like in a `trans` step.
+fn chain_from_equalities<'a>(
+    conclusion: &'a Rc<Term>,
+    links: &[&'a Rc<Term>],
+) -> Result<EqualityChain<'a>, CheckerError> {
+    Ok(EqualityChain {
+        conclusion: match_term_err!((= t u) = conclusion)?,
+        links: links
+            .iter()
+            .map(|&term| match_term_err!((= t u) = term))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Checks that the conclusion of the chain can be derived by transitivity from its links.
+fn check_chain(chain: &EqualityChain) -> RuleResult {
+    chain
+        .check()
+        .map_err(|(a, b)| CheckerError::BrokenTransitivityChain(a.clone(), b.clone()))
 }
 
-/// Similar to `find_chain`, but reorders a premises vector to match the found chain. In `trans`,
-/// this is used to reorder the step premises vector; in `eq_transitive`, it is used to reorder the
-/// clause. This returns a boolean indicating whether any reordering was needed, a `usize`
-/// indicating how many premises are needed to prove the conclusion, and a vector of indices of the
-/// premise equalities that need to be flipped.
+/// Finds a transitive chain like [`EqualityChain::check`], but reorders a premises vector to match
+/// the found chain. In `trans`, this is used to reorder the step premises vector; in
+/// `eq_transitive`, it is used to reorder the clause. This returns a boolean indicating whether any
+/// reordering was needed, a `usize` indicating how many premises are needed to prove the
+/// conclusion, and a vector of indices of the premise equalities that need to be flipped.
 fn find_and_trace_chain<'a, T>(
     mut conclusion: (&'a Rc<Term>, &'a Rc<Term>),
     premise_equalities: &mut [(&'a Rc<Term>, &'a Rc<Term>)],
@@ -92,17 +88,9 @@ pub fn eq_transitive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3..)?;
 
     // The last term in the conclusion clause should be an equality, and it will be the conclusion
-    // of the transitive chain
-    let chain_conclusion = match_term_err!((= t u) = conclusion.last().unwrap())?;
-
-    // The first `conclusion.len()` - 1 terms in the conclusion clause must be a sequence of
-    // inequalities, and they will be the premises of the transitive chain
-    let mut premises: Vec<_> = conclusion[..conclusion.len() - 1]
-        .iter()
-        .map(|term| match_term_err!((not (= t u)) = term))
-        .collect::<Result<_, _>>()?;
-
-    find_chain(chain_conclusion, &mut premises)
+    // of the transitive chain. The other terms must be a sequence of inequalities, and they will be
+    // the links of the chain
+    check_chain(&chain_from_clause(conclusion)?)
 }
 
 pub fn elaborate_eq_transitive(
@@ -113,16 +101,10 @@ pub fn elaborate_eq_transitive(
     assert_clause_len(conclusion, 3..)?;
     let n = conclusion.len();
 
-    // The last term in the conclusion clause should be an equality, and it will be the conclusion
-    // of the transitive chain
-    let conclusion_equality = match_term_err!((= t u) = conclusion.last().unwrap())?;
-
-    // The first `conclusion.len()` - 1 terms in the conclusion clause must be a sequence of
-    // inequalities, and they will be the premises of the transitive chain
-    let mut premise_equalities: Vec<_> = conclusion[..n - 1]
-        .iter()
-        .map(|term| match_term_err!((not (= t u)) = term))
-        .collect::<Result<_, _>>()?;
+    let EqualityChain {
+        conclusion: conclusion_equality,
+        links: mut premise_equalities,
+    } = chain_from_clause(conclusion)?;
 
     let mut new_clause: Vec<_> = conclusion.to_vec();
     let (needs_reordering, num_needed, should_flip) = find_and_trace_chain(
@@ -160,6 +142,7 @@ pub fn elaborate_eq_transitive(
         premises: Vec::new(),
         args: Vec::new(),
         discharge: Vec::new(),
+        source_position: None,
     };
     let new_eq_transitive_step = elaborator.add_new_step(new_eq_transitive_step);
     let mut latest_step_index = new_eq_transitive_step;
@@ -188,6 +171,7 @@ pub fn elaborate_eq_transitive(
             premises: vec![latest_step_index],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: None,
         };
         latest_step_index = elaborator.add_new_step(or_intro_step);
     }
@@ -199,6 +183,7 @@ pub fn elaborate_eq_transitive(
         premises: vec![latest_step_index],
         args: Vec::new(),
         discharge: Vec::new(),
+        source_position: None,
     });
     Ok(())
 }
@@ -225,6 +210,7 @@ fn flip_eq_transitive_premises(
                 premises: Vec::new(),
                 args: Vec::new(),
                 discharge: Vec::new(),
+                source_position: None,
             };
             (elaborator.add_new_step(new_step), pivot, to_introduce)
         })
@@ -266,6 +252,7 @@ fn flip_eq_transitive_premises(
         premises,
         args,
         discharge: Vec::new(),
+        source_position: None,
     };
     (clause, elaborator.add_new_step(final_step))
 }
@@ -273,13 +260,11 @@ fn flip_eq_transitive_premises(
 pub fn trans(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
-    let conclusion = match_term_err!((= t u) = &conclusion[0])?;
-    let mut premises: Vec<_> = premises
+    let premises: Vec<_> = premises
         .iter()
-        .map(|premise| match_term_err!((= t u) = get_premise_term(premise)?))
+        .map(get_premise_term)
         .collect::<Result<_, _>>()?;
-
-    find_chain(conclusion, &mut premises)
+    check_chain(&chain_from_equalities(&conclusion[0], &premises)?)
 }
 
 pub fn elaborate_trans(
@@ -289,11 +274,14 @@ pub fn elaborate_trans(
 ) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
-    let conclusion_equality = match_term_err!((= t u) = &conclusion[0])?;
-    let mut premise_equalities: Vec<_> = premises
+    let premise_terms: Vec<_> = premises
         .iter()
-        .map(|premise| match_term_err!((= t u) = get_premise_term(premise)?))
+        .map(get_premise_term)
         .collect::<Result<_, _>>()?;
+    let EqualityChain {
+        conclusion: conclusion_equality,
+        links: mut premise_equalities,
+    } = chain_from_equalities(&conclusion[0], &premise_terms)?;
 
     let mut new_premises: Vec<_> = premises.iter().map(|p| p.index).collect();
     let (_, num_needed, should_flip) = find_and_trace_chain(
@@ -339,6 +327,7 @@ pub fn elaborate_trans(
         premises: new_premises,
         args: Vec::new(),
         discharge: Vec::new(),
+        source_position: None,
     });
     Ok(())
 }
@@ -0,0 +1,106 @@
+use super::*;
+use crate::ast::printer::write_proof_starting_at;
+use std::io;
+
+/// A proof checker that elaborates a proof one command at a time, writing the elaborated commands
+/// as soon as each command is checked.
+///
+/// Unlike [`ProofChecker::check_and_elaborate`], this doesn't need to hold the entire proof in
+/// memory. For each command in the root proof that was already received or written, only a summary
+/// with its id and conclusion is kept, which is all that is needed to check later steps that use it
+/// as a premise. Since it is not known whether a command will be used by later steps, the
+/// elaborated proof is not pruned, and it is written without term sharing.
+pub struct StreamingElaborator<'c> {
+    checker: ProofChecker<'c>,
+    premises: AHashSet<Rc<Term>>,
+
+    /// The commands received so far, all but the last of which are replaced by their summaries.
+    received: Vec<ProofCommand>,
+
+    /// The summaries of the commands written so far, in the elaborated proof.
+    written: Vec<ProofCommand>,
+}
+
+/// Returns a command with the same id and clause as `command`, but without any of the other
+/// information, like the rule and premises of a step, or the commands inside a subproof. Subproofs
+/// are summarized by their end step, which has the same id and clause as them.
+fn summarize(command: &ProofCommand) -> ProofCommand {
+    match command {
+        ProofCommand::Assume { .. } => command.clone(),
+        _ => ProofCommand::Step(ProofStep {
+            id: command.id().to_owned(),
+            clause: command.clause().to_vec(),
+            rule: String::new(),
+            premises: Vec::new(),
+            args: Vec::new(),
+            discharge: Vec::new(),
+            source_position: None,
+        }),
+    }
+}
+
+impl<'c> StreamingElaborator<'c> {
+    /// Constructs a new `StreamingElaborator`. The `premises` are the terms introduced by the
+    /// `assert` commands in the original problem.
+    pub fn new(
+        pool: &'c mut TermPool,
+        config: Config<'c>,
+        prelude: ProblemPrelude,
+        premises: AHashSet<Rc<Term>>,
+    ) -> Self {
+        let mut checker = ProofChecker::new(pool, config, prelude);
+        checker.elaborator = Some(Elaborator::new());
+        Self {
+            checker,
+            premises,
+            received: Vec::new(),
+            written: Vec::new(),
+        }
+    }
+
+    /// Checks and elaborates a command, and writes the resulting commands to `out`. The command
+    /// must be in the root proof, so subproofs must be given as a single `ProofCommand::Subproof`,
+    /// like the commands returned by [`Parser::next_command`](crate::parser::Parser::next_command).
+    /// Premises are indexed the same way as in a regular proof.
+    ///
+    /// If the command is invalid, an error is returned, and the elaborator should not be used
+    /// anymore.
+    pub fn push_command(
+        &mut self,
+        command: ProofCommand,
+        out: &mut dyn io::Write,
+    ) -> CarcaraResult<()> {
+        let index = self.received.len();
+        self.received.push(command);
+        let iter = ProofIter::starting_at(&self.received, index);
+        self.checker.check_commands(iter, &self.premises, None)?;
+
+        let summary = summarize(&self.received[index]);
+        let command = std::mem::replace(&mut self.received[index], summary);
+        let elaborator = self.checker.elaborator.as_mut().unwrap();
+        let elaborated = elaborator.end_command(command, index);
+
+        let start = self.written.len();
+        self.written.extend(elaborated);
+        write_proof_starting_at(out, &self.written, start)?;
+        for command in &mut self.written[start..] {
+            *command = summarize(command);
+        }
+        Ok(())
+    }
+
+    /// Returns the term pool used by the checker.
+    pub fn pool(&mut self) -> &mut TermPool {
+        self.checker.pool
+    }
+
+    /// Finishes elaborating the proof. Returns an error if the commands received do not reach the
+    /// empty clause. Otherwise, returns `true` if the proof is holey.
+    pub fn finish(self) -> CarcaraResult<bool> {
+        if self.checker.config.is_running_test || self.checker.reached_empty_clause {
+            Ok(self.checker.is_holey)
+        } else {
+            Err(Error::DoesNotReachEmptyClause)
+        }
+    }
+}
@@ -0,0 +1,1514 @@
+use super::*;
+use crate::parser::parse_instance;
+use proptest::strategy::{Just, Strategy};
+
+const PROBLEM: &str = "
+    (declare-fun p () Bool)
+    (declare-fun q () Bool)
+    (assert (or p q))
+    (assert (not p))
+    (assert (not q))
+";
+
+const PROOF: &str = "
+    (assume h1 (or p q))
+    (assume h2 (not p))
+    (assume h3 (not q))
+    (step t4 (cl p q) :rule or :premises (h1))
+    (step t5 (cl q) :rule resolution :premises (t4 h2))
+    (step t6 (cl) :rule resolution :premises (t5 h3))
+";
+
+fn check_with_max_steps(proof: &str, max_steps: Option<usize>) -> CarcaraResult<bool> {
+    let (prelude, proof, mut pool) =
+        parse_instance(PROBLEM.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let config = Config::new().max_steps(max_steps);
+    ProofChecker::new(&mut pool, config, prelude).check(&proof)
+}
+
+#[test]
+fn test_max_steps() {
+    assert!(matches!(check_with_max_steps(PROOF, None), Ok(false)));
+    assert!(matches!(check_with_max_steps(PROOF, Some(6)), Ok(false)));
+    assert!(matches!(check_with_max_steps(PROOF, Some(100)), Ok(false)));
+
+    // The proof without its last step does not reach the empty clause
+    let incomplete = PROOF.rsplit_once("(step t6").unwrap().0;
+    assert!(matches!(
+        check_with_max_steps(incomplete, None),
+        Err(Error::DoesNotReachEmptyClause)
+    ));
+    assert!(matches!(
+        check_with_max_steps(incomplete, Some(5)),
+        Ok(false)
+    ));
+    assert!(matches!(check_with_max_steps(PROOF, Some(4)), Ok(false)));
+    assert!(matches!(check_with_max_steps(PROOF, Some(0)), Ok(false)));
+
+    // Errors in the steps that were checked are still reported, but errors in later steps are not
+    let invalid = PROOF.replace("(step t5 (cl q)", "(step t5 (cl p)");
+    assert!(matches!(
+        check_with_max_steps(&invalid, Some(5)),
+        Err(Error::Checker { step, .. }) if step == "t5"
+    ));
+    assert!(matches!(check_with_max_steps(&invalid, Some(4)), Ok(false)));
+}
+
+#[test]
+fn test_deep_eq_depth_limit() {
+    // The two sides of the equality only differ by the order of the innermost equality, so `refl`
+    // needs to compare them all the way down
+    let nested = |inner: &str| "(not ".repeat(100) + inner + &")".repeat(100);
+    let check = |left: &str, right: &str, limit| {
+        let proof = format!("(step t1 (cl (= {} {})) :rule refl)", left, right);
+        let (prelude, proof, mut pool) =
+            parse_instance(PROBLEM.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let config = Config::new().max_steps(Some(1)).deep_eq_depth_limit(limit);
+        ProofChecker::new(&mut pool, config, prelude).check(&proof)
+    };
+
+    let (left, right) = (nested("(= p q)"), nested("(= q p)"));
+    assert!(matches!(check(&left, &right, None), Ok(false)));
+    assert!(matches!(check(&left, &right, Some(200)), Ok(false)));
+    assert!(matches!(
+        check(&left, &right, Some(50)),
+        Err(Error::Checker { step, .. }) if step == "t1"
+    ));
+
+    // Terms that are alpha-equivalent without reordering equalities are also subject to the limit
+    let left = format!("(forall ((x Bool)) {})", nested("x"));
+    let right = format!("(forall ((y Bool)) {})", nested("y"));
+    assert!(matches!(check(&left, &right, None), Ok(false)));
+    assert!(matches!(check(&left, &right, Some(200)), Ok(false)));
+    assert!(matches!(
+        check(&left, &right, Some(50)),
+        Err(Error::Checker { step, .. }) if step == "t1"
+    ));
+}
+
+#[test]
+fn test_elaboration_statistics() {
+    use crate::benchmarking::OnlineBenchmarkResults;
+
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (assert (= a b))
+        (assert (not (= a b)))
+    ";
+    // The first `assume` only matches its premise modulo reordering of equalities, so it needs to
+    // be elaborated
+    let proof = "
+        (assume h1 (= b a))
+        (assume h2 (not (= a b)))
+        (step t3 (cl (= a b)) :rule symm :premises (h1))
+        (step t4 (cl) :rule resolution :premises (t3 h2))
+    ";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+
+    let mut results = OnlineBenchmarkResults::new();
+    let [mut elaboration, mut deep_eq, mut assume, mut assume_core] = [Duration::ZERO; 4];
+    let mut deep_eq_by_rule = AHashMap::new();
+    let mut rule_histogram = AHashMap::new();
+    let config = Config::new().statistics(CheckerStatistics {
+        file_name: "test",
+        elaboration_time: &mut elaboration,
+        deep_eq_time: &mut deep_eq,
+        deep_eq_time_by_rule: &mut deep_eq_by_rule,
+        rule_histogram: &mut rule_histogram,
+        assume_time: &mut assume,
+        assume_core_time: &mut assume_core,
+        results: &mut results,
+    });
+    ProofChecker::new(&mut pool, config, prelude)
+        .check_and_elaborate(proof)
+        .unwrap();
+
+    assert!(elaboration > Duration::ZERO);
+    assert!(deep_eq_by_rule.contains_key("assume"));
+    assert_eq!(rule_histogram.len(), 2);
+    assert_eq!(rule_histogram["symm"], 1);
+    assert_eq!(rule_histogram["resolution"], 1);
+}
+
+#[test]
+fn test_get_rule_metadata() {
+    let (_, metadata) = ProofChecker::get_rule("th_resolution", true).unwrap();
+    assert_eq!(
+        metadata,
+        RuleMetadata {
+            name: "th_resolution",
+            category: RuleCategory::Propositional,
+            is_eliminable: false,
+            has_strict_version: true,
+        }
+    );
+    assert!(
+        ProofChecker::get_rule("la_disequality", false)
+            .unwrap()
+            .1
+            .is_eliminable
+    );
+
+    let (_, metadata) = ProofChecker::get_rule("comp_simplify", false).unwrap();
+    assert_eq!(metadata.category, RuleCategory::Arithmetic);
+    assert!(!metadata.is_eliminable);
+    assert_eq!(
+        ProofChecker::get_rule("sko_ex", false).unwrap().1.category,
+        RuleCategory::Subproof
+    );
+    assert_eq!(
+        ProofChecker::get_rule("hole", false).unwrap().1.category,
+        RuleCategory::Hole
+    );
+    assert!(ProofChecker::get_rule("foo", false).is_none());
+
+    for &name in RULE_NAMES {
+        for strict in [false, true] {
+            let (_, metadata) = ProofChecker::get_rule(name, strict).unwrap();
+            assert_eq!(metadata.name, name);
+        }
+    }
+
+    let all_rules = super::list_all_rules();
+    assert_eq!(all_rules.len(), RULE_NAMES.len());
+    let strict: Vec<_> = all_rules
+        .iter()
+        .filter(|r| r.has_strict_version)
+        .map(|r| r.name)
+        .collect();
+    assert_eq!(strict, ["resolution", "th_resolution", "refl"]);
+    let holes: Vec<_> = all_rules
+        .iter()
+        .filter(|r| r.is_hole())
+        .map(|r| r.name)
+        .collect();
+    assert_eq!(holes, ["hole"]);
+}
+
+#[test]
+fn test_checker_error_position() {
+    let invalid = PROOF.replace("(step t5 (cl q)", "(step t5 (cl p)");
+    let err = check_with_max_steps(&invalid, None).unwrap_err();
+    assert!(matches!(
+        &err,
+        Error::Checker { position: Some(pos), .. } if (pos.line, pos.column) == (6, 5)
+    ));
+    assert!(err.to_string().contains("(on line 6, column 5)"));
+
+    // The checker doesn't know the file name, which is only added by the functions that parse the
+    // proof
+    let err = crate::check(
+        PROBLEM.as_bytes(),
+        invalid.as_bytes(),
+        crate::CarcaraOptions::default(),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("(in <proof>, on line 6, column 5)"));
+
+    // `assume` commands have no position
+    let invalid = PROOF.replace("(assume h3 (not q))", "(assume h3 q)");
+    assert!(matches!(
+        check_with_max_steps(&invalid, None),
+        Err(Error::Checker { position: None, .. })
+    ));
+}
+
+#[test]
+fn test_error_context() {
+    let invalid = PROOF.replace(
+        "(step t5 (cl q) :rule resolution :premises (t4 h2))",
+        "(step t5 (cl q) :rule resolution :premises (t4 h2) :args (q true))",
+    );
+    let Err(Error::Checker { inner, .. }) = check_with_max_steps(&invalid, None) else {
+        panic!("expected checker error");
+    };
+    assert!(matches!(inner, CheckerError::WithContext { .. }));
+    assert_eq!(inner.error_code(), "E_RESOLUTION");
+    assert!(inner
+        .to_string()
+        .starts_with("while checking pivot at position 0: "));
+}
+
+#[test]
+fn test_sort_mismatch_error() {
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+    ";
+    let proof = "(step t1 (cl (not (= p q)) (not (= q p))) :rule la_generic :args (1.0 1.0))";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let result = ProofChecker::new(&mut pool, Config::new(), prelude).check_fragment(&proof);
+    let Err(Error::Checker { inner, rule, .. }) = result else {
+        panic!("expected checker error, got {:?}", result);
+    };
+    assert_eq!(rule, "la_generic");
+    assert!(matches!(inner, CheckerError::SortMismatch { .. }));
+    assert_eq!(inner.error_code(), "E_SORT_MISMATCH");
+    assert_eq!(inner.to_string(), "expected sort 'Real', got 'Bool'");
+}
+
+#[test]
+fn test_clone_errors() {
+    use std::io;
+
+    let invalid = PROOF.replace("(step t5 (cl q)", "(step t5 (cl p)");
+    let errors = vec![
+        check_with_max_steps(&invalid, None).unwrap_err(),
+        Error::Io(io::Error::new(io::ErrorKind::NotFound, "no such file")),
+        Error::Checker {
+            inner: error::LiaGenericError::Cvc5Timeout.into(),
+            rule: "lia_generic".into(),
+            step: "t1".into(),
+            position: None,
+        },
+    ];
+    let cloned = errors.clone();
+    for (original, clone) in errors.iter().zip(&cloned) {
+        assert_eq!(original.to_string(), clone.to_string());
+    }
+    let Error::Io(e) = &cloned[1] else { panic!() };
+    assert_eq!(e.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_incremental_checker() {
+    let problem = "
+        (declare-fun p (Int) Bool)
+        (assert (forall ((x Int)) (p x)))
+        (assert (not (forall ((y Int)) (p y))))
+    ";
+    let proof = "
+        (assume h1 (forall ((x Int)) (p x)))
+        (assume h2 (not (forall ((y Int)) (p y))))
+        (anchor :step t3 :args ((y Int) (:= x y)))
+        (step t3.t1 (cl (= x y)) :rule refl)
+        (step t3.t2 (cl (= (p x) (p y))) :rule cong :premises (t3.t1))
+        (step t3 (cl (= (forall ((x Int)) (p x)) (forall ((y Int)) (p y)))) :rule bind)
+        (step t4 (cl (not (forall ((x Int)) (p x))) (forall ((y Int)) (p y)))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2))
+    ";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let mut checker =
+        IncrementalProofChecker::new(&mut pool, Config::new(), prelude, proof.premises.clone());
+
+    let [h1, h2, t3, t4, t5] = proof.commands.as_slice() else {
+        panic!()
+    };
+    checker.push_step(h1).unwrap();
+    checker.push_step(h2).unwrap();
+
+    // An invalid command is not added to the proof, and doesn't prevent checking from continuing
+    let ProofCommand::Subproof(subproof) = t3 else {
+        panic!()
+    };
+    let mut invalid_subproof = subproof.clone();
+    invalid_subproof.commands.remove(1);
+    assert!(checker
+        .push_step(&ProofCommand::Subproof(invalid_subproof))
+        .is_err());
+    assert_eq!(checker.proof().commands.len(), 2);
+
+    checker.push_step(t3).unwrap();
+    checker.push_step(t4).unwrap();
+    assert!(!checker.is_complete());
+    checker.push_step(t5).unwrap();
+    assert!(checker.is_complete());
+    assert_eq!(proof.commands, checker.proof().commands);
+
+    let (is_holey, elaborated) = checker.elaborate().unwrap();
+    assert!(!is_holey);
+    assert_eq!(elaborated.commands.len(), proof.commands.len());
+}
+
+#[test]
+fn test_lia_generic_with_coefficients() {
+    let problem = "
+        (declare-fun x () Int)
+        (assert (> x 0))
+        (assert (< x 0))
+    ";
+    let check = |proof: &str| {
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        ProofChecker::new(&mut pool, Config::new(), prelude).check(&proof)
+    };
+    let proof = "
+        (assume h1 (> x 0))
+        (assume h2 (< x 0))
+        (step t3 (cl (not (> x 0)) (not (< x 0))) :rule lia_generic ARGS)
+        (step t4 (cl) :rule resolution :premises (t3 h1 h2))
+    ";
+
+    // With Farkas coefficients, the step is checked internally and the proof is not holey
+    assert!(matches!(
+        check(&proof.replace("ARGS", ":args (1 1)")),
+        Ok(false)
+    ));
+    assert!(matches!(
+        check(&proof.replace("ARGS", ":args (1 0)")),
+        Err(Error::Checker { step, .. }) if step == "t3"
+    ));
+
+    // Without them, the step is ignored, and the proof is holey
+    assert!(matches!(check(&proof.replace("ARGS", "")), Ok(true)));
+}
+
+#[test]
+fn test_lia_generic_certificate() {
+    use rug::Rational;
+
+    // A proof printed by cvc5 whose only theory lemma is a `la_generic` step
+    let output = "
+        (assume a0 (not (not (> x 0))))
+        (assume a1 (not (not (< (* 2 x) 0))))
+        (step t1 (cl (not (> x 0)) (not (< (* 2 x) 0))) :rule la_generic :args (1 (/ 1 2)))
+        (step t2 (cl) :rule resolution :premises (t1 a0 a1) :args ((> x 0) true (< (* 2 x) 0) true))
+    ";
+    let certificate = lia_generic::extract_certificate(output).unwrap();
+    assert_eq!(certificate, [Rational::from(1), Rational::from((1, 2))]);
+
+    let extract = |args: &str| {
+        let output = format!("(step t1 (cl) :rule la_generic {})", args);
+        lia_generic::extract_certificate(&output)
+    };
+    assert_eq!(
+        extract(":args ((- 3) 2.5 (/ (- 1) 4))"),
+        Some(vec![
+            Rational::from(-3),
+            Rational::from((5, 2)),
+            Rational::from((-1, 4)),
+        ])
+    );
+    assert_eq!(extract(":premises (a0 a1) :args (1)"), Some(vec![1.into()]));
+    assert_eq!(extract(""), None);
+    assert_eq!(extract(":args (1 (/ 1 0))"), None);
+    assert_eq!(extract(":args (1 x)"), None);
+    assert_eq!(lia_generic::extract_certificate("unknown"), None);
+    let two_steps = format!("{} (step t3 (cl) :rule la_generic :args (1))", output);
+    assert_eq!(lia_generic::extract_certificate(&two_steps), None);
+
+    // The certificate, embedded as the arguments of a `la_generic` step, is accepted by the
+    // internal checker
+    let problem = "
+        (declare-fun x () Int)
+        (assert (> x 0))
+        (assert (< (* 2 x) 0))
+    ";
+    let proof = "
+        (assume h1 (> x 0))
+        (assume h2 (< (* 2 x) 0))
+        (step t3 (cl (not (> x 0)) (not (< (* 2 x) 0))) :rule la_generic :args ARGS)
+        (step t4 (cl) :rule resolution :premises (t3 h1 h2))
+    ";
+    let mut pool = TermPool::new();
+    let args = lia_generic::certificate_args(&mut pool, &certificate)
+        .iter()
+        .map(|a| a.as_term().unwrap().to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(args, "1 (/ 1.0 2.0)");
+    let proof = proof.replace("ARGS", &format!("({})", args));
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let result = ProofChecker::new(&mut pool, Config::new(), prelude).check(&proof);
+    assert!(matches!(result, Ok(false)));
+}
+
+#[test]
+fn test_th_lemma_holes() {
+    let problem = "
+        (declare-fun x () Real)
+        (assert (> x 0.0))
+        (assert (< x 0.0))
+    ";
+    let check = |proof: &str| {
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        ProofChecker::new(&mut pool, Config::new(), prelude).check(&proof)
+    };
+    let proof = "
+        (assume h1 (> x 0.0))
+        (assume h2 (< x 0.0))
+        (step t3 (cl (not (> x 0.0)) (not (< x 0.0))) :rule th_lemma :args (ARGS))
+        (step t4 (cl) :rule resolution :premises (t3 h1 h2))
+    ";
+
+    assert!(matches!(
+        check(&proof.replace("ARGS", "\"lra\" 1.0 1.0")),
+        Ok(false)
+    ));
+    assert!(matches!(
+        check(&proof.replace("ARGS", "\"arrays\"")),
+        Ok(true)
+    ));
+}
+
+#[test]
+fn test_sym_bp_is_hole() {
+    let problem = "
+        (declare-sort U 0)
+        (declare-fun a () U)
+        (declare-fun b () U)
+        (declare-fun lt (U U) Bool)
+        (assert (not (lt a b)))
+    ";
+    let proof = "
+        (assume h1 (not (lt a b)))
+        (step t2 (cl (lt a b)) :rule sym_bp :args (a b lt))
+        (step t3 (cl) :rule resolution :premises (h1 t2))
+    ";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let result = ProofChecker::new(&mut pool, Config::new(), prelude).check(&proof);
+    assert!(matches!(result, Ok(true)));
+}
+
+#[test]
+fn test_timeout() {
+    use std::{io, thread, time::Duration};
+
+    // A reader that takes a while before returning any data, so the timeout is guaranteed to
+    // elapse before the checker starts
+    struct SlowReader<'a>(&'a [u8], bool);
+
+    impl io::Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if !self.1 {
+                thread::sleep(Duration::from_millis(100));
+                self.1 = true;
+            }
+            self.0.read(buf)
+        }
+    }
+
+    let check = |timeout| {
+        let problem = io::BufReader::new(SlowReader(PROBLEM.as_bytes(), false));
+        let proof = io::BufReader::new(SlowReader(PROOF.as_bytes(), false));
+        crate::check(
+            problem,
+            proof,
+            crate::CarcaraOptions::new().with_timeout(timeout),
+        )
+    };
+    assert!(matches!(
+        check(Duration::from_millis(1)),
+        Err(Error::Timeout)
+    ));
+    assert!(matches!(check(Duration::from_secs(60)), Ok(false)));
+
+    let (prelude, proof, mut pool) =
+        parse_instance(PROBLEM.as_bytes(), PROOF.as_bytes(), true, false, false).unwrap();
+    let config = Config::new().abort_flag(Some(Arc::new(AtomicBool::new(true))));
+    let result = ProofChecker::new(&mut pool, config, prelude).check(&proof);
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[test]
+fn test_check_many() {
+    let invalid = "
+        (assume h1 (or p q))
+        (step t2 (cl p) :rule or :premises (h1))
+    ";
+    let unknown_symbol = "(assume h1 (or p r))";
+    let proofs = [PROOF, invalid, PROOF, unknown_symbol];
+    let options = crate::CarcaraOptions::new();
+    let results: Vec<_> = crate::check_many(
+        PROBLEM.as_bytes(),
+        proofs.iter().map(|p| p.as_bytes()),
+        &options,
+    )
+    .collect();
+    assert_eq!(results.len(), 4);
+    assert!(matches!(results[0], Ok(false)));
+    assert!(matches!(&results[1], Err(Error::Checker { step, .. }) if step == "t2"));
+    assert!(matches!(results[2], Ok(false)));
+    assert!(matches!(results[3], Err(Error::Parser(..))));
+
+    let results: Vec<_> =
+        crate::check_many("(assert".as_bytes(), [PROOF.as_bytes()], &options).collect();
+    assert!(matches!(results.as_slice(), [Err(Error::Parser(..))]));
+}
+
+#[test]
+fn test_check_from_paths() {
+    let dir = std::env::temp_dir().join(format!("carcara-from-paths-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let problem_path = dir.join("problem.smt2");
+    let proof_path = dir.join("proof.alethe");
+    std::fs::write(&problem_path, PROBLEM).unwrap();
+    std::fs::write(&proof_path, PROOF).unwrap();
+
+    let options = crate::CarcaraOptions::new();
+    let checked = crate::check_from_paths(&problem_path, &proof_path, crate::CarcaraOptions::new());
+    let elaborated = crate::check_and_elaborate_from_paths(
+        &problem_path,
+        &proof_path,
+        crate::CarcaraOptions::new(),
+    );
+    let missing = dir.join("missing.alethe");
+    let error = crate::check_from_paths(&problem_path, &missing, crate::CarcaraOptions::new());
+
+    // Errors found while reading the file, like invalid UTF-8, also mention its path
+    let invalid_path = dir.join("invalid.alethe");
+    std::fs::write(&invalid_path, b"(assume h1 (or p q))\n(assume h2 \xff)\n").unwrap();
+    let invalid = crate::check_from_paths(&problem_path, &invalid_path, options);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(checked, Ok(false)));
+    let (is_holey, proof, _) = elaborated.unwrap();
+    assert!(!is_holey);
+    assert!(!proof.commands.is_empty());
+    match error {
+        Err(Error::Io(e)) => assert!(e.to_string().contains(&missing.display().to_string())),
+        other => panic!("expected IO error, got {:?}", other.map(|_| ())),
+    }
+    match invalid {
+        Err(Error::Io(e)) => assert!(e.to_string().contains(&invalid_path.display().to_string())),
+        other => panic!("expected IO error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_elaborate_streaming() {
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun p (Int) Bool)
+        (assert (= a b))
+        (assert (not (= (p a) (p b))))
+    ";
+    // The first `assume` only matches its premise modulo reordering of equalities, and the `trans`
+    // step needs to be reordered, so both are elaborated. The subproof uses a premise from the
+    // root proof, and is used as a premise by a later step
+    let proof = "
+        (assume h1 (= b a))
+        (assume h2 (not (= (p a) (p b))))
+        (step t3 (cl (= a b)) :rule symm :premises (h1))
+        (step t4 (cl (= b b)) :rule refl)
+        (step t5 (cl (= a b)) :rule trans :premises (t4 t3))
+        (anchor :step t6)
+        (assume t6.a0 (= a b))
+        (step t6.t1 (cl (= (p a) (p b))) :rule cong :premises (t6.a0))
+        (step t6 (cl (not (= a b)) (= (p a) (p b))) :rule subproof :discharge (t6.a0))
+        (step t7 (cl (= (p a) (p b))) :rule resolution :premises (t6 t5))
+        (step t8 (cl) :rule resolution :premises (t7 h2))
+    ";
+    let options = crate::CarcaraOptions::new();
+    let mut output = Vec::new();
+    let result =
+        crate::elaborate_streaming(problem.as_bytes(), proof.as_bytes(), &mut output, options);
+    assert!(matches!(result, Ok(false)));
+
+    // The output must be a valid proof, that can be checked in strict mode
+    let (prelude, elaborated, mut pool) =
+        parse_instance(problem.as_bytes(), output.as_slice(), true, false, false).unwrap();
+    let result = ProofChecker::new_strict(&mut pool, prelude).check(&elaborated);
+    assert!(matches!(result, Ok(false)));
+
+    // Errors are reported like when checking the proof normally
+    let invalid = proof.replace("(step t3 (cl (= a b))", "(step t3 (cl (= b b))");
+    let result = crate::elaborate_streaming(
+        problem.as_bytes(),
+        invalid.as_bytes(),
+        &mut Vec::new(),
+        crate::CarcaraOptions::new(),
+    );
+    assert!(matches!(result, Err(Error::Checker { step, .. }) if step == "t3"));
+
+    let incomplete = proof.rsplit_once("(step t8").unwrap().0;
+    let result = crate::elaborate_streaming(
+        problem.as_bytes(),
+        incomplete.as_bytes(),
+        &mut Vec::new(),
+        crate::CarcaraOptions::new(),
+    );
+    assert!(matches!(result, Err(Error::DoesNotReachEmptyClause)));
+
+    // The maximum term depth is applied when parsing the proof
+    let deep = proof.replace(
+        "(assume h1 (= b a))",
+        "(assume h1 (= b (+ (+ (+ a 0) 0) 0)))",
+    );
+    let options = crate::CarcaraOptions {
+        max_term_depth: Some(3),
+        ..crate::CarcaraOptions::new()
+    };
+    let result = crate::elaborate_streaming(
+        problem.as_bytes(),
+        deep.as_bytes(),
+        &mut Vec::new(),
+        options,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Parser(crate::parser::ParserError::TermTooDeep(3), _)),
+    ));
+}
+
+#[test]
+fn test_onepoint_flipped_equality() {
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun p () Bool)
+        (assert (forall ((x Int)) (=> (= x a) p)))
+        (assert (not (=> (= a a) p)))
+    ";
+
+    // The equality in the last step of the subproof is flipped in relation to the `onepoint`
+    // conclusion, which is accepted both in strict mode and outside of it
+    let proof = "
+        (assume h1 (forall ((x Int)) (=> (= x a) p)))
+        (assume h2 (not (=> (= a a) p)))
+        (anchor :step t3 :args ((:= x a)))
+        (step t3.t1 (cl (= (=> (= a a) p) (=> (= x a) p))) :rule refl)
+        (step t3 (cl (= (forall ((x Int)) (=> (= x a) p)) (=> (= a a) p))) :rule onepoint)
+        (step t4 (cl (not (forall ((x Int)) (=> (= x a) p))) (=> (= a a) p))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl (=> (= a a) p)) :rule resolution :premises (h1 t4)
+            :args ((forall ((x Int)) (=> (= x a) p)) true))
+        (step t6 (cl) :rule resolution :premises (t5 h2) :args ((=> (= a a) p) true))
+    ";
+    let (prelude, proof, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+
+    let result = ProofChecker::new_strict(&mut pool, prelude.clone()).check(&proof);
+    assert!(matches!(result, Ok(false)));
+
+    // `onepoint` steps are not elaborated
+    let (_, elaborated) = ProofChecker::new(&mut pool, Config::new(), prelude)
+        .check_and_elaborate(proof)
+        .unwrap();
+    let ProofCommand::Subproof(subproof) = &elaborated.commands[2] else {
+        panic!("expected subproof");
+    };
+    let rules: Vec<_> = subproof
+        .commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Step(s) => s.rule.as_str(),
+            _ => "",
+        })
+        .collect();
+    assert_eq!(rules, ["refl", "onepoint"]);
+}
+
+/// Elaborates the proof, and checks that the result is valid in strict mode and doesn't use any of
+/// the given rules, or any eliminable rule.
+fn assert_elaborated_without_rules(problem: &str, proof: &str, rules: &[&str]) {
+    let options = crate::CarcaraOptions {
+        apply_function_defs: true,
+        ..Default::default()
+    };
+    let (is_holey, elaborated) =
+        crate::elaborate_and_verify(problem.as_bytes(), proof.as_bytes(), options).unwrap();
+    assert!(!is_holey);
+    for command in elaborated.iter() {
+        if let ProofCommand::Step(s) = command {
+            assert!(!rules.contains(&s.rule.as_str()), "found rule {}", s.rule);
+            let (_, metadata) = ProofChecker::get_rule(&s.rule, false).unwrap();
+            assert!(!metadata.is_eliminable, "found eliminable rule {}", s.rule);
+        }
+    }
+}
+
+/// Elaborates a proof that concludes `term` in a single step using `rule`, and then refutes the
+/// negation of `term`, asserted in a problem with the given declarations.
+fn elaborate_single_step(declarations: &str, rule: &str, term: &str) -> Proof {
+    let problem = format!("{} (assert (not {}))", declarations, term);
+    let proof = format!(
+        "(assume h1 (not {0}))
+        (step t2 (cl {0}) :rule {1})
+        (step t3 (cl) :rule resolution :premises (t2 h1) :args ({0} true))",
+        term, rule
+    );
+    let (_, elaborated) = crate::elaborate_and_verify(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::default(),
+    )
+    .unwrap();
+    elaborated
+}
+
+/// Returns the rules of the top-level steps of the proof, in order.
+fn step_rules(proof: &Proof) -> Vec<&str> {
+    proof
+        .commands
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Step(s) => Some(s.rule.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_elaborate_and_verify() {
+    // This uses the elaborators for `assume`, `trans`, `eq_transitive`, `refl` and `resolution`
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun c () Int)
+        (declare-fun p (Int) Bool)
+        (assert (= b a))
+        (assert (= b c))
+        (assert (not (= a c)))
+    ";
+    let proof = "
+        (assume h1 (= a b))
+        (assume h2 (= b c))
+        (assume h3 (not (= a c)))
+        (step t4 (cl (= a c)) :rule trans :premises (h2 h1))
+        (step t5 (cl (not (= b c)) (not (= a b)) (= a c)) :rule eq_transitive)
+        (step t6 (cl (= (forall ((x Int)) (p x)) (forall ((y Int)) (p y)))) :rule refl)
+        (step t7 (cl) :rule resolution :premises (t4 h3))
+    ";
+    assert_elaborated_without_rules(problem, proof, &[]);
+
+    // If a step is skipped because of an unknown rule, it is kept in the elaborated proof, which
+    // is then invalid
+    let proof = PROOF.replace(":rule or", ":rule foo");
+    let options = crate::CarcaraOptions {
+        skip_unknown_rules: true,
+        ..Default::default()
+    };
+    let result = crate::elaborate_and_verify(PROBLEM.as_bytes(), proof.as_bytes(), options);
+    match result {
+        Err(Error::ElaborationVerificationFailed { step, rule, inner }) => {
+            assert_eq!((step.as_str(), rule.as_str()), ("t4", "foo"));
+            assert!(matches!(
+                *inner,
+                Error::Checker {
+                    inner: CheckerError::UnknownRule(ref rule),
+                    ..
+                } if rule == "foo"
+            ));
+        }
+        other => panic!("expected elaboration verification error, got {:?}", other),
+    }
+
+    // Errors in the original proof are not reported as elaboration errors
+    let proof = PROOF.replace("(step t4 (cl p q)", "(step t4 (cl q p q)");
+    let result = crate::elaborate_and_verify(
+        PROBLEM.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::new(),
+    );
+    assert!(matches!(result, Err(Error::Checker { .. })));
+}
+
+#[test]
+fn test_elaborate_assume() {
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun p (Bool) Bool)
+        (assert (p (= b a)))
+        (assert (not (p (= a b))))
+    ";
+
+    // The first `assume` only matches its premise modulo reordering of equalities, which is not
+    // allowed in strict mode
+    let proof = "
+        (assume h1 (p (= a b)))
+        (assume h2 (not (p (= a b))))
+        (step t3 (cl) :rule resolution :premises (h1 h2))
+    ";
+    let (prelude, parsed, mut pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    let result = ProofChecker::new_strict(&mut pool, prelude).check(&parsed);
+    assert!(matches!(
+        result,
+        Err(Error::Checker { inner: CheckerError::Assume(_), .. })
+    ));
+
+    // In the elaborated proof, the premise is assumed directly, and the original term is derived
+    // from it by resolution, using an explicit proof of their equality
+    let options = crate::CarcaraOptions::new();
+    let (_, elaborated) =
+        crate::elaborate_and_verify(problem.as_bytes(), proof.as_bytes(), options).unwrap();
+    let [ProofCommand::Assume { term: premise, .. }, .., ProofCommand::Step(last)] =
+        &elaborated.commands[..]
+    else {
+        panic!("unexpected elaborated proof");
+    };
+    assert_eq!(premise.to_string(), "(p (= b a))");
+    let derived = elaborated.iter().find_map(|c| match c {
+        ProofCommand::Step(s) if s.rule == "resolution" && s.premises.contains(&(0, 0)) => Some(s),
+        _ => None,
+    });
+    let derived = derived.expect("expected resolution step using the premise");
+    assert_eq!(derived.clause[0].to_string(), "(p (= a b))");
+    assert!(last.clause.is_empty());
+}
+
+#[test]
+fn test_elaborate_assume_symm() {
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (assert (= b a))
+        (assert (not (= a b)))
+    ";
+
+    // The first `assume` matches its premise only after flipping the equality, so it is
+    // elaborated into the assumption of the premise followed by a `symm` step
+    let proof = "
+        (assume h1 (= a b))
+        (assume h2 (not (= a b)))
+        (step t3 (cl) :rule resolution :premises (h1 h2))
+    ";
+    let options = crate::CarcaraOptions::new();
+    let (_, elaborated) =
+        crate::elaborate_and_verify(problem.as_bytes(), proof.as_bytes(), options).unwrap();
+    let [ProofCommand::Assume { id, term: premise }, ProofCommand::Step(symm), ..] =
+        &elaborated.commands[..]
+    else {
+        panic!("unexpected elaborated proof");
+    };
+    assert_eq!(
+        (id.as_str(), premise.to_string().as_str()),
+        ("h1", "(= b a)")
+    );
+    assert_eq!(symm.rule, "symm");
+    assert_eq!(symm.premises, [(0, 0)]);
+    assert_eq!(symm.clause[0].to_string(), "(= a b)");
+    assert!(elaborated.iter().all(|c| match c {
+        ProofCommand::Step(s) => s.rule != "equiv1",
+        _ => true,
+    }));
+}
+
+#[test]
+fn test_elaborate_resolution() {
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (declare-fun r () Bool)
+        (declare-fun s () Bool)
+        (assert (or p q r))
+        (assert (not p))
+        (assert (or (not q) s))
+        (assert (not r))
+        (assert (not s))
+    ";
+    let proof = "
+        (assume h1 (or p q r))
+        (assume h2 (not p))
+        (assume h3 (or (not q) s))
+        (assume h4 (not r))
+        (assume h5 (not s))
+        (step t6 (cl p q r) :rule or :premises (h1))
+        (step t7 (cl (not q) s) :rule or :premises (h3))
+        (step t8 (cl s r) :rule resolution :premises (t7 h2 t6))
+        (step t9 (cl) :rule resolution :premises (t8 h4 h5) :args (r true s true))
+    ";
+    let options = crate::CarcaraOptions::new();
+    let (_, elaborated) =
+        crate::elaborate_and_verify(problem.as_bytes(), proof.as_bytes(), options).unwrap();
+
+    let steps: Vec<_> = elaborated
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Step(s) if s.rule == "resolution" => Some(s),
+            _ => None,
+        })
+        .collect();
+    let args: Vec<Vec<_>> = steps
+        .iter()
+        .map(|s| {
+            s.args
+                .iter()
+                .map(|a| a.as_term().unwrap().to_string())
+                .collect()
+        })
+        .collect();
+
+    // The pivots of `t8` are inferred, while the ones given for `t9` are kept
+    assert_eq!(steps[0].id, "t8");
+    assert_eq!(args[0], ["p", "true", "q", "true"]);
+    assert_eq!(steps[1].id, "t9");
+    assert_eq!(args[1], ["r", "true", "s", "true"]);
+}
+
+#[test]
+fn test_elaborate_skolemization() {
+    let problem = "
+        (declare-fun p (Int) Bool)
+        (assert (exists ((x Int)) (p x)))
+        (assert (not (p (choice ((x Int)) (p x)))))
+    ";
+    let proof = "
+        (assume h1 (exists ((x Int)) (p x)))
+        (assume h2 (not (p (choice ((x Int)) (p x)))))
+        (anchor :step t3 :args ((:= x (choice ((x Int)) (p x)))))
+        (step t3.t1 (cl (= (p x) (p (choice ((x Int)) (p x))))) :rule refl)
+        (step t3 (cl (= (exists ((x Int)) (p x)) (p (choice ((x Int)) (p x))))) :rule sko_ex)
+        (step t4 (cl (not (exists ((x Int)) (p x))) (p (choice ((x Int)) (p x))))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2)
+            :args ((exists ((x Int)) (p x)) false (p (choice ((x Int)) (p x))) true))
+    ";
+    assert_elaborated_without_rules(problem, proof, &["sko_ex"]);
+}
+
+#[test]
+fn test_elaborate_skolemization_multiple_bindings() {
+    // The Skolem term for `y` depends on the Skolem term for `x`. Here, the right-hand side of the
+    // `sko_forall` conclusion is not the skolemized term itself, so the elaborated proof must also
+    // use the previous command in the subproof
+    let problem = "
+        (declare-fun q (Int Int) Bool)
+        (define-fun sk1 () Int (choice ((x Int)) (not (forall ((y Int)) (q x y)))))
+        (define-fun sk2 () Int (choice ((y Int)) (not (q sk1 y))))
+        (assert (forall ((x Int) (y Int)) (q x y)))
+        (assert (not (q sk1 sk2)))
+    ";
+    let proof = "
+        (assume h1 (forall ((x Int) (y Int)) (q x y)))
+        (assume h2 (not (q sk1 sk2)))
+        (anchor :step t3 :args ((:= x sk1) (:= y sk2)))
+        (step t3.t1 (cl (= (q x y) (q sk1 sk2))) :rule refl)
+        (step t3.t2 (cl (= (not (not (q sk1 sk2))) (q sk1 sk2))) :rule not_simplify)
+        (step t3.t3 (cl (= (q sk1 sk2) (not (not (q sk1 sk2))))) :rule symm :premises (t3.t2))
+        (step t3.t4 (cl (= (q x y) (not (not (q sk1 sk2))))) :rule trans :premises (t3.t1 t3.t3))
+        (step t3 (cl (= (forall ((x Int) (y Int)) (q x y)) (not (not (q sk1 sk2)))))
+            :rule sko_forall)
+        (step t4 (cl (not (forall ((x Int) (y Int)) (q x y))) (not (not (q sk1 sk2))))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2)
+            :args ((forall ((x Int) (y Int)) (q x y)) false (not (q sk1 sk2)) false))
+    ";
+    assert_elaborated_without_rules(problem, proof, &["sko_forall"]);
+}
+
+#[test]
+fn test_elaborate_bind() {
+    // `bind` steps are kept, but the steps in their subproofs are still elaborated
+    let problem = "
+        (assert (forall ((x Int)) (= x 0)))
+        (assert (not (forall ((y Int)) (= 0 y))))
+    ";
+    let proof = "
+        (assume h1 (forall ((x Int)) (= x 0)))
+        (assume h2 (not (forall ((y Int)) (= 0 y))))
+        (anchor :step t3 :args ((y Int) (:= x y)))
+        (step t3.t1 (cl (= (= x 0) (= 0 y))) :rule refl)
+        (step t3 (cl (= (forall ((x Int)) (= x 0)) (forall ((y Int)) (= 0 y)))) :rule bind)
+        (step t4 (cl (not (forall ((x Int)) (= x 0))) (forall ((y Int)) (= 0 y)))
+            :rule equiv1 :premises (t3))
+        (step t5 (cl) :rule resolution :premises (t4 h1 h2))
+    ";
+    let (_, elaborated) = crate::elaborate_and_verify(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::default(),
+    )
+    .unwrap();
+    let ProofCommand::Subproof(subproof) = &elaborated.commands[2] else {
+        panic!("expected subproof");
+    };
+    assert!(subproof.commands.len() > 2);
+    assert!(matches!(
+        subproof.commands.last(),
+        Some(ProofCommand::Step(s)) if s.rule == "bind"
+    ));
+}
+
+#[test]
+fn test_elaborate_la_disequality() {
+    let elaborated = elaborate_single_step(
+        "(declare-fun a () Int) (declare-fun b () Int)",
+        "la_disequality",
+        "(or (= a b) (not (<= a b)) (not (<= b a)))",
+    );
+    assert_eq!(
+        step_rules(&elaborated),
+        [
+            "la_rw_eq",
+            "equiv2",
+            "and_neg",
+            "resolution",
+            "or_neg",
+            "or_neg",
+            "or_neg",
+            "resolution",
+            "resolution",
+        ]
+    );
+
+    // When both sides are the same term, the repeated literal is only resolved once
+    let elaborated = elaborate_single_step(
+        "(declare-fun a () Int)",
+        "la_disequality",
+        "(or (= a a) (not (<= a a)) (not (<= a a)))",
+    );
+    let ProofCommand::Step(step) = elaborated.commands.iter().rev().nth(1).unwrap() else {
+        panic!("expected step");
+    };
+    assert_eq!((step.id.as_str(), step.rule.as_str()), ("t2", "resolution"));
+    assert_eq!(step.premises.len(), 3);
+}
+
+#[test]
+fn test_elaborate_la_tautology() {
+    let run = |term| {
+        let declarations = "(declare-fun a () Int) (declare-fun b () Int) (declare-fun x () Real)";
+        let elaborated = elaborate_single_step(declarations, "la_tautology", term);
+        step_rules(&elaborated).join(" ")
+    };
+
+    for term in ["(<= 0 0)", "(< 0.0 1.5)", "(not (<= (+ a 1) a))"] {
+        assert_eq!(run(term), "la_generic resolution", "{}", term);
+    }
+    for term in [
+        "(or (not (<= (+ a b) 1)) (<= (+ a b) 2))",
+        "(or (<= a 3) (not (<= a 3)))",
+        "(or (not (>= x 2.0)) (>= x 1.0))",
+        "(or (>= x 1.5) (not (>= x 1.5)))",
+        "(or (not (<= x 1.0)) (not (>= x 2.0)))",
+    ] {
+        assert_eq!(
+            run(term),
+            "la_generic or_neg or_neg resolution resolution",
+            "{}",
+            term
+        );
+    }
+}
+
+#[test]
+fn test_elaborate_or_intro() {
+    let problem = "
+        (declare-fun a () Bool)
+        (declare-fun b () Bool)
+        (declare-fun c () Bool)
+        (assert (or (not a) b))
+        (assert a)
+        (assert (not b))
+        (assert (not c))
+    ";
+    let proof = "
+        (assume h1 (or (not a) b))
+        (assume h2 a)
+        (assume h3 (not b))
+        (assume h4 (not c))
+        (step t5 (cl (not a) b) :rule or :premises (h1))
+        (step t6 (cl (not a) b c) :rule or_intro :premises (t5))
+        (step t7 (cl) :rule resolution :premises (t6 h2 h3 h4)
+            :args (a false b true c true))
+    ";
+    let (_, elaborated) = crate::elaborate_and_verify(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::default(),
+    )
+    .unwrap();
+    let steps: Vec<_> = elaborated
+        .commands
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Step(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+    let rules: Vec<_> = steps.iter().map(|s| s.rule.as_str()).collect();
+    assert_eq!(
+        rules,
+        [
+            "or",
+            "or_neg",
+            "or_neg",
+            "or_pos",
+            "resolution",
+            "resolution"
+        ]
+    );
+
+    // The new steps have fresh ids, and the original id is kept for the resulting clause
+    let ids: AHashSet<_> = elaborated.iter().map(ProofCommand::id).collect();
+    assert_eq!(ids.len(), elaborated.iter().count());
+    assert_eq!(steps[4].id, "t6");
+    assert_eq!(steps[4].clause, steps[3].clause[1..]);
+}
+
+#[test]
+fn test_elaborate_distinct_elim() {
+    let run = |expansion: &str| {
+        let declarations = "(declare-fun a () Int) (declare-fun b () Int) (declare-fun c () Int)
+            (declare-fun d () Int) (declare-fun e () Int)";
+        let term = format!("(= (distinct a b c d e) {})", expansion);
+        elaborate_single_step(declarations, "distinct_elim", &term)
+    };
+
+    let pairs = [
+        "(not (= a b))",
+        "(not (= a c))",
+        "(not (= a d))",
+        "(not (= a e))",
+        "(not (= b c))",
+        "(not (= b d))",
+        "(not (= b e))",
+        "(not (= c d))",
+        "(not (= c e))",
+        "(not (= d e))",
+    ];
+
+    // If the equalities follow the order of the arguments, the step is unchanged
+    let elaborated = run(&format!("(and {})", pairs.join(" ")));
+    assert!(matches!(
+        &elaborated.commands[1],
+        ProofCommand::Step(s) if s.id == "t2" && s.rule == "distinct_elim"
+    ));
+
+    // Otherwise, it is elaborated into a `distinct_elim` step with the equalities in order, followed
+    // by a proof that the two conjunctions are equal
+    let mut flipped = pairs.map(str::to_owned);
+    flipped[1] = "(not (= c a))".to_owned();
+    flipped[9] = "(not (= e d))".to_owned();
+    let elaborated = run(&format!("(and {})", flipped.join(" ")));
+    let ProofCommand::Step(first) = &elaborated.commands[1] else {
+        panic!("expected step");
+    };
+    assert_eq!(first.rule, "distinct_elim");
+    let and_args = match_term!((= (distinct ...) (and ...)) = &first.clause[0])
+        .unwrap()
+        .1;
+    assert_eq!(and_args.len(), 10);
+    assert_eq!(and_args[1].to_string(), "(not (= a c))");
+    assert!(matches!(
+        elaborated.commands.iter().rev().nth(1),
+        Some(ProofCommand::Step(s)) if s.id == "t2" && s.rule == "trans"
+    ));
+}
+
+#[test]
+fn test_elaborate_connective_def() {
+    let run = |term| {
+        let declarations = "(declare-fun a () Bool) (declare-fun b () Bool) (declare-fun c () Bool)
+            (declare-fun f (Int) Bool)";
+        elaborate_single_step(declarations, "connective_def", term)
+    };
+
+    let cases = [
+        "(= (xor a b) (or (and (not a) b) (and a (not b))))",
+        "(= (xor (not a) (and b c)) (or (and (not (not a)) (and b c)) (and (not a) (not (and b c)))))",
+        "(= (= a b) (and (=> a b) (=> b a)))",
+        "(= (= (not a) (or b c)) (and (=> (not a) (or b c)) (=> (or b c) (not a))))",
+        "(= (ite a b c) (and (=> a b) (=> (not a) c)))",
+        "(= (ite (not a) (xor b c) c) (and (=> (not a) (xor b c)) (=> (not (not a)) c)))",
+    ];
+    for term in cases {
+        let elaborated = run(term);
+        let rules = step_rules(&elaborated);
+        assert!(!rules.contains(&"connective_def"), "{}", term);
+        assert!(rules.contains(&"equiv_neg1") && rules.contains(&"equiv_neg2"));
+        let num_resolutions = rules.iter().filter(|&&r| r == "resolution").count();
+        assert_eq!(num_resolutions, 7, "{}", term); // Including `t3`
+    }
+
+    // If two of the operands are the same, or one is the negation of the other, the step is kept
+    // as is
+    let degenerate_cases = [
+        "(= (xor a a) (or (and (not a) a) (and a (not a))))",
+        "(= (xor a (not a)) (or (and (not a) (not a)) (and a (not (not a)))))",
+        "(= (= a a) (and (=> a a) (=> a a)))",
+        "(= (ite a a b) (and (=> a a) (=> (not a) b)))",
+        "(= (ite a b a) (and (=> a b) (=> (not a) a)))",
+    ];
+    for term in degenerate_cases {
+        let elaborated = run(term);
+        assert!(
+            matches!(
+                &elaborated.commands[1],
+                ProofCommand::Step(s) if s.id == "t2" && s.rule == "connective_def"
+            ),
+            "{}",
+            term
+        );
+    }
+
+    // The definition of `exists` is kept as is
+    let elaborated = run("(= (exists ((x Int)) (f x)) (not (forall ((x Int)) (not (f x)))))");
+    assert!(matches!(
+        &elaborated.commands[1],
+        ProofCommand::Step(s) if s.id == "t2" && s.rule == "connective_def"
+    ));
+}
+
+#[test]
+fn test_check_step_isolated() {
+    let mut pool = TermPool::new();
+    let bool_sort = pool.add(Term::Sort(Sort::Bool));
+    let [p, q] = ["p", "q"].map(|name| pool.add(Term::var(name, bool_sort.clone())));
+    let not_p = pool.add(Term::Op(Operator::Not, vec![p.clone()]));
+    let (p_or_q, p_or_p) = (vec![p.clone(), q.clone()], vec![p.clone(), p.clone()]);
+    let (p, q, not_p) = (vec![p], vec![q], vec![not_p]);
+
+    let mut check = |rule, conclusion: &[_], premises: &[&[_]]| {
+        ProofChecker::check_step_isolated(rule, conclusion, premises, &[], &mut pool)
+    };
+    assert!(check("resolution", &q, &[&p_or_q, &not_p]).is_ok());
+    assert!(check("contraction", &p, &[&p_or_p]).is_ok());
+    assert!(check("contraction", &q, &[&p_or_p]).is_err());
+    assert!(matches!(
+        check("unknown_rule", &p, &[]),
+        Err(CheckerError::UnknownRule(rule)) if rule == "unknown_rule"
+    ));
+
+    // `lia_generic` steps with Farkas coefficients are checked like `la_generic` steps
+    let clause = crate::parser::tests::parse_terms(
+        &mut pool,
+        "(declare-fun x () Int)",
+        ["(not (> x 0))", "(not (< x 0))"],
+    );
+    let args = [1, 1].map(|n| ProofArg::Term(pool.add(Term::integer(n))));
+    for rule in ["la_generic", "lia_generic"] {
+        assert!(ProofChecker::check_step_isolated(rule, &clause, &[], &args, &mut pool).is_ok());
+        assert!(ProofChecker::check_step_isolated(rule, &clause, &[], &[], &mut pool).is_err());
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_reordering_accepts_permutations(
+        literals in proptest::collection::vec(0..10usize, 0..20)
+            .prop_flat_map(|v| (Just(v.clone()), Just(v).prop_shuffle()))
+    ) {
+        let mut pool = TermPool::new();
+        let bool_sort = pool.add(Term::Sort(Sort::Bool));
+        let mut to_clause = |indices: &[usize]| -> Vec<_> {
+            indices
+                .iter()
+                .map(|i| pool.add(Term::var(format!("p{}", i), bool_sort.clone())))
+                .collect()
+        };
+        let (premise, conclusion) = (to_clause(&literals.0), to_clause(&literals.1));
+        let result = ProofChecker::check_step_isolated(
+            "reordering",
+            &conclusion,
+            &[&premise],
+            &[],
+            &mut pool,
+        );
+        proptest::prop_assert!(result.is_ok());
+    }
+}
+
+#[test]
+fn test_check_proof_fragment() {
+    use crate::{check_proof_fragment, CarcaraOptions};
+
+    let (_, proof, mut pool) =
+        parse_instance(PROBLEM.as_bytes(), PROOF.as_bytes(), true, false, false).unwrap();
+
+    // The fragment doesn't need to reach the empty clause
+    let fragment = &proof.commands[..5];
+    let result = check_proof_fragment(fragment, &proof.premises, &mut pool, CarcaraOptions::new());
+    assert!(matches!(result, Ok(false)));
+
+    // The `assume` commands can only use the given premises
+    let ProofCommand::Assume { term, .. } = &proof.commands[0] else {
+        unreachable!()
+    };
+    let premises = AHashSet::from([term.clone()]);
+    let result = check_proof_fragment(fragment, &premises, &mut pool, CarcaraOptions::new());
+    assert!(matches!(result, Err(Error::Checker { step, .. }) if step == "h2"));
+
+    // Modified steps are checked as usual
+    let modify = |f: &dyn Fn(&mut ProofStep)| {
+        let mut fragment = fragment.to_vec();
+        if let ProofCommand::Step(s) = &mut fragment[4] {
+            f(s);
+        }
+        fragment
+    };
+    let fragment = modify(&|s| {
+        s.premises.pop();
+    });
+    let result = check_proof_fragment(&fragment, &proof.premises, &mut pool, CarcaraOptions::new());
+    assert!(matches!(result, Err(Error::Checker { step, .. }) if step == "t5"));
+
+    let fragment = modify(&|s| s.rule = "hole".to_owned());
+    let result = check_proof_fragment(&fragment, &proof.premises, &mut pool, CarcaraOptions::new());
+    assert!(matches!(result, Ok(true)));
+
+    // In a slice taken from the middle of the proof, the premise indices still refer to the
+    // original proof, so they are out of range or point to the wrong commands
+    for fragment in [&proof.commands[3..], &proof.commands[4..]] {
+        let result =
+            check_proof_fragment(fragment, &proof.premises, &mut pool, CarcaraOptions::new());
+        assert!(matches!(
+            result,
+            Err(Error::Checker {
+                inner: CheckerError::InvalidPremiseIndex(_),
+                ..
+            })
+        ));
+    }
+    let fragment = modify(&|s| s.discharge.push((1, 0)));
+    let result = check_proof_fragment(&fragment, &proof.premises, &mut pool, CarcaraOptions::new());
+    assert!(matches!(
+        result,
+        Err(Error::Checker { inner: CheckerError::InvalidPremiseIndex((1, 0)), step, .. })
+            if step == "t5"
+    ));
+}
+
+#[test]
+fn test_context_apply_to_clause() {
+    let mut pool = TermPool::new();
+    let int = pool.add(Term::Sort(Sort::Int));
+    let [a, b, x, y] = ["a", "b", "x", "y"].map(|s| pool.add(Term::var(s, int.clone())));
+    let clause: Vec<_> = (0..100)
+        .map(|i| {
+            let n = pool.add(Term::integer(i));
+            let sum = build_term!(pool, (+ {x.clone()} {n}));
+            build_term!(pool, (= {sum} {y.clone()}))
+        })
+        .collect();
+
+    let mut context = ContextStack::new();
+    assert_eq!(context.apply_to_clause(&mut pool, &clause), clause);
+
+    let assignment_args = [("x".to_owned(), a), ("y".to_owned(), b)];
+    context.push(&mut pool, &assignment_args, &[]).unwrap();
+    let expected: Vec<_> = clause.iter().map(|t| context.apply(&mut pool, t)).collect();
+    assert_ne!(expected, clause);
+    assert_eq!(context.apply_to_clause(&mut pool, &clause), expected);
+}
+
+#[test]
+fn test_context_validate() {
+    let mut pool = TermPool::new();
+    let [int, real] = [Sort::Int, Sort::Real].map(|s| pool.add(Term::Sort(s)));
+    let one = pool.add(Term::integer(1));
+    let y = pool.add(Term::var("y", int.clone()));
+
+    let valid = [("x".to_owned(), one.clone()), ("z".to_owned(), y)];
+    let variables = [("x".to_owned(), int), ("y".to_owned(), real.clone())];
+    assert!(Context::validate(&mut pool, &valid, &variables).is_ok());
+
+    let repeated = [("x".to_owned(), one.clone()), ("x".to_owned(), one.clone())];
+    let mut context = ContextStack::new();
+    assert!(matches!(
+        context.push(&mut pool, &repeated, &[]),
+        Err(SubstitutionError::RepeatedVariable(x)) if x == "x"
+    ));
+    assert!(context.is_empty());
+
+    let wrong_sort = [("x".to_owned(), one)];
+    assert!(matches!(
+        context.push(&mut pool, &wrong_sort, &[("x".to_owned(), real)]),
+        Err(SubstitutionError::DifferentSorts(_, _))
+    ));
+}
+
+#[test]
+fn test_elaboration_stats() {
+    let problem = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun p () Bool)
+        (assert (= a b))
+        (assert (not (= a b)))
+    ";
+    let proof = "
+        (assume h1 (= b a))
+        (assume h2 (not (= a b)))
+        (step t3 (cl (= a a)) :rule refl)
+        (step t4 (cl (= a b)) :rule symm :premises (h1))
+        (step t5 (cl) :rule resolution :premises (t4 h2))
+    ";
+    let (_, elaborated, stats) = crate::check_and_elaborate(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(stats.steps_before, 5);
+    assert_eq!(stats.steps_after, elaborated.iter().count());
+
+    // The `refl` step is left unchanged by its elaboration rule, so it isn't counted
+    let mut rules: Vec<_> = stats.rules_elaborated.into_iter().collect();
+    rules.sort();
+    assert_eq!(
+        rules,
+        [("assume".to_owned(), 1), ("resolution".to_owned(), 1)]
+    );
+}
+
+#[test]
+fn test_deep_eq_cache() {
+    let mut problem = String::from("(declare-fun a () Int) (declare-fun b () Int)");
+    for i in 0..20 {
+        problem += &format!("(declare-fun p{0} () Bool) (assert (or p{0} (= a b)))", i);
+    }
+    problem += "(assert (not (or p0 (= a b))))";
+
+    // Every `assume` only matches its premise modulo reordering of equalities
+    let mut proof = String::new();
+    for i in 0..1000 {
+        proof += &format!("(assume h{} (or p{} (= b a)))", i, i % 20);
+    }
+    proof += "(assume h1000 (not (or p0 (= b a))))";
+    proof += "(step t1001 (cl) :rule resolution :premises (h0 h1000))";
+
+    for (use_deep_eq_cache, deep_eq_cache_size) in [(false, None), (true, None), (true, Some(4))] {
+        let options = crate::CarcaraOptions {
+            use_deep_eq_cache,
+            deep_eq_cache_size,
+            ..Default::default()
+        };
+        let result = crate::check(problem.as_bytes(), proof.as_bytes(), options);
+        assert!(matches!(result, Ok(false)));
+    }
+
+    // In strict mode, the cache is not used, and the `assume` commands are invalid
+    let options = crate::CarcaraOptions {
+        use_deep_eq_cache: true,
+        strict: true,
+        ..Default::default()
+    };
+    let result = crate::check(problem.as_bytes(), proof.as_bytes(), options);
+    assert!(matches!(result, Err(crate::Error::Checker { .. })));
+}
+
+#[test]
+fn test_strict_and_permissive_checkers() {
+    fn check(proof: &str, strict: bool) -> CarcaraResult<bool> {
+        let (prelude, proof, mut pool) =
+            parse_instance(PROBLEM.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let mut checker = if strict {
+            ProofChecker::new_strict(&mut pool, prelude)
+        } else {
+            ProofChecker::new_permissive(&mut pool, prelude)
+        };
+        checker.check(&proof)
+    }
+
+    // In strict mode, `resolution` steps must have their pivots as arguments
+    assert!(matches!(check(PROOF, true), Err(Error::Checker { rule, .. }) if rule == "resolution"));
+    assert!(matches!(check(PROOF, false), Ok(false)));
+    let proof = PROOF
+        .replace(":premises (t4 h2)", ":premises (t4 h2) :args (p true)")
+        .replace(":premises (t5 h3)", ":premises (t5 h3) :args (q true)");
+    assert!(matches!(check(&proof, true), Ok(false)));
+
+    // Steps with unknown rules are only skipped by the permissive checker, which then considers
+    // the proof holey
+    let proof = proof.replace(":rule or", ":rule foo");
+    assert!(matches!(
+        check(&proof, true),
+        Err(Error::Checker { inner: CheckerError::UnknownRule(rule), .. }) if rule == "foo"
+    ));
+    assert!(matches!(check(&proof, false), Ok(true)));
+}
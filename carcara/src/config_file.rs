@@ -0,0 +1,201 @@
+//! Loading and saving `CarcaraOptions` from TOML configuration files.
+
+use crate::{CarcaraOptions, CarcaraResult, Error};
+use std::{fmt::Write, fs, path::Path, time::Duration};
+use toml::{value::Table, Value};
+
+/// A type of option that can be read from and written to a configuration file.
+trait ConfigValue: Sized {
+    /// Reads the value given to `key` in the file.
+    fn read(value: &Value, key: &str) -> CarcaraResult<Self>;
+
+    /// Formats the value as TOML, or returns `None` if the option is not set.
+    fn write(&self) -> Option<String>;
+}
+
+impl ConfigValue for bool {
+    fn read(value: &Value, key: &str) -> CarcaraResult<Self> {
+        match value {
+            Value::Boolean(b) => Ok(*b),
+            _ => Err(Error::InvalidConfig(format!("`{}` must be a boolean", key))),
+        }
+    }
+
+    fn write(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl ConfigValue for Option<usize> {
+    fn read(value: &Value, key: &str) -> CarcaraResult<Self> {
+        match value {
+            Value::Integer(i) if *i >= 0 => Ok(Some(*i as usize)),
+            _ => Err(Error::InvalidConfig(format!(
+                "`{}` must be a non-negative integer",
+                key
+            ))),
+        }
+    }
+
+    fn write(&self) -> Option<String> {
+        self.map(|n| n.to_string())
+    }
+}
+
+/// Durations are given as a number of seconds.
+impl ConfigValue for Option<Duration> {
+    fn read(value: &Value, key: &str) -> CarcaraResult<Self> {
+        let seconds = match value {
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            _ => return Err(Error::InvalidConfig(format!("`{}` must be a number", key))),
+        };
+        let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+            Error::InvalidConfig(format!("`{}` must be a non-negative number", key))
+        })?;
+        Ok(Some(duration))
+    }
+
+    fn write(&self) -> Option<String> {
+        self.map(|t| format!("{:?}", t.as_secs_f64()))
+    }
+}
+
+/// Defines `KNOWN_KEYS`, `read_keys` and `write_keys` from the list of keys that can appear in a
+/// configuration file. Each key is the name of a field in `CarcaraOptions`.
+macro_rules! config_keys {
+    ($($key:ident),* $(,)?) => {
+        const KNOWN_KEYS: &[&str] = &[$(stringify!($key)),*];
+
+        /// Reads the keys that are present in `table` into `options`. Keys that are not present
+        /// leave the corresponding options unchanged.
+        fn read_keys(table: &Table, options: &mut CarcaraOptions) -> CarcaraResult<()> {
+            $(if let Some(value) = table.get(stringify!($key)) {
+                options.$key = ConfigValue::read(value, stringify!($key))?;
+            })*
+            Ok(())
+        }
+
+        /// Writes every key to `result`. Options that are not set are written as comments.
+        fn write_keys(options: &CarcaraOptions, result: &mut String) {
+            $(match options.$key.write() {
+                Some(v) => writeln!(result, "{} = {}", stringify!($key), v),
+                None => writeln!(result, "# {} =", stringify!($key)),
+            }
+            .unwrap();)*
+        }
+    };
+}
+
+config_keys!(
+    apply_function_defs,
+    expand_lets,
+    allow_int_real_subtyping,
+    lia_via_cvc5,
+    error_recovery,
+    strict,
+    skip_unknown_rules,
+    max_steps,
+    timeout,
+    deep_eq_depth_limit,
+    max_term_depth,
+    use_deep_eq_cache,
+    deep_eq_cache_size,
+);
+
+fn parse_toml(contents: &str) -> CarcaraResult<CarcaraOptions> {
+    let table = match contents.parse::<Value>() {
+        Ok(Value::Table(table)) => table,
+        Ok(_) => unreachable!("a TOML document is always a table"),
+        Err(e) => return Err(Error::InvalidConfig(e.to_string())),
+    };
+    for key in table.keys() {
+        if !KNOWN_KEYS.contains(&key.as_str()) {
+            log::warn!("unknown key in configuration file: `{}`", key);
+        }
+    }
+
+    let mut options = CarcaraOptions::new();
+    read_keys(&table, &mut options)?;
+    Ok(options)
+}
+
+impl CarcaraOptions {
+    /// Loads the options from a TOML configuration file. Each key in the file corresponds to the
+    /// field with the same name in `CarcaraOptions`, and options that are not present take their
+    /// default values. The `timeout` is given in seconds. Unknown keys are ignored, with a warning.
+    pub fn from_toml(path: &Path) -> CarcaraResult<Self> {
+        parse_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the options as a TOML configuration file, that can be read by
+    /// [`CarcaraOptions::from_toml`]. Options that are not set, like a `timeout` of `None`, are
+    /// included as comments, so the result can be used as a template.
+    pub fn to_toml(&self) -> String {
+        let mut result = String::new();
+        write_keys(self, &mut result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_round_trip() {
+        let options = CarcaraOptions {
+            expand_lets: true,
+            strict: true,
+            skip_unknown_rules: true,
+            max_steps: Some(100),
+            timeout: Some(Duration::from_millis(2500)),
+            ..CarcaraOptions::new()
+        };
+        let toml = options.to_toml();
+        assert!(toml.contains("strict = true\n"));
+        assert!(toml.contains("timeout = 2.5\n"));
+        assert!(toml.contains("# deep_eq_depth_limit =\n"));
+
+        let path = std::env::temp_dir().join(format!("carcara-{}.toml", std::process::id()));
+        fs::write(&path, &toml).unwrap();
+        let parsed = CarcaraOptions::from_toml(&path);
+        fs::remove_file(&path).unwrap();
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.to_toml(), toml);
+        assert!(parsed.expand_lets && parsed.strict && parsed.skip_unknown_rules);
+        assert!(!parsed.apply_function_defs && !parsed.lia_via_cvc5);
+        assert_eq!(parsed.max_steps, Some(100));
+        assert_eq!(parsed.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(parsed.deep_eq_depth_limit, None);
+
+        let default = parse_toml(&CarcaraOptions::new().to_toml()).unwrap();
+        assert_eq!(default.to_toml(), CarcaraOptions::new().to_toml());
+    }
+
+    #[test]
+    fn test_toml_errors() {
+        // Unknown keys are ignored, and missing keys take their default values
+        let options = parse_toml("strict = true\nfoo = 1").unwrap();
+        assert!(options.strict);
+        assert_eq!(options.timeout, None);
+
+        for contents in [
+            "strict = 1",
+            "max_steps = -1",
+            "timeout = \"10s\"",
+            "timeout = -1.0",
+            "strict = ",
+        ] {
+            assert!(
+                matches!(parse_toml(contents), Err(Error::InvalidConfig(_))),
+                "{}",
+                contents
+            );
+        }
+        assert!(matches!(
+            CarcaraOptions::from_toml(Path::new("/nonexistent/carcara.toml")),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+//! Comparison of two proofs step by step, for example to see how a proof was changed by
+//! elaboration, or how the proofs produced by two versions of a solver differ.
+
+use crate::ast::*;
+use ahash::AHashMap;
+use std::fmt::Write;
+
+/// The kind of difference found for a command in [`diff_proofs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// The command only exists in the second proof.
+    Added,
+
+    /// The command only exists in the first proof.
+    Removed,
+
+    /// The command exists in both proofs, but its rule, conclusion, premises, or arguments are
+    /// different. For `assume` commands, the rule is `"assume"`.
+    Changed { old_rule: String, new_rule: String },
+}
+
+/// A command that differs between two proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub step_id: String,
+    pub kind: DiffKind,
+}
+
+/// The differences between two proofs, as computed by [`diff_proofs`]. The entries are in the
+/// order in which the commands appear in the proofs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofComparison {
+    pub entries: Vec<DiffEntry>,
+}
+
+impl ProofComparison {
+    /// Returns `true` if no differences were found between the two proofs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a one-line summary of the diff, with the number of added, removed, and changed
+    /// commands.
+    pub fn summary(&self) -> String {
+        let [mut added, mut removed, mut changed] = [0; 3];
+        for entry in &self.entries {
+            match entry.kind {
+                DiffKind::Added => added += 1,
+                DiffKind::Removed => removed += 1,
+                DiffKind::Changed { .. } => changed += 1,
+            }
+        }
+        format!("{} added, {} removed, {} changed", added, removed, changed)
+    }
+
+    /// Returns the diff in a format similar to that of a patch, with one line for each entry.
+    /// Added commands are prefixed with `+`, removed commands with `-`, and changed commands with
+    /// `~`. For changed commands, the old and new rules are also shown.
+    pub fn to_patch_string(&self) -> String {
+        let mut result = String::new();
+        for entry in &self.entries {
+            match &entry.kind {
+                DiffKind::Added => writeln!(result, "+ {}", entry.step_id),
+                DiffKind::Removed => writeln!(result, "- {}", entry.step_id),
+                DiffKind::Changed { old_rule, new_rule } => {
+                    writeln!(result, "~ {} ({} -> {})", entry.step_id, old_rule, new_rule)
+                }
+            }
+            .unwrap();
+        }
+        result
+    }
+}
+
+/// The information about a command that is used to decide if it changed between the two proofs.
+/// Premises are represented by their ids, since their indices may change when commands are added or
+/// removed.
+struct CommandSummary<'a> {
+    id: &'a str,
+    rule: &'a str,
+    clause: &'a [Rc<Term>],
+    premises: Vec<&'a str>,
+    args: &'a [ProofArg],
+}
+
+impl<'a> CommandSummary<'a> {
+    /// Returns the summaries of all `assume` and `step` commands in the proof, including the ones
+    /// inside subproofs. Subproofs themselves are represented by the step that ends them.
+    fn collect(proof: &'a Proof) -> Vec<Self> {
+        let mut result = Vec::new();
+        let mut iter = proof.iter();
+        while let Some(command) = iter.next() {
+            let summary = match command {
+                ProofCommand::Assume { id, term } => CommandSummary {
+                    id,
+                    rule: "assume",
+                    clause: std::slice::from_ref(term),
+                    premises: Vec::new(),
+                    args: &[],
+                },
+                ProofCommand::Step(step) => CommandSummary {
+                    id: &step.id,
+                    rule: &step.rule,
+                    clause: &step.clause,
+                    premises: step
+                        .premises
+                        .iter()
+                        .map(|&p| iter.get_premise(p).id())
+                        .collect(),
+                    args: &step.args,
+                },
+                ProofCommand::Subproof(_) => continue,
+            };
+            result.push(summary);
+        }
+        result
+    }
+
+    /// Returns `true` if the two commands have the same rule, conclusion, premises, and arguments.
+    /// Since the two proofs may not share the same term pool, terms are compared structurally.
+    fn is_same_as(&self, other: &Self, checker: &mut DeepEqualityChecker) -> bool {
+        self.rule == other.rule
+            && self.premises == other.premises
+            && DeepEq::eq(checker, self.clause, other.clause)
+            && DeepEq::eq(checker, self.args, other.args)
+    }
+}
+
+/// Computes the longest common subsequence of the ids of the commands in `before` and `after`, and
+/// returns the pairs of indices of the matched commands. Commands are identified by their id
+/// together with the number of earlier commands with the same id, so that every element of each
+/// sequence is unique. In that case, the longest common subsequence corresponds to the longest
+/// increasing subsequence of the positions in `after` of the elements of `before`, which can be
+/// found in `O(n log n)` time.
+fn matching_commands(before: &[CommandSummary], after: &[CommandSummary]) -> Vec<(usize, usize)> {
+    fn keys<'a>(commands: &[CommandSummary<'a>]) -> Vec<(&'a str, usize)> {
+        let mut counts: AHashMap<&str, usize> = AHashMap::new();
+        commands
+            .iter()
+            .map(|c| {
+                let count = counts.entry(c.id).or_default();
+                *count += 1;
+                (c.id, *count)
+            })
+            .collect()
+    }
+
+    let after_positions: AHashMap<_, _> = keys(after)
+        .into_iter()
+        .enumerate()
+        .map(|(i, key)| (key, i))
+        .collect();
+    let pairs: Vec<(usize, usize)> = keys(before)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, key)| after_positions.get(&key).map(|&j| (i, j)))
+        .collect();
+
+    // `tails[k]` is the index in `pairs` of the smallest possible last element of an increasing
+    // subsequence of length `k + 1`, and `parents` links each element to its predecessor in the
+    // subsequence
+    let mut tails: Vec<usize> = Vec::new();
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (i, &(_, j)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|&t| pairs[t].1 < j);
+        parents.push(k.checked_sub(1).map(|k| tails[k]));
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut result = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        result.push(pairs[i]);
+        current = parents[i];
+    }
+    result.reverse();
+    result
+}
+
+/// Compares two proofs step by step, matching their commands by id. Commands that only exist in
+/// `before` are reported as removed, and commands that only exist in `after` are reported as added.
+/// Commands that exist in both proofs but have a different rule, conclusion, premises, or arguments
+/// are reported as changed. Subproofs are compared through the commands inside them.
+pub fn diff_proofs(before: &Proof, after: &Proof) -> ProofComparison {
+    let before = CommandSummary::collect(before);
+    let after = CommandSummary::collect(after);
+    let removed = |c: &CommandSummary| DiffEntry {
+        step_id: c.id.to_owned(),
+        kind: DiffKind::Removed,
+    };
+    let added = |c: &CommandSummary| DiffEntry {
+        step_id: c.id.to_owned(),
+        kind: DiffKind::Added,
+    };
+
+    let mut checker = DeepEqualityChecker::new(false, false);
+    let mut entries = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in matching_commands(&before, &after) {
+        entries.extend(before[i..next_i].iter().map(removed));
+        entries.extend(after[j..next_j].iter().map(added));
+        let (old, new) = (&before[next_i], &after[next_j]);
+        if !old.is_same_as(new, &mut checker) {
+            entries.push(DiffEntry {
+                step_id: new.id.to_owned(),
+                kind: DiffKind::Changed {
+                    old_rule: old.rule.to_owned(),
+                    new_rule: new.rule.to_owned(),
+                },
+            });
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    entries.extend(before[i..].iter().map(removed));
+    entries.extend(after[j..].iter().map(added));
+    ProofComparison { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_instance;
+
+    const PROBLEM: &str = "
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun p () Bool)
+        (assert (= a b))
+        (assert (not (= a b)))
+    ";
+
+    fn parse(proof: &str) -> Proof {
+        parse_instance(PROBLEM.as_bytes(), proof.as_bytes(), true, false, false)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_diff_proofs() {
+        let before_text = "(assume h1 (= a b))
+            (assume h2 (not (= a b)))
+            (step t3 (cl p) :rule hole)
+            (anchor :step t4)
+            (assume t4.a0 p)
+            (step t4 (cl (not p) p) :rule subproof :discharge (t4.a0))
+            (step t5 (cl) :rule resolution :premises (h1 h2))";
+        let before = parse(before_text);
+        let after = parse(
+            "(assume h1 (= a b))
+            (assume h2 (not (= a b)))
+            (anchor :step t4)
+            (assume t4.a0 p)
+            (step t4.t1 (cl p) :rule hole :premises (t4.a0))
+            (step t4 (cl (not p) p) :rule subproof :discharge (t4.a0))
+            (step t5 (cl) :rule th_resolution :premises (h1 h2))",
+        );
+
+        assert!(diff_proofs(&before, &before).is_empty());
+
+        // Each call to `parse` uses a new term pool, so the terms are only structurally equal
+        assert!(diff_proofs(&before, &parse(before_text)).is_empty());
+
+        let diff = diff_proofs(&before, &after);
+        let expected = [
+            DiffEntry {
+                step_id: "t3".to_owned(),
+                kind: DiffKind::Removed,
+            },
+            DiffEntry {
+                step_id: "t4.t1".to_owned(),
+                kind: DiffKind::Added,
+            },
+            DiffEntry {
+                step_id: "t5".to_owned(),
+                kind: DiffKind::Changed {
+                    old_rule: "resolution".to_owned(),
+                    new_rule: "th_resolution".to_owned(),
+                },
+            },
+        ];
+        assert_eq!(diff.entries, expected);
+        assert_eq!(diff.summary(), "1 added, 1 removed, 1 changed");
+        assert_eq!(
+            diff.to_patch_string(),
+            "- t3\n+ t4.t1\n~ t5 (resolution -> th_resolution)\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_elaborated_proof() {
+        use crate::checker::{Config, ProofChecker};
+
+        // The first `assume` only matches its premise modulo reordering of equalities, so
+        // elaboration replaces it
+        let proof = "
+            (assume h1 (= b a))
+            (assume h2 (not (= a b)))
+            (step t3 (cl (= a b)) :rule symm :premises (h1))
+            (step t4 (cl) :rule resolution :premises (t3 h2))
+        ";
+        let (prelude, proof, mut pool) =
+            parse_instance(PROBLEM.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let (_, elaborated) = ProofChecker::new(&mut pool, Config::new(), prelude)
+            .check_and_elaborate(proof.clone())
+            .unwrap();
+
+        // The `symm` step is no longer needed, and is removed by elaboration
+        let diff = diff_proofs(&proof, &elaborated);
+        assert_eq!(
+            diff.to_patch_string(),
+            "~ h1 (assume -> assume)\n- t3\n~ t4 (resolution -> resolution)\n"
+        );
+    }
+}
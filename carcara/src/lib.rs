@@ -38,13 +38,28 @@
 pub mod ast;
 pub mod benchmarking;
 pub mod checker;
+#[cfg(feature = "toml")]
+mod config_file;
+pub mod diff;
+pub mod lint;
 pub mod parser;
 mod utils;
+pub mod visualization;
 
+use ahash::AHashSet;
 use checker::error::CheckerError;
 use parser::ParserError;
 use parser::Position;
-use std::io;
+use std::{
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
 use thiserror::Error;
 
 pub type CarcaraResult<T> = Result<T, Error>;
@@ -73,9 +88,18 @@ pub struct CarcaraOptions {
     /// Enable checking/elaboration of `lia_generic` steps using cvc5. When checking a proof, this
     /// will call cvc5 to solve the linear integer arithmetic problem, check the proof, and discard
     /// it. When elaborating, the proof will instead be inserted in the place of the `lia_generic`
-    /// step.
+    /// step. If the proof given by cvc5 relies on a single `la_generic` step, its Farkas certificate
+    /// is checked directly instead, and the `lia_generic` step is elaborated into a `la_generic`
+    /// step with the certificate as arguments. This is only used for `lia_generic` steps that have
+    /// no arguments; steps that provide Farkas coefficients as arguments are always checked like
+    /// `la_generic` steps.
     pub lia_via_cvc5: bool,
 
+    /// If `true`, Carcara will try to recover from syntax errors in the proof, so that multiple
+    /// errors can be reported at once, instead of stopping at the first one. If more than one error
+    /// is found, they are returned in an `Error::MultipleParserErrors`.
+    pub error_recovery: bool,
+
     /// Enables "strict" checking of some rules.
     ///
     /// Currently, if enabled, the following rules are affected:
@@ -90,13 +114,72 @@ pub struct CarcaraOptions {
     /// If `true`, Carcara will skip any rules that it does not recognize, and will consider them as
     /// holes. Normally, using an unknown rule is considered an error.
     pub skip_unknown_rules: bool,
+
+    /// If `Some(n)`, Carcara will only check the first `n` steps of the proof (counting both `assume`
+    /// and `step` commands), and will not require the proof to reach the empty clause. This is
+    /// useful to check the beginning of a proof that is still incomplete. This is ignored when
+    /// elaborating.
+    pub max_steps: Option<usize>,
+
+    /// If `Some`, checking is aborted with an `Error::Timeout` if it takes longer than the given
+    /// duration. The time spent parsing counts towards this limit, but parsing itself is never
+    /// interrupted.
+    pub timeout: Option<Duration>,
+
+    /// If `Some(n)`, comparisons of terms modulo reordering of equalities or alpha-equivalence give
+    /// up after recursing `n` levels deep, and the terms are considered different. This prevents
+    /// pathological terms from taking too long to check or overflowing the stack, but may cause
+    /// valid steps that involve very deep terms to be rejected.
+    pub deep_eq_depth_limit: Option<usize>,
+
+    /// If `Some(n)`, parsing fails with a `ParserError::TermTooDeep` error if any term in the
+    /// problem or proof, as written in the input, is nested more than `n` levels deep. Since terms
+    /// are parsed recursively, this prevents adversarial inputs from overflowing the parser's
+    /// stack. The limit doesn't apply to the terms that result from expanding function definitions
+    /// or `let` terms, so these may still be deeper than `n`.
+    pub max_term_depth: Option<usize>,
+
+    /// If `true`, the premise matched by each `assume` command modulo reordering of equalities is
+    /// cached, so terms that are assumed many times are only compared with the problem premises
+    /// once. The cache keeps the most recently used terms, up to `deep_eq_cache_size` entries.
+    pub use_deep_eq_cache: bool,
+
+    /// The maximum number of terms kept when `use_deep_eq_cache` is enabled. If `None`,
+    /// [`DEFAULT_DEEP_EQ_CACHE_SIZE`] is used.
+    pub deep_eq_cache_size: Option<usize>,
 }
 
+/// The default value of [`CarcaraOptions::deep_eq_cache_size`].
+pub const DEFAULT_DEEP_EQ_CACHE_SIZE: usize = 1024;
+
 impl CarcaraOptions {
     /// Constructs a new `CarcaraOptions` with all options set to `false`.
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sets the `timeout` option. See [`CarcaraOptions::timeout`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the number of entries in the `deep_eq` cache, or `None` if the cache is disabled.
+    /// See [`CarcaraOptions::use_deep_eq_cache`].
+    pub fn deep_eq_cache_capacity(&self) -> Option<usize> {
+        self.use_deep_eq_cache.then(|| {
+            self.deep_eq_cache_size
+                .unwrap_or(DEFAULT_DEEP_EQ_CACHE_SIZE)
+        })
+    }
+}
+
+/// Formats a position to be included in an error message.
+fn format_position(pos: &Position) -> String {
+    match &pos.file_name {
+        Some(file) => format!("in {}, on line {}, column {}", file, pos.line, pos.column),
+        None => format!("on line {}, column {}", pos.line, pos.column),
+    }
 }
 
 fn wrap_parser_error_message(e: &ParserError, pos: &Position) -> String {
@@ -104,7 +187,37 @@ fn wrap_parser_error_message(e: &ParserError, pos: &Position) -> String {
     if matches!(e, ParserError::UnclosedSubproof(_)) {
         format!("parser error: {}", e)
     } else {
-        format!("parser error: {} (on line {}, column {})", e, pos.0, pos.1)
+        format!("parser error: {} ({})", e, format_position(pos))
+    }
+}
+
+fn wrap_multiple_parser_errors(errors: &[(ParserError, Position)]) -> String {
+    let mut result = format!("{} parser errors:", errors.len());
+    for (e, pos) in errors {
+        result.push_str("\n    ");
+        result.push_str(&wrap_parser_error_message(e, pos));
+    }
+    result
+}
+
+fn wrap_checker_error_message(
+    inner: &CheckerError,
+    rule: &str,
+    step: &str,
+    position: &Option<Position>,
+) -> String {
+    match position {
+        Some(pos) => format!(
+            "checking failed on step '{}' with rule '{}' ({}): {}",
+            step,
+            rule,
+            format_position(pos),
+            inner
+        ),
+        None => format!(
+            "checking failed on step '{}' with rule '{}': {}",
+            step, rule, inner
+        ),
     }
 }
 
@@ -116,51 +229,508 @@ pub enum Error {
     #[error("{}", wrap_parser_error_message(.0, .1))]
     Parser(ParserError, Position),
 
-    #[error("checking failed on step '{step}' with rule '{rule}': {inner}")]
+    /// Multiple errors found while parsing a proof with error recovery enabled. See
+    /// `CarcaraOptions::error_recovery`.
+    #[error("{}", wrap_multiple_parser_errors(.0))]
+    MultipleParserErrors(Vec<(ParserError, Position)>),
+
+    #[error("{}", wrap_checker_error_message(.inner, .rule, .step, .position))]
     Checker {
         inner: CheckerError,
         rule: String,
         step: String,
+
+        /// The position of the step in the proof file, if it is known. This is `None` for errors
+        /// in `assume` commands and subproof arguments.
+        position: Option<Position>,
     },
 
     // While this is a kind of checking error, it does not happen in a specific step like all other
     // checker errors, so we model it as a different variant
     #[error("checker error: proof does not conclude empty clause")]
     DoesNotReachEmptyClause,
+
+    /// A step depends on itself, through its premises. This can only happen in proofs that were
+    /// built or modified programmatically, since the parser only allows premises to refer to earlier
+    /// commands. See [`ast::Proof::topological_sort`].
+    #[error("proof is cyclic: step '{0}' depends on itself")]
+    CyclicProof(String),
+
+    /// Checking took longer than the limit given in `CarcaraOptions::timeout`.
+    #[error("checking timed out")]
+    Timeout,
+
+    /// The proof produced by elaboration is not valid in strict mode. This means there is a bug in
+    /// the elaborator, not in the original proof. See [`elaborate_and_verify`]. If the error
+    /// happened in a specific step, `step` and `rule` are its id and rule, otherwise they are
+    /// empty.
+    #[error("elaborated proof is invalid: {inner}")]
+    ElaborationVerificationFailed {
+        step: String,
+        rule: String,
+        inner: Box<Error>,
+    },
+
+    /// A configuration file could not be parsed, or one of its options has an invalid value. See
+    /// `CarcaraOptions::from_toml`.
+    #[cfg(feature = "toml")]
+    #[error("invalid configuration file: {0}")]
+    InvalidConfig(String),
+}
+
+// Since `io::Error` doesn't implement `Clone`, we can't derive it
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::Io(e) => Error::Io(utils::clone_io_error(e)),
+            Error::Parser(e, pos) => Error::Parser(e.clone(), pos.clone()),
+            Error::MultipleParserErrors(errors) => Error::MultipleParserErrors(errors.clone()),
+            Error::Checker { inner, rule, step, position } => Error::Checker {
+                inner: inner.clone(),
+                rule: rule.clone(),
+                step: step.clone(),
+                position: position.clone(),
+            },
+            Error::DoesNotReachEmptyClause => Error::DoesNotReachEmptyClause,
+            Error::CyclicProof(step) => Error::CyclicProof(step.clone()),
+            Error::Timeout => Error::Timeout,
+            Error::ElaborationVerificationFailed { step, rule, inner } => {
+                Error::ElaborationVerificationFailed {
+                    step: step.clone(),
+                    rule: rule.clone(),
+                    inner: inner.clone(),
+                }
+            }
+            #[cfg(feature = "toml")]
+            Error::InvalidConfig(message) => Error::InvalidConfig(message.clone()),
+        }
+    }
+}
+
+impl Error {
+    /// Sets the file name in the positions of a parser or checker error, unless they already have
+    /// one. Other errors are returned unchanged. This is used to tell apart errors in the problem
+    /// from errors in the proof, for example.
+    pub fn with_file_name(mut self, file_name: &str) -> Self {
+        let file_name: Arc<str> = file_name.into();
+        let set = |pos: &mut Position| {
+            pos.file_name.get_or_insert_with(|| file_name.clone());
+        };
+        match &mut self {
+            Error::Parser(_, pos) | Error::Checker { position: Some(pos), .. } => set(pos),
+            Error::MultipleParserErrors(errors) => errors.iter_mut().for_each(|(_, pos)| set(pos)),
+            _ => (),
+        }
+        self
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error {
+    /// Returns a JSON representation of this error, meant to be consumed by other tools. The
+    /// resulting object always contains the name of the error variant in the `"kind"` field and the
+    /// human-readable error message in the `"message"` field. Depending on the kind of error, it may
+    /// also contain:
+    /// - `"line"`, `"column"` and `"file"`, for parser errors and checker errors (these may be
+    ///   `null` if the position or file name is unknown);
+    /// - `"errors"`, for multiple parser errors. This is an array of objects, each containing the
+    ///   `"message"`, `"line"`, `"column"` and `"file"` of one error;
+    /// - `"step"`, `"rule"`, `"code"` and `"inner"`, for checker errors. The `"code"` field is the
+    ///   value returned by `CheckerError::error_code`, and `"inner"` is the message of the inner
+    ///   checker error.
+    /// - `"step"`, `"rule"` and `"inner"`, for elaboration verification errors. In this case,
+    ///   `"inner"` is the JSON representation of the error found in the elaborated proof.
+    /// - `"step"`, for cyclic proofs.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        let message = self.to_string();
+        match self {
+            Error::Io(_) => json!({ "kind": "Io", "message": message }),
+            Error::Parser(_, pos) => json!({
+                "kind": "Parser",
+                "message": message,
+                "line": pos.line,
+                "column": pos.column,
+                "file": pos.file_name.as_deref(),
+            }),
+            Error::MultipleParserErrors(errors) => {
+                let errors: Vec<_> = errors
+                    .iter()
+                    .map(|(e, pos)| {
+                        json!({
+                            "message": wrap_parser_error_message(e, pos),
+                            "line": pos.line,
+                            "column": pos.column,
+                            "file": pos.file_name.as_deref(),
+                        })
+                    })
+                    .collect();
+                json!({ "kind": "MultipleParserErrors", "message": message, "errors": errors })
+            }
+            Error::Checker { inner, rule, step, position } => json!({
+                "kind": "Checker",
+                "message": message,
+                "step": step,
+                "rule": rule,
+                "code": inner.error_code(),
+                "inner": inner.to_string(),
+                "line": position.as_ref().map(|pos| pos.line),
+                "column": position.as_ref().map(|pos| pos.column),
+                "file": position.as_ref().and_then(|pos| pos.file_name.as_deref()),
+            }),
+            Error::DoesNotReachEmptyClause => {
+                json!({ "kind": "DoesNotReachEmptyClause", "message": message })
+            }
+            Error::CyclicProof(step) => {
+                json!({ "kind": "CyclicProof", "message": message, "step": step })
+            }
+            Error::Timeout => json!({ "kind": "Timeout", "message": message }),
+            Error::ElaborationVerificationFailed { step, rule, inner } => json!({
+                "kind": "ElaborationVerificationFailed",
+                "message": message,
+                "step": step,
+                "rule": rule,
+                "inner": inner.to_json(),
+            }),
+            #[cfg(feature = "toml")]
+            Error::InvalidConfig(_) => json!({ "kind": "InvalidConfig", "message": message }),
+        }
+    }
+}
+
+/// Calls `f`, passing it a flag that is set once `timeout` elapses. If `timeout` is `None`, no flag
+/// is created. The background thread that sets the flag stops as soon as `f` returns.
+fn with_timeout_flag<T, F>(timeout: Option<Duration>, f: F) -> T
+where
+    F: FnOnce(Option<Arc<AtomicBool>>) -> T,
+{
+    let Some(timeout) = timeout else {
+        return f(None);
+    };
+    let flag = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel::<()>();
+    let handle = {
+        let flag = flag.clone();
+        thread::spawn(move || {
+            // The sender is never used, so `recv_timeout` only returns early if it is dropped,
+            // which means `f` has already returned
+            if receiver.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        })
+    };
+    let result = f(Some(flag));
+    drop(sender);
+    handle.join().unwrap();
+    result
+}
+
+/// Builds the checker configuration from the checking options in `options`.
+fn checker_config<'c>(
+    options: &CarcaraOptions,
+    abort_flag: Option<Arc<AtomicBool>>,
+) -> checker::Config<'c> {
+    checker::Config::new()
+        .strict(options.strict)
+        .skip_unknown_rules(options.skip_unknown_rules)
+        .lia_via_cvc5(options.lia_via_cvc5)
+        .max_steps(options.max_steps)
+        .deep_eq_depth_limit(options.deep_eq_depth_limit)
+        .deep_eq_cache_size(options.deep_eq_cache_capacity())
+        .abort_flag(abort_flag)
+}
+
+/// Parses a problem and proof using `parse`, and then calls `run` with a checker configured from
+/// `options`. Errors found by the checker are reported with `proof_name` as their file name.
+fn parse_and_check<T, P, R>(
+    options: &CarcaraOptions,
+    proof_name: &str,
+    parse: P,
+    run: R,
+) -> CarcaraResult<T>
+where
+    P: FnOnce() -> CarcaraResult<(ast::ProblemPrelude, ast::Proof, ast::TermPool)>,
+    R: FnOnce(&mut checker::ProofChecker, ast::Proof) -> CarcaraResult<T>,
+{
+    with_timeout_flag(options.timeout, |abort_flag| {
+        let (prelude, proof, mut pool) = parse()?;
+        let config = checker_config(options, abort_flag);
+        let mut checker = checker::ProofChecker::new(&mut pool, config, prelude);
+        run(&mut checker, proof).map_err(|e| e.with_file_name(proof_name))
+    })
 }
 
 pub fn check<T: io::BufRead>(problem: T, proof: T, options: CarcaraOptions) -> Result<bool, Error> {
-    let (prelude, proof, mut pool) = parser::parse_instance(
+    check_named(
         problem,
         proof,
+        parser::DEFAULT_PROBLEM_NAME,
+        parser::DEFAULT_PROOF_NAME,
+        options,
+    )
+}
+
+/// Similar to [`check`], but uses the given names for the problem and proof files in the positions
+/// reported in errors.
+pub fn check_named<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    problem_name: &str,
+    proof_name: &str,
+    options: CarcaraOptions,
+) -> Result<bool, Error> {
+    parse_and_check(
+        &options,
+        proof_name,
+        || {
+            parser::parse_instance_with_options_named(
+                problem,
+                proof,
+                problem_name,
+                proof_name,
+                &options,
+            )
+        },
+        |checker, proof| checker.check(&proof),
+    )
+}
+
+/// Similar to [`check`], but reads the problem and proof from the files at the given paths. Errors
+/// when opening or reading a file mention its path. See [`parser::parse_instance_from_paths`].
+pub fn check_from_paths(
+    problem: &Path,
+    proof: &Path,
+    options: CarcaraOptions,
+) -> Result<bool, Error> {
+    parse_and_check(
+        &options,
+        &proof.to_string_lossy(),
+        || parser::parse_instance_from_paths(problem, proof, &options),
+        |checker, proof| checker.check(&proof),
+    )
+}
+
+/// Checks many proofs for the same problem. The problem is only parsed once, and each proof is
+/// parsed and checked using a copy of the resulting term pool. The returned iterator yields the
+/// result of checking each proof, lazily and in order. If the problem itself can't be parsed, the
+/// iterator instead yields only the error.
+///
+/// The timeout in `options`, if any, applies to each proof individually.
+pub fn check_many<'a, I>(
+    problem: impl io::BufRead,
+    proofs: I,
+    options: &'a CarcaraOptions,
+) -> impl Iterator<Item = CarcaraResult<bool>> + 'a
+where
+    I: IntoIterator,
+    I::IntoIter: 'a,
+    I::Item: io::BufRead,
+{
+    let problem = parser::ParsedProblem::new(
+        problem,
         options.apply_function_defs,
         options.expand_lets,
         options.allow_int_real_subtyping,
-    )?;
+        options.max_term_depth,
+    );
+    let results: Box<dyn Iterator<Item = CarcaraResult<bool>>> = match problem {
+        Ok(problem) => Box::new(proofs.into_iter().map(move |proof| {
+            parse_and_check(
+                options,
+                parser::DEFAULT_PROOF_NAME,
+                || {
+                    if options.error_recovery {
+                        problem.parse_proof_collecting_errors(proof)
+                    } else {
+                        problem.parse_proof(proof)
+                    }
+                },
+                |checker, proof| checker.check(&proof),
+            )
+        })),
+        Err(e) => Box::new(std::iter::once(Err(e))),
+    };
+    results
+}
 
-    let config = checker::Config::new()
-        .strict(options.strict)
-        .skip_unknown_rules(options.skip_unknown_rules)
-        .lia_via_cvc5(options.lia_via_cvc5);
-    checker::ProofChecker::new(&mut pool, config, prelude).check(&proof)
+/// Checks a fragment of a proof, for example a subproof extracted from a larger proof, without
+/// the rest of the proof or the original problem. The `assume` commands in the fragment may only
+/// introduce terms in `premises`, and the premises of each step must refer to earlier commands in
+/// the fragment, or the checker returns an error. The terms in the fragment must have been added
+/// to `pool`. Unlike [`check`], the fragment doesn't need to reach the empty clause. Returns `true`
+/// if the fragment is holey.
+///
+/// Since there is no problem, the parsing options in `options` are ignored, and `lia_generic`
+/// steps can only be checked by cvc5 if they don't use any declared sorts or functions.
+pub fn check_proof_fragment(
+    steps: &[ast::ProofCommand],
+    premises: &AHashSet<ast::Rc<ast::Term>>,
+    pool: &mut ast::TermPool,
+    options: CarcaraOptions,
+) -> CarcaraResult<bool> {
+    let proof = ast::Proof {
+        premises: premises.clone(),
+        commands: steps.to_vec(),
+    };
+    with_timeout_flag(options.timeout, |abort_flag| {
+        let config = checker_config(&options, abort_flag);
+        let prelude = ast::ProblemPrelude::default();
+        checker::ProofChecker::new(pool, config, prelude).check_fragment(&proof)
+    })
 }
 
+/// Checks and elaborates a proof. Returns `true` if the proof is holey, together with the elaborated
+/// proof and statistics about the elaboration.
 pub fn check_and_elaborate<T: io::BufRead>(
     problem: T,
     proof: T,
     options: CarcaraOptions,
-) -> Result<(bool, ast::Proof), Error> {
-    let (prelude, proof, mut pool) = parser::parse_instance(
+) -> Result<(bool, ast::Proof, checker::ElaborationStats), Error> {
+    check_and_elaborate_named(
         problem,
         proof,
-        options.apply_function_defs,
-        options.expand_lets,
-        options.allow_int_real_subtyping,
-    )?;
+        parser::DEFAULT_PROBLEM_NAME,
+        parser::DEFAULT_PROOF_NAME,
+        options,
+    )
+}
 
-    let config = checker::Config::new()
-        .strict(options.strict)
-        .skip_unknown_rules(options.skip_unknown_rules)
-        .lia_via_cvc5(options.lia_via_cvc5);
-    checker::ProofChecker::new(&mut pool, config, prelude).check_and_elaborate(proof)
+/// Similar to [`check_and_elaborate`], but uses the given names for the problem and proof files in
+/// the positions reported in errors.
+// The closure can't be replaced by the method, since the checker's lifetime is chosen by
+// `parse_and_check`
+#[allow(clippy::redundant_closure_for_method_calls)]
+pub fn check_and_elaborate_named<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    problem_name: &str,
+    proof_name: &str,
+    options: CarcaraOptions,
+) -> Result<(bool, ast::Proof, checker::ElaborationStats), Error> {
+    // `max_steps` is ignored when elaborating
+    parse_and_check(
+        &options,
+        proof_name,
+        || {
+            parser::parse_instance_with_options_named(
+                problem,
+                proof,
+                problem_name,
+                proof_name,
+                &options,
+            )
+        },
+        |checker, proof| checker.check_and_elaborate_with_stats(proof),
+    )
+}
+
+/// Similar to [`check_and_elaborate`], but reads the problem and proof from the files at the given
+/// paths. Errors when opening or reading a file mention its path. See
+/// [`parser::parse_instance_from_paths`].
+// The closure can't be replaced by the method, since the checker's lifetime is chosen by
+// `parse_and_check`
+#[allow(clippy::redundant_closure_for_method_calls)]
+pub fn check_and_elaborate_from_paths(
+    problem: &Path,
+    proof: &Path,
+    options: CarcaraOptions,
+) -> Result<(bool, ast::Proof, checker::ElaborationStats), Error> {
+    parse_and_check(
+        &options,
+        &proof.to_string_lossy(),
+        || parser::parse_instance_from_paths(problem, proof, &options),
+        |checker, proof| checker.check_and_elaborate_with_stats(proof),
+    )
+}
+
+/// Checks and elaborates a proof, like [`check_and_elaborate`], and then checks the elaborated
+/// proof again in strict mode, without skipping unknown rules. If the elaborated proof is invalid,
+/// an `Error::ElaborationVerificationFailed` is returned. Errors in the original proof are returned
+/// as usual.
+pub fn elaborate_and_verify(
+    problem: impl io::BufRead,
+    proof: impl io::BufRead,
+    options: CarcaraOptions,
+) -> CarcaraResult<(bool, ast::Proof)> {
+    // `parse_instance` expects both inputs to be of the same type
+    let (problem, proof): (Box<dyn io::BufRead>, Box<dyn io::BufRead>) =
+        (Box::new(problem), Box::new(proof));
+    with_timeout_flag(options.timeout, |abort_flag| {
+        let (prelude, proof, mut pool) =
+            parser::parse_instance_with_options(problem, proof, &options)?;
+        let config = checker_config(&options, abort_flag.clone());
+        let (is_holey, elaborated) = checker::ProofChecker::new(&mut pool, config, prelude.clone())
+            .check_and_elaborate(proof)
+            .map_err(|e| e.with_file_name(parser::DEFAULT_PROOF_NAME))?;
+
+        let config = checker_config(&options, abort_flag)
+            .strict(true)
+            .skip_unknown_rules(false)
+            .max_steps(None);
+        match checker::ProofChecker::new(&mut pool, config, prelude).check(&elaborated) {
+            Ok(_) => Ok((is_holey, elaborated)),
+            Err(Error::Timeout) => Err(Error::Timeout),
+            Err(inner) => {
+                let (step, rule) = match &inner {
+                    Error::Checker { step, rule, .. } => (step.clone(), rule.clone()),
+                    _ => (String::new(), String::new()),
+                };
+                let inner = Box::new(inner);
+                Err(Error::ElaborationVerificationFailed { step, rule, inner })
+            }
+        }
+    })
+}
+
+/// Checks and elaborates a proof one command at a time, writing the elaborated proof to `out`.
+/// Unlike [`check_and_elaborate`], this never holds the entire proof in memory, so it can be used
+/// with very large proof files. Returns `true` if the proof is holey.
+///
+/// Since each command is written as soon as it is checked, part of the elaborated proof may
+/// already have been written when an error is found. The elaborated proof is not pruned, and is
+/// written without term sharing. See [`checker::StreamingElaborator`] for more details. The
+/// `error_recovery` option is ignored.
+pub fn elaborate_streaming<R: io::BufRead, W: io::Write>(
+    problem: R,
+    proof: R,
+    mut out: W,
+    options: CarcaraOptions,
+) -> CarcaraResult<bool> {
+    with_timeout_flag(options.timeout, |abort_flag| {
+        let mut pool = ast::TermPool::new();
+        let in_problem = |e: Error| e.with_file_name(parser::DEFAULT_PROBLEM_NAME);
+        let in_proof = |e: Error| e.with_file_name(parser::DEFAULT_PROOF_NAME);
+        let mut parser = parser::Parser::new(
+            &mut pool,
+            problem,
+            options.apply_function_defs,
+            options.expand_lets,
+            options.allow_int_real_subtyping,
+        )
+        .map_err(in_problem)?;
+        parser.set_max_term_depth(options.max_term_depth);
+        let (prelude, premises) = parser.parse_problem().map_err(in_problem)?;
+        parser.reset(proof).map_err(in_proof)?;
+
+        // The parser and the checker can't both hold a mutable reference to the same term pool. So,
+        // the checker is given an empty pool, and the contents of the two pools are swapped before
+        // and after parsing each command. That way, the terms are always added to the same pool
+        let mut checker_pool = ast::TermPool::default();
+        let config = checker_config(&options, abort_flag);
+        let mut elaborator =
+            checker::StreamingElaborator::new(&mut checker_pool, config, prelude, premises);
+        std::mem::swap(parser.pool(), elaborator.pool());
+        loop {
+            std::mem::swap(parser.pool(), elaborator.pool());
+            let command = parser.next_command();
+            std::mem::swap(parser.pool(), elaborator.pool());
+            match command {
+                Some(command) => elaborator
+                    .push_command(command.map_err(in_proof)?, &mut out)
+                    .map_err(in_proof)?,
+                None => return elaborator.finish().map_err(in_proof),
+            }
+        }
+    })
 }
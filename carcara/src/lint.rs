@@ -0,0 +1,262 @@
+//! A linter that reports stylistic and structural issues in proofs, such as steps that are never
+//! used. These issues don't make a proof invalid, but they may indicate that it can be simplified.
+//! This module also implements [`remove_dead_steps`], which removes the unused steps of a proof.
+
+use crate::ast::*;
+use crate::checker::elaboration::{diff::apply_diff, pruning::prune_proof_at};
+use ahash::{AHashMap, AHashSet};
+use std::fmt;
+
+/// An issue found by [`proof_lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintWarning {
+    /// A step whose conclusion is never used as a premise. The associated string is the step id.
+    UnusedStep(String),
+
+    /// An `assume` command whose term is the same as that of an earlier `assume` in the same
+    /// subproof. The associated strings are the ids of the duplicate and of the earlier command.
+    DuplicateAssume(String, String),
+
+    /// A `contraction` step whose premise has no repeated literals, and therefore does nothing. The
+    /// associated string is the step id.
+    RedundantContraction(String),
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::UnusedStep(id) => write!(f, "step '{}' is never used", id),
+            LintWarning::DuplicateAssume(id, earlier) => {
+                write!(f, "assume '{}' duplicates earlier assume '{}'", id, earlier)
+            }
+            LintWarning::RedundantContraction(id) => {
+                write!(
+                    f,
+                    "contraction step '{}' has no repeated literals to remove",
+                    id
+                )
+            }
+        }
+    }
+}
+
+/// Returns the addresses of all commands in the proof that are used by some step, either as a
+/// premise, as a discharged assumption, or implicitly as the step before the end of a subproof.
+/// If a step references a subproof, its end step is considered used.
+pub(crate) fn used_commands(proof: &Proof) -> AHashSet<*const ProofCommand> {
+    let mut used = AHashSet::new();
+    let mut iter = proof.iter();
+    while let Some(command) = iter.next() {
+        if let ProofCommand::Step(step) = command {
+            for &p in step.premises.iter().chain(&step.discharge) {
+                let premise = match iter.get_premise(p) {
+                    ProofCommand::Subproof(s) => s.commands.last().unwrap(),
+                    other => other,
+                };
+                used.insert(premise as *const _);
+            }
+        }
+        if iter.is_end_step() {
+            let commands = iter.current_subproof().unwrap();
+            if let [.., previous, _] = commands {
+                used.insert(previous as *const _);
+            }
+        }
+    }
+    used
+}
+
+/// Checks a proof for issues that don't make it invalid, and returns a list of warnings, in the
+/// order in which the offending commands appear. Returns an empty vector if no issues are found.
+///
+/// The last command of the proof is never reported as unused, since it is the proof's conclusion.
+pub fn proof_lint(proof: &Proof) -> Vec<LintWarning> {
+    let used = used_commands(proof);
+    let last = proof.commands.last().map(|c| c as *const ProofCommand);
+
+    let mut warnings = Vec::new();
+    let mut assumes: Vec<AHashMap<&Rc<Term>, &str>> = vec![AHashMap::new()];
+    let mut iter = proof.iter();
+    while let Some(command) = iter.next() {
+        match command {
+            ProofCommand::Assume { id, term } => {
+                let scope = assumes.last_mut().unwrap();
+                if let Some(earlier) = scope.get(term) {
+                    warnings.push(LintWarning::DuplicateAssume(
+                        id.clone(),
+                        (*earlier).to_owned(),
+                    ));
+                } else {
+                    scope.insert(term, id);
+                }
+            }
+            ProofCommand::Step(step) => {
+                let ptr = command as *const _;
+                if !used.contains(&ptr) && Some(ptr) != last {
+                    warnings.push(LintWarning::UnusedStep(step.id.clone()));
+                }
+                if step.rule == "contraction" && step.premises.len() == 1 {
+                    let premise = iter.get_premise(step.premises[0]).clause();
+                    let distinct: AHashSet<_> = premise.iter().collect();
+                    if distinct.len() == premise.len() {
+                        warnings.push(LintWarning::RedundantContraction(step.id.clone()));
+                    }
+                }
+            }
+            ProofCommand::Subproof(_) => {
+                assumes.push(AHashMap::new());
+                continue;
+            }
+        }
+        if iter.is_end_step() {
+            assumes.pop();
+        }
+    }
+    warnings
+}
+
+/// Removes the commands that are not needed to derive the conclusion of the proof. The conclusion
+/// is the first step in the root proof that concludes the empty clause or, if there is no such
+/// step, the last command of the proof. A command is live if it is the conclusion, or if it is
+/// (transitively) used as a premise by a live step. This uses the same pruning as the elaborator,
+/// so if a subproof is live, its `assume` commands and its last two commands are always kept.
+///
+/// The premise indices of the remaining steps are updated, so the resulting proof can be checked
+/// normally.
+pub fn remove_dead_steps(proof: Proof) -> Proof {
+    let conclusion = proof
+        .commands
+        .iter()
+        .position(|c| c.is_step() && c.clause().is_empty())
+        .or_else(|| proof.commands.len().checked_sub(1));
+    let Some(conclusion) = conclusion else {
+        return proof;
+    };
+    let diff = prune_proof_at(&proof.commands, conclusion);
+    let commands = apply_diff(diff, proof.commands);
+    Proof { commands, ..proof }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_instance;
+
+    fn lint(proof: &str) -> Vec<LintWarning> {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert p)
+            (assert (not p))
+        ";
+        let (_, proof, _) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        proof_lint(&proof)
+    }
+
+    #[test]
+    fn test_clean_proof() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (anchor :step t3)
+            (assume t3.a0 q)
+            (step t3.t1 (cl q) :rule hole :premises (t3.a0))
+            (step t3 (cl (not q) q) :rule subproof :discharge (t3.a0))
+            (step t4 (cl q q) :rule hole :premises (t3))
+            (step t5 (cl q) :rule contraction :premises (t4))
+            (step t6 (cl) :rule resolution :premises (h1 h2 t5))
+        ";
+        assert_eq!(lint(proof), []);
+    }
+
+    #[test]
+    fn test_unused_step() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl p) :rule hole :premises (h1))
+            (anchor :step t4)
+            (assume t4.a0 q)
+            (step t4 (cl (not q) q) :rule subproof :discharge (t4.a0))
+            (step t5 (cl) :rule resolution :premises (h1 h2))
+        ";
+        let expected = [
+            LintWarning::UnusedStep("t3".to_owned()),
+            LintWarning::UnusedStep("t4".to_owned()),
+        ];
+        assert_eq!(lint(proof), expected);
+    }
+
+    #[test]
+    fn test_duplicate_assume() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (assume h3 p)
+            (anchor :step t4)
+            (assume t4.a0 p)
+            (assume t4.a1 p)
+            (step t4 (cl (not p) p) :rule subproof :discharge (t4.a0 t4.a1))
+            (step t5 (cl) :rule resolution :premises (h1 h2 t4))
+        ";
+        let expected = [
+            LintWarning::DuplicateAssume("h3".to_owned(), "h1".to_owned()),
+            LintWarning::DuplicateAssume("t4.a1".to_owned(), "t4.a0".to_owned()),
+        ];
+        assert_eq!(lint(proof), expected);
+    }
+
+    #[test]
+    fn test_redundant_contraction() {
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl p) :rule contraction :premises (h1))
+            (step t4 (cl) :rule resolution :premises (t3 h2))
+        ";
+        let expected = [LintWarning::RedundantContraction("t3".to_owned())];
+        assert_eq!(lint(proof), expected);
+    }
+
+    #[test]
+    fn test_remove_dead_steps() {
+        use crate::checker::{Config, ProofChecker};
+
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert p)
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (step t3 (cl p p) :rule hole)
+            (anchor :step t4)
+            (assume t4.a0 q)
+            (step t4.t1 (cl q) :rule hole :premises (t4.a0 t3))
+            (step t4 (cl (not q) q) :rule subproof :discharge (t4.a0))
+            (step t5 (cl p) :rule contraction :premises (t3))
+            (anchor :step t6)
+            (assume t6.a0 q)
+            (step t6 (cl (not q) q) :rule subproof :discharge (t6.a0))
+            (step t7 (cl p) :rule hole :premises (t4 t5))
+            (step t8 (cl) :rule resolution :premises (t7 h2))
+            (step t9 (cl q) :rule hole :premises (t6))
+        ";
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let minimized = remove_dead_steps(proof.clone());
+
+        let ids: Vec<_> = minimized.iter().map(ProofCommand::id).collect();
+        let expected = ["h2", "t3", "t4", "t4.a0", "t4.t1", "t4", "t5", "t7", "t8"];
+        assert_eq!(ids, expected);
+        assert!(proof_lint(&minimized)
+            .iter()
+            .all(|w| !matches!(w, LintWarning::UnusedStep(_))));
+
+        let mut checker = ProofChecker::new(&mut pool, Config::new(), prelude);
+        assert!(matches!(checker.check(&minimized), Ok(true)));
+    }
+}
@@ -1,7 +1,7 @@
 //! The types for parser errors.
 
 use crate::{
-    ast::{Identifier, Sort},
+    ast::{Identifier, Rc, Sort, Term},
     parser::Token,
     utils::Range,
 };
@@ -10,7 +10,7 @@ use std::fmt;
 use thiserror::Error;
 
 /// The error type for the parser.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ParserError {
     /// The lexer encountered an unexpected character.
     #[error("unexpected character: '{0}'")]
@@ -49,6 +49,15 @@ pub enum ParserError {
     #[error("sort error: {0}")]
     SortError(#[from] SortError),
 
+    /// A term was used in a position that requires a different sort, for example as the argument
+    /// of a function application.
+    #[error("term '{term}' has sort '{got_sort}' but expected '{expected_sort}'")]
+    TypeMismatch {
+        expected_sort: Sort,
+        got_sort: Sort,
+        term: Rc<Term>,
+    },
+
     /// A term that is not a function was used as a function.
     #[error("'{0}' is not a function sort")]
     NotAFunction(Sort), // TODO: This should also carry the actual function term
@@ -93,6 +102,11 @@ pub enum ParserError {
     /// An unknown attribute was given to an annotated term.
     #[error("unknown attribute: ':{0}'")]
     UnknownAttribute(String),
+
+    /// A term, as written in the input, is nested more deeply than the limit given by
+    /// `max_term_depth`.
+    #[error("term is nested more than {0} levels deep")]
+    TermTooDeep(usize),
 }
 
 /// Returns an error if the length of `sequence` is not in the `expected` range.
@@ -108,8 +122,22 @@ where
     }
 }
 
+/// Returns a `ParserError::TypeMismatch` error if the sort of `term`, given by `got`, does not
+/// equal `expected`.
+pub fn assert_term_sort(expected: &Sort, term: &Rc<Term>, got: &Sort) -> Result<(), ParserError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(ParserError::TypeMismatch {
+            expected_sort: expected.clone(),
+            got_sort: got.clone(),
+            term: term.clone(),
+        })
+    }
+}
+
 /// An error in sort checking.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub struct SortError {
     /// The possible sorts that were expected.
     pub expected: Vec<Sort>,
@@ -120,17 +148,14 @@ pub struct SortError {
 
 impl fmt::Display for SortError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.expected.as_slice() {
-            [] => unreachable!(),
-            [p] => write!(f, "expected '{}', got '{}'", p, self.got),
-            [first, middle @ .., last] => {
-                write!(f, "expected '{}'", first)?;
-                for p in middle {
-                    write!(f, ", '{}'", p)?;
-                }
-                write!(f, " or '{}', got '{}'", last, self.got)
+        write!(f, "expected sort ")?;
+        for (i, p) in self.expected.iter().enumerate() {
+            if i > 0 {
+                write!(f, " or ")?;
             }
+            write!(f, "'{}'", p)?;
         }
+        write!(f, ", got '{}'", self.got)
     }
 }
 
@@ -5,10 +5,11 @@ use rug::{ops::Pow, Integer, Rational};
 use std::{
     io::{self, BufRead},
     str::FromStr,
+    sync::Arc,
 };
 
 /// A token in the SMT-LIB and Alethe formats.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     /// The `(` token.
     OpenParen,
@@ -125,8 +126,25 @@ impl_str_conversion_traits!(Reserved {
     SetLogic: "set-logic",
 });
 
-/// Represents a position (line and column numbers) in the source input.
-pub type Position = (usize, usize);
+/// Represents a position (line and column numbers) in the source input. Positions reported in
+/// errors may also contain the name of the input file, to distinguish between positions in the
+/// problem and in the proof, for example. See [`crate::Error::with_file_name`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+
+    /// The name of the input file, if known.
+    pub file_name: Option<Arc<str>>,
+}
+
+impl Position {
+    /// Constructs a new `Position` with the given line and column numbers, and no file name.
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column, file_name: None }
+    }
+}
 
 /// A lexer for the SMT-LIB and Alethe formats.
 pub struct Lexer<R> {
@@ -148,7 +166,7 @@ impl<R: BufRead> Lexer<R> {
                 input,
                 current_line: None,
                 current_char: None,
-                position: (0, 0),
+                position: Position::new(0, 0),
             })
         } else {
             let mut line = buf.chars().collect::<Vec<_>>().into_iter();
@@ -157,7 +175,7 @@ impl<R: BufRead> Lexer<R> {
                 input,
                 current_line: Some(line),
                 current_char,
-                position: (1, 1),
+                position: Position::new(1, 1),
             })
         }
     }
@@ -172,7 +190,7 @@ impl<R: BufRead> Lexer<R> {
         }
 
         let new = if let Some(line) = &mut self.current_line {
-            self.position.1 += 1;
+            self.position.column += 1;
             line.next()
         } else {
             None
@@ -190,8 +208,8 @@ impl<R: BufRead> Lexer<R> {
         } else {
             let line = buf.chars().collect::<Vec<_>>().into_iter();
             self.current_line = Some(line);
-            self.position.0 += 1;
-            self.position.1 = 0;
+            self.position.line += 1;
+            self.position.column = 0;
         }
         Ok(())
     }
@@ -240,7 +258,7 @@ impl<R: BufRead> Lexer<R> {
     /// Reads a token from the input source.
     pub fn next_token(&mut self) -> CarcaraResult<(Token, Position)> {
         self.consume_whitespace()?;
-        let start_position = self.position;
+        let start_position = self.position.clone();
         let token = match self.current_char {
             Some('(') => {
                 self.next_char()?;
@@ -259,7 +277,7 @@ impl<R: BufRead> Lexer<R> {
             None => Ok(Token::Eof),
             Some(other) => Err(Error::Parser(
                 ParserError::UnexpectedChar(other),
-                self.position,
+                self.position.clone(),
             )),
         }?;
         Ok((token, start_position))
@@ -282,9 +300,12 @@ impl<R: BufRead> Lexer<R> {
         match self.current_char {
             Some('\\') => Err(Error::Parser(
                 ParserError::BackslashInQuotedSymbol,
-                self.position,
+                self.position.clone(),
+            )),
+            None => Err(Error::Parser(
+                ParserError::EofInQuotedSymbol,
+                self.position.clone(),
             )),
-            None => Err(Error::Parser(ParserError::EofInQuotedSymbol, self.position)),
             Some('|') => {
                 self.next_char()?;
                 Ok(Token::Symbol(symbol))
@@ -308,11 +329,16 @@ impl<R: BufRead> Lexer<R> {
         let base = match self.next_char()? {
             Some('b') => 2,
             Some('x') => 16,
-            None => return Err(Error::Parser(ParserError::EofInNumeral, self.position)),
+            None => {
+                return Err(Error::Parser(
+                    ParserError::EofInNumeral,
+                    self.position.clone(),
+                ))
+            }
             Some(other) => {
                 return Err(Error::Parser(
                     ParserError::UnexpectedChar(other),
-                    self.position,
+                    self.position.clone(),
                 ))
             }
         };
@@ -327,7 +353,7 @@ impl<R: BufRead> Lexer<R> {
         if int_part.len() > 1 && int_part.starts_with('0') {
             return Err(Error::Parser(
                 ParserError::LeadingZero(int_part),
-                self.position,
+                self.position.clone(),
             ));
         }
 
@@ -350,7 +376,10 @@ impl<R: BufRead> Lexer<R> {
         loop {
             result += &self.read_chars_while(|c| c != '"')?;
             if self.current_char.is_none() {
-                return Err(Error::Parser(ParserError::EofInString, self.position));
+                return Err(Error::Parser(
+                    ParserError::EofInString,
+                    self.position.clone(),
+                ));
             }
             self.next_char()?; // Consume `"`
             if self.current_char == Some('"') {
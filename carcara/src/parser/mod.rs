@@ -10,24 +10,58 @@ pub use lexer::{Lexer, Position, Reserved, Token};
 use crate::{
     ast::*,
     utils::{HashCache, SymbolTable},
-    CarcaraResult, Error,
+    CarcaraOptions, CarcaraResult, Error,
 };
 use ahash::{AHashMap, AHashSet};
-use error::assert_num_args;
+use error::{assert_num_args, assert_term_sort};
 use rug::Integer;
-use std::{io::BufRead, str::FromStr};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
 
 /// Parses an SMT problem instance (in the SMT-LIB format) and its associated proof (in the Alethe
 /// format).
 ///
 /// This returns the parsed proof, as well as the `TermPool` used in parsing. Can take any type that
-/// implements `BufRead`.
+/// implements `BufRead`. In error positions, the problem and proof are called `<problem>` and
+/// `<proof>`. To use other names, see [`parse_instance_named`].
 pub fn parse_instance<T: BufRead>(
     problem: T,
     proof: T,
     apply_function_defs: bool,
     expand_lets: bool,
     allow_int_real_subtyping: bool,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    parse_instance_named(
+        problem,
+        proof,
+        DEFAULT_PROBLEM_NAME,
+        DEFAULT_PROOF_NAME,
+        apply_function_defs,
+        expand_lets,
+        allow_int_real_subtyping,
+    )
+}
+
+/// The file name used in error positions for the problem, if no other name is given.
+pub(crate) const DEFAULT_PROBLEM_NAME: &str = "<problem>";
+
+/// The file name used in error positions for the proof, if no other name is given.
+pub(crate) const DEFAULT_PROOF_NAME: &str = "<proof>";
+
+/// Similar to [`parse_instance`], but uses the given names for the problem and proof files in the
+/// positions reported in errors.
+pub fn parse_instance_named<T: BufRead>(
+    problem: T,
+    proof: T,
+    problem_name: &str,
+    proof_name: &str,
+    apply_function_defs: bool,
+    expand_lets: bool,
+    allow_int_real_subtyping: bool,
 ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
     let mut pool = TermPool::new();
     let mut parser = Parser::new(
@@ -36,16 +70,278 @@ pub fn parse_instance<T: BufRead>(
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
-    )?;
-    let (prelude, premises) = parser.parse_problem()?;
-    parser.reset(proof)?;
-    let commands = parser.parse_proof()?;
+    )
+    .map_err(|e| e.with_file_name(problem_name))?;
+    let (prelude, premises) = parser
+        .parse_problem()
+        .map_err(|e| e.with_file_name(problem_name))?;
+    let commands = parser
+        .reset(proof)
+        .and_then(|()| parser.parse_proof())
+        .map_err(|e| e.with_file_name(proof_name))?;
 
     let proof = Proof { premises, commands };
     Ok((prelude, proof, pool))
 }
 
+/// Similar to [`parse_instance`], but takes the parsing options from a `CarcaraOptions`. If
+/// `error_recovery` is set, the proof is parsed using [`Parser::parse_proof_collecting_errors`],
+/// so multiple syntax errors in the proof may be reported at once. `max_term_depth` is given to
+/// [`Parser::set_max_term_depth`].
+pub fn parse_instance_with_options<T: BufRead>(
+    problem: T,
+    proof: T,
+    options: &CarcaraOptions,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    parse_instance_with_options_named(
+        problem,
+        proof,
+        DEFAULT_PROBLEM_NAME,
+        DEFAULT_PROOF_NAME,
+        options,
+    )
+}
+
+/// Similar to [`parse_instance_with_options`], but reads the problem and proof from the files at
+/// the given paths. The paths are used as the file names in the positions reported in errors, and
+/// errors when opening or reading a file also mention its path.
+pub fn parse_instance_from_paths(
+    problem_path: &Path,
+    proof_path: &Path,
+    options: &CarcaraOptions,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    parse_instance_with_options_named(
+        FileReader::open(problem_path)?,
+        FileReader::open(proof_path)?,
+        &problem_path.to_string_lossy(),
+        &proof_path.to_string_lossy(),
+        options,
+    )
+}
+
+/// A buffered file reader that adds the path of the file to the messages of the errors found when
+/// opening or reading it.
+struct FileReader<'p> {
+    inner: BufReader<File>,
+    path: &'p Path,
+}
+
+impl<'p> FileReader<'p> {
+    fn open(path: &'p Path) -> io::Result<Self> {
+        let inner = File::open(path).map_err(|e| add_path_to_error(path, e))?;
+        let inner = BufReader::new(inner);
+        Ok(Self { inner, path })
+    }
+}
+
+fn add_path_to_error(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+impl io::Read for FileReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner
+            .read(buf)
+            .map_err(|e| add_path_to_error(self.path, e))
+    }
+}
+
+impl BufRead for FileReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner
+            .fill_buf()
+            .map_err(|e| add_path_to_error(self.path, e))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+    }
+
+    // The default implementation reports invalid UTF-8 without going through `fill_buf`, so the
+    // error wouldn't mention the path
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.inner
+            .read_line(buf)
+            .map_err(|e| add_path_to_error(self.path, e))
+    }
+}
+
+/// Similar to [`parse_instance_with_options`], but uses the given names for the problem and proof
+/// files in the positions reported in errors.
+pub fn parse_instance_with_options_named<T: BufRead>(
+    problem: T,
+    proof: T,
+    problem_name: &str,
+    proof_name: &str,
+    options: &CarcaraOptions,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    let mut pool = TermPool::new();
+    let mut parser = Parser::new(
+        &mut pool,
+        problem,
+        options.apply_function_defs,
+        options.expand_lets,
+        options.allow_int_real_subtyping,
+    )
+    .map_err(|e| e.with_file_name(problem_name))?;
+    parser.set_max_term_depth(options.max_term_depth);
+    let (prelude, premises) = parser
+        .parse_problem()
+        .map_err(|e| e.with_file_name(problem_name))?;
+    let commands = parser
+        .reset(proof)
+        .and_then(|()| {
+            if options.error_recovery {
+                parser.parse_proof_collecting_errors()
+            } else {
+                parser.parse_proof()
+            }
+        })
+        .map_err(|e| e.with_file_name(proof_name))?;
+
+    let proof = Proof { premises, commands };
+    Ok((prelude, proof, pool))
+}
+
+/// A problem that was already parsed. This can be used to parse many proofs for the same problem,
+/// without having to parse the problem again for each of them.
+pub struct ParsedProblem {
+    pub prelude: ProblemPrelude,
+    pub premises: AHashSet<Rc<Term>>,
+    pool: TermPool,
+    state: ParserState,
+    interpret_integers_as_reals: bool,
+    apply_function_defs: bool,
+    expand_lets: bool,
+    allow_int_real_subtyping: bool,
+    max_term_depth: Option<usize>,
+}
+
+impl ParsedProblem {
+    /// Parses a problem. The flags have the same meaning as in [`parse_instance`], and are also
+    /// used when parsing proofs for this problem. `max_term_depth` is given to
+    /// [`Parser::set_max_term_depth`].
+    pub fn new<T: BufRead>(
+        problem: T,
+        apply_function_defs: bool,
+        expand_lets: bool,
+        allow_int_real_subtyping: bool,
+        max_term_depth: Option<usize>,
+    ) -> CarcaraResult<Self> {
+        let mut pool = TermPool::new();
+        let mut parser = Parser::new(
+            &mut pool,
+            problem,
+            apply_function_defs,
+            expand_lets,
+            allow_int_real_subtyping,
+        )
+        .map_err(|e| e.with_file_name(DEFAULT_PROBLEM_NAME))?;
+        parser.set_max_term_depth(max_term_depth);
+        let (prelude, premises) = parser
+            .parse_problem()
+            .map_err(|e| e.with_file_name(DEFAULT_PROBLEM_NAME))?;
+        let state = std::mem::take(&mut parser.state);
+        let interpret_integers_as_reals = parser.interpret_integers_as_reals;
+        Ok(Self {
+            prelude,
+            premises,
+            pool,
+            state,
+            interpret_integers_as_reals,
+            apply_function_defs,
+            expand_lets,
+            allow_int_real_subtyping,
+            max_term_depth,
+        })
+    }
+
+    /// Parses a proof for this problem. The proof terms are added to a copy of the term pool used
+    /// to parse the problem, which is returned along with the proof. The terms themselves are
+    /// shared with the original pool, but creating the copy still takes time linear in the number
+    /// of terms in it.
+    pub fn parse_proof<T: BufRead>(
+        &self,
+        proof: T,
+    ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+        self.parse_proof_with(proof, false)
+    }
+
+    /// Similar to [`ParsedProblem::parse_proof`], but the proof is parsed using
+    /// [`Parser::parse_proof_collecting_errors`].
+    pub fn parse_proof_collecting_errors<T: BufRead>(
+        &self,
+        proof: T,
+    ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+        self.parse_proof_with(proof, true)
+    }
+
+    fn parse_proof_with<T: BufRead>(
+        &self,
+        proof: T,
+        collect_errors: bool,
+    ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+        let mut pool = self.pool.clone();
+        let mut parser = Parser::new(
+            &mut pool,
+            proof,
+            self.apply_function_defs,
+            self.expand_lets,
+            self.allow_int_real_subtyping,
+        )
+        .map_err(|e| e.with_file_name(DEFAULT_PROOF_NAME))?;
+        parser.state = self.state.clone();
+        parser.interpret_integers_as_reals = self.interpret_integers_as_reals;
+        parser.set_max_term_depth(self.max_term_depth);
+        let commands = if collect_errors {
+            parser.parse_proof_collecting_errors()
+        } else {
+            parser.parse_proof()
+        }
+        .map_err(|e| e.with_file_name(DEFAULT_PROOF_NAME))?;
+
+        let proof = Proof {
+            premises: self.premises.clone(),
+            commands,
+        };
+        Ok((self.prelude.clone(), proof, pool))
+    }
+}
+
+/// Parses a single term (in the SMT-LIB format), using the sort and function declarations from
+/// `prelude`.
+///
+/// This is useful when a term needs to be constructed from its textual representation, without
+/// parsing an entire problem and proof. Note that the declarations in `prelude` should have been
+/// parsed using the same `TermPool` as the one passed to this function.
+pub fn parse_term(
+    input: &str,
+    pool: &mut TermPool,
+    prelude: &ProblemPrelude,
+) -> CarcaraResult<Rc<Term>> {
+    let mut parser = Parser::new(pool, input.as_bytes(), true, false, false)?;
+    parser.load_prelude(prelude);
+    let term = parser.parse_term()?;
+    parser.expect_token(Token::Eof)?;
+    Ok(term)
+}
+
+/// Parses a single sort (in the SMT-LIB format), using the sort declarations from `prelude`.
+#[cfg(feature = "serde")]
+pub(crate) fn parse_sort(
+    input: &str,
+    pool: &mut TermPool,
+    prelude: &ProblemPrelude,
+) -> CarcaraResult<Rc<Term>> {
+    let mut parser = Parser::new(pool, input.as_bytes(), true, false, false)?;
+    parser.load_prelude(prelude);
+    let sort = parser.parse_sort()?;
+    parser.expect_token(Token::Eof)?;
+    Ok(parser.pool.add(sort))
+}
+
 /// A function definition, from a `define-fun` command.
+#[derive(Clone)]
 struct FunctionDef {
     params: Vec<SortedVar>,
     body: Rc<Term>,
@@ -66,11 +362,50 @@ enum AnchorArg {
     Variable(SortedVar),
 }
 
+/// The assignment and variable arguments of a subproof.
+type SubproofArgs = (Vec<(String, Rc<Term>)>, Vec<SortedVar>);
+
+/// The stacks used while parsing the commands of a proof. Each open subproof has an entry in each
+/// of these stacks, and the commands stack also has an entry for the root proof.
+struct ProofStacks {
+    commands: Vec<Vec<ProofCommand>>,
+    end_steps: Vec<String>,
+    subproof_args: Vec<SubproofArgs>,
+    finished_assumes: bool,
+
+    /// The number of commands of the root proof that were already removed from the commands stack
+    /// by [`Parser::next_command`].
+    num_taken: usize,
+}
+
+impl ProofStacks {
+    fn new() -> Self {
+        Self {
+            commands: vec![Vec::new()],
+            end_steps: Vec::new(),
+            subproof_args: Vec::new(),
+            finished_assumes: false,
+            num_taken: 0,
+        }
+    }
+
+    /// Returns the index of the last command in the innermost open subproof, or in the root proof if
+    /// no subproof is open.
+    fn last_index(&self) -> usize {
+        let offset = if self.commands.len() == 1 {
+            self.num_taken
+        } else {
+            0
+        };
+        self.commands.last().unwrap().len() - 1 + offset
+    }
+}
+
 /// The state of the parser.
 ///
 /// This holds all the function, constant or sort declarations and definitions, as well as the term
 /// pool used by the parser.
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct ParserState {
     symbol_table: SymbolTable<HashCache<Identifier>, Rc<Term>>,
     function_defs: AHashMap<String, FunctionDef>,
@@ -90,6 +425,18 @@ pub struct Parser<'a, R> {
     expand_lets: bool,
     problem: Option<(ProblemPrelude, AHashSet<Rc<Term>>)>,
     allow_int_real_subtyping: bool,
+
+    /// The number of currently open parentheses, not counting `current_token`. This is used to
+    /// find the start of the next command when recovering from errors.
+    paren_depth: usize,
+
+    /// The stacks used by [`Parser::next_command`], which are kept between calls.
+    proof_stacks: Option<ProofStacks>,
+
+    /// The maximum nesting depth of terms, and the depth of the term currently being parsed. See
+    /// [`Parser::set_max_term_depth`].
+    max_term_depth: Option<usize>,
+    term_depth: usize,
 }
 
 impl<'a, R: BufRead> Parser<'a, R> {
@@ -122,6 +469,10 @@ impl<'a, R: BufRead> Parser<'a, R> {
             expand_lets,
             problem: None,
             allow_int_real_subtyping,
+            paren_depth: 0,
+            proof_stacks: None,
+            max_term_depth: None,
+            term_depth: 0,
         })
     }
 
@@ -133,9 +484,26 @@ impl<'a, R: BufRead> Parser<'a, R> {
         self.lexer = lexer;
         self.current_token = current_token;
         self.current_position = current_position;
+        self.paren_depth = 0;
+        self.proof_stacks = None;
+        self.term_depth = 0;
         Ok(())
     }
 
+    /// Sets the maximum nesting depth of the terms being parsed. If a term, as written in the
+    /// input, contains more than `max_term_depth` nested applications or binders, parsing fails
+    /// with a `ParserError::TermTooDeep` error. Since terms are parsed recursively, this prevents
+    /// very deep terms from overflowing the stack. Terms that are built by expanding function
+    /// definitions or `let` terms are not checked against the limit. If `None`, there is no limit.
+    pub fn set_max_term_depth(&mut self, max_term_depth: Option<usize>) {
+        self.max_term_depth = max_term_depth;
+    }
+
+    /// Returns the term pool used by the parser.
+    pub fn pool(&mut self) -> &mut TermPool {
+        self.pool
+    }
+
     /// Advances the parser one token, and returns the previous `current_token`.
     fn next_token(&mut self) -> CarcaraResult<(Token, Position)> {
         use std::mem::replace;
@@ -143,6 +511,11 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let (new_token, new_position) = self.lexer.next_token()?;
         let old_token = replace(&mut self.current_token, new_token);
         let old_position = replace(&mut self.current_position, new_position);
+        match old_token {
+            Token::OpenParen => self.paren_depth += 1,
+            Token::CloseParen => self.paren_depth = self.paren_depth.saturating_sub(1),
+            _ => (),
+        }
         Ok((old_token, old_position))
     }
 
@@ -153,6 +526,32 @@ impl<'a, R: BufRead> Parser<'a, R> {
             .insert(HashCache::new(Identifier::Simple(symbol)), sort);
     }
 
+    /// Adds all sort and function declarations from a problem prelude to the parser state, and
+    /// sets the problem logic.
+    fn load_prelude(&mut self, prelude: &ProblemPrelude) {
+        for (name, arity) in &prelude.sort_declarations {
+            self.state.sort_declarations.insert(name.clone(), *arity);
+        }
+        for var in &prelude.function_declarations {
+            self.insert_sorted_var(var.clone());
+        }
+        for (name, params, return_sort, _) in &prelude.function_definitions {
+            // Defined functions are treated as if they were only declared, like it is done when
+            // `apply_function_defs` is false
+            let sort = if params.is_empty() {
+                return_sort.clone()
+            } else {
+                let mut sorts: Vec<_> = params.iter().map(|(_, s)| s.clone()).collect();
+                sorts.push(return_sort.clone());
+                self.pool.add(Term::Sort(Sort::Function(sorts)))
+            };
+            self.insert_sorted_var((name.clone(), sort));
+        }
+        if let Some(logic) = &prelude.logic {
+            self.interpret_integers_as_reals = logic.contains('R') && !logic.contains('I');
+        }
+    }
+
     /// Shortcut for `self.problem.as_mut().unwrap().0`
     fn prelude(&mut self) -> &mut ProblemPrelude {
         &mut self.problem.as_mut().unwrap().0
@@ -310,6 +709,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     }
                 }
             }
+            Operator::StrConcat => {
+                assert_num_args(&args, 2..)?;
+                for s in sorts {
+                    SortError::assert_eq(&Sort::String, s)?;
+                }
+            }
+            Operator::StrLen => {
+                assert_num_args(&args, 1)?;
+                SortError::assert_eq(&Sort::String, sorts[0])?;
+            }
         }
         Ok(self.pool.add(Term::Op(op, args)))
     }
@@ -331,7 +740,11 @@ impl<'a, R: BufRead> Parser<'a, R> {
         };
         assert_num_args(&args, sorts.len() - 1)?;
         for i in 0..args.len() {
-            SortError::assert_eq(sorts[i].as_sort().unwrap(), self.pool.sort(&args[i]))?;
+            assert_term_sort(
+                sorts[i].as_sort().unwrap(),
+                &args[i],
+                self.pool.sort(&args[i]),
+            )?;
         }
         Ok(self.pool.add(Term::App(function, args)))
     }
@@ -388,7 +801,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
         if non_empty && result.is_empty() {
             Err(Error::Parser(
                 ParserError::EmptySequence,
-                self.current_position,
+                self.current_position.clone(),
             ))
         } else {
             self.next_token()?; // Consume `)` token
@@ -501,6 +914,15 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 }
                 Token::ReservedWord(Reserved::DefineFun) => {
                     let (name, func_def) = self.parse_define_fun()?;
+                    let return_sort = self
+                        .pool
+                        .add(Term::Sort(self.pool.sort(&func_def.body).clone()));
+                    self.prelude().function_definitions.push((
+                        name.clone(),
+                        BindingList(func_def.params.clone()),
+                        return_sort,
+                        func_def.body.clone(),
+                    ));
 
                     if self.apply_function_defs {
                         self.state.function_defs.insert(name, func_def);
@@ -529,6 +951,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 Token::ReservedWord(Reserved::Assert) => {
                     let term = self.parse_term()?;
                     self.expect_token(Token::CloseParen)?;
+                    self.prelude().assertions.push(term.clone());
                     self.premises().insert(term);
                 }
                 Token::ReservedWord(Reserved::SetLogic) => {
@@ -554,112 +977,219 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// Parses a proof in the Alethe format. All function, constant and sort declarations needed
     /// should already be in the parser state.
     pub fn parse_proof(&mut self) -> CarcaraResult<Vec<ProofCommand>> {
-        // To avoid stack overflows in proofs with many nested subproofs, we parse the subproofs
+        // To avoid stack overflows in proofs with many nested subproofs, the subproofs are parsed
         // iteratively, instead of recursively
-        let mut commands_stack = vec![Vec::new()];
-        let mut end_step_stack = Vec::new();
-        let mut subproof_args_stack = Vec::new();
+        let mut commands = Vec::new();
+        while let Some(command) = self.next_command() {
+            commands.push(command?);
+        }
+        Ok(commands)
+    }
 
-        let mut finished_assumes = false;
+    /// Parses the next command of the proof, and returns it. Returns `None` once the end of the
+    /// input is reached. Like in [`Parser::parse_proof`], all function, constant and sort
+    /// declarations needed should already be in the parser state. The parser state, including the
+    /// declarations made by `define-fun` commands and the ids of the steps already parsed, is kept
+    /// between calls.
+    ///
+    /// Subproofs are returned as a single `ProofCommand::Subproof`, after their end step is parsed,
+    /// so the returned commands are always in the root proof. `define-fun` commands are not
+    /// returned, since they only change the parser state. The premise indices of each command take
+    /// into account all of the commands returned by earlier calls.
+    pub fn next_command(&mut self) -> Option<CarcaraResult<ProofCommand>> {
+        let mut stacks = self.proof_stacks.take().unwrap_or_else(ProofStacks::new);
+        let result = loop {
+            if self.current_token == Token::Eof {
+                // If a subproof is still open, we report it as an error. The stacks are then
+                // discarded, so the error is only returned once
+                break stacks.end_steps.pop().map(|id| {
+                    stacks = ProofStacks::new();
+                    Err(Error::Parser(
+                        ParserError::UnclosedSubproof(id),
+                        self.current_position.clone(),
+                    ))
+                });
+            }
+            if let Err(e) = self.parse_proof_command(&mut stacks) {
+                break Some(Err(e));
+            }
+            if stacks.commands.len() == 1 {
+                if let Some(command) = stacks.commands[0].pop() {
+                    stacks.num_taken += 1;
+                    break Some(Ok(command));
+                }
+            }
+        };
+        self.proof_stacks = Some(stacks);
+        result
+    }
 
+    /// Parses a proof, like [`Parser::parse_proof`], but tries to recover from errors instead of
+    /// returning at the first one.
+    ///
+    /// When an error is encountered in a command, the parser skips to the start of the next
+    /// top-level command (that is, the next `(` that is not nested inside any other parentheses)
+    /// and continues parsing from there. If a single error was found, it is returned as an
+    /// `Error::Parser`. If multiple errors were found, they are all returned in an
+    /// `Error::MultipleParserErrors`. Lexer errors and IO errors can't be recovered from, so
+    /// parsing stops when one is encountered.
+    pub fn parse_proof_collecting_errors(&mut self) -> CarcaraResult<Vec<ProofCommand>> {
+        let mut stacks = ProofStacks::new();
+        let mut errors = Vec::new();
         while self.current_token != Token::Eof {
-            self.expect_token(Token::OpenParen)?;
-            let (token, position) = self.next_token()?;
-            let (id, command) = match token {
-                Token::ReservedWord(Reserved::Assume) => {
-                    let (id, term) = self.parse_assume_command()?;
-                    if end_step_stack.is_empty() && finished_assumes {
-                        log::warn!("`assume` command '{}' appears after `step` commands", &id);
+            let num_scopes = self.state.symbol_table.num_scopes();
+            match self.parse_proof_command(&mut stacks) {
+                Ok(()) => (),
+                Err(Error::Parser(e, pos)) => {
+                    errors.push((e, pos));
+
+                    // If the error happened while parsing a binder term, the symbol table may
+                    // contain scopes that were never popped
+                    while self.state.symbol_table.num_scopes() > num_scopes {
+                        self.state.symbol_table.pop_scope();
+                    }
+                    if !self.skip_to_next_command() {
+                        break;
                     }
-                    (id.clone(), ProofCommand::Assume { id, term })
-                }
-                Token::ReservedWord(Reserved::Step) => {
-                    finished_assumes = true;
-                    let step = self.parse_step_command()?;
-                    (step.id.clone(), ProofCommand::Step(step))
-                }
-                Token::ReservedWord(Reserved::DefineFun) => {
-                    let (name, func_def) = self.parse_define_fun()?;
-                    self.state.function_defs.insert(name, func_def);
-                    continue;
                 }
-                Token::ReservedWord(Reserved::Anchor) => {
-                    let anchor = self.parse_anchor_command()?;
-
-                    // When we encounter an `anchor` command, we push a new scope into the step ids
-                    // symbol table, a fresh commands vector into the commands stack for the
-                    // subproof to fill, and the `anchor` data (end step and arguments) into their
-                    // respective stacks. All of this will be popped off at the end of the subproof.
-                    // We don't need to push a new scope into the symbol table because
-                    // `Parser::parse_anchor_command` already does that for us
-                    self.state.step_ids.push_scope();
-                    commands_stack.push(Vec::new());
-                    end_step_stack.push(anchor.end_step_id);
-                    subproof_args_stack.push((anchor.assignment_args, anchor.variable_args));
-                    continue;
+                Err(e) => return Err(e),
+            }
+        }
+        let result = match self.finish_proof(stacks) {
+            Err(Error::Parser(e, pos)) => {
+                errors.push((e, pos));
+                None
+            }
+            other => Some(other),
+        };
+        match errors.len() {
+            0 => result.unwrap(),
+            1 => {
+                let (e, pos) = errors.pop().unwrap();
+                Err(Error::Parser(e, pos))
+            }
+            _ => Err(Error::MultipleParserErrors(errors)),
+        }
+    }
+
+    /// Skips tokens until the start of the next top-level command. Returns `false` if a lexer error
+    /// was encountered while doing so.
+    fn skip_to_next_command(&mut self) -> bool {
+        while self.current_token != Token::Eof
+            && !(self.current_token == Token::OpenParen && self.paren_depth == 0)
+        {
+            if self.next_token().is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Parses a single proof command, adding it to the given stacks.
+    fn parse_proof_command(&mut self, stacks: &mut ProofStacks) -> CarcaraResult<()> {
+        let command_position = self.current_position.clone();
+        self.expect_token(Token::OpenParen)?;
+        let (token, position) = self.next_token()?;
+        let (id, command) = match token {
+            Token::ReservedWord(Reserved::Assume) => {
+                let (id, term) = self.parse_assume_command()?;
+                if stacks.end_steps.is_empty() && stacks.finished_assumes {
+                    log::warn!("`assume` command '{}' appears after `step` commands", &id);
                 }
-                _ => return Err(Error::Parser(ParserError::UnexpectedToken(token), position)),
-            };
-            let id = HashCache::new(id);
-            if self.state.step_ids.get(&id).is_some() {
+                (id.clone(), ProofCommand::Assume { id, term })
+            }
+            Token::ReservedWord(Reserved::Step) => {
+                stacks.finished_assumes = true;
+                let step = self.parse_step_command(command_position)?;
+                (step.id.clone(), ProofCommand::Step(step))
+            }
+            Token::ReservedWord(Reserved::DefineFun) => {
+                let (name, func_def) = self.parse_define_fun()?;
+                self.state.function_defs.insert(name, func_def);
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::Anchor) => {
+                let anchor = self.parse_anchor_command()?;
+
+                // When we encounter an `anchor` command, we push a new scope into the step ids
+                // symbol table, a fresh commands vector into the commands stack for the
+                // subproof to fill, and the `anchor` data (end step and arguments) into their
+                // respective stacks. All of this will be popped off at the end of the subproof.
+                // We don't need to push a new scope into the symbol table because
+                // `Parser::parse_anchor_command` already does that for us
+                self.state.step_ids.push_scope();
+                stacks.commands.push(Vec::new());
+                stacks.end_steps.push(anchor.end_step_id);
+                stacks
+                    .subproof_args
+                    .push((anchor.assignment_args, anchor.variable_args));
+                return Ok(());
+            }
+            _ => return Err(Error::Parser(ParserError::UnexpectedToken(token), position)),
+        };
+        let id = HashCache::new(id);
+        if self.state.step_ids.get(&id).is_some() {
+            return Err(Error::Parser(
+                ParserError::RepeatedStepIndex(id.unwrap()),
+                position,
+            ));
+        }
+
+        stacks.commands.last_mut().unwrap().push(command);
+        if stacks.end_steps.last() == Some(id.as_ref()) {
+            // If this is the last step in a subproof, we need to pop all the subproof data off
+            // of the stacks and build the subproof command with it
+            self.state.symbol_table.pop_scope();
+            self.state.step_ids.pop_scope();
+            let commands = stacks.commands.pop().unwrap();
+            stacks.end_steps.pop().unwrap();
+            let (assignment_args, variable_args) = stacks.subproof_args.pop().unwrap();
+
+            // The subproof must contain at least two commands: the end step and the previous
+            // command it implicitly references
+            if commands.len() < 2 {
                 return Err(Error::Parser(
-                    ParserError::RepeatedStepIndex(id.unwrap()),
+                    ParserError::EmptySubproof(id.unwrap()),
                     position,
                 ));
             }
 
-            commands_stack.last_mut().unwrap().push(command);
-            if end_step_stack.last() == Some(id.as_ref()) {
-                // If this is the last step in a subproof, we need to pop all the subproof data off
-                // of the stacks and build the subproof command with it
-                self.state.symbol_table.pop_scope();
-                self.state.step_ids.pop_scope();
-                let commands = commands_stack.pop().unwrap();
-                end_step_stack.pop().unwrap();
-                let (assignment_args, variable_args) = subproof_args_stack.pop().unwrap();
-
-                // The subproof must contain at least two commands: the end step and the previous
-                // command it implicitly references
-                if commands.len() < 2 {
+            // We also need to make sure that the last command is in fact a `step`
+            match commands.last() {
+                Some(ProofCommand::Step(_)) => (),
+                _ => {
                     return Err(Error::Parser(
-                        ParserError::EmptySubproof(id.unwrap()),
+                        ParserError::LastSubproofStepIsNotStep(id.unwrap()),
                         position,
-                    ));
+                    ))
                 }
+            };
 
-                // We also need to make sure that the last command is in fact a `step`
-                match commands.last() {
-                    Some(ProofCommand::Step(_)) => (),
-                    _ => {
-                        return Err(Error::Parser(
-                            ParserError::LastSubproofStepIsNotStep(id.unwrap()),
-                            position,
-                        ))
-                    }
-                };
-
-                commands_stack
-                    .last_mut()
-                    .unwrap()
-                    .push(ProofCommand::Subproof(Subproof {
-                        commands,
-                        assignment_args,
-                        variable_args,
-                    }));
-            }
-            self.state
-                .step_ids
-                .insert(id, commands_stack.last().unwrap().len() - 1);
+            stacks
+                .commands
+                .last_mut()
+                .unwrap()
+                .push(ProofCommand::Subproof(Subproof {
+                    commands,
+                    assignment_args,
+                    variable_args,
+                }));
         }
-        match commands_stack.len() {
+        self.state.step_ids.insert(id, stacks.last_index());
+        Ok(())
+    }
+
+    /// Returns the commands of the root proof, after all commands were parsed.
+    fn finish_proof(&self, mut stacks: ProofStacks) -> CarcaraResult<Vec<ProofCommand>> {
+        match stacks.commands.len() {
             0 => unreachable!(),
-            1 => Ok(commands_stack.pop().unwrap()),
+            1 => Ok(stacks.commands.pop().unwrap()),
 
             // If there is more than one vector in the commands stack, we are inside a subproof
             // that should be closed before the outer proof is finished
             _ => Err(Error::Parser(
-                ParserError::UnclosedSubproof(end_step_stack.pop().unwrap()),
-                self.current_position,
+                ParserError::UnclosedSubproof(stacks.end_steps.pop().unwrap()),
+                self.current_position.clone(),
             )),
         }
     }
@@ -674,8 +1204,8 @@ impl<'a, R: BufRead> Parser<'a, R> {
     }
 
     /// Parses a `step` proof command. This method assumes that the `(` and `step` tokens were
-    /// already consumed.
-    fn parse_step_command(&mut self) -> CarcaraResult<ProofStep> {
+    /// already consumed. The `position` argument should be the position of the `(` token.
+    fn parse_step_command(&mut self, position: Position) -> CarcaraResult<ProofStep> {
         let id = self.expect_symbol()?;
         let clause = self.parse_clause()?;
         self.expect_token(Token::Keyword("rule".into()))?;
@@ -729,13 +1259,14 @@ impl<'a, R: BufRead> Parser<'a, R> {
             premises,
             args,
             discharge,
+            source_position: Some(position),
         })
     }
 
     /// Parses a premise for a `step` command. This already converts it into the depth and command
     /// index used to reference commands in the AST.
     fn parse_step_premise(&mut self) -> CarcaraResult<(usize, usize)> {
-        let position = self.current_position;
+        let position = self.current_position.clone();
         let id = HashCache::new(self.expect_symbol()?);
         self.state
             .step_ids
@@ -751,7 +1282,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// in other SMT solvers, like cvc5. To work around that, this function tries to find the
     /// command considering both possibilities.
     fn parse_discharge_premise(&mut self, root_id: &str) -> CarcaraResult<(usize, usize)> {
-        let position = self.current_position;
+        let position = self.current_position.clone();
         let id = self.expect_symbol()?;
         let absolute_id = format!("{}.{}", root_id, &id);
         let id = HashCache::new(id);
@@ -845,7 +1376,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// assumes that the `(` and `declare-sort` tokens were already consumed.
     fn parse_declare_sort(&mut self) -> CarcaraResult<(String, usize)> {
         let name = self.expect_symbol()?;
-        let arity_pos = self.current_position;
+        let arity_pos = self.current_position.clone();
         let arity = self.expect_numeral()?;
         self.expect_token(Token::CloseParen)?;
         let arity = arity.to_usize().ok_or(Error::Parser(
@@ -943,7 +1474,17 @@ impl<'a, R: BufRead> Parser<'a, R> {
                         .map_err(|err| Error::Parser(err, pos))?
                 });
             }
-            (Token::OpenParen, _) => return self.parse_application(),
+            (Token::OpenParen, pos) => {
+                if let Some(max) = self.max_term_depth {
+                    if self.term_depth >= max {
+                        return Err(Error::Parser(ParserError::TermTooDeep(max), pos));
+                    }
+                }
+                self.term_depth += 1;
+                let result = self.parse_application();
+                self.term_depth -= 1;
+                return result;
+            }
             (other, pos) => return Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
         };
         Ok(self.pool.add(term))
@@ -951,10 +1492,10 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
     /// Parses a term and checks that its sort matches the expected sort. If not, returns an error.
     fn parse_term_expecting_sort(&mut self, expected_sort: &Sort) -> CarcaraResult<Rc<Term>> {
-        let pos = self.current_position;
+        let pos = self.current_position.clone();
         let term = self.parse_term()?;
-        SortError::assert_eq(expected_sort, self.pool.sort(&term))
-            .map_err(|e| Error::Parser(e.into(), pos))?;
+        assert_term_sort(expected_sort, &term, self.pool.sort(&term))
+            .map_err(|e| Error::Parser(e, pos))?;
         Ok(term)
     }
 
@@ -1060,7 +1601,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let inner = self.parse_term()?;
         self.parse_sequence(
             |p| {
-                let attribute_pos = p.current_position;
+                let attribute_pos = p.current_position.clone();
                 let attribute = p.expect_keyword()?;
                 match attribute.as_str() {
                     "named" => {
@@ -1094,7 +1635,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// Parses any term that starts with `(`, that is, any term that is not a constant or a
     /// variable. This method assumes that the `(` token was already consumed.
     fn parse_application(&mut self) -> CarcaraResult<Rc<Term>> {
-        let head_pos = self.current_position;
+        let head_pos = self.current_position.clone();
         match &self.current_token {
             &Token::ReservedWord(reserved) => {
                 self.next_token()?;
@@ -1125,7 +1666,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     .map_err(|err| Error::Parser(err, head_pos))
             }
             Token::Symbol(s) if self.state.function_defs.get(s).is_some() => {
-                let head_pos = self.current_position;
+                let head_pos = self.current_position.clone();
                 let func_name = self.expect_symbol()?;
                 let args = self.parse_sequence(Self::parse_term, true)?;
                 let func = self.state.function_defs.get(&func_name).unwrap();
@@ -1133,10 +1674,10 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 // If there is a function definition with this function name, we sort check
                 // the arguments and apply the definition by performing a beta reduction.
                 assert_num_args(&args, func.params.len())
-                    .map_err(|err| Error::Parser(err, head_pos))?;
+                    .map_err(|err| Error::Parser(err, head_pos.clone()))?;
                 for (arg, param) in args.iter().zip(func.params.iter()) {
-                    SortError::assert_eq(param.1.as_sort().unwrap(), self.pool.sort(arg))
-                        .map_err(|err| Error::Parser(err.into(), head_pos))?;
+                    assert_term_sort(param.1.as_sort().unwrap(), arg, self.pool.sort(arg))
+                        .map_err(|err| Error::Parser(err, head_pos.clone()))?;
                 }
 
                 // Build a hash map of all the parameter names and the values they will
@@ -1167,7 +1708,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
     /// Parses a sort.
     fn parse_sort(&mut self) -> CarcaraResult<Term> {
-        let pos = self.current_position;
+        let pos = self.current_position.clone();
         let (name, args) = match self.next_token()?.0 {
             Token::Symbol(s) => (s, Vec::new()),
             Token::OpenParen => {
@@ -138,6 +138,63 @@ fn test_arithmetic_ops() {
     ));
 }
 
+#[test]
+fn test_sort_error_messages() {
+    let cases = [
+        (
+            "(+ (- 1 2) (* 3.0 4.2))",
+            "sort error: expected sort 'Int', got 'Real'",
+        ),
+        (
+            "(+ true false)",
+            "sort error: expected sort 'Int' or 'Real', got 'Bool'",
+        ),
+        (
+            "(and true (- 1 2))",
+            "sort error: expected sort 'Bool', got 'Int'",
+        ),
+    ];
+    for (term, expected) in cases {
+        match parse_term_err(term) {
+            Error::Parser(e @ ParserError::SortError(_), _) => assert_eq!(e.to_string(), expected),
+            other => panic!("expected sort error, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_type_mismatch_errors() {
+    let mut pool = TermPool::new();
+    let definitions = "
+        (declare-fun f (Int Bool) Int)
+        (define-fun g ((x Int)) Int (+ x 1))
+    ";
+    let cases = [
+        ("(f 1 2)", "term '2' has sort 'Int' but expected 'Bool'"),
+        (
+            "(f (- 1 2) (+ 1 2))",
+            "term '(+ 1 2)' has sort 'Int' but expected 'Bool'",
+        ),
+        ("(g true)", "term 'true' has sort 'Bool' but expected 'Int'"),
+        (
+            "(exists ((x Real)) (+ x 1.0))",
+            "term '(+ x 1.0)' has sort 'Real' but expected 'Bool'",
+        ),
+    ];
+    for (term, expected) in cases {
+        let mut parser =
+            Parser::new(&mut pool, definitions.as_bytes(), true, false, false).unwrap();
+        parser.parse_problem().unwrap();
+        parser.reset(term.as_bytes()).unwrap();
+        match parser.parse_term() {
+            Err(Error::Parser(e @ ParserError::TypeMismatch { .. }, _)) => {
+                assert_eq!(e.to_string(), expected);
+            }
+            other => panic!("expected type mismatch error, got {:?}", other),
+        }
+    }
+}
+
 #[test]
 fn test_logic_ops() {
     let mut p = TermPool::new();
@@ -293,7 +350,7 @@ fn test_quantifiers() {
     ));
     assert!(matches!(
         parse_term_err("(forall ((x Int)) (+ x x)"),
-        Error::Parser(ParserError::SortError(_), _),
+        Error::Parser(ParserError::TypeMismatch { .. }, _),
     ));
 }
 
@@ -383,7 +440,7 @@ fn test_lambda_terms() {
     ));
     assert!(matches!(
         parse_term_err("((lambda ((x Int)) (+ x 1)) false)"),
-        Error::Parser(ParserError::SortError(_), _),
+        Error::Parser(ParserError::TypeMismatch { .. }, _),
     ));
 }
 
@@ -463,6 +520,24 @@ fn test_declare_sort() {
     );
     let expected_sort = p.add(Term::Sort(Sort::Atom("T".to_owned(), Vec::new())));
     assert_eq!(p.add(Term::var("x", expected_sort)), got);
+
+    // Sorts that were not declared in the problem can't be used in the proof
+    let problem = "(declare-sort T 0)";
+    let proofs = [
+        "(step t1 (cl (forall ((x S)) true)) :rule hole)",
+        "(anchor :step t1 :args ((x (Array Int S))))
+        (step t1.t1 (cl) :rule hole)
+        (step t1 (cl) :rule hole)",
+    ];
+    for proof in proofs {
+        let result = parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false);
+        match result {
+            Err(Error::Parser(e @ ParserError::UndefinedSort(_), _)) => {
+                assert_eq!(e.to_string(), "sort 'S' is not defined");
+            }
+            other => panic!("expected undefined sort error, got {:?}", other.err()),
+        }
+    }
 }
 
 #[test]
@@ -502,6 +577,17 @@ fn test_step() {
     let proof = parse_proof(&mut p, input);
     assert_eq!(proof.commands.len(), 5);
 
+    // Source positions are ignored when comparing steps, so they are checked separately
+    let lines: Vec<_> = proof
+        .commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Step(s) => s.source_position.as_ref().map(|p| p.line),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(lines, [Some(2), Some(3), Some(4), Some(5), Some(6)]);
+
     assert_eq!(
         &proof.commands[0],
         &ProofCommand::Step(ProofStep {
@@ -511,6 +597,7 @@ fn test_step() {
             premises: Vec::new(),
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: Some(Position::new(2, 9)),
         })
     );
 
@@ -523,6 +610,7 @@ fn test_step() {
             premises: vec![(0, 0)],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: Some(Position::new(3, 9)),
         })
     );
 
@@ -540,6 +628,7 @@ fn test_step() {
                     .collect()
             },
             discharge: Vec::new(),
+            source_position: Some(Position::new(4, 9)),
         })
     );
 
@@ -558,6 +647,7 @@ fn test_step() {
                 ]
             },
             discharge: Vec::new(),
+            source_position: Some(Position::new(5, 9)),
         })
     );
 
@@ -570,6 +660,7 @@ fn test_step() {
             premises: vec![(0, 0), (0, 1), (0, 2)],
             args: vec![ProofArg::Term(p.add(Term::integer(42)))],
             discharge: Vec::new(),
+            source_position: Some(Position::new(6, 9)),
         })
     );
 }
@@ -601,6 +692,7 @@ fn test_premises_in_subproofs() {
             premises: vec![(0, 0), (0, 1)],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: Some(Position::new(5, 9)),
         })
     );
     assert_eq!(
@@ -612,6 +704,7 @@ fn test_premises_in_subproofs() {
             premises: vec![(1, 0), (0, 0), (0, 1)],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: Some(Position::new(6, 9)),
         })
     );
     assert_eq!(
@@ -623,6 +716,282 @@ fn test_premises_in_subproofs() {
             premises: vec![(0, 0), (1, 0), (0, 1), (1, 1)],
             args: Vec::new(),
             discharge: Vec::new(),
+            source_position: Some(Position::new(7, 9)),
         })
     );
 }
+
+#[test]
+fn test_parse_term_with_prelude() {
+    let mut pool = TermPool::new();
+    let problem = "
+        (set-logic QF_LRA)
+        (declare-sort T 0)
+        (declare-fun f (T) Real)
+        (declare-const a T)
+    ";
+    let (prelude, _) = Parser::new(&mut pool, problem.as_bytes(), true, false, false)
+        .and_then(|mut p| p.parse_problem())
+        .expect(ERROR_MESSAGE);
+
+    let got = super::parse_term("(< (f a) 1)", &mut pool, &prelude).expect(ERROR_MESSAGE);
+    let [expected] = parse_terms(&mut pool, problem, ["(< (f a) 1.0)"]);
+    assert_eq!(expected, got);
+
+    assert!(matches!(
+        super::parse_term("(f b)", &mut pool, &prelude),
+        Err(Error::Parser(ParserError::UndefinedIden(_), _)),
+    ));
+    assert!(matches!(
+        super::parse_term("a a", &mut pool, &prelude),
+        Err(Error::Parser(ParserError::UnexpectedToken(_), _)),
+    ));
+}
+
+#[test]
+fn test_parse_proof_collecting_errors() {
+    let mut pool = TermPool::new();
+    let input = "
+        (step t1 (cl (= 1 1)) :rule refl)
+        (step t2 (cl (forall ((x Int)) (= x undefined))) :rule rule-name)
+        (step t3 (cl) :rule rule-name :premises (t1))
+        (step t4 (cl) :rule)
+        (step t5 (cl) :rule rule-name :premises (t3))
+    ";
+    let errors = Parser::new(&mut pool, input.as_bytes(), true, false, false)
+        .and_then(|mut p| p.parse_proof_collecting_errors())
+        .expect_err("expected error");
+    match errors {
+        Error::MultipleParserErrors(errors) => {
+            assert_eq!(errors.len(), 2);
+            assert!(matches!(
+                errors[0],
+                (ParserError::UndefinedIden(_), Position { line: 3, .. })
+            ));
+            assert!(matches!(
+                errors[1],
+                (ParserError::UnexpectedToken(_), Position { line: 5, .. })
+            ));
+        }
+        other => panic!("expected multiple parser errors, got {:?}", other),
+    }
+
+    // If there are no errors, this should behave exactly like `Parser::parse_proof`
+    let input = "
+        (step t1 (cl (= 1 1)) :rule refl)
+        (step t2 (cl) :rule rule-name :premises (t1))
+    ";
+    let commands = Parser::new(&mut pool, input.as_bytes(), true, false, false)
+        .and_then(|mut p| p.parse_proof_collecting_errors())
+        .expect(ERROR_MESSAGE);
+    assert_eq!(parse_proof(&mut pool, input).commands, commands);
+}
+
+#[test]
+fn test_error_file_names() {
+    let problem = "(declare-fun p () Bool)";
+    let get_position = |result: CarcaraResult<_>| match result {
+        Err(Error::Parser(_, pos)) => pos,
+        _ => panic!("expected parser error"),
+    };
+
+    let result = parse_instance(
+        problem.as_bytes(),
+        "(assume h1 q)".as_bytes(),
+        true,
+        false,
+        false,
+    );
+    let pos = get_position(result);
+    assert_eq!(pos.file_name.as_deref(), Some("<proof>"));
+    assert_eq!((pos.line, pos.column), (1, 12));
+
+    let result = parse_instance_named(
+        "(assert q)".as_bytes(),
+        "".as_bytes(),
+        "a.smt2",
+        "a.smt2.alethe",
+        true,
+        false,
+        false,
+    );
+    assert_eq!(get_position(result).file_name.as_deref(), Some("a.smt2"));
+    let result = parse_instance_named(
+        problem.as_bytes(),
+        "(assume h1 q)".as_bytes(),
+        "a.smt2",
+        "a.smt2.alethe",
+        true,
+        false,
+        false,
+    );
+    let error = Error::Parser(
+        ParserError::EmptySubproof("t1".into()),
+        get_position(result),
+    );
+    assert!(error
+        .to_string()
+        .ends_with("(in a.smt2.alethe, on line 1, column 12)"));
+}
+
+#[test]
+fn test_parse_instance_from_paths() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("carcara-parse-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let problem_path = dir.join("a.smt2");
+    let proof_path = dir.join("a.smt2.alethe");
+    let invalid_path = dir.join("b.smt2.alethe");
+    let missing_path = dir.join("missing.smt2");
+    fs::write(&problem_path, "(declare-fun p () Bool) (assert p)").unwrap();
+    fs::write(&proof_path, "(assume h1 p)").unwrap();
+    fs::write(&invalid_path, "(assume h1 q)").unwrap();
+
+    let options = CarcaraOptions::new();
+    let parsed = parse_instance_from_paths(&problem_path, &proof_path, &options);
+    let invalid = parse_instance_from_paths(&problem_path, &invalid_path, &options);
+    let missing = parse_instance_from_paths(&missing_path, &proof_path, &options);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let (_, proof, _) = parsed.unwrap();
+    assert_eq!(proof.commands.len(), 1);
+    assert_eq!(proof.premises.len(), 1);
+
+    // Errors mention the path of the file in which they happened
+    match invalid {
+        Err(Error::Parser(ParserError::UndefinedIden(_), pos)) => {
+            let expected = invalid_path.to_string_lossy();
+            assert_eq!(pos.file_name.as_deref(), Some(expected.as_ref()));
+        }
+        other => panic!("expected parser error, got {:?}", other.err()),
+    }
+    match missing {
+        Err(e @ Error::Io(_)) => {
+            let message = e.to_string();
+            assert!(
+                message.contains(missing_path.to_str().unwrap()),
+                "{}",
+                message
+            );
+        }
+        other => panic!("expected IO error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_next_command() {
+    let mut pool = TermPool::new();
+    let input = "
+        (assume h1 (= 1 2))
+        (define-fun f () Int 3)
+        (step t2 (cl (= 1 f)) :rule rule-name :premises (h1))
+        (anchor :step t3)
+        (step t3.t1 (cl (= 2 3)) :rule rule-name :premises (h1 t2))
+        (step t3 (cl) :rule rule-name :premises (t3.t1))
+        (step t4 (cl) :rule rule-name :premises (t2 t3))
+    ";
+    let mut parser = Parser::new(&mut pool, input.as_bytes(), true, false, false).unwrap();
+
+    // Each command is returned as soon as it is parsed, so the terms of later commands are not yet
+    // in the pool
+    let before = parser.pool().live_term_count();
+    let h1 = parser.next_command().unwrap().unwrap();
+    assert_eq!(h1.id(), "h1");
+    let after_h1 = parser.pool().live_term_count();
+    assert!(after_h1 > before);
+    let term = h1.clause()[0].clone();
+    assert_eq!(parser.pool().add(term.as_ref().clone()), term);
+    assert_eq!(parser.pool().live_term_count(), after_h1);
+
+    // `define-fun` commands are not returned, but the definitions are kept in the parser state
+    let t2 = parser.next_command().unwrap().unwrap();
+    assert_eq!(t2.id(), "t2");
+    let [expected] = parse_terms(parser.pool(), "", ["(= 1 3)"]);
+    assert_eq!(t2.clause(), [expected]);
+    match &t2 {
+        ProofCommand::Step(s) => assert_eq!(s.premises, [(0, 0)]),
+        _ => panic!(),
+    }
+
+    // Subproofs are returned whole, and premise indices take into account the commands returned
+    // by earlier calls
+    match parser.next_command().unwrap().unwrap() {
+        ProofCommand::Subproof(s) => {
+            assert_eq!(s.commands.len(), 2);
+            match &s.commands[0] {
+                ProofCommand::Step(s) => assert_eq!(s.premises, [(0, 0), (0, 1)]),
+                _ => panic!(),
+            }
+        }
+        _ => panic!(),
+    }
+    match parser.next_command().unwrap().unwrap() {
+        ProofCommand::Step(s) => assert_eq!(s.premises, [(0, 1), (0, 2)]),
+        _ => panic!(),
+    }
+    assert!(parser.next_command().is_none());
+
+    // An unclosed subproof is reported once, when the end of the input is reached
+    let input = "
+        (assume h1 (= 1 2))
+        (anchor :step t2)
+        (step t2.t1 (cl) :rule rule-name)
+    ";
+    let mut parser = Parser::new(&mut pool, input.as_bytes(), true, false, false).unwrap();
+    assert!(matches!(parser.next_command(), Some(Ok(_))));
+    assert!(matches!(
+        parser.next_command(),
+        Some(Err(Error::Parser(ParserError::UnclosedSubproof(_), _)))
+    ));
+    assert!(parser.next_command().is_none());
+}
+
+#[test]
+fn test_max_term_depth() {
+    fn nested_and(depth: usize) -> String {
+        "(and ".repeat(depth) + "true" + &")".repeat(depth)
+    }
+
+    let mut pool = TermPool::new();
+    let parse = |pool: &mut TermPool, input: &str, max| {
+        let mut parser = Parser::new(pool, input.as_bytes(), true, false, false)?;
+        parser.set_max_term_depth(max);
+        parser.parse_term()
+    };
+
+    let term = parse(&mut pool, &nested_and(10), Some(10)).unwrap();
+    assert_eq!(term.depth(), 10);
+    let term = parse(&mut pool, "(forall ((x Int)) (and (= x 0)))", Some(3)).unwrap();
+    assert_eq!(term.depth(), 3);
+
+    let err = parse(&mut pool, &nested_and(11), Some(10)).unwrap_err();
+    let Error::Parser(ParserError::TermTooDeep(10), pos) = err else {
+        panic!("expected `TermTooDeep` error, got {:?}", err);
+    };
+    assert_eq!(pos.column, 51);
+    assert!(matches!(
+        parse(&mut pool, "(forall ((x Int)) (and (= x 0)))", Some(2)),
+        Err(Error::Parser(ParserError::TermTooDeep(2), _)),
+    ));
+
+    // The limit is also applied when parsing through `parse_instance_with_options`, both in the
+    // problem and in the proof
+    let options = CarcaraOptions {
+        max_term_depth: Some(10),
+        ..CarcaraOptions::new()
+    };
+    let problem = format!("(assert {})", nested_and(10));
+    let proof = format!("(assume h1 {})", nested_and(11));
+    assert!(matches!(
+        parse_instance_with_options(problem.as_bytes(), proof.as_bytes(), &options),
+        Err(Error::Parser(ParserError::TermTooDeep(10), _)),
+    ));
+    let deep_problem = format!("(assert {})", nested_and(11));
+    assert!(matches!(
+        parse_instance_with_options(deep_problem.as_bytes(), b"".as_slice(), &options),
+        Err(Error::Parser(ParserError::TermTooDeep(10), _)),
+    ));
+    let proof = format!("(assume h1 {})", nested_and(10));
+    assert!(parse_instance_with_options(problem.as_bytes(), proof.as_bytes(), &options).is_ok());
+}
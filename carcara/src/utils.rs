@@ -2,9 +2,10 @@ use crate::ast::{BindingList, Quantifier, Rc, Term};
 use ahash::{AHashMap, AHashSet, AHasher};
 use std::{
     borrow::Borrow,
+    collections::BTreeMap,
     fmt,
     hash::{Hash, Hasher},
-    ops,
+    io, ops,
 };
 
 /// Returns `true` if the character is a valid symbol character in the SMT-LIB and Alethe formats.
@@ -22,6 +23,12 @@ pub fn is_symbol_character(ch: char) -> bool {
     }
 }
 
+/// Clones an `io::Error`. Since `io::Error` doesn't implement `Clone`, this creates a new error with
+/// the same kind and message. The original error's source, if any, is not preserved.
+pub fn clone_io_error(error: &io::Error) -> io::Error {
+    io::Error::new(error.kind(), error.to_string())
+}
+
 /// An iterator that removes duplicate elements from `iter`. This will yield the elements in
 /// `iter` in order, skipping elements that have already been seen before.
 pub struct Dedup<T, I> {
@@ -63,6 +70,7 @@ impl<T, I: Iterator<Item = T>> DedupIterator<T> for I {
     }
 }
 
+#[derive(Clone)]
 pub struct HashCache<T> {
     hash: u64,
     value: T,
@@ -100,7 +108,7 @@ impl<T> AsRef<T> for HashCache<T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolTable<K, V> {
     scopes: Vec<AHashMap<K, V>>,
 }
@@ -114,6 +122,10 @@ impl<K, V> SymbolTable<K, V> {
         self.scopes.push(AHashMap::new());
     }
 
+    pub fn num_scopes(&self) -> usize {
+        self.scopes.len()
+    }
+
     pub fn pop_scope(&mut self) {
         match self.scopes.len() {
             0 => unreachable!(),
@@ -167,8 +179,55 @@ impl<K, V> Default for SymbolTable<K, V> {
     }
 }
 
+/// A map that holds at most a fixed number of entries. When it is full, inserting a new entry
+/// evicts the least recently used one.
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+
+    // Each entry stores the "time" at which it was last used, which is also its key in `order`
+    entries: AHashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+    clock: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: AHashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns the value associated with `key`, marking it as the most recently used entry.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self.order.remove(last_used).unwrap();
+        self.clock += 1;
+        *last_used = self.clock;
+        self.order.insert(self.clock, key);
+        Some(value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.clock)) {
+            self.order.remove(&last_used);
+        } else if self.entries.len() > self.capacity {
+            let (_, oldest) = self.order.pop_first().unwrap();
+            self.entries.remove(&oldest);
+        }
+        self.order.insert(self.clock, key);
+    }
+}
+
 // TODO: Document this struct
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Range(Option<usize>, Option<usize>);
 
 impl Range {
@@ -236,3 +295,33 @@ impl TypeName for Quantifier {
 impl TypeName for BindingList {
     const NAME: &'static str = "binding list";
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_cache() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        // "b" is now the least recently used entry, so it is evicted
+        cache.insert("c", 3);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+
+        // Updating an existing entry doesn't evict anything
+        cache.insert("a", 4);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&"a"), Some(&4));
+        assert_eq!(cache.get(&"c"), Some(&3));
+
+        let mut empty = LruCache::new(0);
+        empty.insert("a", 1);
+        assert_eq!(empty.get(&"a"), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! Utilities for visualizing the structure of Alethe proofs.
+
+use crate::ast::*;
+use crate::checker::{ProofChecker, RuleCategory};
+use ahash::AHashMap;
+use std::io;
+
+/// The colour used to fill the node of a step, based on the category of its rule (see
+/// [`RuleCategory`]). Unknown rules, and rules in the other categories, are drawn in white.
+fn rule_color(rule: &str) -> &'static str {
+    // The checker handles `lia_generic` steps separately, but they are arithmetic steps like
+    // `la_generic` ones
+    let rule = if rule == "lia_generic" {
+        "la_generic"
+    } else {
+        rule
+    };
+    match ProofChecker::get_rule(rule, false).map(|(_, metadata)| metadata.category) {
+        Some(RuleCategory::Arithmetic) => "lightblue",
+        Some(RuleCategory::Quantifier) => "lightgoldenrod",
+        Some(RuleCategory::Propositional) => "palegreen",
+        _ => "white",
+    }
+}
+
+/// Escapes a string so it can be used inside a quoted DOT label.
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Writes the dependency graph of a proof to `out`, in the Graphviz DOT format.
+///
+/// Each command becomes a node labelled with its id (and, for steps, its rule), and edges point
+/// from the premises of each step to the step itself. Edges from discharged assumptions are dashed.
+/// Subproofs are rendered as clusters, and `assume` commands are drawn as grey boxes. Steps are
+/// coloured according to the category of their rule: arithmetic rules are blue, quantifier rules are
+/// yellow, and propositional rules are green.
+pub fn proof_to_dot(proof: &Proof, out: &mut dyn io::Write) -> io::Result<()> {
+    writeln!(out, "digraph proof {{")?;
+    writeln!(out, "  node [style=filled];")?;
+
+    // Since step ids are not necessarily unique across subproofs, we give each node a name based on
+    // the order in which it appears. To find the node that corresponds to a premise, we keep a map
+    // from each command's address to its node index. Premises that reference a subproof point to
+    // the node of the step that ends it
+    let mut node_indices: AHashMap<*const ProofCommand, usize> = AHashMap::new();
+    let mut subproof_stack: Vec<&ProofCommand> = Vec::new();
+    let mut num_nodes = 0;
+    let mut iter = proof.iter();
+    while let Some(command) = iter.next() {
+        let indentation = "  ".repeat(subproof_stack.len() + 1);
+        if command.is_subproof() {
+            writeln!(out, "{}subgraph cluster_{} {{", indentation, num_nodes)?;
+            writeln!(
+                out,
+                "{}  label=\"{}\";",
+                indentation,
+                escape_label(command.id())
+            )?;
+            subproof_stack.push(command);
+            continue;
+        }
+
+        let index = num_nodes;
+        num_nodes += 1;
+        node_indices.insert(command as *const _, index);
+        match command {
+            ProofCommand::Assume { id, .. } => writeln!(
+                out,
+                "{}n{} [label=\"{}\", shape=box, fillcolor=lightgrey];",
+                indentation,
+                index,
+                escape_label(id),
+            )?,
+            ProofCommand::Step(step) => {
+                writeln!(
+                    out,
+                    "{}n{} [label=\"{}\\n{}\", fillcolor={}];",
+                    indentation,
+                    index,
+                    escape_label(&step.id),
+                    escape_label(&step.rule),
+                    rule_color(&step.rule),
+                )?;
+                let premises = step.premises.iter().map(|p| (p, ""));
+                let discharge = step.discharge.iter().map(|p| (p, " [style=dashed]"));
+                for (&premise, attributes) in premises.chain(discharge) {
+                    let premise = iter.get_premise(premise) as *const _;
+                    writeln!(
+                        out,
+                        "{}n{} -> n{}{};",
+                        indentation, node_indices[&premise], index, attributes,
+                    )?;
+                }
+            }
+            ProofCommand::Subproof(_) => unreachable!(),
+        }
+
+        if iter.is_end_step() {
+            let subproof = subproof_stack.pop().unwrap();
+            node_indices.insert(subproof as *const _, index);
+            writeln!(out, "{}}}", "  ".repeat(subproof_stack.len() + 1))?;
+        }
+    }
+    writeln!(out, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_instance;
+
+    #[test]
+    fn test_proof_to_dot() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert p)
+            (assert (not p))
+        ";
+        let proof = "
+            (assume h1 p)
+            (assume h2 (not p))
+            (anchor :step t3)
+            (assume t3.a0 q)
+            (step t3.t1 (cl q) :rule resolution :premises (t3.a0))
+            (step t3 (cl (not q) q) :rule subproof :discharge (t3.a0))
+            (step t4 (cl) :rule resolution :premises (h1 h2))
+        ";
+        let (_, proof, _) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+        let mut output = Vec::new();
+        proof_to_dot(&proof, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let expected = [
+            "digraph proof {",
+            "  node [style=filled];",
+            "  n0 [label=\"h1\", shape=box, fillcolor=lightgrey];",
+            "  n1 [label=\"h2\", shape=box, fillcolor=lightgrey];",
+            "  subgraph cluster_2 {",
+            "    label=\"t3\";",
+            "    n2 [label=\"t3.a0\", shape=box, fillcolor=lightgrey];",
+            "    n3 [label=\"t3.t1\\nresolution\", fillcolor=palegreen];",
+            "    n2 -> n3;",
+            "    n4 [label=\"t3\\nsubproof\", fillcolor=white];",
+            "    n2 -> n4 [style=dashed];",
+            "  }",
+            "  n5 [label=\"t4\\nresolution\", fillcolor=palegreen];",
+            "  n0 -> n5;",
+            "  n1 -> n5;",
+            "}",
+        ];
+        assert_eq!(expected.as_slice(), output.lines().collect::<Vec<_>>());
+    }
+}
@@ -23,12 +23,11 @@ fn run_test(problem_path: &Path, proof_path: &Path) -> CarcaraResult<()> {
     let (_, elaborated) = checker.check_and_elaborate(proof)?;
 
     // After that, we check the elaborated proof normally, to make sure it is valid
-    checker::ProofChecker::new(&mut pool, Config::new().strict(true), prelude.clone())
-        .check(&elaborated)?;
+    checker::ProofChecker::new_strict(&mut pool, prelude.clone()).check(&elaborated)?;
 
     // Finally, we elaborate the already elaborated proof, to make sure the elaboration step is
     // idempotent
-    let mut checker = checker::ProofChecker::new(&mut pool, Config::new().strict(true), prelude);
+    let mut checker = checker::ProofChecker::new_strict(&mut pool, prelude);
     let (_, elaborated_twice) = checker.check_and_elaborate(elaborated.clone())?;
     assert!(
         elaborated.commands == elaborated_twice.commands,
@@ -52,9 +51,17 @@ fn test_file(proof_path: &str) {
         // performance
         let short_message = match e {
             Error::Io(_) => "IO error".to_owned(),
-            Error::Parser(_, (line, column)) => format!("parser error at {}:{}", line, column),
+            Error::Parser(_, pos) => format!("parser error at {}:{}", pos.line, pos.column),
+            Error::MultipleParserErrors(errors) => format!("{} parser errors", errors.len()),
             Error::Checker { rule, step, .. } => format!("checker error at '{}' ({})", step, rule),
             Error::DoesNotReachEmptyClause => format!("{}", e), // This one is already pretty short
+            Error::CyclicProof(step) => format!("cyclic proof at '{}'", step),
+            Error::Timeout => "timeout".to_owned(),
+            Error::ElaborationVerificationFailed { rule, step, .. } => {
+                format!("invalid elaborated step '{}' ({})", step, rule)
+            }
+            #[cfg(feature = "toml")]
+            Error::InvalidConfig(_) => format!("{}", e),
         };
         panic!(
             "\"{}\" returned error: {}",
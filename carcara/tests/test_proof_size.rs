@@ -0,0 +1,75 @@
+use carcara::{ast::proof_size_bytes, parser::parse_instance};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An allocator that keeps track of the number of bytes currently allocated.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::SeqCst);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_proof_size_bytes() {
+    let n = 200;
+    let mut problem = String::new();
+    for i in 0..n {
+        writeln!(problem, "(declare-fun x{} () Int)", i).unwrap();
+    }
+    let mut proof = String::new();
+    for i in 0..n {
+        let term = format!("(+ x{} (* {} x{}))", i, i + 2, (i * 7) % n);
+        writeln!(
+            proof,
+            "(step t{} (cl (= {} {}) (not (<= {} 0))) :rule hole :args ({}))",
+            i, term, term, term, i
+        )
+        .unwrap();
+    }
+    writeln!(
+        proof,
+        "(anchor :step t{0} :args ((y Int) (:= z x0)))
+        (step t{0}.t1 (cl (= (+ y z) (+ y x0))) :rule hole)
+        (step t{0} (cl (= (+ y z) (+ y x0))) :rule hole)",
+        n
+    )
+    .unwrap();
+
+    // Everything allocated while parsing is freed after the pool and prelude are dropped, except
+    // for the proof and the terms it references
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let (prelude, proof, pool) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), true, false, false).unwrap();
+    drop((prelude, pool));
+    let actual = ALLOCATED.load(Ordering::SeqCst) - before;
+
+    let estimate = proof_size_bytes(&proof);
+    assert!(
+        estimate <= 2 * actual && actual <= 2 * estimate,
+        "estimate: {}, actual: {}",
+        estimate,
+        actual
+    );
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "serde")]
+
+use carcara::{ast::*, checker, parser};
+
+const PROBLEM: &str = "
+    (set-logic UFLIA)
+    (declare-sort U 0)
+    (declare-fun p (U) Bool)
+    (declare-fun f (Int) U)
+    (define-fun q ((x U)) Bool (not (p x)))
+    (assert (forall ((x Int)) (p (f x))))
+    (assert (not (forall ((y Int)) (p (f y)))))
+";
+
+const PROOF: &str = "
+    (assume h1 (forall ((x Int)) (p (f x))))
+    (assume h2 (not (forall ((y Int)) (p (f y)))))
+    (anchor :step t3 :args ((y Int) (:= x y)))
+    (step t3.t1 (cl (= x y)) :rule refl)
+    (step t3.t2 (cl (= (f x) (f y))) :rule cong :premises (t3.t1))
+    (step t3.t3 (cl (= (p (f x)) (p (f y)))) :rule cong :premises (t3.t2))
+    (step t3 (cl (= (forall ((x Int)) (p (f x))) (forall ((y Int)) (p (f y))))) :rule bind)
+    (step t4 (cl (not (forall ((x Int)) (p (f x)))) (forall ((y Int)) (p (f y))))
+        :rule equiv1 :premises (t3))
+    (step t5 (cl) :rule resolution :premises (t4 h1 h2))
+";
+
+#[test]
+fn test_proof_round_trip() {
+    let (prelude, proof, mut pool) =
+        parser::parse_instance(PROBLEM.as_bytes(), PROOF.as_bytes(), true, false, false).unwrap();
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let deserialized: Proof =
+        with_deserialization_context(&mut pool, &prelude, || serde_json::from_str(&json).unwrap());
+    assert_eq!(proof.premises, deserialized.premises);
+    assert_eq!(proof.commands, deserialized.commands);
+
+    checker::ProofChecker::new(&mut pool, checker::Config::new(), prelude)
+        .check(&deserialized)
+        .unwrap();
+}
+
+#[test]
+fn test_prelude_round_trip() {
+    let (prelude, _, mut pool) =
+        parser::parse_instance(PROBLEM.as_bytes(), "".as_bytes(), false, false, false).unwrap();
+
+    let json = serde_json::to_string(&prelude).unwrap();
+    let deserialized: ProblemPrelude =
+        with_deserialization_context(&mut pool, &ProblemPrelude::default(), || {
+            serde_json::from_str(&json).unwrap()
+        });
+    assert_eq!(prelude, deserialized);
+}
+
+#[test]
+fn test_deserialize_without_context() {
+    let result = serde_json::from_str::<ProofArg>(r#"{"Term":"(+ 1 2)"}"#);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_error_to_json() {
+    let proof = "(assume h1 (forall ((x Int)) (p (f x))))
+        (step t2 (cl) :rule resolution :premises (h1))";
+    let err = carcara::check(
+        PROBLEM.as_bytes(),
+        proof.as_bytes(),
+        carcara::CarcaraOptions::new(),
+    )
+    .unwrap_err();
+    let json = err.to_json();
+    assert_eq!(json["kind"], "Checker");
+    assert_eq!(json["step"], "t2");
+    assert_eq!(json["rule"], "resolution");
+    assert_eq!(json["code"], "E_RESOLUTION");
+    assert_eq!(json["line"], 2);
+    assert_eq!(json["message"], err.to_string());
+
+    let err = carcara::check(
+        PROBLEM.as_bytes(),
+        "(step t1 (cl) :rule)".as_bytes(),
+        carcara::CarcaraOptions::new(),
+    )
+    .unwrap_err();
+    let json = err.to_json();
+    assert_eq!(json["kind"], "Parser");
+    assert_eq!(json["line"], 1);
+    assert_eq!(json["file"], "<proof>");
+}
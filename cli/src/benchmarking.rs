@@ -1,7 +1,8 @@
+use ahash::AHashMap;
 use carcara::{
-    benchmarking::{CollectResults, CsvBenchmarkResults, RunMeasurement},
+    benchmarking::{peak_memory_usage, CollectResults, CsvBenchmarkResults, RunMeasurement},
     checker,
-    parser::parse_instance,
+    parser::parse_instance_with_options,
     CarcaraOptions,
 };
 use crossbeam_queue::ArrayQueue;
@@ -31,17 +32,20 @@ fn run_job<T: CollectResults + Default>(
     let total = Instant::now();
 
     let parsing = Instant::now();
-    let (prelude, proof, mut pool) = parse_instance(
+    let (prelude, proof, mut pool) = parse_instance_with_options(
         BufReader::new(File::open(job.problem_file)?),
         BufReader::new(File::open(job.proof_file)?),
-        options.apply_function_defs,
-        options.expand_lets,
-        options.allow_int_real_subtyping,
+        options,
     )?;
     let parsing = parsing.elapsed();
+    let parsing_peak_memory = peak_memory_usage();
+    let max_subproof_depth = proof.max_subproof_depth();
+    let subproof_count = proof.subproof_count();
 
     let mut elaboration = Duration::ZERO;
     let mut deep_eq = Duration::ZERO;
+    let mut deep_eq_by_rule = AHashMap::new();
+    let mut rule_histogram = AHashMap::new();
     let mut assume = Duration::ZERO;
     let mut assume_core = Duration::ZERO;
 
@@ -49,10 +53,15 @@ fn run_job<T: CollectResults + Default>(
         .strict(options.strict)
         .skip_unknown_rules(options.skip_unknown_rules)
         .lia_via_cvc5(options.lia_via_cvc5)
+        .max_steps(options.max_steps)
+        .deep_eq_depth_limit(options.deep_eq_depth_limit)
+        .deep_eq_cache_size(options.deep_eq_cache_capacity())
         .statistics(checker::CheckerStatistics {
             file_name: proof_file_name,
             elaboration_time: &mut elaboration,
             deep_eq_time: &mut deep_eq,
+            deep_eq_time_by_rule: &mut deep_eq_by_rule,
+            rule_histogram: &mut rule_histogram,
             assume_time: &mut assume,
             assume_core_time: &mut assume_core,
             results,
@@ -69,6 +78,7 @@ fn run_job<T: CollectResults + Default>(
         checker.check(&proof)
     };
     let checking = checking.elapsed();
+    let peak_memory_bytes = peak_memory_usage().max(parsing_peak_memory);
 
     let total = total.elapsed();
 
@@ -82,6 +92,12 @@ fn run_job<T: CollectResults + Default>(
             deep_eq,
             assume,
             assume_core,
+            peak_memory_bytes,
+            deep_eq_by_rule,
+            step_count: rule_histogram.values().sum(),
+            rule_histogram,
+            max_subproof_depth,
+            subproof_count,
         },
     );
     checking_result
@@ -144,11 +160,7 @@ pub fn run_benchmark<T: CollectResults + Default + Send>(
             })
             .collect();
 
-        workers
-            .into_iter()
-            .map(|w| w.join().unwrap())
-            .reduce(T::combine)
-            .unwrap()
+        T::combine_all(workers.into_iter().map(|w| w.join().unwrap()).collect())
     })
 }
 
@@ -6,17 +6,23 @@ mod path_args;
 use carcara::{
     ast::print_proof,
     benchmarking::{Metrics, OnlineBenchmarkResults},
-    check, check_and_elaborate, parser, CarcaraOptions,
+    check_and_elaborate_named, check_named,
+    checker::{list_all_rules, ProofChecker},
+    parser,
+    visualization::proof_to_dot,
+    CarcaraOptions,
 };
-use clap::{AppSettings, ArgEnum, Args, Parser, Subcommand};
+use clap::{AppSettings, ArgEnum, Args, CommandFactory, ErrorKind, Parser, Subcommand};
 use const_format::{formatcp, str_index};
 use error::{CliError, CliResult};
 use git_version::git_version;
 use path_args::{get_instances_from_paths, infer_problem_path};
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{self, BufRead},
     path::Path,
+    time::Duration,
 };
 
 // `git describe --all` will try to find any ref (including tags) that describes the current commit.
@@ -46,7 +52,11 @@ const VERSION_STRING: &str = formatcp!(
 )]
 struct Cli {
     #[clap(subcommand)]
-    command: Command,
+    command: Option<Command>,
+
+    /// Prints a table with all rules supported by the checker, and exits.
+    #[clap(long)]
+    dump_rules: bool,
 
     /// Sets the maximum logging level.
     #[clap(arg_enum, global = true, long = "log", default_value_t = LogLevel::Warn)]
@@ -99,6 +109,15 @@ struct ParsingOptions {
     /// to arithmetic operators that are expecting a term of sort `Real`.
     #[clap(long)]
     allow_int_real_subtyping: bool,
+
+    /// Tries to recover from syntax errors in the proof, reporting all of them at once instead of
+    /// stopping at the first one.
+    #[clap(long)]
+    error_recovery: bool,
+
+    /// Fail if any term in the input is nested more than N levels deep.
+    #[clap(long, value_name = "N")]
+    max_term_depth: Option<usize>,
 }
 
 #[derive(Args, Clone, Copy)]
@@ -111,9 +130,29 @@ struct CheckingOptions {
     #[clap(long)]
     skip_unknown_rules: bool,
 
-    /// Check `lia_generic` steps by calling into cvc5.
+    /// Check `lia_generic` steps that have no Farkas coefficients by calling into cvc5.
     #[clap(long)]
     lia_via_cvc5: bool,
+
+    /// Only check the first N steps of the proof, without requiring it to reach the empty clause.
+    #[clap(long, value_name = "N")]
+    max_steps: Option<usize>,
+
+    /// Abort checking if it takes longer than the given number of seconds.
+    #[clap(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// Give up comparing terms modulo reordering of equalities after recursing N levels deep.
+    #[clap(long, value_name = "N")]
+    deep_eq_depth_limit: Option<usize>,
+
+    /// Cache the premise matched by each `assume` command, for terms that are assumed many times.
+    #[clap(long)]
+    deep_eq_cache: bool,
+
+    /// Keep at most N terms in the cache enabled by `--deep-eq-cache`.
+    #[clap(long, value_name = "N")]
+    deep_eq_cache_size: Option<usize>,
 }
 
 #[derive(Args)]
@@ -128,20 +167,34 @@ fn build_carcara_options(
         apply_function_defs,
         expand_let_bindings,
         allow_int_real_subtyping,
+        error_recovery,
+        max_term_depth,
     }: ParsingOptions,
     CheckingOptions {
         strict,
         skip_unknown_rules,
         lia_via_cvc5,
+        max_steps,
+        timeout,
+        deep_eq_depth_limit,
+        deep_eq_cache,
+        deep_eq_cache_size,
     }: CheckingOptions,
 ) -> CarcaraOptions {
     CarcaraOptions {
         apply_function_defs,
         expand_lets: expand_let_bindings,
         allow_int_real_subtyping,
+        error_recovery,
         lia_via_cvc5,
         strict,
         skip_unknown_rules,
+        max_steps,
+        timeout: timeout.map(Duration::from_secs),
+        deep_eq_depth_limit,
+        max_term_depth,
+        use_deep_eq_cache: deep_eq_cache,
+        deep_eq_cache_size,
     }
 }
 
@@ -155,6 +208,10 @@ struct ParseCommandOptions {
 
     #[clap(flatten)]
     printing: PrintingOptions,
+
+    /// Also writes the dependency graph of the proof to the given file, in the Graphviz DOT format.
+    #[clap(long)]
+    dot: Option<String>,
 }
 
 #[derive(Args)]
@@ -242,7 +299,17 @@ fn main() {
     let colors_enabled = !cli.no_color && atty::is(atty::Stream::Stderr);
     logger::init(cli.log_level.into(), colors_enabled);
 
-    let result = match cli.command {
+    if cli.dump_rules {
+        dump_rules();
+        return;
+    }
+    let Some(command) = cli.command else {
+        Cli::command()
+            .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+            .exit()
+    };
+
+    let result = match command {
         Command::Parse(options) => parse_command(options),
         Command::Check(options) => {
             match check_command(options) {
@@ -265,15 +332,44 @@ fn main() {
     }
 }
 
-fn get_instance(options: &Input) -> CliResult<(Box<dyn BufRead>, Box<dyn BufRead>)> {
-    fn reader_from_path<P: AsRef<Path>>(path: P) -> CliResult<Box<dyn BufRead>> {
-        Ok(Box::new(io::BufReader::new(File::open(path)?)))
+fn dump_rules() {
+    println!(
+        "{:<24} {:<14} {:<8} {:<5} eliminable",
+        "rule", "category", "strict", "hole"
+    );
+    for rule in list_all_rules() {
+        println!(
+            "{:<24} {:<14} {:<8} {:<5} {}",
+            rule.name,
+            rule.category.to_string(),
+            if rule.has_strict_version {
+                "differs"
+            } else {
+                "same"
+            },
+            rule.is_hole(),
+            rule.is_eliminable,
+        );
+    }
+}
+
+/// An input file, together with the name used for it in error messages.
+type NamedInput = (Box<dyn BufRead>, String);
+
+fn get_instance(options: &Input) -> CliResult<(NamedInput, NamedInput)> {
+    fn reader_from_path<P: AsRef<Path>>(path: P) -> CliResult<NamedInput> {
+        let name = path.as_ref().display().to_string();
+        Ok((Box::new(io::BufReader::new(File::open(path)?)), name))
+    }
+
+    fn stdin() -> NamedInput {
+        (Box::new(io::stdin().lock()), "<stdin>".to_owned())
     }
 
     match (options.problem_file.as_deref(), options.proof_file.as_str()) {
         (Some("-"), "-") | (None, "-") => Err(CliError::BothFilesStdin),
-        (Some(problem), "-") => Ok((reader_from_path(problem)?, Box::new(io::stdin().lock()))),
-        (Some("-"), proof) => Ok((Box::new(io::stdin().lock()), reader_from_path(proof)?)),
+        (Some(problem), "-") => Ok((reader_from_path(problem)?, stdin())),
+        (Some("-"), proof) => Ok((stdin(), reader_from_path(proof)?)),
         (Some(problem), proof) => Ok((reader_from_path(problem)?, reader_from_path(proof)?)),
         (None, proof) => Ok((
             reader_from_path(infer_problem_path(proof)?)?,
@@ -283,35 +379,49 @@ fn get_instance(options: &Input) -> CliResult<(Box<dyn BufRead>, Box<dyn BufRead
 }
 
 fn parse_command(options: ParseCommandOptions) -> CliResult<()> {
-    let (problem, proof) = get_instance(&options.input)?;
-    let (_, proof, _) = parser::parse_instance(
+    let ((problem, problem_name), (proof, proof_name)) = get_instance(&options.input)?;
+    let parsing_options = CarcaraOptions {
+        apply_function_defs: options.parsing.apply_function_defs,
+        expand_lets: options.parsing.expand_let_bindings,
+        allow_int_real_subtyping: options.parsing.allow_int_real_subtyping,
+        error_recovery: options.parsing.error_recovery,
+        max_term_depth: options.parsing.max_term_depth,
+        ..CarcaraOptions::new()
+    };
+    let (_, proof, _) = parser::parse_instance_with_options_named(
         problem,
         proof,
-        options.parsing.apply_function_defs,
-        options.parsing.expand_let_bindings,
-        options.parsing.allow_int_real_subtyping,
-    )
-    .map_err(carcara::Error::from)?;
+        &problem_name,
+        &proof_name,
+        &parsing_options,
+    )?;
+    if let Some(path) = &options.dot {
+        proof_to_dot(&proof, &mut File::create(path)?)?;
+    }
     print_proof(&proof.commands, options.printing.use_sharing)?;
     Ok(())
 }
 
 fn check_command(options: CheckCommandOptions) -> CliResult<bool> {
-    let (problem, proof) = get_instance(&options.input)?;
-    check(
+    let ((problem, problem_name), (proof, proof_name)) = get_instance(&options.input)?;
+    check_named(
         problem,
         proof,
+        &problem_name,
+        &proof_name,
         build_carcara_options(options.parsing, options.checking),
     )
     .map_err(Into::into)
 }
 
 fn elaborate_command(options: ElaborateCommandOptions) -> CliResult<()> {
-    let (problem, proof) = get_instance(&options.input)?;
+    let ((problem, problem_name), (proof, proof_name)) = get_instance(&options.input)?;
 
-    let (_, elaborated) = check_and_elaborate(
+    let (_, elaborated, _) = check_and_elaborate_named(
         problem,
         proof,
+        &problem_name,
+        &proof_name,
         build_carcara_options(options.parsing, options.checking),
     )?;
     print_proof(&elaborated.commands, options.printing.use_sharing)?;
@@ -402,6 +512,25 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
     println!("deep equality ratio: {}", results.deep_eq_time_ratio);
     println!("total accounted for: {}", accounted_for);
     println!("total:               {}", total);
+    let num_steps: usize = results.runs.values().map(|m| m.step_count).sum();
+    println!(
+        "steps:               {} ({:.02} per run)",
+        num_steps,
+        num_steps as f64 / results.runs.len() as f64,
+    );
+    let num_subproofs: usize = results.runs.values().map(|m| m.subproof_count).sum();
+    let max_depth = results.runs.values().map(|m| m.max_subproof_depth).max();
+    println!(
+        "subproofs:           {} (max depth {})",
+        num_subproofs,
+        max_depth.unwrap_or(0),
+    );
+    if let Some((_, bytes)) = results.peak_memory() {
+        println!(
+            "peak memory:         {:.02} MiB",
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
 
     let data_by_rule = results.step_time_by_rule();
     let mut data_by_rule: Vec<_> = data_by_rule.iter().collect();
@@ -417,6 +546,19 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
         }
     }
 
+    let mut data_by_category: BTreeMap<_, (Duration, usize)> = BTreeMap::new();
+    for (rule, data) in results.step_time_by_rule() {
+        if let Some((_, metadata)) = ProofChecker::get_rule(rule, false) {
+            let entry = data_by_category.entry(metadata.category).or_default();
+            entry.0 += data.total();
+            entry.1 += data.count();
+        }
+    }
+    println!("by category:");
+    for (category, (total, count)) in data_by_category {
+        println!("    {: <18}{:?} ({} steps)", category, total, count);
+    }
+
     println!("worst cases:");
     let worst_step = results.step_time().max();
     println!("    step:            {} ({:?})", worst_step.0, worst_step.1);
@@ -433,6 +575,14 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
         worst_file_checking.0 .0, worst_file_checking.1
     );
 
+    if let Some((id, bytes)) = results.peak_memory() {
+        println!(
+            "    file (memory):   {} ({:.02} MiB)",
+            id.0,
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
     let worst_file_assume = results.assume_time_ratio.max();
     println!(
         "    file (assume):   {} ({:.04}%)",
@@ -0,0 +1,14 @@
+use std::{env, path::PathBuf};
+
+fn main() {
+    // The header is written to `OUT_DIR`, since build scripts shouldn't modify the source tree.
+    // The copy in `include/carcara.h` is kept in sync by a test
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    cbindgen::generate(crate_dir)
+        .expect("failed to generate C bindings")
+        .write_to_file(out_dir.join("carcara.h"));
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
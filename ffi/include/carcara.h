@@ -0,0 +1,74 @@
+#ifndef CARCARA_H
+#define CARCARA_H
+
+/* This file is generated by cbindgen from carcara-ffi. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of checking a proof. If the proof is valid, `ok` is `true` and `error_message` is
+ * null. Otherwise, `ok` is `false` and `error_message` is a null-terminated string describing the
+ * error. In both cases, the result must be released with `carcara_free_result`.
+ */
+typedef struct CCarcaraResult {
+  bool ok;
+  /**
+   * If the proof is valid, whether it contains any `hole` steps, or steps that were skipped
+   * because of unknown rules.
+   */
+  bool is_holey;
+  const char *error_message;
+} CCarcaraResult;
+
+/**
+ * The options used when checking a proof. Each field has the same meaning as the field with the
+ * same name in `carcara::CarcaraOptions`. Options that are not listed here take their default
+ * values.
+ */
+typedef struct CCarcaraOptions {
+  bool apply_function_defs;
+  bool expand_lets;
+  bool allow_int_real_subtyping;
+  bool lia_via_cvc5;
+  bool strict;
+  bool skip_unknown_rules;
+} CCarcaraOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Checks a proof against the problem it proves. The problem and proof are given as byte buffers,
+ * which don't need to be null-terminated. If `options` is null, the default options are used.
+ *
+ * # Safety
+ *
+ * `problem_ptr` and `proof_ptr` must either be null or point to buffers of at least `problem_len`
+ * and `proof_len` bytes, respectively. `options` must either be null or point to a valid
+ * `CCarcaraOptions`.
+ */
+struct CCarcaraResult carcara_check(const uint8_t *problem_ptr,
+                                    size_t problem_len,
+                                    const uint8_t *proof_ptr,
+                                    size_t proof_len,
+                                    const struct CCarcaraOptions *options);
+
+/**
+ * Releases the memory owned by a result returned by `carcara_check`.
+ *
+ * # Safety
+ *
+ * `result` must have been returned by `carcara_check`, and must not have been released before.
+ */
+void carcara_free_result(struct CCarcaraResult result);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* CARCARA_H */
@@ -0,0 +1,125 @@
+//! A C interface to Carcara, so that it can be embedded in proof tools that are not written in
+//! Rust. The header file `include/carcara.h` is generated from this module by `cbindgen`. The build
+//! script writes the generated header to `OUT_DIR`, and a test checks that the copy in `include`
+//! is up to date. After changing this module, copy the new header over it.
+
+use carcara::CarcaraOptions;
+use std::{
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// The options used when checking a proof. Each field has the same meaning as the field with the
+/// same name in `carcara::CarcaraOptions`. Options that are not listed here take their default
+/// values.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CCarcaraOptions {
+    pub apply_function_defs: bool,
+    pub expand_lets: bool,
+    pub allow_int_real_subtyping: bool,
+    pub lia_via_cvc5: bool,
+    pub strict: bool,
+    pub skip_unknown_rules: bool,
+}
+
+impl From<&CCarcaraOptions> for CarcaraOptions {
+    fn from(options: &CCarcaraOptions) -> Self {
+        CarcaraOptions {
+            apply_function_defs: options.apply_function_defs,
+            expand_lets: options.expand_lets,
+            allow_int_real_subtyping: options.allow_int_real_subtyping,
+            lia_via_cvc5: options.lia_via_cvc5,
+            strict: options.strict,
+            skip_unknown_rules: options.skip_unknown_rules,
+            ..CarcaraOptions::new()
+        }
+    }
+}
+
+/// The result of checking a proof. If the proof is valid, `ok` is `true` and `error_message` is
+/// null. Otherwise, `ok` is `false` and `error_message` is a null-terminated string describing the
+/// error. In both cases, the result must be released with `carcara_free_result`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CCarcaraResult {
+    pub ok: bool,
+
+    /// If the proof is valid, whether it contains any `hole` steps, or steps that were skipped
+    /// because of unknown rules.
+    pub is_holey: bool,
+
+    pub error_message: *const c_char,
+}
+
+impl CCarcaraResult {
+    fn from_error(message: String) -> Self {
+        // Error messages may include parts of the input, which could in principle contain null
+        // bytes. Since these can't be represented in a C string, we remove them
+        let message = CString::new(message.replace('\0', "")).unwrap();
+        Self {
+            ok: false,
+            is_holey: false,
+            error_message: message.into_raw(),
+        }
+    }
+}
+
+/// Reads a byte buffer passed through the C interface. A null pointer is treated as an empty
+/// buffer.
+unsafe fn buffer<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if ptr.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Checks a proof against the problem it proves. The problem and proof are given as byte buffers,
+/// which don't need to be null-terminated. If `options` is null, the default options are used.
+///
+/// # Safety
+///
+/// `problem_ptr` and `proof_ptr` must either be null or point to buffers of at least `problem_len`
+/// and `proof_len` bytes, respectively. `options` must either be null or point to a valid
+/// `CCarcaraOptions`.
+#[no_mangle]
+pub unsafe extern "C" fn carcara_check(
+    problem_ptr: *const u8,
+    problem_len: usize,
+    proof_ptr: *const u8,
+    proof_len: usize,
+    options: *const CCarcaraOptions,
+) -> CCarcaraResult {
+    let problem = buffer(problem_ptr, problem_len);
+    let proof = buffer(proof_ptr, proof_len);
+    let options = match options.as_ref() {
+        Some(options) => CarcaraOptions::from(options),
+        None => CarcaraOptions::new(),
+    };
+
+    // Unwinding across the C boundary is not allowed, so any panic is reported as an error instead
+    let result = panic::catch_unwind(AssertUnwindSafe(|| carcara::check(problem, proof, options)));
+    match result {
+        Ok(Ok(is_holey)) => CCarcaraResult {
+            ok: true,
+            is_holey,
+            error_message: ptr::null(),
+        },
+        Ok(Err(e)) => CCarcaraResult::from_error(e.to_string()),
+        Err(_) => CCarcaraResult::from_error("internal error: checker panicked".to_owned()),
+    }
+}
+
+/// Releases the memory owned by a result returned by `carcara_check`.
+///
+/// # Safety
+///
+/// `result` must have been returned by `carcara_check`, and must not have been released before.
+#[no_mangle]
+pub unsafe extern "C" fn carcara_free_result(result: CCarcaraResult) {
+    if !result.error_message.is_null() {
+        drop(CString::from_raw(result.error_message as *mut c_char));
+    }
+}
@@ -0,0 +1,29 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "carcara.h"
+
+static const char *PROBLEM =
+    "(declare-fun p () Bool)\n"
+    "(assert p)\n"
+    "(assert (not p))\n";
+
+static int check(const char *proof, bool expected) {
+    CCarcaraOptions options = {0};
+    CCarcaraResult result = carcara_check(
+        (const uint8_t *)PROBLEM, strlen(PROBLEM), (const uint8_t *)proof, strlen(proof), &options);
+    int failed = result.ok != expected || (result.error_message == NULL) != expected;
+    if (result.error_message != NULL) {
+        printf("%s\n", result.error_message);
+    }
+    carcara_free_result(result);
+    return failed;
+}
+
+int main(void) {
+    int failed = 0;
+    failed |= check("(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule resolution :premises (h1 h2))", true);
+    failed |= check("(assume h1 p) (step t2 (cl) :rule resolution :premises (h1))", false);
+    failed |= check("(step t1 (cl) :rule)", false);
+    return failed;
+}
@@ -0,0 +1,98 @@
+use carcara_ffi::*;
+use std::{env, ffi::CStr, path::PathBuf, process::Command, ptr};
+
+const PROBLEM: &str = "
+    (declare-fun p () Bool)
+    (assert p)
+    (assert (not p))
+";
+
+fn check(proof: &str, options: Option<&CCarcaraOptions>) -> Result<bool, String> {
+    let options = options.map_or(ptr::null(), |o| o as *const _);
+    unsafe {
+        let result = carcara_check(
+            PROBLEM.as_ptr(),
+            PROBLEM.len(),
+            proof.as_ptr(),
+            proof.len(),
+            options,
+        );
+        let output = if result.ok {
+            assert!(result.error_message.is_null());
+            Ok(result.is_holey)
+        } else {
+            let message = CStr::from_ptr(result.error_message);
+            Err(message.to_str().unwrap().to_owned())
+        };
+        carcara_free_result(result);
+        output
+    }
+}
+
+#[test]
+fn test_check() {
+    let proof =
+        "(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule resolution :premises (h1 h2))";
+    assert_eq!(check(proof, None), Ok(false));
+
+    let proof = "(assume h1 p) (step t2 (cl) :rule hole :premises (h1))";
+    assert_eq!(check(proof, None), Ok(true));
+
+    let proof = "(assume h1 p) (step t2 (cl) :rule resolution :premises (h1))";
+    let message = check(proof, None).unwrap_err();
+    assert!(message.starts_with("checking failed on step 't2'"));
+
+    let message = check("(step t1 (cl) :rule)", None).unwrap_err();
+    assert!(message.starts_with("parser error"));
+}
+
+#[test]
+fn test_options() {
+    let proof = "(assume h1 p) (assume h2 (not p)) (step t3 (cl) :rule foo :premises (h1 h2))";
+    assert!(check(proof, Some(&CCarcaraOptions::default())).is_err());
+
+    let options = CCarcaraOptions {
+        skip_unknown_rules: true,
+        ..Default::default()
+    };
+    assert_eq!(check(proof, Some(&options)), Ok(true));
+}
+
+#[test]
+fn test_c_program() {
+    // The test executable is placed in the `deps` subdirectory of the directory that contains the
+    // static library
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("carcara-ffi-smoke");
+
+    let status = Command::new("cc")
+        .arg(crate_dir.join("tests/smoke.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .args(["-lcarcara_ffi", "-lpthread", "-lm", "-ldl"])
+        .arg("-o")
+        .arg(&output)
+        .status()
+        .expect("failed to run C compiler");
+    assert!(status.success());
+
+    let output = Command::new(&output).output().unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_header_is_up_to_date() {
+    let generated = PathBuf::from(env!("OUT_DIR")).join("carcara.h");
+    let checked_in = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("include/carcara.h");
+    assert!(
+        std::fs::read_to_string(&generated).unwrap()
+            == std::fs::read_to_string(&checked_in).unwrap(),
+        "`{}` is out of date, copy the generated header from `{}`",
+        checked_in.display(),
+        generated.display(),
+    );
+}